@@ -0,0 +1,202 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::ops::Range;
+
+use serde_json::Value;
+
+/// A value selected by a JSONPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathMatch {
+    pub value: Value,
+
+    /// Where the value is in the document indented by `serde_json::to_string_pretty`,
+    /// counted in characters.
+    pub span: Range<usize>,
+}
+
+fn newline(text: &mut String, depth: usize) {
+    text.push('\n');
+    text.push_str(&"  ".repeat(depth));
+}
+
+/// Writes a value the same way as `serde_json::to_string_pretty`, keeping the
+/// place of every value that is one of the targets.
+fn write_pretty(
+    value: &Value,
+    depth: usize,
+    targets: &[&Value],
+    text: &mut String,
+    spans: &mut Vec<(*const Value, Range<usize>)>,
+) {
+    let start = text.len();
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            text.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    text.push(',');
+                }
+                newline(text, depth + 1);
+                write_pretty(item, depth + 1, targets, text, spans);
+            }
+            newline(text, depth);
+            text.push(']');
+        }
+        Value::Object(map) if !map.is_empty() => {
+            text.push('{');
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    text.push(',');
+                }
+                newline(text, depth + 1);
+                text.push_str(&Value::String(key.clone()).to_string());
+                text.push_str(": ");
+                write_pretty(item, depth + 1, targets, text, spans);
+            }
+            newline(text, depth);
+            text.push('}');
+        }
+        value => text.push_str(&value.to_string()),
+    }
+    if targets.iter().any(|target| std::ptr::eq(*target, value)) {
+        spans.push((value as *const Value, start..text.len()));
+    }
+}
+
+/// Turns a byte offset of the text into a character offset.
+fn char_offset(text: &str, offset: usize) -> usize {
+    text[..offset].chars().count()
+}
+
+/// Evaluates a JSONPath expression, such as `$.users[0].email`, over a document.
+pub fn select(document: &Value, expression: &str) -> Result<Vec<JsonPathMatch>, String> {
+    let targets = jsonpath_lib::select(document, expression).map_err(|e| e.to_string())?;
+
+    let mut text = String::new();
+    let mut spans = Vec::new();
+    write_pretty(document, 0, &targets, &mut text, &mut spans);
+
+    let matches = targets
+        .iter()
+        .map(|target| {
+            let span = spans
+                .iter()
+                .find(|(value, _)| std::ptr::eq(*value, *target))
+                .map(|(_, span)| char_offset(&text, span.start)..char_offset(&text, span.end))
+                .unwrap_or_default();
+            JsonPathMatch {
+                value: (*target).clone(),
+                span,
+            }
+        })
+        .collect();
+    Ok(matches)
+}
+
+/// The text to store when the matches are saved into a variable.
+///
+/// A single string is stored without quotes. Other values are stored as JSON,
+/// and multiple matches are stored as a JSON array.
+pub fn extracted_text(matches: &[JsonPathMatch]) -> Option<String> {
+    match matches {
+        [] => None,
+        [single] => match &single.value {
+            Value::String(text) => Some(text.clone()),
+            value => Some(value.to_string()),
+        },
+        matches => {
+            let values = matches.iter().map(|m| m.value.clone()).collect();
+            Some(Value::Array(values).to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        serde_json::json!({
+            "users": [
+                {"name": "Ada", "email": "ada@example.com", "tags": []},
+                {"name": "Grace", "email": "grace@example.com", "tags": ["ñ", 1.5]},
+            ],
+            "total": 2,
+        })
+    }
+
+    fn spanned(matches: &[JsonPathMatch]) -> Vec<String> {
+        let text: Vec<char> = serde_json::to_string_pretty(&document())
+            .unwrap()
+            .chars()
+            .collect();
+        matches
+            .iter()
+            .map(|m| text[m.span.clone()].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_single_value() {
+        let matches = select(&document(), "$.users[0].email").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].value, Value::from("ada@example.com"));
+        assert_eq!(spanned(&matches), vec!["\"ada@example.com\""]);
+        assert_eq!(
+            extracted_text(&matches),
+            Some(String::from("ada@example.com"))
+        );
+    }
+
+    #[test]
+    fn test_select_many_values() {
+        let matches = select(&document(), "$.users[*].tags").unwrap();
+        assert_eq!(
+            spanned(&matches),
+            vec!["[]", "[\n        \"ñ\",\n        1.5\n      ]"]
+        );
+        assert_eq!(
+            extracted_text(&matches),
+            Some(String::from("[[],[\"ñ\",1.5]]"))
+        );
+
+        // The spans are counted in characters, so they still match after the ñ.
+        let matches = select(&document(), "$.total").unwrap();
+        assert_eq!(spanned(&matches), vec!["2"]);
+        assert_eq!(extracted_text(&matches), Some(String::from("2")));
+    }
+
+    #[test]
+    fn test_select_nothing() {
+        let matches = select(&document(), "$.missing").unwrap();
+        assert!(matches.is_empty());
+        assert_eq!(extracted_text(&matches), None);
+    }
+
+    #[test]
+    fn test_select_invalid_expression() {
+        assert!(select(&document(), "$.users[").is_err());
+    }
+
+    #[test]
+    fn test_pretty_text_matches_serde() {
+        let mut text = String::new();
+        write_pretty(&document(), 0, &[], &mut text, &mut Vec::new());
+        assert_eq!(text, serde_json::to_string_pretty(&document()).unwrap());
+    }
+}
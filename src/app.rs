@@ -21,7 +21,9 @@ use glib::Object;
 use gtk::gio::{self, ActionEntryBuilder, Settings};
 use gtk::prelude::ActionMapExtManual;
 
-use crate::config::{APP_ID, BASE_ID, RESOURCE_PATH};
+use crate::client::parse_default_headers;
+use crate::config::{APP_ID, BASE_ID, RESOURCE_PATH, VERSION};
+use crate::utils::CustomShortcuts;
 use crate::win::CarteroWindow;
 
 #[macro_export]
@@ -35,6 +37,29 @@ macro_rules! accelerator {
     };
 }
 
+/// The actions that can be triggered with a keyboard shortcut, along with their
+/// default accelerator. The user may override them from the shortcuts dialog.
+pub const DEFAULT_SHORTCUTS: &[(&str, &str)] = &[
+    ("win.new", accelerator!("t")),
+    ("win.new-from-url", accelerator!("<Shift>n")),
+    ("win.open", accelerator!("o")),
+    ("win.save", accelerator!("s")),
+    ("win.save-as", accelerator!("<Shift>s")),
+    ("win.close", accelerator!("w")),
+    ("win.next-tab", accelerator!("Tab")),
+    ("win.previous-tab", accelerator!("<Shift>Tab")),
+    ("win.tab-overview", accelerator!("<Shift>a")),
+    ("win.request", accelerator!("Return")),
+    ("win.preview-request", accelerator!("<Shift>Return")),
+    ("win.format-body", accelerator!("<Shift>f")),
+    ("win.undo", accelerator!("z")),
+    ("win.redo", accelerator!("<Shift>z")),
+    ("win.toggle-console", "F12"),
+    ("win.preferences", accelerator!("comma")),
+    ("app.quit", accelerator!("q")),
+    ("win.show-help-overlay", accelerator!("question")),
+];
+
 mod imp {
     use std::cell::OnceCell;
 
@@ -81,15 +106,15 @@ mod imp {
             gtk::Window::set_default_icon_name(APP_ID);
 
             let obj = self.obj();
-            obj.set_accels_for_action("win.new", &[accelerator!("t")]);
-            obj.set_accels_for_action("win.open", &[accelerator!("o")]);
-            obj.set_accels_for_action("win.save", &[accelerator!("s")]);
-            obj.set_accels_for_action("win.save-as", &[accelerator!("<Shift>s")]);
-            obj.set_accels_for_action("win.close", &[accelerator!("w")]);
-            obj.set_accels_for_action("win.request", &[accelerator!("Return")]);
-            obj.set_accels_for_action("app.quit", &[accelerator!("q")]);
-            obj.set_accels_for_action("win.show-help-overlay", &[accelerator!("question")]);
+            obj.apply_shortcuts();
+            obj.apply_color_scheme();
             obj.setup_app_actions();
+            obj.settings().connect_changed(
+                Some("color-scheme"),
+                glib::clone!(@weak obj => move |_, _| {
+                    obj.apply_color_scheme();
+                }),
+            );
         }
 
         fn open(&self, files: &[gio::File], hint: &str) {
@@ -104,10 +129,11 @@ mod imp {
                 if is_new_window {
                     window.open_last_session().await;
                 }
+                // Present the window first, so that errors opening a file can be shown on it.
+                window.present();
                 for file in thread_files {
-                    window.add_endpoint(Some(&file)).await;
+                    window.open_endpoint(&file).await;
                 }
-                window.present();
             });
         }
     }
@@ -149,6 +175,74 @@ impl CarteroApplication {
         self.imp().settings.get_or_init(|| Settings::new(BASE_ID))
     }
 
+    pub fn custom_shortcuts(&self) -> CustomShortcuts {
+        CustomShortcuts::from_json(&self.settings().string("custom-shortcuts"))
+    }
+
+    /// The headers sent with every request, unless the request overrides them.
+    pub fn default_headers(&self) -> Vec<(String, String)> {
+        let lines = self.settings().strv("default-headers");
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        parse_default_headers(&lines, VERSION)
+    }
+
+    /// The accelerator of every action, giving priority to the ones customized
+    /// by the user over the defaults. Disabled shortcuts have an empty accelerator.
+    pub fn effective_shortcuts(&self) -> Vec<(&'static str, String)> {
+        let custom = self.custom_shortcuts();
+        DEFAULT_SHORTCUTS
+            .iter()
+            .map(|(action, default)| {
+                let accel = custom.get(action).unwrap_or(default);
+                (*action, accel.to_string())
+            })
+            .collect()
+    }
+
+    /// Uses the style picked by the user, or the one of the system by default.
+    pub fn apply_color_scheme(&self) {
+        let scheme = match self.settings().string("color-scheme").as_str() {
+            "light" => adw::ColorScheme::PreferLight,
+            "dark" => adw::ColorScheme::PreferDark,
+            _ => adw::ColorScheme::Default,
+        };
+        adw::StyleManager::default().set_color_scheme(scheme);
+    }
+
+    pub fn apply_shortcuts(&self) {
+        for (action, accel) in self.effective_shortcuts() {
+            if accel.is_empty() {
+                self.set_accels_for_action(action, &[]);
+            } else {
+                self.set_accels_for_action(action, &[&accel]);
+            }
+        }
+    }
+
+    /// Assigns a new accelerator to an action, or disables its shortcut if the
+    /// accelerator is empty. Any other action using the same accelerator loses it.
+    pub fn set_custom_shortcut(&self, action: &str, accel: &str) {
+        let mut custom = self.custom_shortcuts();
+        if let Some(parsed) = gtk::accelerator_parse(accel) {
+            for (other, current) in self.effective_shortcuts() {
+                if other != action && gtk::accelerator_parse(&current) == Some(parsed) {
+                    custom.set(other, "");
+                }
+            }
+        }
+        custom.set(action, accel);
+        let _ = self
+            .settings()
+            .set_string("custom-shortcuts", &custom.to_json());
+        self.apply_shortcuts();
+    }
+
+    /// Forgets every customized shortcut, going back to the defaults.
+    pub fn reset_shortcuts(&self) {
+        self.settings().reset("custom-shortcuts");
+        self.apply_shortcuts();
+    }
+
     fn setup_app_actions(&self) {
         let quit = ActionEntryBuilder::new("quit")
             .activate(glib::clone!(@weak self as app => move |_, _, _| {
@@ -0,0 +1,192 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io::{Read, Write};
+
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::GzEncoder,
+    Compression,
+};
+use gettextrs::gettext;
+
+/// The value of the Accept-Encoding header, listing every coding that can be decoded.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// The outcome of decoding a response body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedBody {
+    pub body: Vec<u8>,
+    /// Why the body could not be decoded. When set, `body` holds the bytes as received.
+    pub warning: Option<String>,
+}
+
+fn read_all(mut reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn decode_coding(coding: &str, body: &[u8]) -> Result<Vec<u8>, String> {
+    match coding {
+        "gzip" | "x-gzip" => read_all(GzDecoder::new(body)).map_err(|e| e.to_string()),
+        // Many servers send a raw deflate stream instead of the zlib one required
+        // by the RFC, so try the standard format first and fall back to the raw one.
+        "deflate" => read_all(ZlibDecoder::new(body))
+            .or_else(|_| read_all(DeflateDecoder::new(body)))
+            .map_err(|e| e.to_string()),
+        "br" => read_all(brotli::Decompressor::new(body, 4096)).map_err(|e| e.to_string()),
+        "identity" | "" => Ok(body.to_vec()),
+        coding => {
+            Err(gettext("Unsupported content encoding: {coding}").replace("{coding}", coding))
+        }
+    }
+}
+
+/// Decodes a body according to the value of its Content-Encoding header.
+///
+/// Codings are undone in the reverse order in which they are listed. This never
+/// fails: if the body cannot be decoded, the original bytes are returned along
+/// with a warning, so that the response can still be displayed.
+pub fn decode_body(content_encoding: Option<&str>, raw: &[u8]) -> DecodedBody {
+    let Some(content_encoding) = content_encoding else {
+        return DecodedBody {
+            body: raw.to_vec(),
+            warning: None,
+        };
+    };
+
+    let mut body = raw.to_vec();
+    for coding in content_encoding.rsplit(',') {
+        let coding = coding.trim().to_lowercase();
+        match decode_coding(&coding, &body) {
+            Ok(decoded) => body = decoded,
+            Err(e) => {
+                return DecodedBody {
+                    body: raw.to_vec(),
+                    warning: Some(e),
+                }
+            }
+        }
+    }
+    DecodedBody {
+        body,
+        warning: None,
+    }
+}
+
+/// Compresses a request body using gzip, to be sent with `Content-Encoding: gzip`.
+pub fn gzip_encode(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: &str = "{\"message\": \"¡Hola, Cartero!\"}\n";
+
+    const GZIP: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x56, 0xca, 0x4d, 0x2d,
+        0x2e, 0x4e, 0x4c, 0x4f, 0x55, 0xb2, 0x52, 0x50, 0x3a, 0xb4, 0xd0, 0x23, 0x3f, 0x27, 0x51,
+        0x47, 0xc1, 0x39, 0xb1, 0xa8, 0x24, 0xb5, 0x28, 0x5f, 0x51, 0xa9, 0x96, 0x0b, 0x00, 0x67,
+        0x81, 0x0f, 0xdb, 0x20, 0x00, 0x00, 0x00,
+    ];
+
+    const ZLIB: &[u8] = &[
+        0x78, 0x9c, 0xab, 0x56, 0xca, 0x4d, 0x2d, 0x2e, 0x4e, 0x4c, 0x4f, 0x55, 0xb2, 0x52, 0x50,
+        0x3a, 0xb4, 0xd0, 0x23, 0x3f, 0x27, 0x51, 0x47, 0xc1, 0x39, 0xb1, 0xa8, 0x24, 0xb5, 0x28,
+        0x5f, 0x51, 0xa9, 0x96, 0x0b, 0x00, 0xbb, 0xe0, 0x0a, 0xee,
+    ];
+
+    const RAW_DEFLATE: &[u8] = &[
+        0xab, 0x56, 0xca, 0x4d, 0x2d, 0x2e, 0x4e, 0x4c, 0x4f, 0x55, 0xb2, 0x52, 0x50, 0x3a, 0xb4,
+        0xd0, 0x23, 0x3f, 0x27, 0x51, 0x47, 0xc1, 0x39, 0xb1, 0xa8, 0x24, 0xb5, 0x28, 0x5f, 0x51,
+        0xa9, 0x96, 0x0b, 0x00,
+    ];
+
+    const BROTLI: &[u8] = &[
+        0x8b, 0x0f, 0x80, 0x7b, 0x22, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65, 0x22, 0x3a, 0x20,
+        0x22, 0xc2, 0xa1, 0x48, 0x6f, 0x6c, 0x61, 0x2c, 0x20, 0x43, 0x61, 0x72, 0x74, 0x65, 0x72,
+        0x6f, 0x21, 0x22, 0x7d, 0x0a, 0x03,
+    ];
+
+    fn assert_decodes(encoding: &str, payload: &[u8]) {
+        let decoded = decode_body(Some(encoding), payload);
+        assert_eq!(decoded.warning, None, "{encoding}");
+        assert_eq!(String::from_utf8(decoded.body).unwrap(), PLAIN);
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        assert_decodes("gzip", GZIP);
+        assert_decodes("x-gzip", GZIP);
+    }
+
+    #[test]
+    fn test_decode_deflate() {
+        assert_decodes("deflate", ZLIB);
+        assert_decodes("deflate", RAW_DEFLATE);
+    }
+
+    #[test]
+    fn test_decode_brotli() {
+        assert_decodes("br", BROTLI);
+        assert_decodes("BR", BROTLI);
+    }
+
+    #[test]
+    fn test_decode_without_encoding() {
+        let decoded = decode_body(None, PLAIN.as_bytes());
+        assert_eq!(decoded.body, PLAIN.as_bytes());
+        assert_eq!(decoded.warning, None);
+
+        let decoded = decode_body(Some("identity"), PLAIN.as_bytes());
+        assert_eq!(decoded.body, PLAIN.as_bytes());
+        assert_eq!(decoded.warning, None);
+    }
+
+    #[test]
+    fn test_decode_multiple_codings() {
+        let twice = gzip_encode(BROTLI).unwrap();
+        assert_decodes("br, gzip", &twice);
+    }
+
+    #[test]
+    fn test_decode_truncated_gzip_keeps_raw_bytes() {
+        let truncated = &GZIP[..GZIP.len() - 12];
+        let decoded = decode_body(Some("gzip"), truncated);
+        assert_eq!(decoded.body, truncated);
+        assert!(decoded.warning.is_some());
+    }
+
+    #[test]
+    fn test_decode_unknown_coding() {
+        let decoded = decode_body(Some("zstd"), PLAIN.as_bytes());
+        assert_eq!(decoded.body, PLAIN.as_bytes());
+        assert!(decoded.warning.is_some());
+    }
+
+    #[test]
+    fn test_gzip_encode_roundtrip() {
+        let encoded = gzip_encode(PLAIN.as_bytes()).unwrap();
+        assert_ne!(encoded, PLAIN.as_bytes());
+        assert_decodes("gzip", &encoded);
+    }
+}
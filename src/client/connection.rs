@@ -0,0 +1,108 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::net::SocketAddr;
+
+use gettextrs::gettext;
+
+/// The sockets used to receive a response.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub was_reused: bool,
+    pub local_addr: Option<SocketAddr>,
+    pub remote_addr: Option<SocketAddr>,
+}
+
+impl ConnectionInfo {
+    /// Describes the connection of a response, given the one of the previous response.
+    ///
+    /// curl does not tell whether a connection comes from its pool, but a reused
+    /// connection keeps the same pair of sockets as the previous request.
+    pub fn new(
+        local_addr: Option<SocketAddr>,
+        remote_addr: Option<SocketAddr>,
+        previous: Option<&ConnectionInfo>,
+    ) -> Self {
+        let was_reused = local_addr.is_some()
+            && remote_addr.is_some()
+            && previous.is_some_and(|previous| {
+                previous.local_addr == local_addr && previous.remote_addr == remote_addr
+            });
+        Self {
+            was_reused,
+            local_addr,
+            remote_addr,
+        }
+    }
+
+    /// A single line that describes the connection, such as the ones shown in tooltips.
+    pub fn summary(&self) -> String {
+        let unknown = gettext("unknown");
+        let address = |addr: Option<SocketAddr>| {
+            addr.map(|addr| addr.to_string())
+                .unwrap_or_else(|| unknown.clone())
+        };
+        let text = if self.was_reused {
+            gettext("Connection reused · local {local} → remote {remote}")
+        } else {
+            gettext("New connection · local {local} → remote {remote}")
+        };
+        text.replace("{local}", &address(self.local_addr))
+            .replace("{remote}", &address(self.remote_addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(text: &str) -> Option<SocketAddr> {
+        Some(text.parse().unwrap())
+    }
+
+    #[test]
+    fn test_connection_reused() {
+        let first = ConnectionInfo::new(addr("127.0.0.1:54321"), addr("1.2.3.4:443"), None);
+        assert!(!first.was_reused);
+
+        let second =
+            ConnectionInfo::new(addr("127.0.0.1:54321"), addr("1.2.3.4:443"), Some(&first));
+        assert!(second.was_reused);
+        assert_eq!(
+            second.summary(),
+            "Connection reused · local 127.0.0.1:54321 → remote 1.2.3.4:443"
+        );
+    }
+
+    #[test]
+    fn test_connection_not_reused() {
+        let first = ConnectionInfo::new(addr("127.0.0.1:54321"), addr("1.2.3.4:443"), None);
+        let other = ConnectionInfo::new(addr("127.0.0.1:54322"), addr("1.2.3.4:443"), Some(&first));
+        assert!(!other.was_reused);
+        assert_eq!(
+            other.summary(),
+            "New connection · local 127.0.0.1:54322 → remote 1.2.3.4:443"
+        );
+
+        let unknown = ConnectionInfo::new(None, None, Some(&ConnectionInfo::default()));
+        assert!(!unknown.was_reused);
+        assert_eq!(
+            unknown.summary(),
+            "New connection · local unknown → remote unknown"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+
+use gettextrs::gettext;
+use url::Url;
+
+use crate::utils::format_bytes;
+
+/// Suggests a name for the file where the response to the given URL is downloaded,
+/// taken from the last segment of its path.
+pub fn download_file_name(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let segment = url
+                .path_segments()?
+                .rev()
+                .find(|segment| !segment.is_empty())?
+                .to_string();
+            // Escaped slashes would turn the name into a path.
+            glib::Uri::unescape_string(&segment, Some("/\\"))
+        })
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| String::from("download"))
+}
+
+/// How far the download of a response body has gone.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DownloadProgress {
+    /// Bytes written so far.
+    pub received: u64,
+    /// The size announced by the server, if any.
+    pub total: Option<u64>,
+    /// Time since the body started to arrive.
+    pub elapsed: Duration,
+}
+
+impl DownloadProgress {
+    /// Between 0 and 1, or None if the server did not announce the size of the body.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total? {
+            0 => Some(1.0),
+            total => Some((self.received as f64 / total as f64).min(1.0)),
+        }
+    }
+
+    /// Average speed since the download started, in bytes per second.
+    pub fn speed(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            (self.received as f64 / seconds) as u64
+        } else {
+            0
+        }
+    }
+
+    /// Time left at the average speed, if the size of the body is known.
+    pub fn eta(&self) -> Option<Duration> {
+        let left = self.total?.saturating_sub(self.received);
+        match self.speed() {
+            0 => None,
+            speed => Some(Duration::from_secs(left.div_ceil(speed))),
+        }
+    }
+
+    /// A single line such as "1.2 MiB of 4.0 MiB · 512 KiB/s · 6 s left".
+    pub fn summary(&self) -> String {
+        let received = format_bytes(self.received as usize);
+        let mut text = match self.total {
+            Some(total) => gettext("{received} of {total}")
+                .replace("{received}", &received)
+                .replace("{total}", &format_bytes(total as usize)),
+            None => received,
+        };
+        let speed = self.speed();
+        if speed > 0 {
+            let speed = gettext("{size}/s").replace("{size}", &format_bytes(speed as usize));
+            text = format!("{text} · {speed}");
+        }
+        if let Some(eta) = self.eta() {
+            let eta = gettext("{count} s left").replace("{count}", &eta.as_secs().to_string());
+            text = format!("{text} · {eta}");
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_file_name() {
+        assert_eq!(
+            download_file_name("https://example.com/files/report%202024.pdf?token=1"),
+            "report 2024.pdf"
+        );
+        assert_eq!(download_file_name("https://example.com/files/"), "files");
+        assert_eq!(download_file_name("https://example.com"), "download");
+        assert_eq!(download_file_name("https://example.com/a%2Fb"), "download");
+        assert_eq!(download_file_name("not a url"), "download");
+    }
+
+    #[test]
+    fn test_progress_with_known_size() {
+        let progress = DownloadProgress {
+            received: 1000,
+            total: Some(4000),
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(progress.speed(), 500);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_progress_with_unknown_size() {
+        let progress = DownloadProgress {
+            received: 1000,
+            total: None,
+            elapsed: Duration::from_secs(1),
+        };
+        assert_eq!(progress.fraction(), None);
+        assert_eq!(progress.speed(), 1000);
+        assert_eq!(progress.eta(), None);
+    }
+
+    #[test]
+    fn test_progress_before_any_byte() {
+        let progress = DownloadProgress {
+            received: 0,
+            total: Some(0),
+            elapsed: Duration::ZERO,
+        };
+        assert_eq!(progress.fraction(), Some(1.0));
+        assert_eq!(progress.speed(), 0);
+        assert_eq!(progress.eta(), None);
+    }
+}
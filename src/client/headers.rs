@@ -0,0 +1,125 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+/// Parses the default headers stored in the settings, one `Name: Value` per line.
+///
+/// Lines without a name are skipped. `{version}` in a value is replaced by the
+/// given version, so that the User-Agent can announce the running Cartero.
+pub fn parse_default_headers(lines: &[String], version: &str) -> Vec<(String, String)> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let name = name.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let value = value.trim().replace("{version}", version);
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Computes the headers that are sent out of several layers of headers.
+///
+/// Layers are given from the lowest to the highest priority: the defaults of
+/// the application first, and the headers of the endpoint last. Header names
+/// are compared ignoring case, so a layer replaces a header of a previous layer
+/// even if it is spelled differently, and the spelling of the winning layer is
+/// used. A header with an empty value removes the header from previous layers,
+/// which allows an endpoint to stop sending a default header.
+pub fn merge_headers(layers: &[Vec<(String, String)>]) -> HashMap<String, String> {
+    let mut merged: HashMap<String, (String, String)> = HashMap::new();
+    for layer in layers {
+        for (name, value) in layer {
+            let key = name.to_lowercase();
+            if value.is_empty() {
+                merged.remove(&key);
+            } else {
+                merged.insert(key, (name.clone(), value.clone()));
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(headers: &[(&str, &str)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_default_headers() {
+        let lines = vec![
+            String::from("User-Agent: Cartero/{version}"),
+            String::from("  X-Trace :  on  "),
+            String::from("Accept: text/html: or not"),
+            String::from(": no name"),
+            String::from("no separator"),
+        ];
+        assert_eq!(
+            parse_default_headers(&lines, "0.2.0"),
+            layer(&[
+                ("User-Agent", "Cartero/0.2.0"),
+                ("X-Trace", "on"),
+                ("Accept", "text/html: or not"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_merge_headers_priority() {
+        let app = layer(&[("User-Agent", "Cartero/0.2.0"), ("Accept", "*/*")]);
+        let endpoint = layer(&[("Accept", "application/json")]);
+        let merged = merge_headers(&[app, endpoint]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged["User-Agent"], "Cartero/0.2.0");
+        assert_eq!(merged["Accept"], "application/json");
+    }
+
+    #[test]
+    fn test_merge_headers_ignores_case() {
+        let computed = layer(&[("Content-Type", "application/json")]);
+        let endpoint = layer(&[("content-type", "application/vnd.api+json")]);
+        let merged = merge_headers(&[computed, endpoint]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged["content-type"], "application/vnd.api+json");
+    }
+
+    #[test]
+    fn test_merge_headers_empty_value_removes() {
+        let app = layer(&[("User-Agent", "Cartero/0.2.0")]);
+        let endpoint = layer(&[("user-agent", "")]);
+        assert!(merge_headers(&[app, endpoint]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_headers_duplicates_in_layer() {
+        let endpoint = layer(&[("Host", "a.example.com"), ("HOST", "b.example.com")]);
+        let merged = merge_headers(&[endpoint]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged["HOST"], "b.example.com");
+    }
+}
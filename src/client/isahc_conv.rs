@@ -15,15 +15,31 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::entities::{RequestMethod, ResponseData};
+use crate::entities::{HttpVersion, HttpVersionPreference, RequestMethod, ResponseData};
 
-use super::{BoundRequest, RequestError};
-use futures_lite::io::AsyncReadExt;
+use super::{
+    decode_body, BoundRequest, ConnectionInfo, DownloadProgress, RequestError, SseEvent, SseParser,
+    Timings,
+};
+use futures_lite::{future, io::AsyncReadExt};
+use gtk::gio::{self, prelude::*};
 use isahc::{
+    config::{Configurable, VersionNegotiation},
     http::{HeaderName, HeaderValue},
-    AsyncBody, Body,
+    AsyncBody, Body, ResponseExt,
+};
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    str::FromStr,
+    time::{Duration, Instant},
 };
-use std::{io::Read, str::FromStr, time::Instant};
+
+/// How often the progress of a download is reported.
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The largest piece of a streamed body that is handed over at once.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
 
 impl From<&RequestMethod> for isahc::http::Method {
     fn from(value: &RequestMethod) -> Self {
@@ -36,15 +52,48 @@ impl From<&RequestMethod> for isahc::http::Method {
             RequestMethod::Options => isahc::http::Method::OPTIONS,
             RequestMethod::Delete => isahc::http::Method::DELETE,
             RequestMethod::Trace => isahc::http::Method::TRACE,
+            RequestMethod::Connect => isahc::http::Method::CONNECT,
+            // Custom methods are checked to be tokens when they are typed.
+            RequestMethod::Custom(method) => {
+                isahc::http::Method::from_bytes(method.as_bytes()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl From<HttpVersionPreference> for VersionNegotiation {
+    fn from(value: HttpVersionPreference) -> Self {
+        match value {
+            HttpVersionPreference::Auto => VersionNegotiation::latest_compatible(),
+            HttpVersionPreference::Http1Only => VersionNegotiation::http11(),
+            HttpVersionPreference::Http2Prior => VersionNegotiation::http2(),
         }
     }
 }
 
+fn response_version(version: isahc::http::Version) -> Option<HttpVersion> {
+    match version {
+        isahc::http::Version::HTTP_10 => Some(HttpVersion::Http10),
+        isahc::http::Version::HTTP_11 => Some(HttpVersion::Http11),
+        isahc::http::Version::HTTP_2 => Some(HttpVersion::Http2),
+        isahc::http::Version::HTTP_3 => Some(HttpVersion::Http3),
+        _ => None,
+    }
+}
+
 impl TryFrom<BoundRequest> for isahc::Request<Vec<u8>> {
     type Error = RequestError;
 
     fn try_from(req: BoundRequest) -> Result<Self, Self::Error> {
-        let mut builder = isahc::Request::builder().uri(&req.url).method(&req.method);
+        // Bodies are decompressed by Cartero itself, so that the transfer size is
+        // known and a broken body can still be displayed. Metrics are collected
+        // by curl anyway, and they feed the timings shown in the console.
+        let mut builder = isahc::Request::builder()
+            .uri(&req.url)
+            .method(&req.method)
+            .automatic_decompression(false)
+            .metrics(true)
+            .version_negotiation(req.http_version.into());
         let Some(headers) = builder.headers_mut() else {
             return Err(RequestError::InvalidHeaders);
         };
@@ -82,17 +131,18 @@ impl TryFrom<&mut isahc::Response<Body>> for ResponseData {
         Ok(ResponseData {
             duration: 0,
             size: 0,
+            transfer_size: 0,
             status_code,
             headers,
             body,
+            decode_warning: None,
+            version: response_version(value.version()),
         })
     }
 }
 
-pub async fn extract_isahc_response(
-    value: &mut isahc::Response<AsyncBody>,
-    start: &Instant,
-) -> Result<ResponseData, RequestError> {
+/// Extracts the status and the headers of a response whose body has not been read yet.
+pub fn extract_isahc_head(value: &isahc::Response<AsyncBody>, start: &Instant) -> ResponseData {
     let status_code: u32 = value.status().as_u16() as u32;
     let headers = value
         .headers()
@@ -103,6 +153,48 @@ pub async fn extract_isahc_response(
             (header_name, header_value).into()
         })
         .collect();
+    ResponseData {
+        duration: start.elapsed().as_millis(),
+        size: 0,
+        transfer_size: 0,
+        status_code,
+        headers,
+        body: Vec::new(),
+        decode_warning: None,
+        version: response_version(value.version()),
+    }
+}
+
+/// Extracts the address of the server and the timings of a response.
+///
+/// The timings are only complete once the body has been read.
+pub fn extract_isahc_timings<T>(value: &isahc::Response<T>) -> (Option<SocketAddr>, Timings) {
+    let timings = value
+        .metrics()
+        .map(|metrics| Timings {
+            dns: metrics.name_lookup_time(),
+            connect: metrics.connect_time(),
+            tls: metrics.secure_connect_time(),
+            first_byte: metrics.transfer_start_time(),
+            total: metrics.total_time(),
+        })
+        .unwrap_or_default();
+    (value.remote_addr(), timings)
+}
+
+/// Extracts the sockets of a response, given the connection of the previous response.
+pub fn extract_isahc_connection<T>(
+    value: &isahc::Response<T>,
+    previous: Option<&ConnectionInfo>,
+) -> ConnectionInfo {
+    ConnectionInfo::new(value.local_addr(), value.remote_addr(), previous)
+}
+
+pub async fn extract_isahc_response(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+) -> Result<ResponseData, RequestError> {
+    let head = extract_isahc_head(value, start);
     let body = {
         let mut buffer = Vec::new();
         let body = value.body_mut();
@@ -110,11 +202,191 @@ pub async fn extract_isahc_response(
         buffer
     };
     let duration = start.elapsed();
+    let content_encoding = head
+        .headers
+        .header("content-encoding")
+        .map(|values| values.join(", "));
+    let decoded = decode_body(content_encoding.as_deref(), &body);
     Ok(ResponseData {
         duration: duration.as_millis(),
-        size: body.len(),
-        status_code,
-        headers,
-        body,
+        size: decoded.body.len(),
+        transfer_size: body.len(),
+        body: decoded.body,
+        decode_warning: decoded.warning,
+        ..head
+    })
+}
+
+/// Reads a `text/event-stream` body as it arrives, until the server closes it
+/// or the `stop` cancellable is triggered.
+///
+/// Every event is handed to `on_event` as soon as it is complete. The events are
+/// not kept, so the returned response has an empty body, although its size
+/// accounts for every byte received.
+pub async fn read_isahc_event_stream(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+    stop: &gio::Cancellable,
+    mut on_event: impl FnMut(SseEvent),
+) -> Result<ResponseData, RequestError> {
+    let head = extract_isahc_head(value, start);
+    let mut parser = SseParser::new();
+    let mut buffer = [0u8; 8192];
+    let mut size = 0;
+    let body = value.body_mut();
+    let stopped = stop.future();
+    futures_lite::pin!(stopped);
+    loop {
+        let read = future::or(async { Some(body.read(&mut buffer).await) }, async {
+            (&mut stopped).await;
+            None
+        })
+        .await;
+        let read = match read {
+            Some(read) => read?,
+            None => break,
+        };
+        if read == 0 {
+            break;
+        }
+        size += read;
+        for event in parser.feed(&buffer[..read]) {
+            on_event(event);
+        }
+    }
+    Ok(ResponseData {
+        duration: start.elapsed().as_millis(),
+        size,
+        transfer_size: size,
+        ..head
     })
 }
+
+/// Reads the body of a response as it arrives, until the server closes it or
+/// the `stop` cancellable is triggered.
+///
+/// Every piece of the body, of up to 64 KB, is handed to `on_chunk` as soon as it
+/// is received. Unlike event streams, the body is kept, so the returned response
+/// holds everything that arrived before stopping.
+pub async fn read_isahc_body_stream(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+    stop: &gio::Cancellable,
+    mut on_chunk: impl FnMut(&[u8]),
+) -> Result<ResponseData, RequestError> {
+    let head = extract_isahc_head(value, start);
+    let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut received = Vec::new();
+    let body = value.body_mut();
+    let stopped = stop.future();
+    futures_lite::pin!(stopped);
+    loop {
+        let read = future::or(async { Some(body.read(&mut buffer).await) }, async {
+            (&mut stopped).await;
+            None
+        })
+        .await;
+        let read = match read {
+            Some(read) => read?,
+            None => break,
+        };
+        if read == 0 {
+            break;
+        }
+        on_chunk(&buffer[..read]);
+        received.extend_from_slice(&buffer[..read]);
+    }
+    let content_encoding = head
+        .headers
+        .header("content-encoding")
+        .map(|values| values.join(", "));
+    let decoded = decode_body(content_encoding.as_deref(), &received);
+    Ok(ResponseData {
+        duration: start.elapsed().as_millis(),
+        size: decoded.body.len(),
+        transfer_size: received.len(),
+        body: decoded.body,
+        decode_warning: decoded.warning,
+        ..head
+    })
+}
+
+/// Writes the body of a response into `output` as it arrives, until the server
+/// closes it. The download fails with `RequestError::Cancelled` if the `stop`
+/// cancellable is triggered.
+///
+/// The body is written as received, without decompressing it.
+pub async fn download_isahc_body(
+    value: &mut isahc::Response<AsyncBody>,
+    output: &mut impl Write,
+    stop: &gio::Cancellable,
+    mut on_progress: impl FnMut(&DownloadProgress),
+) -> Result<DownloadProgress, RequestError> {
+    let start = Instant::now();
+    let mut progress = DownloadProgress {
+        total: value.body().len(),
+        ..Default::default()
+    };
+    let mut reported = start;
+    let mut buffer = [0u8; 8192];
+    let body = value.body_mut();
+    let stopped = stop.future();
+    futures_lite::pin!(stopped);
+    loop {
+        let read = future::or(async { Some(body.read(&mut buffer).await) }, async {
+            (&mut stopped).await;
+            None
+        })
+        .await;
+        let read = match read {
+            Some(read) => read?,
+            None => return Err(RequestError::Cancelled),
+        };
+        if read == 0 {
+            break;
+        }
+        output.write_all(&buffer[..read])?;
+        progress.received += read as u64;
+        progress.elapsed = start.elapsed();
+        if reported.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL {
+            on_progress(&progress);
+            reported = Instant::now();
+        }
+    }
+    output.flush()?;
+    progress.elapsed = start.elapsed();
+    on_progress(&progress);
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_body_stream() {
+        let body: Vec<u8> = (0..STREAM_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        let mut response = isahc::Response::builder()
+            .status(200)
+            .header("Content-Type", "application/octet-stream")
+            .body(AsyncBody::from(body.clone()))
+            .unwrap();
+        let mut chunks = Vec::new();
+        let stop = gio::Cancellable::new();
+        let outcome = future::block_on(read_isahc_body_stream(
+            &mut response,
+            &Instant::now(),
+            &stop,
+            |chunk| chunks.push(chunk.len()),
+        ))
+        .unwrap();
+
+        assert!(chunks
+            .iter()
+            .all(|&len| len > 0 && len <= STREAM_CHUNK_SIZE));
+        assert_eq!(chunks.iter().sum::<usize>(), body.len());
+        assert_eq!(outcome.status_code, 200);
+        assert_eq!(outcome.body, body);
+        assert_eq!(outcome.size, body.len());
+    }
+}
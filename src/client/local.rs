@@ -16,16 +16,24 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use formdata::FormData;
+use gettextrs::gettext;
 use isahc::http::header::{InvalidHeaderName, InvalidHeaderValue};
 use srtemplate::SrTemplate;
 use std::{
     collections::HashMap,
+    fmt,
     io::{BufWriter, Write},
 };
 use thiserror::Error;
 
+use super::{
+    current_amz_date, gzip_encode, merge_headers, sign_request, SigV4Params, ACCEPT_ENCODING,
+};
 use crate::{
-    entities::{EndpointData, KeyValueTable, RawEncoding, RequestMethod, RequestPayload},
+    entities::{
+        BodyCharset, EndpointData, HttpVersionPreference, KeyValueTable, RawEncoding,
+        RequestMethod, RequestPayload,
+    },
     error::CarteroError,
 };
 
@@ -35,6 +43,7 @@ pub struct BoundRequest {
     pub method: RequestMethod,
     pub headers: HashMap<String, String>,
     pub body: Option<Vec<u8>>,
+    pub http_version: HttpVersionPreference,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -102,6 +111,7 @@ fn bind_multipart_payload(
 
 fn bind_raw_payload(
     body: &[u8],
+    charset: BodyCharset,
     processor: &SrTemplate,
 ) -> Result<Option<BoundBody>, CarteroError> {
     if body.is_empty() {
@@ -109,7 +119,7 @@ fn bind_raw_payload(
     }
     let processable_body = String::from_utf8_lossy(body);
     let processed_body = processor.render(processable_body)?;
-    let body = Vec::from(processed_body.as_str());
+    let body = charset.encode(&processed_body);
     Ok(Some(BoundBody {
         content: body,
         boundary: String::default(),
@@ -126,8 +136,15 @@ fn bind_payload(
         RequestPayload::Multipart { params } => bind_multipart_payload(params, processor),
         RequestPayload::Raw {
             content,
-            encoding: _,
-        } => bind_raw_payload(content, processor),
+            file: Some(_),
+            ..
+        } if std::str::from_utf8(content).is_err() => Ok(Some(BoundBody {
+            content: content.clone(),
+            boundary: String::default(),
+        })),
+        RequestPayload::Raw {
+            content, charset, ..
+        } => bind_raw_payload(content, *charset, processor),
     }
 }
 
@@ -135,12 +152,40 @@ impl TryFrom<EndpointData> for BoundRequest {
     type Error = CarteroError;
 
     fn try_from(value: EndpointData) -> Result<Self, Self::Error> {
+        Self::bind(value, &[])
+    }
+}
+
+impl BoundRequest {
+    /// Binds an endpoint, sending the given default headers unless the endpoint overrides them.
+    pub fn bind(
+        value: EndpointData,
+        default_headers: &[(String, String)],
+    ) -> Result<Self, CarteroError> {
+        Self::bind_with(value, default_headers, |_| Ok(()))
+    }
+
+    /// Binds an endpoint, letting `prepare` change the request before it is signed.
+    pub fn bind_with(
+        value: EndpointData,
+        default_headers: &[(String, String)],
+        prepare: impl FnOnce(&mut BoundRequest) -> Result<(), CarteroError>,
+    ) -> Result<Self, CarteroError> {
         let processor = value.template_processor();
 
         let url = processor.render(&value.url)?;
         let method = value.method.clone();
+        if RequestMethod::try_from(method.as_str()).is_err() {
+            return Err(RequestError::InvalidHttpVerb.into());
+        }
 
-        let body = bind_payload(&value.body, &processor)?;
+        let mut body = bind_payload(&value.body, &processor)?;
+        let compress_body = value.compression.compress_body && body.is_some();
+        if compress_body {
+            if let Some(ref mut bound) = body {
+                bound.content = gzip_encode(&bound.content).map_err(RequestError::IOError)?;
+            }
+        }
         let content_type = match value.body {
             RequestPayload::None => None,
             RequestPayload::Urlencoded(_) => Some("application/x-www-form-urlencoded".to_string()),
@@ -150,19 +195,36 @@ impl TryFrom<EndpointData> for BoundRequest {
             )),
             RequestPayload::Raw {
                 ref encoding,
-                content: _,
-            } => match encoding {
-                RawEncoding::OctetStream => Some("application/octet-stream".into()),
-                RawEncoding::Xml => Some("application/xml".into()),
-                RawEncoding::Json => Some("application/json".into()),
-            },
+                charset,
+                ..
+            } => {
+                let mime = match encoding {
+                    RawEncoding::OctetStream => "application/octet-stream",
+                    RawEncoding::Xml => "application/xml",
+                    RawEncoding::Json => "application/json",
+                };
+                match charset {
+                    BodyCharset::Utf8 => Some(mime.into()),
+                    charset => Some(format!("{mime}; charset={}", charset.label())),
+                }
+            }
         };
 
-        let mut base_headers = HashMap::new();
+        let mut base_headers = Vec::new();
         if let Some(content_type) = content_type {
-            base_headers.insert("Content-Type".to_string(), content_type);
+            base_headers.push(("Content-Type".to_string(), content_type));
+        }
+        if compress_body {
+            base_headers.push(("Content-Encoding".to_string(), "gzip".to_string()));
         }
-        base_headers.extend(value.process_headers());
+        if value.compression.accept_compressed {
+            base_headers.push(("Accept-Encoding".to_string(), ACCEPT_ENCODING.to_string()));
+        }
+        let base_headers = merge_headers(&[
+            default_headers.to_vec(),
+            base_headers,
+            value.process_headers(),
+        ]);
 
         let headers: Result<HashMap<String, String>, CarteroError> = base_headers
             .iter()
@@ -174,45 +236,96 @@ impl TryFrom<EndpointData> for BoundRequest {
             .collect();
         let headers = headers?;
 
-        Ok(Self {
+        let mut request = Self {
             url,
             method,
             headers,
             body: body.map(|b| b.content),
-        })
+            http_version: value.http_version,
+        };
+        prepare(&mut request)?;
+        // The signature covers the final URL, headers and body, so it goes last.
+        if let Some(aws) = &value.aws_sigv4 {
+            let access_key = processor.render(&aws.access_key)?;
+            let secret_key = processor.render(&aws.secret_key)?;
+            let session_token = processor.render(&aws.session_token)?;
+            let region = processor.render(&aws.region)?;
+            let service = processor.render(&aws.service)?;
+            let amz_date = current_amz_date();
+            let params = SigV4Params {
+                access_key: &access_key,
+                secret_key: &secret_key,
+                session_token: Some(session_token.as_str()).filter(|t| !t.is_empty()),
+                region: &region,
+                service: &service,
+                amz_date: &amz_date,
+            };
+            sign_request(&mut request, &params)?;
+        }
+        Ok(request)
     }
 }
 
 #[derive(Error, Debug)]
 pub enum RequestError {
-    #[error("Illegal HTTP verb")]
     InvalidHttpVerb,
 
-    #[error("Invalid headers state")]
     InvalidHeaders,
 
-    #[error("Invalid payload state")]
     InvalidPayload,
 
-    #[error("Illegal header")]
     InvalidHeaderName(#[from] InvalidHeaderName),
 
-    #[error("Illegal header value")]
     InvalidHeaderValue(#[from] InvalidHeaderValue),
 
-    #[error("Request error")]
     NetworkError(#[from] isahc::error::Error),
 
-    #[error("HTTP error")]
     HttpError(#[from] isahc::http::Error),
 
-    #[error("Unknown I/O error")]
     IOError(#[from] std::io::Error),
+
+    Cancelled,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            RequestError::InvalidHttpVerb => gettext("Illegal HTTP verb"),
+            RequestError::InvalidHeaders => gettext("Invalid headers state"),
+            RequestError::InvalidPayload => gettext("Invalid payload state"),
+            RequestError::InvalidHeaderName(_) => gettext("Illegal header"),
+            RequestError::InvalidHeaderValue(_) => gettext("Illegal header value"),
+            RequestError::NetworkError(_) => gettext("Request error"),
+            RequestError::HttpError(_) => gettext("HTTP error"),
+            RequestError::IOError(_) => gettext("Unknown I/O error"),
+            RequestError::Cancelled => gettext("The request was cancelled"),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl RequestError {
+    /// Whether the error means that the connection to the server failed or dropped.
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            RequestError::NetworkError(e) => matches!(
+                e.kind(),
+                isahc::error::ErrorKind::ConnectionFailed
+                    | isahc::error::ErrorKind::Io
+                    | isahc::error::ErrorKind::Timeout
+            ),
+            RequestError::IOError(_) => true,
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::entities::KeyValueTable;
+    use sha2::{Digest, Sha256};
+
+    use crate::client::decode_body;
+    use crate::entities::{AwsSigV4, CompressionOptions, KeyValueTable};
 
     use super::*;
 
@@ -243,6 +356,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -283,6 +397,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -320,7 +435,9 @@ mod tests {
         let variables = KeyValueTable::new(&variables);
         let body = RequestPayload::Raw {
             encoding: RawEncoding::Json,
+            charset: BodyCharset::Utf8,
             content: Vec::from(b"{\"hello\": \"world\"}"),
+            file: None,
         };
         let endpoint = EndpointData {
             url,
@@ -328,6 +445,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -361,7 +479,9 @@ mod tests {
         let variables = KeyValueTable::new(&variables);
         let body = RequestPayload::Raw {
             encoding: RawEncoding::Xml,
+            charset: BodyCharset::Utf8,
             content: Vec::from(b"<envelope>1234</envelope>"),
+            file: None,
         };
         let endpoint = EndpointData {
             url,
@@ -369,6 +489,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -402,7 +523,9 @@ mod tests {
         let variables = KeyValueTable::new(&variables);
         let body = RequestPayload::Raw {
             encoding: RawEncoding::OctetStream,
+            charset: BodyCharset::Utf8,
             content: Vec::from(b"12341234"),
+            file: None,
         };
         let endpoint = EndpointData {
             url,
@@ -410,6 +533,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -424,6 +548,170 @@ mod tests {
         assert_eq!(body, "12341234");
     }
 
+    #[test]
+    pub fn test_bind_of_latin1_body() {
+        let body = RequestPayload::Raw {
+            encoding: RawEncoding::Xml,
+            charset: BodyCharset::Latin1,
+            content: Vec::from("<name>{{NAME}}</name>"),
+            file: None,
+        };
+        let endpoint = EndpointData {
+            url: "https://www.example.com/v1/books".into(),
+            method: RequestMethod::Post,
+            variables: KeyValueTable::new(&[("NAME", "Begoña").into()]),
+            body,
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        assert_eq!(
+            bound.headers["Content-Type"],
+            "application/xml; charset=windows-1252"
+        );
+
+        let body = bound.body.unwrap();
+        assert_eq!(body, b"<name>Bego\xF1a</name>");
+        assert_eq!(BodyCharset::Latin1.decode(&body), "<name>Begoña</name>");
+    }
+
+    #[test]
+    pub fn test_bind_of_binary_file_body() {
+        let png = vec![0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0xff];
+        let body = RequestPayload::Raw {
+            encoding: RawEncoding::OctetStream,
+            charset: BodyCharset::Latin1,
+            content: png.clone(),
+            file: Some("/tmp/image.png".into()),
+        };
+        let endpoint = EndpointData {
+            url: "https://www.example.com/v1/upload".into(),
+            method: RequestMethod::Post,
+            body,
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        assert_eq!(bound.body.unwrap(), png);
+    }
+
+    #[test]
+    pub fn test_bind_with_compression() {
+        let body = RequestPayload::Raw {
+            encoding: RawEncoding::Json,
+            charset: BodyCharset::Utf8,
+            content: Vec::from("{\"name\": \"John\"}"),
+            file: None,
+        };
+        let endpoint = EndpointData {
+            url: "https://www.example.com/v1/books".into(),
+            method: RequestMethod::Post,
+            body,
+            compression: CompressionOptions {
+                accept_compressed: true,
+                compress_body: true,
+            },
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::try_from(endpoint.clone()).unwrap();
+        assert_eq!(bound.headers["Accept-Encoding"], ACCEPT_ENCODING);
+        assert_eq!(bound.headers["Content-Encoding"], "gzip");
+        let decoded = decode_body(Some("gzip"), &bound.body.unwrap());
+        assert_eq!(decoded.body, b"{\"name\": \"John\"}");
+
+        let endpoint = EndpointData {
+            compression: CompressionOptions {
+                accept_compressed: false,
+                compress_body: false,
+            },
+            ..endpoint
+        };
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        assert!(!bound.headers.contains_key("Accept-Encoding"));
+        assert!(!bound.headers.contains_key("Content-Encoding"));
+        assert_eq!(bound.body, Some(Vec::from("{\"name\": \"John\"}")));
+    }
+
+    #[test]
+    pub fn test_bind_with_aws_sigv4() {
+        let endpoint = EndpointData {
+            url: "https://examplebucket.s3.amazonaws.com/{{KEY}}".into(),
+            method: RequestMethod::Put,
+            body: RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                charset: BodyCharset::Utf8,
+                content: Vec::from("{\"name\": \"John\"}"),
+                file: None,
+            },
+            variables: KeyValueTable::new(&[
+                ("KEY", "books.json").into(),
+                ("AWS_SECRET", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY").into(),
+            ]),
+            compression: CompressionOptions {
+                accept_compressed: false,
+                compress_body: true,
+            },
+            aws_sigv4: Some(AwsSigV4 {
+                access_key: "AKIDEXAMPLE".into(),
+                secret_key: "{{AWS_SECRET}}".into(),
+                session_token: String::new(),
+                region: "us-east-1".into(),
+                service: "s3".into(),
+            }),
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        assert_eq!(
+            bound.url,
+            "https://examplebucket.s3.amazonaws.com/books.json"
+        );
+        assert!(
+            bound.headers["Authorization"].starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/")
+        );
+        assert!(bound.headers["Authorization"].contains("/us-east-1/s3/aws4_request"));
+        assert!(!bound.headers.contains_key("X-Amz-Security-Token"));
+
+        // The payload hash is computed over the compressed body.
+        let body = bound.body.unwrap();
+        let hash: String = Sha256::digest(&body)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert_eq!(bound.headers["X-Amz-Content-Sha256"], hash);
+    }
+
+    #[test]
+    pub fn test_bind_with_prepares_before_signing() {
+        let endpoint = EndpointData {
+            url: "https://examplebucket.s3.amazonaws.com/books.json".into(),
+            method: RequestMethod::Get,
+            aws_sigv4: Some(AwsSigV4 {
+                access_key: "AKIDEXAMPLE".into(),
+                secret_key: "secret".into(),
+                session_token: String::new(),
+                region: "us-east-1".into(),
+                service: "s3".into(),
+            }),
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::bind_with(endpoint.clone(), &[], |request| {
+            assert!(!request.headers.contains_key("Authorization"));
+            request.url.push_str("?versionId=2");
+            Ok(())
+        })
+        .unwrap();
+        assert!(bound.url.ends_with("?versionId=2"));
+        assert!(bound.headers.contains_key("Authorization"));
+
+        let failed = BoundRequest::bind_with(endpoint, &[], |_| {
+            Err(CarteroError::ScriptError("broken".into()))
+        });
+        assert!(matches!(failed, Err(CarteroError::ScriptError(_))));
+    }
+
     #[test]
     pub fn test_bind_of_parameters_may_still_override_header() {
         // Build a request.
@@ -444,7 +732,9 @@ mod tests {
         let variables = KeyValueTable::new(&variables);
         let body = RequestPayload::Raw {
             encoding: RawEncoding::Json,
+            charset: BodyCharset::Utf8,
             content: Vec::from(b"{\"hello\": \"world\"}"),
+            file: None,
         };
         let endpoint = EndpointData {
             url,
@@ -452,6 +742,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -482,12 +773,63 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         let bound = BoundRequest::try_from(endpoint).unwrap();
         assert_eq!(bound.headers["Accept"], "application/xml")
     }
 
+    #[test]
+    fn test_bind_custom_method() {
+        let endpoint = EndpointData {
+            url: "https://www.example.com/dav".into(),
+            method: RequestMethod::Custom("PROPFIND".into()),
+            ..Default::default()
+        };
+        let bound = BoundRequest::try_from(endpoint.clone()).unwrap();
+        assert_eq!(bound.method.as_str(), "PROPFIND");
+
+        let endpoint = EndpointData {
+            method: RequestMethod::Custom("PROP FIND".into()),
+            ..endpoint
+        };
+        assert!(BoundRequest::try_from(endpoint).is_err());
+    }
+
+    #[test]
+    fn test_bind_with_default_headers() {
+        let headers = KeyValueTable::new(&[
+            ("accept", "application/json").into(),
+            ("User-Agent", "").into(),
+            ("content-type", "application/vnd.api+json").into(),
+        ]);
+        let endpoint = EndpointData {
+            url: "https://www.example.com/v1/books".into(),
+            method: RequestMethod::Post,
+            headers,
+            body: RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(b"{}"),
+                file: None,
+            },
+            ..Default::default()
+        };
+        let defaults = vec![
+            ("User-Agent".to_string(), "Cartero/0.2.0".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+            ("X-Client".to_string(), "cartero".to_string()),
+        ];
+
+        let bound = BoundRequest::bind(endpoint, &defaults).unwrap();
+        assert_eq!(bound.headers.len(), 4);
+        assert!(!bound.headers.contains_key("User-Agent"));
+        assert_eq!(bound.headers["accept"], "application/json");
+        assert_eq!(bound.headers["content-type"], "application/vnd.api+json");
+        assert_eq!(bound.headers["X-Client"], "cartero");
+    }
+
     #[test]
     fn test_bind_with_duplicate_variables() {
         let url = "https://www.example.com/v1/books".into();
@@ -504,6 +846,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         let bound = BoundRequest::try_from(endpoint).unwrap();
@@ -534,6 +877,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -15,8 +15,33 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod compression;
+mod connection;
+mod download;
+mod headers;
 mod isahc_conv;
 mod local;
+mod preview;
+mod query;
+mod retry;
+mod sigv4;
+mod sse;
+mod trace;
+mod websocket;
 
-pub use isahc_conv::extract_isahc_response;
+pub use compression::{decode_body, gzip_encode, ACCEPT_ENCODING};
+pub use connection::ConnectionInfo;
+pub use download::{download_file_name, DownloadProgress};
+pub use headers::{merge_headers, parse_default_headers};
+pub use isahc_conv::{
+    download_isahc_body, extract_isahc_connection, extract_isahc_head, extract_isahc_response,
+    extract_isahc_timings, read_isahc_body_stream, read_isahc_event_stream,
+};
 pub use local::*;
+pub use preview::PREVIEW_BODY_LIMIT;
+pub use query::{build_url_with_params, parse_query_params};
+pub use retry::send_with_retries;
+pub use sigv4::{current_amz_date, sign_request, SigV4Params};
+pub use sse::{SseEvent, SseParser};
+pub use trace::*;
+pub use websocket::*;
@@ -0,0 +1,229 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::ngettext;
+use url::Url;
+
+use super::BoundRequest;
+
+/// How many bytes of the request body are rendered in the preview.
+pub const PREVIEW_BODY_LIMIT: usize = 16 * 1024;
+
+impl BoundRequest {
+    /// Derives the value of the Host header from the request URL.
+    ///
+    /// The port is only part of the value when it is not the default port for the scheme.
+    pub fn host(&self) -> Option<String> {
+        let url = Url::parse(&self.url).ok()?;
+        let host = url.host_str()?;
+        match url.port() {
+            Some(port) => Some(format!("{host}:{port}")),
+            None => Some(host.to_string()),
+        }
+    }
+
+    /// Returns the headers in the order they are presented in the preview.
+    ///
+    /// The Host header always goes first, followed by the rest of the headers sorted
+    /// by name. A Content-Length header is added when the request has a body and it
+    /// was not explicitly set, since the HTTP client will add it anyway.
+    pub fn ordered_headers(&self) -> Vec<(String, String)> {
+        let mut host = self.host();
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for (name, value) in &self.headers {
+            if name.eq_ignore_ascii_case("host") {
+                host = Some(value.clone());
+            } else {
+                headers.push((name.clone(), value.clone()));
+            }
+        }
+
+        let has_length = headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-length"));
+        if let Some(body) = &self.body {
+            if !has_length && !body.is_empty() {
+                headers.push(("Content-Length".into(), body.len().to_string()));
+            }
+        }
+        headers.sort_by_key(|(name, _)| name.to_lowercase());
+
+        if let Some(host) = host {
+            headers.insert(0, ("Host".into(), host));
+        }
+        headers
+    }
+
+    /// Returns the target of the request line: the path and the query of the URL.
+    fn request_target(&self) -> String {
+        match Url::parse(&self.url) {
+            Ok(url) => match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            },
+            Err(_) => self.url.clone(),
+        }
+    }
+
+    /// Renders the request as the raw HTTP/1.1 message that would be sent.
+    ///
+    /// Bodies that are not valid UTF-8 are summarized instead of printed, and text
+    /// bodies are cut after `body_limit` bytes.
+    pub fn to_http_text(&self, body_limit: usize) -> String {
+        let method = self.method.as_str();
+        let mut text = format!("{method} {} HTTP/1.1\r\n", self.request_target());
+        for (name, value) in self.ordered_headers() {
+            text.push_str(&format!("{name}: {value}\r\n"));
+        }
+        text.push_str("\r\n");
+
+        if let Some(body) = self.body.as_ref().filter(|b| !b.is_empty()) {
+            match std::str::from_utf8(body) {
+                Ok(content) if content.len() <= body_limit => text.push_str(content),
+                Ok(content) => {
+                    let mut cut = body_limit;
+                    while !content.is_char_boundary(cut) {
+                        cut -= 1;
+                    }
+                    text.push_str(&content[..cut]);
+                    let rest = content.len() - cut;
+                    let more = ngettext(
+                        "[... {count} more byte]",
+                        "[... {count} more bytes]",
+                        rest as u32,
+                    )
+                    .replace("{count}", &rest.to_string());
+                    text.push('\n');
+                    text.push_str(&more);
+                }
+                Err(_) => {
+                    let binary = ngettext(
+                        "[binary body, {count} byte]",
+                        "[binary body, {count} bytes]",
+                        body.len() as u32,
+                    )
+                    .replace("{count}", &body.len().to_string());
+                    text.push_str(&binary);
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::entities::RequestMethod;
+
+    use super::*;
+
+    fn request(url: &str, headers: &[(&str, &str)], body: Option<&[u8]>) -> BoundRequest {
+        BoundRequest {
+            url: url.into(),
+            method: RequestMethod::Post,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<String, String>>(),
+            body: body.map(Vec::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_host_derivation() {
+        let cases = [
+            ("https://api.example.com/v1/books", Some("api.example.com")),
+            ("https://api.example.com:443/v1", Some("api.example.com")),
+            ("http://localhost:8080/", Some("localhost:8080")),
+            ("http://127.0.0.1/status", Some("127.0.0.1")),
+            ("not an url", None),
+        ];
+        for (url, expected) in cases {
+            let req = request(url, &[], None);
+            assert_eq!(req.host().as_deref(), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_explicit_host_header_wins() {
+        let req = request("http://localhost:8080/", &[("host", "example.com")], None);
+        let headers = req.ordered_headers();
+        assert_eq!(headers[0], ("Host".into(), "example.com".into()));
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn test_header_ordering() {
+        let req = request(
+            "https://example.com/",
+            &[
+                ("X-Request-Id", "1"),
+                ("accept", "application/json"),
+                ("Content-Type", "application/json"),
+                ("Authorization", "Bearer abc"),
+            ],
+            Some(b"{}"),
+        );
+        let names: Vec<String> = req.ordered_headers().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Host",
+                "accept",
+                "Authorization",
+                "Content-Length",
+                "Content-Type",
+                "X-Request-Id",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_http_text() {
+        let req = request(
+            "https://example.com/v1/books?page=2",
+            &[("Content-Type", "application/json")],
+            Some(b"{\"hello\": \"world\"}"),
+        );
+        let text = req.to_http_text(PREVIEW_BODY_LIMIT);
+        assert_eq!(
+            text,
+            "POST /v1/books?page=2 HTTP/1.1\r\n\
+             Host: example.com\r\n\
+             Content-Length: 18\r\n\
+             Content-Type: application/json\r\n\
+             \r\n\
+             {\"hello\": \"world\"}"
+        );
+    }
+
+    #[test]
+    fn test_to_http_text_binary_and_truncated_bodies() {
+        let binary = request("https://example.com/", &[], Some(&[0xff, 0xfe, 0x00, 0x01]));
+        assert!(binary
+            .to_http_text(PREVIEW_BODY_LIMIT)
+            .ends_with("\r\n\r\n[binary body, 4 bytes]"));
+
+        let long = request("https://example.com/", &[], Some(b"abcdefghij"));
+        assert!(long
+            .to_http_text(4)
+            .ends_with("\r\n\r\nabcd\n[... 6 more bytes]"));
+    }
+}
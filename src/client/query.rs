@@ -0,0 +1,139 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use url::form_urlencoded;
+
+/// Characters escaped in the names and values of the query parameters. The
+/// braces are kept, so that variables such as `{{token}}` still work.
+const QUERY_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'{')
+    .remove(b'}');
+
+/// Splits a URL into the part before the query, the query and the fragment.
+fn split_url(url: &str) -> (&str, Option<&str>, Option<&str>) {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    match rest.split_once('?') {
+        Some((base, query)) => (base, Some(query), fragment),
+        None => (rest, None, fragment),
+    }
+}
+
+/// The decoded query parameters of a URL, in the order they are written.
+///
+/// The URL does not have to be valid, so that a URL starting with a variable,
+/// such as `{{base}}/users?page=2`, can be edited as well.
+pub fn parse_query_params(url: &str) -> Vec<(String, String)> {
+    let (_, query, _) = split_url(url);
+    query
+        .map(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Replaces the query of a URL with the given parameters, escaping them.
+///
+/// The fragment is kept. The query is removed if there are no parameters.
+pub fn build_url_with_params(base: &str, params: &[(String, String)]) -> String {
+    let (base, _, fragment) = split_url(base);
+    let mut url = base.to_string();
+    let query: Vec<String> = params
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(name, QUERY_COMPONENT),
+                utf8_percent_encode(value, QUERY_COMPONENT)
+            )
+        })
+        .collect();
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_url_with_params, parse_query_params};
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_query_params() {
+        assert!(parse_query_params("https://example.com/users").is_empty());
+        assert_eq!(
+            parse_query_params("https://example.com/search?q=caf%C3%A9+au+lait&page=2#top"),
+            pairs(&[("q", "café au lait"), ("page", "2")])
+        );
+        assert_eq!(
+            parse_query_params("{{base}}/users?id={{id}}&tag=a&tag=b"),
+            pairs(&[("id", "{{id}}"), ("tag", "a"), ("tag", "b")])
+        );
+        assert_eq!(
+            parse_query_params("https://example.com/?a%26b=1%3D2&empty"),
+            pairs(&[("a&b", "1=2"), ("empty", "")])
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_params() {
+        assert_eq!(
+            build_url_with_params("https://example.com/search?old=1#top", &pairs(&[])),
+            "https://example.com/search#top"
+        );
+        assert_eq!(
+            build_url_with_params(
+                "https://example.com/search",
+                &pairs(&[("q", "café au lait"), ("a&b", "1=2")])
+            ),
+            "https://example.com/search?q=caf%C3%A9%20au%20lait&a%26b=1%3D2"
+        );
+        assert_eq!(
+            build_url_with_params("{{base}}/users", &pairs(&[("id", "{{id}}")])),
+            "{{base}}/users?id={{id}}"
+        );
+    }
+
+    #[test]
+    fn test_query_params_round_trip() {
+        let params = pairs(&[("q", "50% off + free"), ("ñ", "/path?x#y")]);
+        let url = build_url_with_params("https://example.com/#section", &params);
+        assert_eq!(parse_query_params(&url), params);
+        assert!(url.ends_with("#section"));
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures_lite::future;
+use gtk::gio::{self, prelude::*};
+
+use crate::entities::{ResponseData, RetryPolicy};
+
+use super::RequestError;
+
+/// Whether the outcome of an attempt is a failure that the policy wants to retry.
+fn should_retry(policy: &RetryPolicy, outcome: &Result<ResponseData, RequestError>) -> bool {
+    match outcome {
+        Ok(response) => match response.status_code {
+            502..=504 => policy.on_gateway_error || policy.on_server_error,
+            500..=599 => policy.on_server_error,
+            _ => false,
+        },
+        Err(e) => policy.on_connection_error && e.is_connection_error(),
+    }
+}
+
+/// Awaits the given future unless the cancellable is triggered first.
+async fn or_cancelled<T>(
+    cancellable: &gio::Cancellable,
+    fut: impl Future<Output = T>,
+) -> Result<T, RequestError> {
+    if cancellable.is_cancelled() {
+        return Err(RequestError::Cancelled);
+    }
+    let cancelled = cancellable.future();
+    future::or(async { Ok(fut.await) }, async {
+        cancelled.await;
+        Err(RequestError::Cancelled)
+    })
+    .await
+}
+
+/// Sends a request using the given function, retrying it according to the policy.
+///
+/// `on_retry` is called with the number of the attempt that is about to start,
+/// before waiting for the backoff. The cancellable stops the loop immediately,
+/// whether it is waiting for a response or for the backoff to expire.
+///
+/// On success, returns the last response and the number of attempts made.
+pub async fn send_with_retries<F, Fut>(
+    policy: &RetryPolicy,
+    cancellable: &gio::Cancellable,
+    mut send: F,
+    mut on_retry: impl FnMut(u32),
+) -> Result<(ResponseData, u32), RequestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ResponseData, RequestError>>,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let outcome = or_cancelled(cancellable, send()).await?;
+        if attempt >= max_attempts || !should_retry(policy, &outcome) {
+            return outcome.map(|response| (response, attempt));
+        }
+
+        attempt += 1;
+        on_retry(attempt);
+        let backoff = glib::timeout_future(Duration::from_millis(policy.backoff_before(attempt)));
+        or_cancelled(cancellable, backoff).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::client::extract_isahc_response;
+
+    /// Spawns an HTTP server that answers 503 to the first `failures` requests.
+    fn flaky_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    break;
+                };
+                let mut buffer = [0u8; 4096];
+                let _ = stream.read(&mut buffer);
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let response = if hit < failures {
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (format!("http://{addr}/"), hits)
+    }
+
+    async fn get(url: String) -> Result<ResponseData, RequestError> {
+        let start = Instant::now();
+        let mut response = isahc::get_async(url).await?;
+        extract_isahc_response(&mut response, &start).await
+    }
+
+    fn policy(max_attempts: u32, backoff: u64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff,
+            exponential_backoff: false,
+            on_connection_error: true,
+            on_gateway_error: true,
+            on_server_error: false,
+        }
+    }
+
+    #[test]
+    fn test_retries_until_success() {
+        let (url, hits) = flaky_server(2);
+        let policy = policy(3, 10);
+        let cancellable = gio::Cancellable::new();
+        let mut retries = Vec::new();
+
+        let ctx = glib::MainContext::new();
+        let outcome = ctx.block_on(send_with_retries(
+            &policy,
+            &cancellable,
+            || get(url.clone()),
+            |attempt| retries.push(attempt),
+        ));
+
+        let (response, attempts) = outcome.unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"ok");
+        assert_eq!(attempts, 3);
+        assert_eq!(retries, vec![2, 3]);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (url, hits) = flaky_server(5);
+        let policy = policy(2, 10);
+        let cancellable = gio::Cancellable::new();
+
+        let ctx = glib::MainContext::new();
+        let outcome = ctx.block_on(send_with_retries(
+            &policy,
+            &cancellable,
+            || get(url.clone()),
+            |_| {},
+        ));
+
+        let (response, attempts) = outcome.unwrap();
+        assert_eq!(response.status_code, 503);
+        assert_eq!(attempts, 2);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_does_not_retry_disabled_conditions() {
+        let (url, hits) = flaky_server(1);
+        let policy = RetryPolicy {
+            on_gateway_error: false,
+            ..policy(3, 10)
+        };
+        let cancellable = gio::Cancellable::new();
+
+        let ctx = glib::MainContext::new();
+        let outcome = ctx.block_on(send_with_retries(
+            &policy,
+            &cancellable,
+            || get(url.clone()),
+            |_| {},
+        ));
+
+        let (response, attempts) = outcome.unwrap();
+        assert_eq!(response.status_code, 503);
+        assert_eq!(attempts, 1);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retries_connection_errors() {
+        // Bind and drop a listener to get a port where nobody is listening.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{addr}/");
+        let policy = policy(2, 10);
+        let cancellable = gio::Cancellable::new();
+        let mut retries = 0;
+
+        let ctx = glib::MainContext::new();
+        let outcome = ctx.block_on(send_with_retries(
+            &policy,
+            &cancellable,
+            || get(url.clone()),
+            |_| retries += 1,
+        ));
+
+        assert!(outcome.is_err_and(|e| e.is_connection_error()));
+        assert_eq!(retries, 1);
+    }
+
+    #[test]
+    fn test_cancel_during_backoff() {
+        let (url, hits) = flaky_server(5);
+        let policy = policy(3, 60_000);
+        let cancellable = gio::Cancellable::new();
+
+        let ctx = glib::MainContext::new();
+        let start = Instant::now();
+        let outcome = ctx.block_on(send_with_retries(
+            &policy,
+            &cancellable,
+            || get(url.clone()),
+            |_| cancellable.cancel(),
+        ));
+
+        assert!(matches!(outcome, Err(RequestError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_should_retry_server_errors() {
+        let response = |status_code| {
+            Ok(ResponseData {
+                status_code,
+                duration: 0,
+                size: 0,
+                transfer_size: 0,
+                headers: Default::default(),
+                body: Vec::new(),
+                decode_warning: None,
+                version: None,
+            })
+        };
+        let gateway = policy(3, 10);
+        assert!(should_retry(&gateway, &response(503)));
+        assert!(!should_retry(&gateway, &response(500)));
+        assert!(!should_retry(&gateway, &response(404)));
+
+        let server = RetryPolicy {
+            on_gateway_error: false,
+            on_server_error: true,
+            ..gateway
+        };
+        assert!(should_retry(&server, &response(500)));
+        assert!(should_retry(&server, &response(503)));
+        assert!(!should_retry(&server, &response(429)));
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::BoundRequest;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Headers that are computed while signing, replacing any value set by the user.
+const SIGNING_HEADERS: [&str; 4] = [
+    "authorization",
+    "x-amz-date",
+    "x-amz-security-token",
+    "x-amz-content-sha256",
+];
+
+/// The credentials and the scope used to sign a request.
+#[derive(Debug, Clone, Copy)]
+pub struct SigV4Params<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    pub session_token: Option<&'a str>,
+    pub region: &'a str,
+    pub service: &'a str,
+    /// The time of the request in UTC, formatted as `20150830T123600Z`.
+    pub amz_date: &'a str,
+}
+
+impl SigV4Params<'_> {
+    fn date(&self) -> &str {
+        self.amz_date.get(..8).unwrap_or(self.amz_date)
+    }
+
+    fn scope(&self) -> String {
+        format!(
+            "{}/{}/{}/aws4_request",
+            self.date(),
+            self.region,
+            self.service
+        )
+    }
+}
+
+/// The current time formatted as expected by the X-Amz-Date header.
+pub fn current_amz_date() -> String {
+    glib::DateTime::now_utc()
+        .and_then(|now| now.format("%Y%m%dT%H%M%SZ"))
+        .map(|date| date.to_string())
+        .unwrap_or_default()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything except the unreserved characters of RFC 3986.
+fn uri_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Undoes the percent-encoding applied by the URL parser to a path segment.
+fn uri_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn canonical_uri(url: &Url) -> String {
+    let path = url.path();
+    if path.is_empty() {
+        return String::from("/");
+    }
+    path.split('/')
+        .map(|segment| uri_encode(&uri_decode(segment)))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn canonical_header_value(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Signs a request using AWS Signature Version 4.
+///
+/// This must be done once the request is final, since the URL, the headers and
+/// the body are part of the signature. The Authorization and X-Amz-Date headers
+/// are added to the request, along with X-Amz-Security-Token when a session
+/// token is given, and X-Amz-Content-Sha256 for S3, which requires it.
+pub fn sign_request(
+    request: &mut BoundRequest,
+    params: &SigV4Params,
+) -> Result<(), url::ParseError> {
+    let url = Url::parse(&request.url)?;
+    let payload_hash = sha256_hex(request.body.as_deref().unwrap_or_default());
+
+    request
+        .headers
+        .retain(|name, _| !SIGNING_HEADERS.contains(&name.to_lowercase().as_str()));
+    request
+        .headers
+        .insert("X-Amz-Date".into(), params.amz_date.into());
+    if let Some(token) = params.session_token {
+        request
+            .headers
+            .insert("X-Amz-Security-Token".into(), token.into());
+    }
+    if params.service == "s3" {
+        request
+            .headers
+            .insert("X-Amz-Content-Sha256".into(), payload_hash.clone());
+    }
+
+    let mut headers: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), canonical_header_value(value)))
+        .collect();
+    if !headers.iter().any(|(name, _)| name == "host") {
+        let host = url.host_str().unwrap_or_default();
+        let host = match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        };
+        headers.push(("host".into(), host));
+    }
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let method = request.method.as_str();
+    let canonical_request = [
+        method,
+        &canonical_uri(&url),
+        &canonical_query(&url),
+        &canonical_headers,
+        &signed_headers,
+        &payload_hash,
+    ]
+    .join("\n");
+
+    let scope = params.scope();
+    let string_to_sign = [
+        ALGORITHM,
+        params.amz_date,
+        &scope,
+        &sha256_hex(canonical_request.as_bytes()),
+    ]
+    .join("\n");
+
+    let secret = format!("AWS4{}", params.secret_key);
+    let key = hmac_sha256(secret.as_bytes(), params.date());
+    let key = hmac_sha256(&key, params.region);
+    let key = hmac_sha256(&key, params.service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+    let authorization = format!(
+        "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        params.access_key
+    );
+    request
+        .headers
+        .insert("Authorization".into(), authorization);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::entities::RequestMethod;
+
+    use super::*;
+
+    // The following tests use the requests and the credentials of the
+    // AWS Signature Version 4 test suite.
+
+    const PARAMS: SigV4Params = SigV4Params {
+        access_key: "AKIDEXAMPLE",
+        secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        session_token: None,
+        region: "us-east-1",
+        service: "service",
+        amz_date: "20150830T123600Z",
+    };
+
+    fn signature_of(request: &BoundRequest) -> String {
+        let authorization = &request.headers["Authorization"];
+        let (_, signature) = authorization.split_once("Signature=").unwrap();
+        signature.to_string()
+    }
+
+    fn request(method: RequestMethod, url: &str) -> BoundRequest {
+        BoundRequest {
+            url: url.into(),
+            method,
+            headers: HashMap::new(),
+            body: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_vanilla() {
+        let mut req = request(RequestMethod::Get, "https://example.amazonaws.com/");
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(
+            req.headers["Authorization"],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert_eq!(req.headers["X-Amz-Date"], "20150830T123600Z");
+    }
+
+    #[test]
+    fn test_get_vanilla_empty_path() {
+        let mut req = request(RequestMethod::Get, "https://example.amazonaws.com");
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(
+            signature_of(&req),
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_get_vanilla_query() {
+        let mut req = request(
+            RequestMethod::Get,
+            "https://example.amazonaws.com/?Param1=value1",
+        );
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(
+            signature_of(&req),
+            "a67d582fa61cc504c4bae71f336f98b97f1ea3c7a6bfe1b6e45aec72011b9aeb"
+        );
+    }
+
+    #[test]
+    fn test_get_vanilla_query_order_key_case() {
+        let mut req = request(
+            RequestMethod::Get,
+            "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+        );
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(
+            signature_of(&req),
+            "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn test_post_vanilla() {
+        let mut req = request(RequestMethod::Post, "https://example.amazonaws.com/");
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(
+            signature_of(&req),
+            "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+    }
+
+    #[test]
+    fn test_post_x_www_form_urlencoded() {
+        let mut req = request(RequestMethod::Post, "https://example.amazonaws.com/");
+        req.headers.insert(
+            "Content-Type".into(),
+            "application/x-www-form-urlencoded".into(),
+        );
+        req.body = Some(Vec::from("Param1=value1"));
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert!(
+            req.headers["Authorization"].contains("SignedHeaders=content-type;host;x-amz-date,")
+        );
+        assert_eq!(
+            signature_of(&req),
+            "ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+    }
+
+    #[test]
+    fn test_session_token_is_signed() {
+        let mut req = request(RequestMethod::Get, "https://example.amazonaws.com/");
+        let params = SigV4Params {
+            session_token: Some("AQoDYXdzEPT//////////wEXAMPLE"),
+            ..PARAMS
+        };
+        sign_request(&mut req, &params).unwrap();
+        assert_eq!(
+            req.headers["X-Amz-Security-Token"],
+            "AQoDYXdzEPT//////////wEXAMPLE"
+        );
+        assert!(req.headers["Authorization"]
+            .contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+    }
+
+    #[test]
+    fn test_s3_signs_payload_hash() {
+        let mut req = request(
+            RequestMethod::Get,
+            "https://examplebucket.s3.amazonaws.com/",
+        );
+        let params = SigV4Params {
+            service: "s3",
+            ..PARAMS
+        };
+        sign_request(&mut req, &params).unwrap();
+        assert_eq!(
+            req.headers["X-Amz-Content-Sha256"],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(req.headers["Authorization"]
+            .contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date,"));
+    }
+
+    #[test]
+    fn test_signing_replaces_previous_signature() {
+        let mut req = request(RequestMethod::Get, "https://example.amazonaws.com/");
+        req.headers
+            .insert("authorization".into(), "Bearer 1234".into());
+        req.headers.insert("X-AMZ-DATE".into(), "yesterday".into());
+        sign_request(&mut req, &PARAMS).unwrap();
+        assert_eq!(req.headers.len(), 2);
+        assert_eq!(
+            signature_of(&req),
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_canonical_uri_encoding() {
+        let url = Url::parse("https://example.amazonaws.com/example space/ñ~").unwrap();
+        assert_eq!(canonical_uri(&url), "/example%20space/%C3%B1~");
+    }
+}
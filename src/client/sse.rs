@@ -0,0 +1,214 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+/// A message received from a server that streams `text/event-stream` responses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The last event ID seen in the stream, which is inherited by later events.
+    pub id: Option<String>,
+    /// The event type, `message` unless the server sets a different one.
+    pub event: String,
+    /// The data of the event, with multiple data fields joined by newlines.
+    pub data: String,
+}
+
+/// Incremental parser for the event stream format described by the HTML spec.
+///
+/// Chunks can be fed as they arrive from the network; they do not need to be
+/// aligned to lines or events. Events are only returned once they have been
+/// terminated by a blank line.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    after_cr: bool,
+    started: bool,
+    data: String,
+    event: String,
+    last_id: Option<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the given chunk, returning the events that were completed by it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            // A CRLF pair may be split between two chunks.
+            if std::mem::take(&mut self.after_cr) && byte == b'\n' {
+                continue;
+            }
+            match byte {
+                b'\r' => {
+                    self.after_cr = true;
+                    self.end_line(&mut events);
+                }
+                b'\n' => self.end_line(&mut events),
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    fn end_line(&mut self, events: &mut Vec<SseEvent>) {
+        let bytes = std::mem::take(&mut self.line);
+        let mut line = String::from_utf8_lossy(&bytes).into_owned();
+        if !self.started {
+            self.started = true;
+            if line.starts_with('\u{FEFF}') {
+                line.remove(0);
+            }
+        }
+
+        if line.is_empty() {
+            if let Some(event) = self.dispatch() {
+                events.push(event);
+            }
+            return;
+        }
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "event" => self.event = value.to_string(),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            // The connection is never reopened, so the retry field is ignored as well.
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let mut data = std::mem::take(&mut self.data);
+        let event = std::mem::take(&mut self.event);
+        if data.is_empty() {
+            return None;
+        }
+        data.pop();
+        Some(SseEvent {
+            id: self.last_id.clone(),
+            event: if event.is_empty() {
+                "message".into()
+            } else {
+                event
+            },
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(data: &str) -> SseEvent {
+        SseEvent {
+            id: None,
+            event: "message".into(),
+            data: data.into(),
+        }
+    }
+
+    #[test]
+    fn test_single_event() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"data: hello\n\n");
+        assert_eq!(events, vec![message("hello")]);
+    }
+
+    #[test]
+    fn test_multi_line_data() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"data: first\ndata:second\ndata\n\n");
+        assert_eq!(events, vec![message("first\nsecond\n")]);
+    }
+
+    #[test]
+    fn test_comments_are_ignored() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b": keep-alive\n\n:another comment\ndata: x\n\n");
+        assert_eq!(events, vec![message("x")]);
+    }
+
+    #[test]
+    fn test_crlf_and_cr_line_endings() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"data: a\r\n\r\ndata: b\r\rdata: c\n\n");
+        assert_eq!(events, vec![message("a"), message("b"), message("c")]);
+    }
+
+    #[test]
+    fn test_crlf_split_between_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: a\r").is_empty());
+        assert!(parser.feed(b"\ndata: b\r").is_empty());
+        assert_eq!(parser.feed(b"\n\r\n"), vec![message("a\nb")]);
+    }
+
+    #[test]
+    fn test_event_split_between_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"da").is_empty());
+        assert!(parser.feed(b"ta: ni\xC3").is_empty());
+        assert_eq!(parser.feed(b"\xB1o\n\n"), vec![message("niño")]);
+    }
+
+    #[test]
+    fn test_event_type_and_id() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"event: update\nid: 7\ndata: one\n\ndata: two\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    id: Some("7".into()),
+                    event: "update".into(),
+                    data: "one".into(),
+                },
+                SseEvent {
+                    id: Some("7".into()),
+                    event: "message".into(),
+                    data: "two".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_without_data_are_not_dispatched() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"event: ping\n\nretry: 3000\n\ndata:\n\n");
+        assert_eq!(events, vec![message("")]);
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"\xEF\xBB\xBFdata: hello\n\n");
+        assert_eq!(events, vec![message("hello")]);
+    }
+}
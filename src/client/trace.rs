@@ -0,0 +1,284 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{net::SocketAddr, time::Duration};
+
+use gettextrs::gettext;
+
+use crate::{entities::ResponseData, utils::format_duration};
+
+use super::{BoundRequest, RequestError};
+
+/// What a trace event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    Request,
+    Header,
+    Response,
+    Redirect,
+    Tls,
+    Timing,
+    Warning,
+}
+
+impl TraceKind {
+    /// A short tag for the kind, used when the log is copied as text.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            TraceKind::Request => "request",
+            TraceKind::Header => "header",
+            TraceKind::Response => "response",
+            TraceKind::Redirect => "redirect",
+            TraceKind::Tls => "tls",
+            TraceKind::Timing => "timing",
+            TraceKind::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub kind: TraceKind,
+    pub message: String,
+}
+
+/// How long each phase of a request took.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub dns: Duration,
+    pub connect: Duration,
+    pub tls: Duration,
+    pub first_byte: Duration,
+    pub total: Duration,
+}
+
+/// Receives the events that describe how a request is sent.
+///
+/// A disabled tracer drops every event without even building its message,
+/// so requests can always be traced even if nobody is listening.
+#[derive(Default)]
+pub struct Tracer {
+    sink: Option<Box<dyn Fn(TraceEvent)>>,
+}
+
+impl Tracer {
+    pub fn new(sink: impl Fn(TraceEvent) + 'static) -> Self {
+        Self {
+            sink: Some(Box::new(sink)),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Emits an event. The message is only built if the tracer is enabled.
+    pub fn emit(&self, kind: TraceKind, message: impl FnOnce() -> String) {
+        if let Some(sink) = &self.sink {
+            sink(TraceEvent {
+                kind,
+                message: message(),
+            });
+        }
+    }
+
+    /// Traces the resolved URL and the headers of a request about to be sent.
+    pub fn trace_request(&self, request: &BoundRequest) {
+        self.emit(TraceKind::Request, || {
+            let method = request.method.as_str();
+            format!("{method} {}", request.url)
+        });
+        if !self.is_enabled() {
+            return;
+        }
+        let mut headers: Vec<_> = request.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            self.emit(TraceKind::Header, || format!("{name}: {value}"));
+        }
+    }
+
+    /// Traces the status of a response, and where it redirects to, if anywhere.
+    pub fn trace_response(&self, response: &ResponseData) {
+        self.emit(TraceKind::Response, || {
+            gettext("Received status {status} in {duration}")
+                .replace("{status}", &response.status_code.to_string())
+                .replace("{duration}", &format_duration(response.duration))
+        });
+        if (300..400).contains(&response.status_code) {
+            if let Some(location) = response.headers.header("location") {
+                self.emit(TraceKind::Redirect, || {
+                    gettext("Redirects to {location} (not followed)")
+                        .replace("{location}", location[0])
+                });
+            }
+        }
+        if let Some(warning) = &response.decode_warning {
+            self.emit(TraceKind::Warning, || warning.clone());
+        }
+    }
+
+    /// Traces the connection and the time taken by each phase of the request.
+    pub fn trace_timings(&self, remote: Option<SocketAddr>, timings: &Timings) {
+        if !timings.tls.is_zero() {
+            self.emit(TraceKind::Tls, || {
+                let peer = remote
+                    .map(|addr| addr.to_string())
+                    .unwrap_or_else(|| gettext("the server"));
+                gettext("TLS handshake with {peer} completed in {duration}")
+                    .replace("{peer}", &peer)
+                    .replace("{duration}", &format_duration(timings.tls.as_millis()))
+            });
+        }
+        self.emit(TraceKind::Timing, || {
+            gettext(
+                "DNS {dns}, connect {connect}, TLS {tls}, first byte {first_byte}, total {total}",
+            )
+            .replace("{dns}", &format_duration(timings.dns.as_millis()))
+            .replace("{connect}", &format_duration(timings.connect.as_millis()))
+            .replace("{tls}", &format_duration(timings.tls.as_millis()))
+            .replace(
+                "{first_byte}",
+                &format_duration(timings.first_byte.as_millis()),
+            )
+            .replace("{total}", &format_duration(timings.total.as_millis()))
+        });
+    }
+
+    /// Traces an attempt that failed before a response was received.
+    pub fn trace_error(&self, error: &RequestError) {
+        self.emit(TraceKind::Warning, || error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::entities::{KeyValue, KeyValueTable, RequestMethod};
+
+    use super::*;
+
+    fn recording_tracer() -> (Tracer, Rc<RefCell<Vec<TraceEvent>>>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = events.clone();
+        let tracer = Tracer::new(move |event| sink.borrow_mut().push(event));
+        (tracer, events)
+    }
+
+    #[test]
+    fn test_disabled_tracer_does_not_build_messages() {
+        let tracer = Tracer::disabled();
+        assert!(!tracer.is_enabled());
+        tracer.emit(TraceKind::Warning, || panic!("message should not be built"));
+    }
+
+    #[test]
+    fn test_trace_request() {
+        let (tracer, events) = recording_tracer();
+        let request = BoundRequest {
+            url: String::from("https://example.com/api?page=2"),
+            method: RequestMethod::Post,
+            headers: HashMap::from([
+                (String::from("X-Token"), String::from("1234")),
+                (String::from("Accept"), String::from("*/*")),
+            ]),
+            body: None,
+            ..Default::default()
+        };
+        tracer.trace_request(&request);
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, TraceKind::Request);
+        assert_eq!(events[0].message, "POST https://example.com/api?page=2");
+        assert_eq!(events[1].kind, TraceKind::Header);
+        assert_eq!(events[1].message, "Accept: */*");
+        assert_eq!(events[2].message, "X-Token: 1234");
+    }
+
+    #[test]
+    fn test_trace_redirect_response() {
+        let (tracer, events) = recording_tracer();
+        let response = ResponseData {
+            status_code: 302,
+            duration: 40,
+            size: 0,
+            transfer_size: 0,
+            headers: KeyValueTable::new(&[KeyValue {
+                name: String::from("Location"),
+                value: String::from("https://example.com/login"),
+                active: true,
+                secret: false,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+            }]),
+            body: Vec::new(),
+            decode_warning: None,
+            version: None,
+        };
+        tracer.trace_response(&response);
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, TraceKind::Response);
+        assert_eq!(events[1].kind, TraceKind::Redirect);
+        assert!(events[1].message.contains("https://example.com/login"));
+    }
+
+    #[test]
+    fn test_trace_timings_without_tls() {
+        let (tracer, events) = recording_tracer();
+        let timings = Timings {
+            dns: Duration::from_millis(2),
+            connect: Duration::from_millis(10),
+            tls: Duration::ZERO,
+            first_byte: Duration::from_millis(80),
+            total: Duration::from_millis(95),
+        };
+        tracer.trace_timings(None, &timings);
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, TraceKind::Timing);
+        assert_eq!(
+            events[0].message,
+            "DNS 2 ms, connect 10 ms, TLS 0 ms, first byte 80 ms, total 95 ms"
+        );
+    }
+
+    #[test]
+    fn test_trace_timings_with_tls() {
+        let (tracer, events) = recording_tracer();
+        let timings = Timings {
+            tls: Duration::from_millis(30),
+            ..Default::default()
+        };
+        let remote: SocketAddr = "93.184.216.34:443".parse().unwrap();
+        tracer.trace_timings(Some(remote), &timings);
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, TraceKind::Tls);
+        assert!(events[0].message.contains("93.184.216.34:443"));
+    }
+}
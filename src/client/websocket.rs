@@ -0,0 +1,304 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::{HeaderName, HeaderValue};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::entities::KeyValueTable;
+
+/// How long the connection thread blocks waiting for frames before checking
+/// whether there are frames to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<WebSocketFrame> for Message {
+    fn from(value: WebSocketFrame) -> Self {
+        match value {
+            WebSocketFrame::Text(text) => Message::Text(text),
+            WebSocketFrame::Binary(data) => Message::Binary(data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketEvent {
+    Connected,
+    Sent(WebSocketFrame),
+    Received(WebSocketFrame),
+    Error(String),
+    /// The connection is over. Carries the reason given by the server, if any.
+    Closed(Option<String>),
+}
+
+enum Command {
+    Send(WebSocketFrame),
+    Close,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HexError {
+    #[error("Invalid hexadecimal digit: {0}")]
+    InvalidDigit(char),
+
+    #[error("Hexadecimal input must have an even number of digits")]
+    OddLength,
+}
+
+/// Parses hexadecimal text such as `48 65 6c 6c 6f` or `48656c6c6f` into bytes.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, HexError> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() % 2 == 1 {
+        return Err(HexError::OddLength);
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let high = pair[0]
+                .to_digit(16)
+                .ok_or(HexError::InvalidDigit(pair[0]))?;
+            let low = pair[1]
+                .to_digit(16)
+                .ok_or(HexError::InvalidDigit(pair[1]))?;
+            Ok((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+/// Formats bytes as space separated lowercase hexadecimal pairs.
+pub fn format_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// A WebSocket session running in a background thread.
+///
+/// Frames are sent through [`WebSocketConnection::send`], and everything that
+/// happens to the connection is reported through the receiver returned by
+/// [`WebSocketConnection::connect`]. Dropping the connection closes it.
+pub struct WebSocketConnection {
+    commands: mpsc::Sender<Command>,
+}
+
+impl WebSocketConnection {
+    pub fn connect(
+        url: &str,
+        headers: &KeyValueTable,
+    ) -> (Self, UnboundedReceiver<WebSocketEvent>) {
+        let (commands, command_rx) = mpsc::channel();
+        let (events, event_rx) = unbounded_channel();
+
+        let url = url.to_string();
+        let headers: Vec<(String, String)> = headers
+            .iter()
+            .filter(|h| h.active && !h.name.is_empty())
+            .map(|h| (h.name.clone(), h.value.clone()))
+            .collect();
+        std::thread::spawn(move || {
+            if let Err(e) = run(&url, &headers, command_rx, &events) {
+                let _ = events.send(WebSocketEvent::Error(e));
+                let _ = events.send(WebSocketEvent::Closed(None));
+            }
+        });
+
+        (Self { commands }, event_rx)
+    }
+
+    pub fn send(&self, frame: WebSocketFrame) {
+        let _ = self.commands.send(Command::Send(frame));
+    }
+
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+}
+
+impl Drop for WebSocketConnection {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn set_read_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>) -> std::io::Result<()> {
+    match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(POLL_INTERVAL)),
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(Some(POLL_INTERVAL)),
+        _ => Ok(()),
+    }
+}
+
+fn run(
+    url: &str,
+    headers: &[(String, String)],
+    commands: mpsc::Receiver<Command>,
+    events: &UnboundedSender<WebSocketEvent>,
+) -> Result<(), String> {
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    for (name, value) in headers {
+        let name = HeaderName::from_str(name).map_err(|e| e.to_string())?;
+        let value = HeaderValue::from_str(value).map_err(|e| e.to_string())?;
+        request.headers_mut().append(name, value);
+    }
+
+    let (mut socket, _) = tungstenite::connect(request).map_err(|e| e.to_string())?;
+    set_read_timeout(&socket).map_err(|e| e.to_string())?;
+    let _ = events.send(WebSocketEvent::Connected);
+
+    let mut closing = false;
+    loop {
+        loop {
+            match commands.try_recv() {
+                Ok(Command::Send(frame)) if !closing => {
+                    socket
+                        .send(frame.clone().into())
+                        .map_err(|e| e.to_string())?;
+                    let _ = events.send(WebSocketEvent::Sent(frame));
+                }
+                Ok(Command::Send(_)) => {}
+                Ok(Command::Close) | Err(mpsc::TryRecvError::Disconnected) => {
+                    if !closing {
+                        closing = true;
+                        let _ = socket.close(None);
+                    }
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let _ = events.send(WebSocketEvent::Received(WebSocketFrame::Text(text)));
+            }
+            Ok(Message::Binary(data)) => {
+                let _ = events.send(WebSocketEvent::Received(WebSocketFrame::Binary(data)));
+            }
+            Ok(Message::Close(frame)) => {
+                let reason = frame
+                    .map(|f| f.reason.to_string())
+                    .filter(|r| !r.is_empty());
+                let _ = events.send(WebSocketEvent::Closed(reason));
+                closing = true;
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                if !closing {
+                    let _ = events.send(WebSocketEvent::Closed(None));
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("48656c6c6f"), Ok(b"Hello".to_vec()));
+        assert_eq!(parse_hex("48 65 6C 6c 6F\n"), Ok(b"Hello".to_vec()));
+        assert_eq!(parse_hex(""), Ok(vec![]));
+        assert_eq!(parse_hex("486"), Err(HexError::OddLength));
+        assert_eq!(parse_hex("4g"), Err(HexError::InvalidDigit('g')));
+    }
+
+    #[test]
+    fn test_format_hex() {
+        assert_eq!(format_hex(b"Hello"), "48 65 6c 6c 6f");
+        assert_eq!(format_hex(&[0x00, 0xff]), "00 ff");
+        assert_eq!(parse_hex(&format_hex(&[1, 2, 254])), Ok(vec![1, 2, 254]));
+    }
+
+    #[test]
+    fn test_echo_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            while let Ok(message) = socket.read() {
+                if message.is_text() || message.is_binary() {
+                    let _ = socket.send(message);
+                }
+            }
+        });
+
+        let url = format!("ws://{addr}/");
+        let (connection, mut events) =
+            WebSocketConnection::connect(&url, &KeyValueTable::default());
+        assert_eq!(events.blocking_recv(), Some(WebSocketEvent::Connected));
+
+        let text = WebSocketFrame::Text("hello".into());
+        connection.send(text.clone());
+        assert_eq!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Sent(text.clone()))
+        );
+        assert_eq!(events.blocking_recv(), Some(WebSocketEvent::Received(text)));
+
+        let binary = WebSocketFrame::Binary(vec![0, 1, 2]);
+        connection.send(binary.clone());
+        assert_eq!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Sent(binary.clone()))
+        );
+        assert_eq!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Received(binary))
+        );
+
+        connection.close();
+        assert!(matches!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Closed(_))
+        ));
+    }
+
+    #[test]
+    fn test_connection_error() {
+        let (_connection, mut events) =
+            WebSocketConnection::connect("not a url", &KeyValueTable::default());
+        assert!(matches!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Error(_))
+        ));
+        assert_eq!(events.blocking_recv(), Some(WebSocketEvent::Closed(None)));
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::entities::{KeyValueTable, ResponseData};
+
+/// How many unchanged lines are kept around every change in the unified diff.
+const CONTEXT_RADIUS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderChange {
+    Added {
+        name: String,
+        value: String,
+    },
+    Removed {
+        name: String,
+        value: String,
+    },
+    Changed {
+        name: String,
+        old: String,
+        new: String,
+    },
+}
+
+/// The differences between two responses received in the same pane.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseDiff {
+    pub status: Option<(u32, u32)>,
+    pub headers: Vec<HeaderChange>,
+    pub body: Vec<DiffLine>,
+}
+
+impl ResponseDiff {
+    pub fn new(old: &ResponseData, new: &ResponseData) -> Self {
+        let status = if old.status_code != new.status_code {
+            Some((old.status_code, new.status_code))
+        } else {
+            None
+        };
+        Self {
+            status,
+            headers: diff_headers(&old.headers, &new.headers),
+            body: diff_text(&old.pretty_body_str(), &new.pretty_body_str()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.headers.is_empty() && self.body.is_empty()
+    }
+}
+
+/// Computes an unified diff between two texts, line by line.
+///
+/// The output is empty when both texts are equal. Otherwise, every hunk starts
+/// with a header line followed by the context, removed and added lines.
+pub fn diff_text(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(CONTEXT_RADIUS);
+
+    let mut lines = Vec::new();
+    for hunk in unified.iter_hunks() {
+        lines.push(DiffLine {
+            kind: DiffLineKind::Hunk,
+            text: hunk.header().to_string(),
+        });
+        for change in hunk.iter_changes() {
+            let kind = match change.tag() {
+                ChangeTag::Equal => DiffLineKind::Context,
+                ChangeTag::Insert => DiffLineKind::Added,
+                ChangeTag::Delete => DiffLineKind::Removed,
+            };
+            let text = change.value().trim_end_matches(['\r', '\n']).to_string();
+            lines.push(DiffLine { kind, text });
+        }
+    }
+    lines
+}
+
+/// Groups the headers by lowercase name, keeping the first spelling seen.
+fn group_headers(table: &KeyValueTable) -> BTreeMap<String, (String, String)> {
+    let mut groups: BTreeMap<String, (String, Vec<String>)> = BTreeMap::new();
+    for header in table.iter() {
+        groups
+            .entry(header.name.to_lowercase())
+            .or_insert_with(|| (header.name.clone(), Vec::new()))
+            .1
+            .push(header.value.clone());
+    }
+    groups
+        .into_iter()
+        .map(|(key, (name, values))| (key, (name, values.join(", "))))
+        .collect()
+}
+
+/// Compares two header tables, ignoring the case of the header names.
+pub fn diff_headers(old: &KeyValueTable, new: &KeyValueTable) -> Vec<HeaderChange> {
+    let old = group_headers(old);
+    let new = group_headers(new);
+
+    let mut changes = Vec::new();
+    for (key, (name, old_value)) in &old {
+        match new.get(key) {
+            None => changes.push(HeaderChange::Removed {
+                name: name.clone(),
+                value: old_value.clone(),
+            }),
+            Some((_, new_value)) if new_value != old_value => changes.push(HeaderChange::Changed {
+                name: name.clone(),
+                old: old_value.clone(),
+                new: new_value.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, (name, new_value)) in &new {
+        if !old.contains_key(key) {
+            changes.push(HeaderChange::Added {
+                name: name.clone(),
+                value: new_value.clone(),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::KeyValueTable;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_text_equal() {
+        let text = "{\n  \"hello\": \"world\"\n}";
+        assert!(diff_text(text, text).is_empty());
+    }
+
+    #[test]
+    fn test_diff_text_changed_line() {
+        let old = "one\ntwo\nthree\n";
+        let new = "one\n2\nthree\n";
+        let lines = diff_text(old, new);
+
+        assert_eq!(lines[0].kind, DiffLineKind::Hunk);
+        assert!(lines[0].text.starts_with("@@"));
+        assert!(lines.contains(&DiffLine {
+            kind: DiffLineKind::Removed,
+            text: "two".into(),
+        }));
+        assert!(lines.contains(&DiffLine {
+            kind: DiffLineKind::Added,
+            text: "2".into(),
+        }));
+        assert!(lines.contains(&DiffLine {
+            kind: DiffLineKind::Context,
+            text: "one".into(),
+        }));
+    }
+
+    #[test]
+    fn test_diff_headers() {
+        let old = KeyValueTable::new(&[
+            ("Content-Type", "application/json").into(),
+            ("ETag", "abc").into(),
+            ("X-Removed", "1").into(),
+        ]);
+        let new = KeyValueTable::new(&[
+            ("content-type", "application/json").into(),
+            ("ETag", "def").into(),
+            ("X-Added", "2").into(),
+        ]);
+
+        let changes = diff_headers(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                HeaderChange::Changed {
+                    name: "ETag".into(),
+                    old: "abc".into(),
+                    new: "def".into(),
+                },
+                HeaderChange::Removed {
+                    name: "X-Removed".into(),
+                    value: "1".into(),
+                },
+                HeaderChange::Added {
+                    name: "X-Added".into(),
+                    value: "2".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_headers_multiple_values() {
+        let old = KeyValueTable::new(&[("Set-Cookie", "a=1").into(), ("Set-Cookie", "b=2").into()]);
+        let new = KeyValueTable::new(&[("Set-Cookie", "a=1").into()]);
+
+        let changes = diff_headers(&old, &new);
+        assert_eq!(
+            changes,
+            vec![HeaderChange::Changed {
+                name: "Set-Cookie".into(),
+                old: "a=1, b=2".into(),
+                new: "a=1".into(),
+            }]
+        );
+    }
+}
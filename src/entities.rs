@@ -16,20 +16,33 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
 };
 
+use encoding_rs::Encoding;
+use regex::Regex;
 use srtemplate::SrTemplate;
+use url::Url;
 
 use crate::objects::KeyValueItem;
 
+/// Shown in place of the value of a secret entry when a request is exported.
+pub const SECRET_PLACEHOLDER: &str = "<secret>";
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct KeyValue {
     pub name: String,
     pub value: String,
     pub active: bool,
     pub secret: bool,
+    /// A free text remark about the entry. It does not affect the request.
+    pub comment: String,
+    /// Whether a variable needs a value before the request can be sent.
+    pub required: bool,
+    /// A regular expression that the value of a variable has to match, if not empty.
+    pub validation_rule: String,
 }
 
 impl PartialOrd for KeyValue {
@@ -52,6 +65,9 @@ impl From<(String, String)> for KeyValue {
             value,
             active: true,
             secret: false,
+            comment: String::new(),
+            required: false,
+            validation_rule: String::new(),
         }
     }
 }
@@ -64,6 +80,9 @@ impl From<(&str, &str)> for KeyValue {
             value: v.into(),
             active: true,
             secret: false,
+            comment: String::new(),
+            required: false,
+            validation_rule: String::new(),
         }
     }
 }
@@ -75,10 +94,39 @@ impl From<KeyValueItem> for KeyValue {
             value: value.header_value().clone(),
             active: value.active(),
             secret: value.secret(),
+            comment: value.comment(),
+            required: value.required(),
+            validation_rule: value.validation_rule(),
         }
     }
 }
 
+/// A rule given to a variable that its value does not follow.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum VariableRule {
+    Required,
+    Pattern(String),
+}
+
+impl KeyValue {
+    /// The rule of the variable that its value breaks, if any.
+    ///
+    /// The pattern has to match the whole value, and it is not checked against
+    /// an empty value, so that it only constrains variables that are given one.
+    /// A pattern that is not a valid regular expression is never followed.
+    pub fn broken_rule(&self) -> Option<VariableRule> {
+        if self.value.is_empty() {
+            return self.required.then_some(VariableRule::Required);
+        }
+        if self.validation_rule.is_empty() {
+            return None;
+        }
+        let matches = Regex::new(&format!("^(?:{})$", self.validation_rule))
+            .is_ok_and(|regex| regex.is_match(&self.value));
+        (!matches).then(|| VariableRule::Pattern(self.validation_rule.clone()))
+    }
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq, glib::Boxed)]
 #[boxed_type(name = "GKeyValueTable")]
 pub struct KeyValueTable(Vec<KeyValue>);
@@ -88,17 +136,6 @@ impl KeyValueTable {
         Self(entries.to_vec())
     }
 
-    pub fn group_by(&self) -> HashMap<String, Vec<KeyValue>> {
-        let mut hash_map: HashMap<String, Vec<KeyValue>> = HashMap::new();
-        for row in &self.0 {
-            hash_map
-                .entry(row.name.clone())
-                .or_default()
-                .push(row.clone());
-        }
-        hash_map
-    }
-
     pub fn header(&self, key: &str) -> Option<Vec<&str>> {
         let compare_key: String = key.to_lowercase();
         let mut headers: Vec<&str> = self
@@ -119,6 +156,41 @@ impl KeyValueTable {
             Some(headers)
         }
     }
+
+    /// Replaces the value of every secret entry with a placeholder.
+    pub fn mask_secrets(&mut self) {
+        for item in self.0.iter_mut().filter(|item| item.secret) {
+            item.value = SECRET_PLACEHOLDER.into();
+        }
+    }
+
+    /// Parses a block of text containing one `KEY=VALUE` or `KEY: VALUE` pair per line.
+    ///
+    /// The name is split at the first `=` or `:` found in the line. Empty lines are
+    /// skipped, whitespace around names and values is trimmed, and values wrapped in
+    /// matching single or double quotes are unquoted.
+    pub fn parse_lines(text: &str) -> Self {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (name, value) = match line.find(['=', ':']) {
+                    Some(pos) => (&line[..pos], &line[pos + 1..]),
+                    None => (line, ""),
+                };
+                KeyValue::from((name.trim(), unquote(value.trim())))
+            })
+            .collect()
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
 }
 
 impl Deref for KeyValueTable {
@@ -155,11 +227,33 @@ pub enum RequestMethod {
     Options,
     Head,
     Trace,
+    Connect,
+    /// A method that is not part of HTTP itself, such as the ones of WebDAV.
+    Custom(String),
+}
+
+impl RequestMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RequestMethod::Get => "GET",
+            RequestMethod::Post => "POST",
+            RequestMethod::Put => "PUT",
+            RequestMethod::Patch => "PATCH",
+            RequestMethod::Delete => "DELETE",
+            RequestMethod::Head => "HEAD",
+            RequestMethod::Options => "OPTIONS",
+            RequestMethod::Trace => "TRACE",
+            RequestMethod::Connect => "CONNECT",
+            RequestMethod::Custom(method) => method,
+        }
+    }
 }
 
 impl TryFrom<&str> for RequestMethod {
     type Error = ();
 
+    /// Standard methods are matched regardless of case. Any other name is kept
+    /// as written, as long as it is a token, as described in RFC 9110.
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value.to_lowercase().as_str() {
             "get" => Ok(RequestMethod::Get),
@@ -170,30 +264,22 @@ impl TryFrom<&str> for RequestMethod {
             "options" => Ok(RequestMethod::Options),
             "head" => Ok(RequestMethod::Head),
             "trace" => Ok(RequestMethod::Trace),
+            "connect" => Ok(RequestMethod::Connect),
+            _ if !value.is_empty()
+                && value
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)) =>
+            {
+                Ok(RequestMethod::Custom(value.to_string()))
+            }
             _ => Err(()),
         }
     }
 }
 
-impl From<RequestMethod> for &str {
-    fn from(val: RequestMethod) -> Self {
-        match val {
-            RequestMethod::Get => "GET",
-            RequestMethod::Post => "POST",
-            RequestMethod::Put => "PUT",
-            RequestMethod::Patch => "PATCH",
-            RequestMethod::Delete => "DELETE",
-            RequestMethod::Head => "HEAD",
-            RequestMethod::Options => "OPTIONS",
-            RequestMethod::Trace => "TRACE",
-        }
-    }
-}
-
 impl From<RequestMethod> for String {
     fn from(value: RequestMethod) -> String {
-        let string: &str = value.into();
-        String::from(string)
+        value.as_str().to_string()
     }
 }
 
@@ -205,6 +291,87 @@ pub enum RawEncoding {
     OctetStream,
 }
 
+impl RawEncoding {
+    /// Guesses the encoding of a body loaded from a file out of its extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(RawEncoding::Json),
+            "xml" => Some(RawEncoding::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// The character set used to encode the text of a raw body before sending it.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum BodyCharset {
+    #[default]
+    Utf8,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl BodyCharset {
+    /// The value of the charset parameter in the Content-Type header.
+    pub fn label(&self) -> &'static str {
+        match self {
+            BodyCharset::Utf8 => "utf-8",
+            BodyCharset::Latin1 => "windows-1252",
+            BodyCharset::Utf16Le => "utf-16le",
+            BodyCharset::Utf16Be => "utf-16be",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        let encoding = Encoding::for_label(label.trim().as_bytes())?;
+        if encoding == encoding_rs::UTF_8 {
+            Some(BodyCharset::Utf8)
+        } else if encoding == encoding_rs::WINDOWS_1252 {
+            Some(BodyCharset::Latin1)
+        } else if encoding == encoding_rs::UTF_16LE {
+            Some(BodyCharset::Utf16Le)
+        } else if encoding == encoding_rs::UTF_16BE {
+            Some(BodyCharset::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            BodyCharset::Utf8 => Vec::from(text),
+            BodyCharset::Latin1 => encoding_rs::WINDOWS_1252.encode(text).0.into_owned(),
+            // encoding_rs only decodes UTF-16, so the encoder has to be done by hand.
+            BodyCharset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            BodyCharset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let encoding = match self {
+            BodyCharset::Utf8 => encoding_rs::UTF_8,
+            BodyCharset::Latin1 => encoding_rs::WINDOWS_1252,
+            BodyCharset::Utf16Le => encoding_rs::UTF_16LE,
+            BodyCharset::Utf16Be => encoding_rs::UTF_16BE,
+        };
+        encoding.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+/// Extracts the charset parameter of a Content-Type header value, if it is known.
+fn content_type_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("charset") {
+            Encoding::for_label(value.trim().trim_matches('"').as_bytes())
+        } else {
+            None
+        }
+    })
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub enum RequestPayload {
     #[default]
@@ -215,10 +382,120 @@ pub enum RequestPayload {
     },
     Raw {
         encoding: RawEncoding,
+        charset: BodyCharset,
         content: Vec<u8>,
+        /// The file the body was loaded from, which is read again when the request is opened.
+        file: Option<PathBuf>,
     },
 }
 
+/// Describes when and how many times a request should be sent again if it fails.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. 1 disables retrying.
+    pub max_attempts: u32,
+    /// Milliseconds to wait before sending the request again.
+    pub backoff: u64,
+    /// Doubles the wait after every attempt that fails.
+    pub exponential_backoff: bool,
+    pub on_connection_error: bool,
+    pub on_gateway_error: bool,
+    /// Retries any 5xx response, not just the gateway errors.
+    pub on_server_error: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: 1000,
+            exponential_backoff: false,
+            on_connection_error: true,
+            on_gateway_error: true,
+            on_server_error: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+            && (self.on_connection_error || self.on_gateway_error || self.on_server_error)
+    }
+
+    /// Milliseconds to wait before the given attempt, counting the first one as 1.
+    pub fn backoff_before(&self, attempt: u32) -> u64 {
+        if !self.exponential_backoff || attempt <= 2 {
+            return self.backoff;
+        }
+        let factor = 1u64.checked_shl(attempt - 2).unwrap_or(u64::MAX);
+        self.backoff.saturating_mul(factor)
+    }
+}
+
+/// Describes how the bodies of a request and its response are compressed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CompressionOptions {
+    /// Ask the server for a compressed response using the Accept-Encoding header.
+    pub accept_compressed: bool,
+    /// Compress the request body using gzip.
+    pub compress_body: bool,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self {
+            accept_compressed: true,
+            compress_body: false,
+        }
+    }
+}
+
+/// Which version of HTTP is used to send a request.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum HttpVersionPreference {
+    /// Let the client and the server agree on a version.
+    #[default]
+    Auto,
+    /// Only speak HTTP/1.1, even if the server offers HTTP/2.
+    Http1Only,
+    /// Speak HTTP/2 from the first byte, without upgrading the connection.
+    Http2Prior,
+}
+
+/// The version of HTTP with which a response was received.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HttpVersion {
+    Http10,
+    Http11,
+    Http2,
+    Http3,
+}
+
+impl HttpVersion {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+            HttpVersion::Http3 => "HTTP/3",
+        }
+    }
+}
+
+/// Credentials used to sign a request with AWS Signature Version 4.
+///
+/// Every field may contain variables, so that the keys can be kept in secret variables.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct AwsSigV4 {
+    pub access_key: String,
+    pub secret_key: String,
+    /// Only needed for temporary credentials. Ignored if empty.
+    pub session_token: String,
+    pub region: String,
+    pub service: String,
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct EndpointData {
     pub url: String,
@@ -226,35 +503,210 @@ pub struct EndpointData {
     pub headers: KeyValueTable,
     pub variables: KeyValueTable,
     pub body: RequestPayload,
+    pub retry: RetryPolicy,
+    pub compression: CompressionOptions,
+    pub http_version: HttpVersionPreference,
+    pub aws_sigv4: Option<AwsSigV4>,
+    pub notes: String,
+    /// The title given by the user to the endpoint. The file name is used if empty.
+    pub name: String,
+    /// Rhai script run before sending the request, which may change it.
+    pub pre_request_script: String,
+    /// Rhai script run after receiving the response, which may set variables.
+    pub post_response_script: String,
+    /// The URLs recently sent from this endpoint, most recent first.
+    pub url_history: Vec<String>,
 }
 
 impl EndpointData {
+    /// Creates a GET endpoint for the given URL. The scheme defaults to https if missing.
+    pub fn from_url(text: &str) -> Result<Self, url::ParseError> {
+        let text = text.trim();
+        let url = match Url::parse(text) {
+            Err(url::ParseError::RelativeUrlWithoutBase) => Url::parse(&format!("https://{text}"))?,
+            Ok(url) if url.host_str().is_none() && is_host_and_port(text) => {
+                Url::parse(&format!("https://{text}"))?
+            }
+            otherwise => otherwise?,
+        };
+        if url.host_str().is_none() {
+            return Err(url::ParseError::EmptyHost);
+        }
+        Ok(Self {
+            url: url.to_string(),
+            method: RequestMethod::Get,
+            ..Default::default()
+        })
+    }
+
+    /// The variables that the templates can use: the active ones with a name. When
+    /// several share a name, the first one wins and the later ones are ignored.
+    fn effective_variables(&self) -> Vec<&KeyValue> {
+        let mut seen = HashSet::new();
+        self.variables
+            .iter()
+            .filter(|item| item.active && !item.name.is_empty())
+            .filter(|item| seen.insert(item.name.as_str()))
+            .collect()
+    }
+
     pub fn template_processor(&self) -> SrTemplate {
         let context = SrTemplate::default();
-        for item in self.variables.iter() {
+        for item in self.effective_variables() {
             context.add_variable(item.name.clone(), &item.value);
         }
         context
     }
 
-    pub fn process_headers(&self) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        for item in self.headers.iter() {
-            if item.active {
-                headers.insert(item.name.clone(), item.value.clone());
+    /// The values of the variables by name, as seen by the templates.
+    pub fn variable_values(&self) -> HashMap<String, String> {
+        self.effective_variables()
+            .into_iter()
+            .map(|item| (item.name.clone(), item.value.clone()))
+            .collect()
+    }
+
+    /// A copy of the endpoint whose secret headers, variables and body fields are
+    /// replaced by a placeholder, so that it can be shown without leaking them.
+    /// Anything that uses a secret variable gets the placeholder as well.
+    pub fn masking_secrets(&self) -> Self {
+        let mut endpoint = self.clone();
+        endpoint.headers.mask_secrets();
+        endpoint.variables.mask_secrets();
+        if let RequestPayload::Urlencoded(params) | RequestPayload::Multipart { params } =
+            &mut endpoint.body
+        {
+            params.mask_secrets();
+        }
+        endpoint
+    }
+
+    /// The variables used by the URL, the headers or the body whose value
+    /// breaks one of their rules, together with the rule they break.
+    pub fn broken_variable_rules(&self) -> Vec<(KeyValue, VariableRule)> {
+        let mut texts = vec![self.url.as_str()];
+        for item in self.headers.iter().filter(|item| item.active) {
+            texts.extend([item.name.as_str(), item.value.as_str()]);
+        }
+        match &self.body {
+            RequestPayload::Urlencoded(params) | RequestPayload::Multipart { params } => {
+                for item in params.iter().filter(|item| item.active) {
+                    texts.extend([item.name.as_str(), item.value.as_str()]);
+                }
             }
+            RequestPayload::Raw { content, .. } => {
+                if let Ok(content) = std::str::from_utf8(content) {
+                    texts.push(content);
+                }
+            }
+            RequestPayload::None => {}
         }
-        headers
+        let used: Vec<&str> = texts.into_iter().flat_map(template_names).collect();
+
+        self.effective_variables()
+            .into_iter()
+            .filter(|item| used.contains(&item.name.as_str()))
+            .filter_map(|item| item.broken_rule().map(|rule| (item.clone(), rule)))
+            .collect()
+    }
+
+    /// The active headers of the endpoint, in the order they were given. When several
+    /// share a name, regardless of its case, only the first one is sent.
+    pub fn process_headers(&self) -> Vec<(String, String)> {
+        let mut seen = HashSet::new();
+        self.headers
+            .iter()
+            .filter(|item| item.active)
+            .filter(|item| seen.insert(item.name.to_lowercase()))
+            .map(|item| (item.name.clone(), item.value.clone()))
+            .collect()
     }
 }
 
+/// Whether the text starts with a host followed by a port, such as "localhost:8080/health",
+/// which is parsed as a URL whose scheme is the host.
+fn is_host_and_port(text: &str) -> bool {
+    text.split_once(':')
+        .is_some_and(|(_, rest)| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// The names written inside the `{{ }}` placeholders of a template. Names of
+/// functions are included as well, since they cannot be told apart here.
+fn template_names(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let inner = &rest[start + 2..];
+        let Some(end) = inner.find("}}") else {
+            break;
+        };
+        names.extend(
+            inner[..end]
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+                .filter(|name| !name.is_empty()),
+        );
+        rest = &inner[end + 2..];
+    }
+    names
+}
+
+/// How many recently sent URLs are remembered by an endpoint.
+pub const URL_HISTORY_LENGTH: usize = 10;
+
+/// Puts the URL at the front of the history of recently sent URLs.
+///
+/// A URL already in the history is moved instead of repeated, and the
+/// oldest URLs are forgotten past URL_HISTORY_LENGTH.
+pub fn remember_url(history: &mut VecDeque<String>, url: &str) {
+    let url = url.trim();
+    if url.is_empty() {
+        return;
+    }
+    history.retain(|item| item != url);
+    history.push_front(url.to_string());
+    history.truncate(URL_HISTORY_LENGTH);
+}
+
+/// How many characters of the notes of an endpoint are shown in its tab tooltip.
+const NOTES_PREVIEW_LENGTH: usize = 80;
+
+/// Shortens the notes of an endpoint so that they can be previewed in a tooltip.
+///
+/// Returns None if there are no notes. Whitespace is collapsed, since tooltips
+/// are too small to render the notes with their original layout.
+pub fn notes_preview(notes: &str) -> Option<String> {
+    let words: Vec<&str> = notes.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    let notes = words.join(" ");
+    match notes.char_indices().nth(NOTES_PREVIEW_LENGTH) {
+        Some((cut, _)) => Some(format!("{}…", notes[..cut].trim_end())),
+        None => Some(notes),
+    }
+}
+
+/// The definition of a WebSocket session: where to connect and which headers to send.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct WebSocketData {
+    pub url: String,
+    pub headers: KeyValueTable,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ResponseData {
     pub status_code: u32,
     pub duration: u128,
+    /// Size of the body once decompressed.
     pub size: usize,
+    /// Size of the body as it was received, before decompressing it.
+    pub transfer_size: usize,
     pub headers: KeyValueTable,
     pub body: Vec<u8>,
+    /// Set when the body could not be decompressed and is shown as received.
+    pub decode_warning: Option<String>,
+    /// The version of HTTP spoken by the server, if the response came from the network.
+    pub version: Option<HttpVersion>,
 }
 
 impl ResponseData {
@@ -278,13 +730,88 @@ impl ResponseData {
         }
     }
 
+    /// The methods listed in the `Allow` and `Access-Control-Allow-Methods`
+    /// headers, as usually answered to an OPTIONS request. Repeated ones are dropped.
+    pub fn allowed_methods(&self) -> Vec<String> {
+        let mut methods: Vec<String> = Vec::new();
+        for header in ["allow", "access-control-allow-methods"] {
+            let values = self.headers.header(header).unwrap_or_default();
+            for method in values.iter().flat_map(|value| value.split(',')) {
+                let method = method.trim().to_uppercase();
+                if !method.is_empty() && !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+        methods
+    }
+
+    /// Whether the server is streaming events instead of sending a whole document.
+    pub fn is_event_stream(&self) -> bool {
+        match self.headers.header("content-type") {
+            Some(header) => match header[..] {
+                [value] => value.trim_start().starts_with("text/event-stream"),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    /// The encoding announced in the Content-Type header of the response, if any.
+    pub fn charset(&self) -> Option<&'static Encoding> {
+        match self.headers.header("content-type") {
+            Some(header) => match header[..] {
+                [value] => content_type_charset(value),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Guesses the encoding of the body, in order to show it as text.
+    ///
+    /// The charset announced in the Content-Type header wins, then a byte order
+    /// mark at the beginning of the body. Otherwise the body is taken as UTF-8
+    /// if it is valid UTF-8, or as the given fallback encoding if it is not.
+    pub fn detect_encoding(&self, fallback: &'static Encoding) -> &'static Encoding {
+        if let Some(encoding) = self.charset() {
+            return encoding;
+        }
+        if let Some((encoding, _)) = Encoding::for_bom(&self.body) {
+            return encoding;
+        }
+        if std::str::from_utf8(&self.body).is_ok() {
+            encoding_rs::UTF_8
+        } else {
+            fallback
+        }
+    }
+
+    /// Decodes the body using the given encoding. Malformed sequences are replaced.
+    pub fn body_str_as(&self, encoding: &'static Encoding) -> String {
+        encoding.decode_with_bom_removal(&self.body).0.into_owned()
+    }
+
     pub fn body_str(&self) -> String {
-        String::from_utf8_lossy(&self.body).into_owned()
+        self.body_str_as(self.detect_encoding(encoding_rs::UTF_8))
     }
 
-    pub fn seconds(&self) -> String {
-        let seconds = (self.duration as f64) / 1000.0;
-        format!("{seconds}")
+    /// Returns the body as a string, reindented if the response is a JSON document.
+    pub fn pretty_body_str(&self) -> String {
+        self.pretty_body_str_as(self.detect_encoding(encoding_rs::UTF_8))
+    }
+
+    /// Like pretty_body_str, but decoding the body using the given encoding.
+    pub fn pretty_body_str_as(&self, encoding: &'static Encoding) -> String {
+        let body = self.body_str_as(encoding);
+        if self.is_json() {
+            let json = serde_json::from_str(&body)
+                .and_then(|text: serde_json::Value| serde_json::to_string_pretty(&text));
+            if let Ok(json) = json {
+                return json;
+            }
+        }
+        body
     }
 }
 
@@ -292,7 +819,7 @@ impl ResponseData {
 mod tests {
     use crate::entities::{KeyValue, RequestMethod};
 
-    use super::{KeyValueTable, ResponseData};
+    use super::{BodyCharset, EndpointData, KeyValueTable, RawEncoding, ResponseData, RetryPolicy};
 
     #[test]
     fn test_response_is_json() {
@@ -319,8 +846,11 @@ mod tests {
                 status_code: 200,
                 duration: 0,
                 size: 0,
+                transfer_size: 0,
                 headers: KeyValueTable(vec![header]),
                 body: Vec::new(),
+                decode_warning: None,
+                version: None,
             };
             assert_eq!(response.is_json(), expected);
         }
@@ -351,8 +881,11 @@ mod tests {
                 status_code: 200,
                 duration: 0,
                 size: 0,
+                transfer_size: 0,
                 headers: KeyValueTable(vec![header]),
                 body: Vec::new(),
+                decode_warning: None,
+                version: None,
             };
             assert_eq!(response.is_xml(), expected);
         }
@@ -363,7 +896,34 @@ mod tests {
         assert!(RequestMethod::try_from("GET").is_ok_and(|x| x == RequestMethod::Get));
         assert!(RequestMethod::try_from("post").is_ok_and(|x| x == RequestMethod::Post));
         assert!(RequestMethod::try_from("Patch").is_ok_and(|x| x == RequestMethod::Patch));
-        assert!(RequestMethod::try_from("Juan").is_err());
+        assert!(RequestMethod::try_from("connect").is_ok_and(|x| x == RequestMethod::Connect));
+        assert!(RequestMethod::try_from("Juan")
+            .is_ok_and(|x| x == RequestMethod::Custom(String::from("Juan"))));
+        assert!(RequestMethod::try_from("PROPFIND").is_ok_and(|x| x.as_str() == "PROPFIND"));
+        assert!(RequestMethod::try_from("Ju an").is_err());
+        assert!(RequestMethod::try_from("").is_err());
+    }
+
+    #[test]
+    fn test_allowed_methods() {
+        let headers = KeyValueTable::new(&[
+            KeyValue::from(("Allow", "GET, HEAD,options")),
+            KeyValue::from(("Access-Control-Allow-Methods", "GET, POST")),
+        ]);
+        let response = ResponseData {
+            status_code: 204,
+            duration: 0,
+            size: 0,
+            transfer_size: 0,
+            headers,
+            body: Vec::new(),
+            decode_warning: None,
+            version: None,
+        };
+        assert_eq!(
+            response.allowed_methods(),
+            vec!["GET", "HEAD", "OPTIONS", "POST"]
+        );
     }
 
     #[test]
@@ -386,45 +946,443 @@ mod tests {
     }
 
     #[test]
-    fn test_group_by_table_header() {
-        let headers = vec![
-            ("Content-Type", "application/json").into(),
-            ("Set-Cookie", "cookie2=value2").into(),
-            ("Set-Cookie", "cookie1=value1").into(),
+    fn test_key_value_table_parse_lines() {
+        let text = "API_ROOT=https://api.example.com\nContent-Type: application/json\n";
+        let table = KeyValueTable::parse_lines(text);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[
+                ("API_ROOT", "https://api.example.com").into(),
+                ("Content-Type", "application/json").into(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_key_value_table_parse_lines_trailing_whitespace() {
+        let text = "  TOKEN =  abcd  \t\r\nAccept:\ttext/html   \r\n";
+        let table = KeyValueTable::parse_lines(text);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[("TOKEN", "abcd").into(), ("Accept", "text/html").into()])
+        );
+    }
+
+    #[test]
+    fn test_key_value_table_parse_lines_empty_lines() {
+        let text = "\n\nA=1\n   \n\nB=2\n\n";
+        let table = KeyValueTable::parse_lines(text);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[("A", "1").into(), ("B", "2").into()])
+        );
+        assert!(KeyValueTable::parse_lines("\n  \n").is_empty());
+    }
+
+    #[test]
+    fn test_key_value_table_parse_lines_quoted_values() {
+        let text = "GREETING=\"hello world\"\nNAME: 'Cartero'\nHALF=\"open\nEMPTY=\"\"";
+        let table = KeyValueTable::parse_lines(text);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[
+                ("GREETING", "hello world").into(),
+                ("NAME", "Cartero").into(),
+                ("HALF", "\"open").into(),
+                ("EMPTY", "").into(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_key_value_table_parse_lines_first_separator() {
+        let text = "Authorization: Bearer a=b\nURL=http://localhost:8080\nFLAG";
+        let table = KeyValueTable::parse_lines(text);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[
+                ("Authorization", "Bearer a=b").into(),
+                ("URL", "http://localhost:8080").into(),
+                ("FLAG", "").into(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_duplicate_variables_first_wins() {
+        use super::EndpointData;
+
+        let inactive = KeyValue {
+            active: false,
+            ..KeyValue::from(("page", "0"))
+        };
+        let endpoint = EndpointData {
+            variables: KeyValueTable::new(&[
+                KeyValue::from(("token", "first")),
+                inactive,
+                KeyValue::from(("page", "1")),
+                KeyValue::from(("token", "second")),
+                KeyValue::from(("page", "2")),
+            ]),
+            ..Default::default()
+        };
+
+        let values = endpoint.variable_values();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["token"], "first");
+        assert_eq!(values["page"], "1");
+        let rendered = endpoint
+            .template_processor()
+            .render("{{token}}/{{page}}")
+            .unwrap();
+        assert_eq!(rendered, "first/1");
+    }
+
+    #[test]
+    fn test_duplicate_headers_first_wins() {
+        use super::EndpointData;
+
+        let inactive = KeyValue {
+            active: false,
+            ..KeyValue::from(("Accept", "text/plain"))
+        };
+        let endpoint = EndpointData {
+            headers: KeyValueTable::new(&[
+                inactive,
+                KeyValue::from(("Accept", "text/html")),
+                KeyValue::from(("X-Trace", "1")),
+                KeyValue::from(("accept", "application/json")),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            endpoint.process_headers(),
+            vec![
+                ("Accept".to_string(), "text/html".to_string()),
+                ("X-Trace".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latin1_round_trip() {
+        let text = "Año de la niña: 15 € à côté";
+        let bytes = BodyCharset::Latin1.encode(text);
+        assert_eq!(bytes.len(), text.chars().count());
+        assert_eq!(bytes[1], 0xF1);
+        assert_eq!(BodyCharset::Latin1.decode(&bytes), text);
+    }
+
+    #[test]
+    fn test_utf16_encode() {
+        assert_eq!(
+            BodyCharset::Utf16Le.encode("añ"),
+            vec![0x61, 0x00, 0xF1, 0x00]
+        );
+        assert_eq!(
+            BodyCharset::Utf16Be.encode("añ"),
+            vec![0x00, 0x61, 0x00, 0xF1]
+        );
+        assert_eq!(BodyCharset::Utf16Be.decode(&[0x00, 0x61, 0x00, 0xF1]), "añ");
+    }
+
+    #[test]
+    fn test_charset_from_label() {
+        assert_eq!(BodyCharset::from_label("UTF-8"), Some(BodyCharset::Utf8));
+        assert_eq!(
+            BodyCharset::from_label("iso-8859-1"),
+            Some(BodyCharset::Latin1)
+        );
+        assert_eq!(
+            BodyCharset::from_label("utf-16le"),
+            Some(BodyCharset::Utf16Le)
+        );
+        assert_eq!(BodyCharset::from_label("shift_jis"), None);
+    }
+
+    #[test]
+    fn test_response_body_charset() {
+        let header = KeyValue::from(("Content-Type", "text/plain; charset=ISO-8859-1"));
+        let response = ResponseData {
+            status_code: 200,
+            duration: 0,
+            size: 0,
+            transfer_size: 0,
+            headers: KeyValueTable(vec![header]),
+            body: vec![0x6E, 0x69, 0xF1, 0x6F],
+            decode_warning: None,
+            version: None,
+        };
+        assert_eq!(response.body_str(), "niño");
+
+        let response = ResponseData {
+            headers: KeyValueTable::default(),
+            ..response
+        };
+        assert_eq!(response.body_str(), "ni\u{FFFD}o");
+    }
+
+    fn response_with_body(ctype: Option<&str>, body: &[u8]) -> ResponseData {
+        let headers = ctype
+            .map(|ctype| vec![KeyValue::from(("Content-Type", ctype))])
+            .unwrap_or_default();
+        ResponseData {
+            status_code: 200,
+            duration: 0,
+            size: body.len(),
+            transfer_size: body.len(),
+            headers: KeyValueTable(headers),
+            body: body.to_vec(),
+            decode_warning: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let latin1 = b"Espa\xf1a";
+        let utf16 = b"\xff\xfeE\x00s\x00p\x00a\x00\xf1\x00a\x00";
+        let cases: [(Option<&str>, &[u8], &str); 5] = [
+            (
+                Some("text/plain; charset=ISO-8859-1"),
+                latin1,
+                "windows-1252",
+            ),
+            (
+                Some("text/plain; charset=Shift_JIS"),
+                b"\x93\xfa",
+                "Shift_JIS",
+            ),
+            (None, utf16, "UTF-16LE"),
+            (None, "España".as_bytes(), "UTF-8"),
+            (None, latin1, "ISO-8859-15"),
         ];
-        let table = KeyValueTable(headers);
+        for (ctype, body, expected) in cases {
+            let response = response_with_body(ctype, body);
+            let encoding = response.detect_encoding(encoding_rs::ISO_8859_15);
+            assert_eq!(encoding.name(), expected);
+        }
+    }
+
+    #[test]
+    fn test_body_str_as() {
+        let utf16 = response_with_body(None, b"\xff\xfeE\x00s\x00p\x00a\x00\xf1\x00a\x00");
+        assert_eq!(utf16.body_str(), "España");
 
-        let grouped = table.group_by();
-        assert_eq!(grouped.len(), 2);
+        let shift_jis = response_with_body(Some("text/plain; charset=Shift_JIS"), b"\x93\xfa");
+        assert_eq!(shift_jis.body_str(), "日");
 
-        let ctype = grouped.get("Content-Type").unwrap();
+        // Forcing another encoding decodes the same bytes again.
+        let latin1 = response_with_body(None, b"Espa\xf1a");
+        assert_eq!(latin1.body_str(), "Espa\u{FFFD}a");
+        assert_eq!(latin1.body_str_as(encoding_rs::WINDOWS_1252), "España");
+        assert_eq!(latin1.body, b"Espa\xf1a");
+    }
+
+    #[test]
+    fn test_response_is_event_stream() {
+        let cases = [
+            ("text/event-stream", true),
+            ("text/event-stream; charset=utf-8", true),
+            ("text/plain", false),
+        ];
+        for (ctype, expected) in cases {
+            let response = ResponseData {
+                status_code: 200,
+                duration: 0,
+                size: 0,
+                transfer_size: 0,
+                headers: KeyValueTable(vec![KeyValue::from(("Content-Type", ctype))]),
+                body: Vec::new(),
+                decode_warning: None,
+                version: None,
+            };
+            assert_eq!(response.is_event_stream(), expected);
+        }
+    }
+
+    #[test]
+    fn test_endpoint_from_url() {
+        let endpoint =
+            EndpointData::from_url("https://api.example.com/v1/books?page=2&q=rust").unwrap();
         assert_eq!(
-            ctype,
-            &vec![KeyValue {
-                name: "Content-Type".into(),
-                value: "application/json".into(),
-                active: true,
-                secret: false
-            },]
+            endpoint.url,
+            "https://api.example.com/v1/books?page=2&q=rust"
         );
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert!(endpoint.headers.is_empty());
+
+        let endpoint = EndpointData::from_url("  example.com/health ").unwrap();
+        assert_eq!(endpoint.url, "https://example.com/health");
 
-        let cookies = grouped.get("Set-Cookie").unwrap();
+        let endpoint = EndpointData::from_url("http://localhost:8080").unwrap();
+        assert_eq!(endpoint.url, "http://localhost:8080/");
+
+        let endpoint = EndpointData::from_url("localhost:8080/health").unwrap();
+        assert_eq!(endpoint.url, "https://localhost:8080/health");
+
+        let endpoint = EndpointData::from_url("example.com:8080/x").unwrap();
+        assert_eq!(endpoint.url, "https://example.com:8080/x");
+    }
+
+    #[test]
+    fn test_endpoint_from_invalid_url() {
+        assert!(EndpointData::from_url("").is_err());
+        assert!(EndpointData::from_url("mailto:someone@example.com").is_err());
+        assert!(EndpointData::from_url("https://exa mple.com").is_err());
+    }
+
+    #[test]
+    fn test_notes_preview() {
+        assert_eq!(super::notes_preview(""), None);
+        assert_eq!(super::notes_preview("  \n "), None);
         assert_eq!(
-            cookies,
-            &vec![
+            super::notes_preview("Requires a\n  valid token"),
+            Some("Requires a valid token".into())
+        );
+
+        let long = "ñ".repeat(100);
+        let preview = super::notes_preview(&long).unwrap();
+        assert_eq!(preview, format!("{}…", "ñ".repeat(80)));
+    }
+
+    fn rule(required: bool, validation_rule: &str, value: &str) -> KeyValue {
+        KeyValue {
+            name: "id".into(),
+            value: value.into(),
+            active: true,
+            secret: false,
+            comment: String::new(),
+            required,
+            validation_rule: validation_rule.into(),
+        }
+    }
+
+    #[test]
+    fn test_broken_rule() {
+        use super::VariableRule;
+
+        assert_eq!(rule(false, "", "").broken_rule(), None);
+        assert_eq!(
+            rule(true, "", "").broken_rule(),
+            Some(VariableRule::Required)
+        );
+        assert_eq!(rule(true, "", "42").broken_rule(), None);
+        assert_eq!(rule(false, "[0-9]+", "").broken_rule(), None);
+        assert_eq!(rule(false, "[0-9]+", "42").broken_rule(), None);
+        assert_eq!(
+            rule(false, "[0-9]+", "42a").broken_rule(),
+            Some(VariableRule::Pattern("[0-9]+".into()))
+        );
+        assert_eq!(
+            rule(true, "a|b", "ab").broken_rule(),
+            Some(VariableRule::Pattern("a|b".into()))
+        );
+        assert_eq!(
+            rule(false, "([0-9]", "1").broken_rule(),
+            Some(VariableRule::Pattern("([0-9]".into()))
+        );
+    }
+
+    #[test]
+    fn test_broken_variable_rules() {
+        use super::{EndpointData, RequestPayload, VariableRule};
+
+        let endpoint = EndpointData {
+            url: "https://example.com/users/{{ id }}".into(),
+            headers: KeyValueTable::new(&[KeyValue::from(("Authorization", "Bearer {{token}}"))]),
+            body: RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                charset: BodyCharset::Utf8,
+                content: Vec::from("{\"page\": {{page}}}"),
+                file: None,
+            },
+            variables: KeyValueTable::new(&[
+                rule(false, "[0-9]+", "abc"),
+                KeyValue {
+                    name: "token".into(),
+                    ..rule(true, "", "")
+                },
                 KeyValue {
-                    name: "Set-Cookie".into(),
-                    value: "cookie2=value2".into(),
-                    active: true,
-                    secret: false
+                    name: "page".into(),
+                    ..rule(false, "[0-9]+", "2")
                 },
                 KeyValue {
-                    name: "Set-Cookie".into(),
-                    value: "cookie1=value1".into(),
-                    active: true,
-                    secret: false
+                    name: "unused".into(),
+                    ..rule(true, "", "")
                 },
+            ]),
+            ..Default::default()
+        };
+        let broken: Vec<(String, VariableRule)> = endpoint
+            .broken_variable_rules()
+            .into_iter()
+            .map(|(item, rule)| (item.name, rule))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                ("id".to_string(), VariableRule::Pattern("[0-9]+".into())),
+                ("token".to_string(), VariableRule::Required),
             ]
         );
     }
+
+    #[test]
+    fn test_remember_url() {
+        use std::collections::VecDeque;
+
+        let mut history = VecDeque::new();
+        super::remember_url(&mut history, "https://example.com/users/1");
+        super::remember_url(&mut history, "https://example.com/users/2");
+        super::remember_url(&mut history, "  ");
+        assert_eq!(
+            history,
+            ["https://example.com/users/2", "https://example.com/users/1"]
+        );
+
+        super::remember_url(&mut history, " https://example.com/users/1 ");
+        assert_eq!(
+            history,
+            ["https://example.com/users/1", "https://example.com/users/2"]
+        );
+
+        for i in 0..20 {
+            super::remember_url(&mut history, &format!("https://example.com/posts/{i}"));
+        }
+        assert_eq!(history.len(), super::URL_HISTORY_LENGTH);
+        assert_eq!(history[0], "https://example.com/posts/19");
+        assert_eq!(history[9], "https://example.com/posts/10");
+    }
+
+    #[test]
+    fn test_raw_encoding_from_extension() {
+        use std::path::Path;
+
+        assert_eq!(
+            RawEncoding::from_extension(Path::new("/tmp/payload.json")),
+            Some(RawEncoding::Json)
+        );
+        assert_eq!(
+            RawEncoding::from_extension(Path::new("feed.XML")),
+            Some(RawEncoding::Xml)
+        );
+        assert_eq!(RawEncoding::from_extension(Path::new("photo.png")), None);
+        assert_eq!(RawEncoding::from_extension(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let mut policy = RetryPolicy {
+            max_attempts: 5,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff_before(2), 1000);
+        assert_eq!(policy.backoff_before(4), 1000);
+
+        policy.exponential_backoff = true;
+        let waits: Vec<u64> = (2..=5).map(|a| policy.backoff_before(a)).collect();
+        assert_eq!(waits, vec![1000, 2000, 4000, 8000]);
+        assert_eq!(policy.backoff_before(100), u64::MAX);
+    }
 }
@@ -1,37 +1,399 @@
+use std::error::Error as _;
+use std::fmt;
+
+use gettextrs::{gettext, ngettext};
+use isahc::error::ErrorKind;
 use srtemplate::SrTemplateError;
 use thiserror::Error;
 
 use crate::client::RequestError;
+use crate::entities::{HttpVersionPreference, VariableRule};
 
 #[derive(Debug, Error)]
 pub enum CarteroError {
-    #[error("No file has been picked")]
     NoFilePicked,
 
-    #[error("Internal error on file dialog")]
     FileDialogError,
 
-    #[error("DNS error")]
-    Dns,
-
-    #[error("Invalid protocol")]
     InvalidProtocol,
 
-    #[error("HTTP request error")]
-    Request(#[from] RequestError),
+    InvalidUrl(#[from] url::ParseError),
+
+    Request(#[source] RequestError),
+
+    RequestTimeout,
+
+    ConnectionRefused(String),
+
+    DnsResolutionFailed(String),
+
+    TlsError(String),
+
+    NetworkError(String),
+
+    Http2NegotiationFailed(String),
 
-    #[error("Error operating with files")]
     FileError(#[from] std::io::Error),
 
-    #[error("Error manipulating TOML")]
     DeserializationError(#[from] toml::de::Error),
 
-    #[error("Error manipulating TOML")]
     SerializationError(#[from] toml::ser::Error),
 
-    #[error("Error during variable interpolation: {0}")]
     VariableInterpolationError(#[from] SrTemplateError),
 
-    #[error("Outdated schema, please update the software")]
-    OutdatedSchema,
+    VariableValidationFailed {
+        name: String,
+        rule: VariableRule,
+        value: String,
+    },
+
+    UnsupportedSchemaVersion(u32),
+
+    InvalidTemplateName,
+
+    TemplateExists,
+
+    FileNotFound,
+
+    BodyFileError,
+
+    ScriptError(String),
+
+    LaunchError(String),
+
+    CurlParseError(String),
+
+    InsomniaImportError(String),
+
+    MaxRetriesExceeded(u32),
+
+    JsonConversionError(String),
+
+    DecodeError(String),
+}
+
+impl fmt::Display for CarteroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            CarteroError::NoFilePicked => gettext("No file has been picked"),
+            CarteroError::FileDialogError => gettext("Internal error on file dialog"),
+            CarteroError::InvalidProtocol => gettext("Invalid protocol"),
+            CarteroError::InvalidUrl(e) => {
+                gettext("Invalid URL: {error}").replace("{error}", &e.to_string())
+            }
+            CarteroError::Request(e) => {
+                gettext("HTTP request error: {error}").replace("{error}", &e.to_string())
+            }
+            CarteroError::RequestTimeout => gettext(
+                "The server took too long to respond. Try again later or increase the timeout.",
+            ),
+            CarteroError::ConnectionRefused(_) => gettext(
+                "Could not connect to the server. Check that the host is reachable and try again.",
+            ),
+            CarteroError::DnsResolutionFailed(_) => gettext(
+                "Could not find the server. Check the spelling of the host name and your connection.",
+            ),
+            CarteroError::TlsError(_) => gettext(
+                "Could not establish a secure connection. The server certificate may be invalid.",
+            ),
+            CarteroError::NetworkError(_) => gettext("The request failed due to a network error."),
+            CarteroError::Http2NegotiationFailed(_) => gettext(
+                "The server did not accept HTTP/2. Try again using HTTP/1.1 or automatic negotiation.",
+            ),
+            CarteroError::FileError(_) => gettext("Error operating with files"),
+            CarteroError::DeserializationError(_) | CarteroError::SerializationError(_) => {
+                gettext("Error manipulating TOML")
+            }
+            CarteroError::VariableInterpolationError(e) => {
+                gettext("Error during variable interpolation: {error}")
+                    .replace("{error}", &e.to_string())
+            }
+            CarteroError::VariableValidationFailed {
+                name,
+                rule: VariableRule::Required,
+                ..
+            } => gettext("The variable {name} is required, but it has no value")
+                .replace("{name}", name),
+            CarteroError::VariableValidationFailed {
+                name,
+                rule: VariableRule::Pattern(pattern),
+                value,
+            } => gettext("The value “{value}” of the variable {name} does not match {pattern}")
+                .replace("{value}", value)
+                .replace("{name}", name)
+                .replace("{pattern}", pattern),
+            CarteroError::UnsupportedSchemaVersion(version) => gettext(
+                "This file uses version {version} of the file format, which is newer than this version of Cartero supports. Please update the software.",
+            )
+            .replace("{version}", &version.to_string()),
+            CarteroError::InvalidTemplateName => {
+                gettext("Template names cannot be empty or contain slashes")
+            }
+            CarteroError::TemplateExists => gettext("A template with this name already exists"),
+            CarteroError::FileNotFound => gettext("The file of this request no longer exists"),
+            CarteroError::BodyFileError => gettext("The file of the request body cannot be read"),
+            CarteroError::ScriptError(e) => {
+                gettext("The pre-request script failed: {error}").replace("{error}", e)
+            }
+            CarteroError::LaunchError(e) => {
+                gettext("Cannot open the file: {error}").replace("{error}", e)
+            }
+            CarteroError::CurlParseError(e) => {
+                gettext("Cannot import the curl command: {error}").replace("{error}", e)
+            }
+            CarteroError::InsomniaImportError(e) => {
+                gettext("Cannot import the Insomnia collection: {error}").replace("{error}", e)
+            }
+            CarteroError::MaxRetriesExceeded(attempts) => ngettext(
+                "The request failed {count} time without getting a response. The console shows the error of every attempt.",
+                "The request failed {count} times without getting a response. The console shows the error of every attempt.",
+                *attempts,
+            )
+            .replace("{count}", &attempts.to_string()),
+            CarteroError::JsonConversionError(e) => {
+                gettext("Cannot convert the response to JSON: {error}").replace("{error}", e)
+            }
+            CarteroError::DecodeError(e) => {
+                gettext("Cannot decode the text: {error}").replace("{error}", e)
+            }
+        };
+        f.write_str(&message)
+    }
+}
+
+impl CarteroError {
+    /// A short title for the error, to be shown above its message.
+    pub fn summary(&self) -> String {
+        match self {
+            CarteroError::RequestTimeout => gettext("Request timed out"),
+            CarteroError::ConnectionRefused(_) => gettext("Connection refused"),
+            CarteroError::DnsResolutionFailed(_) => gettext("Server not found"),
+            CarteroError::TlsError(_) => gettext("Secure connection failed"),
+            CarteroError::NetworkError(_) => gettext("Network error"),
+            CarteroError::Http2NegotiationFailed(_) => gettext("HTTP/2 not supported"),
+            CarteroError::InvalidUrl(_) | CarteroError::InvalidProtocol => gettext("Invalid URL"),
+            CarteroError::VariableInterpolationError(_)
+            | CarteroError::VariableValidationFailed { .. } => gettext("Invalid variables"),
+            CarteroError::ScriptError(_) => gettext("Script error"),
+            CarteroError::MaxRetriesExceeded(_) => gettext("Every attempt failed"),
+            _ => gettext("Request failed"),
+        }
+    }
+
+    /// Whether the error happened while talking to the server, rather than
+    /// while preparing the request.
+    pub fn is_network_error(&self) -> bool {
+        matches!(
+            self,
+            CarteroError::RequestTimeout
+                | CarteroError::ConnectionRefused(_)
+                | CarteroError::DnsResolutionFailed(_)
+                | CarteroError::TlsError(_)
+                | CarteroError::NetworkError(_)
+                | CarteroError::Http2NegotiationFailed(_)
+                | CarteroError::MaxRetriesExceeded(_)
+        )
+    }
+
+    /// The name of the variant, such as `ConnectionRefused`.
+    pub fn variant_name(&self) -> String {
+        let debug = format!("{self:?}");
+        debug
+            .split(|c: char| !c.is_alphanumeric())
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// The text given by the network library, which the message leaves out
+    /// in favour of a friendlier explanation.
+    fn network_details(&self) -> Option<&str> {
+        match self {
+            CarteroError::ConnectionRefused(details)
+            | CarteroError::DnsResolutionFailed(details)
+            | CarteroError::TlsError(details)
+            | CarteroError::NetworkError(details)
+            | CarteroError::Http2NegotiationFailed(details) => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Describes the error and every error that caused it, one per line, so
+    /// that it can be pasted into a bug report.
+    pub fn details(&self) -> String {
+        let mut causes: Vec<String> = self
+            .network_details()
+            .map(String::from)
+            .into_iter()
+            .collect();
+        let mut source = self.source();
+        while let Some(error) = source {
+            causes.push(format!("{error:#}"));
+            source = error.source();
+        }
+
+        let mut details = format!("{}: {self}", self.variant_name());
+        if !causes.is_empty() {
+            details.push_str("\n\nCaused by:");
+            for (position, cause) in causes.iter().enumerate() {
+                details.push_str(&format!("\n  {position}: {cause}"));
+            }
+        }
+        details
+    }
+
+    /// Converts an error raised while sending a request with the given HTTP version.
+    ///
+    /// If HTTP/2 was forced, a server that breaks the protocol or drops the
+    /// connection most likely does not speak HTTP/2 at all.
+    pub fn from_request_error(value: RequestError, http_version: HttpVersionPreference) -> Self {
+        match value {
+            RequestError::NetworkError(e)
+                if http_version == HttpVersionPreference::Http2Prior
+                    && matches!(e.kind(), ErrorKind::ProtocolViolation | ErrorKind::Io) =>
+            {
+                CarteroError::Http2NegotiationFailed(e.to_string())
+            }
+            other => other.into(),
+        }
+    }
+}
+
+impl From<isahc::Error> for CarteroError {
+    fn from(value: isahc::Error) -> Self {
+        let details = value.to_string();
+        match value.kind() {
+            ErrorKind::Timeout => CarteroError::RequestTimeout,
+            ErrorKind::ConnectionFailed => CarteroError::ConnectionRefused(details),
+            ErrorKind::NameResolution => CarteroError::DnsResolutionFailed(details),
+            ErrorKind::BadServerCertificate
+            | ErrorKind::BadClientCertificate
+            | ErrorKind::TlsEngine => CarteroError::TlsError(details),
+            _ => CarteroError::NetworkError(details),
+        }
+    }
+}
+
+impl From<RequestError> for CarteroError {
+    fn from(value: RequestError) -> Self {
+        match value {
+            RequestError::NetworkError(e) => e.into(),
+            other => CarteroError::Request(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use isahc::error::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn test_network_errors_are_mapped() {
+        let cases = [
+            (ErrorKind::Timeout, "RequestTimeout"),
+            (ErrorKind::ConnectionFailed, "ConnectionRefused"),
+            (ErrorKind::NameResolution, "DnsResolutionFailed"),
+            (ErrorKind::BadServerCertificate, "TlsError"),
+            (ErrorKind::TlsEngine, "TlsError"),
+            (ErrorKind::TooManyRedirects, "NetworkError"),
+        ];
+        for (kind, expected) in cases {
+            let error = CarteroError::from(RequestError::NetworkError(kind.into()));
+            let debug = format!("{error:?}");
+            assert!(debug.starts_with(expected), "{debug}");
+        }
+    }
+
+    #[test]
+    fn test_other_request_errors_are_kept() {
+        let error = CarteroError::from(RequestError::Cancelled);
+        assert!(matches!(
+            error,
+            CarteroError::Request(RequestError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_error_summary() {
+        let timeout = CarteroError::from(RequestError::NetworkError(ErrorKind::Timeout.into()));
+        assert_eq!(timeout.summary(), "Request timed out");
+        assert!(timeout.is_network_error());
+
+        let cancelled = CarteroError::from(RequestError::Cancelled);
+        assert_eq!(cancelled.summary(), "Request failed");
+        assert!(!cancelled.is_network_error());
+
+        assert_eq!(CarteroError::InvalidProtocol.summary(), "Invalid URL");
+
+        let retries = CarteroError::MaxRetriesExceeded(3);
+        assert_eq!(retries.summary(), "Every attempt failed");
+        assert!(retries
+            .to_string()
+            .starts_with("The request failed 3 times"));
+        assert!(retries.is_network_error());
+
+        let invalid = CarteroError::VariableValidationFailed {
+            name: "user_id".into(),
+            rule: VariableRule::Pattern("[0-9]+".into()),
+            value: "abc".into(),
+        };
+        assert_eq!(invalid.summary(), "Invalid variables");
+        assert_eq!(
+            invalid.to_string(),
+            "The value “abc” of the variable user_id does not match [0-9]+"
+        );
+        assert!(!invalid.is_network_error());
+    }
+
+    #[test]
+    fn test_error_details() {
+        assert_eq!(
+            CarteroError::InvalidProtocol.variant_name(),
+            "InvalidProtocol"
+        );
+        assert_eq!(
+            CarteroError::InvalidProtocol.details(),
+            "InvalidProtocol: Invalid protocol"
+        );
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = CarteroError::from(RequestError::IOError(io));
+        assert_eq!(error.variant_name(), "Request");
+        let details = error.details();
+        assert!(
+            details.starts_with("Request: HTTP request error: "),
+            "{details}"
+        );
+        assert!(details.contains("Caused by:\n  0: "), "{details}");
+        assert!(details.ends_with("1: no such file"), "{details}");
+
+        let error = CarteroError::ConnectionRefused(String::from("port 80 is closed"));
+        assert!(error
+            .details()
+            .ends_with("Caused by:\n  0: port 80 is closed"));
+    }
+
+    #[test]
+    fn test_http2_errors_are_mapped_when_forced() {
+        let error = CarteroError::from_request_error(
+            RequestError::NetworkError(ErrorKind::ProtocolViolation.into()),
+            HttpVersionPreference::Http2Prior,
+        );
+        assert!(matches!(error, CarteroError::Http2NegotiationFailed(_)));
+        assert!(error.is_network_error());
+
+        let error = CarteroError::from_request_error(
+            RequestError::NetworkError(ErrorKind::ProtocolViolation.into()),
+            HttpVersionPreference::Auto,
+        );
+        assert!(matches!(error, CarteroError::NetworkError(_)));
+
+        let error = CarteroError::from_request_error(
+            RequestError::NetworkError(ErrorKind::Timeout.into()),
+            HttpVersionPreference::Http2Prior,
+        );
+        assert!(matches!(error, CarteroError::RequestTimeout));
+    }
 }
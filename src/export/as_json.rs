@@ -0,0 +1,269 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use base64::prelude::*;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{json, Map, Value};
+
+use crate::error::CarteroError;
+use crate::utils::{compact_json, is_binary};
+
+/// How the body of a response is turned into JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonSource {
+    /// The body is already JSON, so it is only compacted.
+    Json,
+    /// The elements of the document become objects.
+    Xml,
+    /// The body is wrapped as `{"body": "..."}`.
+    Text,
+    /// The body is wrapped as `{"body_base64": "..."}`.
+    Binary,
+}
+
+impl JsonSource {
+    pub fn detect(content_type: &str, body: &[u8]) -> Self {
+        let content_type = content_type.to_lowercase();
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        if mime == "application/json" || mime.ends_with("+json") {
+            Self::Json
+        } else if mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml") {
+            Self::Xml
+        } else if !is_binary(body) && std::str::from_utf8(body).is_ok() {
+            Self::Text
+        } else {
+            Self::Binary
+        }
+    }
+
+    /// Whether some details of the body cannot be told from the JSON, such as
+    /// the order of the attributes or the comments of an XML document.
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, Self::Xml)
+    }
+}
+
+fn conversion_error(message: impl ToString) -> CarteroError {
+    CarteroError::JsonConversionError(message.to_string())
+}
+
+fn element_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.name().as_ref()).to_string()
+}
+
+/// The attributes of an element, as keys starting with `@`.
+fn element_attributes(element: &BytesStart) -> Result<Map<String, Value>, CarteroError> {
+    let mut map = Map::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(conversion_error)?;
+        let key = format!("@{}", String::from_utf8_lossy(attribute.key.as_ref()));
+        let value = attribute.unescape_value().map_err(conversion_error)?;
+        map.insert(key, Value::String(value.to_string()));
+    }
+    Ok(map)
+}
+
+/// Adds a child to an element. Children sharing a name are grouped into an array.
+fn insert_child(map: &mut Map<String, Value>, name: String, value: Value) {
+    match map.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            map.insert(name, value);
+        }
+    }
+}
+
+/// The value of an element that has been closed. Elements without attributes
+/// or children become their text, or null if they are empty.
+fn element_value(mut map: Map<String, Value>, text: String) -> Value {
+    if map.is_empty() {
+        if text.is_empty() {
+            Value::Null
+        } else {
+            Value::String(text)
+        }
+    } else {
+        if !text.is_empty() {
+            map.insert(String::from("#text"), Value::String(text));
+        }
+        Value::Object(map)
+    }
+}
+
+fn xml_to_json(text: &str) -> Result<Value, CarteroError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    // Every element still open, holding its name, its children and its text.
+    let mut stack: Vec<(String, Map<String, Value>, String)> =
+        vec![(String::new(), Map::new(), String::new())];
+    loop {
+        match reader.read_event().map_err(conversion_error)? {
+            Event::Start(element) => {
+                let attributes = element_attributes(&element)?;
+                stack.push((element_name(&element), attributes, String::new()));
+            }
+            Event::Empty(element) => {
+                let value = element_value(element_attributes(&element)?, String::new());
+                if let Some((_, parent, _)) = stack.last_mut() {
+                    insert_child(parent, element_name(&element), value);
+                }
+            }
+            Event::End(_) => {
+                if stack.len() < 2 {
+                    return Err(conversion_error("unexpected closing tag"));
+                }
+                let (name, map, text) = stack.pop().unwrap();
+                let value = element_value(map, text);
+                if let Some((_, parent, _)) = stack.last_mut() {
+                    insert_child(parent, name, value);
+                }
+            }
+            Event::Text(content) => {
+                let content = content.unescape().map_err(conversion_error)?;
+                if let Some((_, _, text)) = stack.last_mut() {
+                    text.push_str(&content);
+                }
+            }
+            Event::CData(content) => {
+                if let Some((_, _, text)) = stack.last_mut() {
+                    text.push_str(&String::from_utf8_lossy(&content));
+                }
+            }
+            Event::Eof => break,
+            // Declarations, comments and processing instructions are not kept.
+            _ => {}
+        }
+    }
+
+    match stack.pop() {
+        Some((_, root, _)) if stack.is_empty() => Ok(Value::Object(root)),
+        _ => Err(conversion_error(
+            "the document ends before closing every tag",
+        )),
+    }
+}
+
+/// Converts the body of a response into a compact JSON document.
+pub fn response_to_json(content_type: &str, body: &[u8]) -> Result<String, CarteroError> {
+    match JsonSource::detect(content_type, body) {
+        JsonSource::Json => {
+            let text = String::from_utf8_lossy(body);
+            compact_json(&text).map_err(|e| conversion_error(e.message))
+        }
+        JsonSource::Xml => {
+            let value = xml_to_json(&String::from_utf8_lossy(body))?;
+            Ok(value.to_string())
+        }
+        JsonSource::Text => {
+            let text = String::from_utf8_lossy(body);
+            Ok(json!({ "body": text }).to_string())
+        }
+        JsonSource::Binary => {
+            let encoded = BASE64_STANDARD.encode(body);
+            Ok(json!({ "body_base64": encoded }).to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            JsonSource::detect("application/json; charset=utf-8", b"{}"),
+            JsonSource::Json
+        );
+        assert_eq!(
+            JsonSource::detect("application/problem+json", b"{}"),
+            JsonSource::Json
+        );
+        assert_eq!(JsonSource::detect("Text/XML", b"<a/>"), JsonSource::Xml);
+        assert_eq!(JsonSource::detect("text/plain", b"hello"), JsonSource::Text);
+        assert_eq!(JsonSource::detect("", b"hello"), JsonSource::Text);
+        assert_eq!(
+            JsonSource::detect("image/png", b"\x89PNG\r\n\x1a\n\0\0"),
+            JsonSource::Binary
+        );
+        assert!(JsonSource::Xml.is_lossy());
+        assert!(!JsonSource::Json.is_lossy());
+    }
+
+    #[test]
+    fn test_json_is_compacted() {
+        let body = b"{\n  \"b\": [1, 2.50],\n  \"a\": \"x y\"\n}";
+        assert_eq!(
+            response_to_json("application/json", body).unwrap(),
+            r#"{"b":[1,2.50],"a":"x y"}"#
+        );
+        assert!(response_to_json("application/json", b"{\"a\":").is_err());
+    }
+
+    #[test]
+    fn test_xml() {
+        let body = br#"<?xml version="1.0"?>
+            <!-- A list of books -->
+            <books count="2">
+              <book id="1"><title>Dune</title></book>
+              <book id="2"><title>Neuromancer &amp; more</title><draft/></book>
+            </books>"#;
+        let json: Value =
+            serde_json::from_str(&response_to_json("text/xml", body).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "books": {
+                    "@count": "2",
+                    "book": [
+                        { "@id": "1", "title": "Dune" },
+                        { "@id": "2", "title": "Neuromancer & more", "draft": null },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_xml_text_with_attributes() {
+        let body = b"<price currency=\"EUR\"><![CDATA[10]]></price>";
+        assert_eq!(
+            response_to_json("application/xml", body).unwrap(),
+            r##"{"price":{"#text":"10","@currency":"EUR"}}"##
+        );
+        assert!(response_to_json("application/xml", b"<a><b></a>").is_err());
+        assert!(response_to_json("application/xml", b"<a>").is_err());
+    }
+
+    #[test]
+    fn test_text_and_binary() {
+        assert_eq!(
+            response_to_json("text/plain", b"Hello \"world\"\n").unwrap(),
+            r#"{"body":"Hello \"world\"\n"}"#
+        );
+        assert_eq!(
+            response_to_json("application/octet-stream", &[0, 1, 2, 255]).unwrap(),
+            r#"{"body_base64":"AAEC/w=="}"#
+        );
+    }
+}
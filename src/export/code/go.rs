@@ -0,0 +1,231 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::client::BoundRequest;
+
+use super::{literal, quote, CodeGenerator, LiteralSyntax, RequestParts, SnippetBody};
+
+const GO: LiteralSyntax = LiteralSyntax {
+    null: "nil",
+    true_: "true",
+    false_: "false",
+    object_open: "map[string]any{",
+    object_close: "}",
+    array_open: "[]any{",
+    array_close: "}",
+    indent: "\t",
+    quote,
+};
+
+/// Generates code for the `net/http` package of the Go standard library.
+pub struct GoGenerator;
+
+/// Returns the name of the constant for the method, such as `http.MethodPost`.
+/// Methods without a constant are written as a string.
+fn method_constant(method: &str) -> String {
+    const STANDARD: [&str; 9] = [
+        "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+    ];
+    if !STANDARD.contains(&method) {
+        return quote(method);
+    }
+    let mut chars = method.chars();
+    match chars.next() {
+        Some(first) => format!("http.Method{first}{}", chars.as_str().to_lowercase()),
+        None => String::from("http.MethodGet"),
+    }
+}
+
+impl CodeGenerator for GoGenerator {
+    fn name(&self) -> &'static str {
+        "Go (net/http)"
+    }
+
+    fn generate(&self, request: &BoundRequest) -> String {
+        let parts = RequestParts::from(request);
+
+        // Go refuses to build with unused imports, so only import what is used.
+        let mut imports = vec!["fmt", "io", "net/http"];
+        if !parts.params.is_empty() {
+            imports.push("net/url");
+        }
+        match parts.body {
+            Some(SnippetBody::Json(_)) => imports.extend(["bytes", "encoding/json"]),
+            Some(SnippetBody::Text(_)) => imports.push("strings"),
+            None => {}
+        }
+        imports.sort();
+
+        let mut out = String::from("package main\n\nimport (\n");
+        for import in imports {
+            out.push_str(&format!("\t\"{import}\"\n"));
+        }
+        out.push_str(")\n\nfunc main() {\n");
+
+        if parts.params.is_empty() {
+            out.push_str(&format!("\tendpoint := {}\n", quote(&parts.url)));
+        } else {
+            out.push_str("\tparams := url.Values{}\n");
+            for (key, value) in &parts.params {
+                out.push_str(&format!("\tparams.Add({}, {})\n", quote(key), quote(value)));
+            }
+            out.push_str(&format!(
+                "\tendpoint := {} + \"?\" + params.Encode()\n",
+                quote(&parts.url)
+            ));
+        }
+        out.push('\n');
+
+        let body = match &parts.body {
+            Some(SnippetBody::Json(json)) => {
+                out.push_str(&format!(
+                    "\tpayload, err := json.Marshal({})\n",
+                    literal(json, &GO, 1)
+                ));
+                out.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n\n");
+                "bytes.NewReader(payload)"
+            }
+            Some(SnippetBody::Text(text)) => {
+                out.push_str(&format!(
+                    "\tpayload := strings.NewReader({})\n\n",
+                    quote(text)
+                ));
+                "payload"
+            }
+            None => "nil",
+        };
+
+        out.push_str(&format!(
+            "\treq, err := http.NewRequest({}, endpoint, {body})\n",
+            method_constant(&parts.method)
+        ));
+        out.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+        for (name, value) in &parts.headers {
+            out.push_str(&format!(
+                "\treq.Header.Set({}, {})\n",
+                quote(name),
+                quote(value)
+            ));
+        }
+        out.push('\n');
+
+        out.push_str("\tres, err := http.DefaultClient.Do(req)\n");
+        out.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+        out.push_str("\tdefer res.Body.Close()\n\n");
+        out.push_str("\tbody, err := io.ReadAll(res.Body)\n");
+        out.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+        out.push_str("\tfmt.Println(res.StatusCode)\n");
+        out.push_str("\tfmt.Println(string(body))\n");
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{json_request, plain_request};
+    use super::*;
+
+    #[test]
+    fn test_go_json_request() {
+        let code = GoGenerator.generate(&json_request());
+        let expected = r#"package main
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+)
+
+func main() {
+	params := url.Values{}
+	params.Add("page", "2")
+	params.Add("q", "rust lang")
+	endpoint := "https://api.example.com/v1/books" + "?" + params.Encode()
+
+	payload, err := json.Marshal(map[string]any{
+		"draft": false,
+		"isbn": nil,
+		"price": 9.5,
+		"tags": []any{
+			"http",
+			"gtk",
+		},
+		"title": "Cartero",
+	})
+	if err != nil {
+		panic(err)
+	}
+
+	req, err := http.NewRequest(http.MethodPost, endpoint, bytes.NewReader(payload))
+	if err != nil {
+		panic(err)
+	}
+	req.Header.Set("Authorization", "Bearer 1234")
+	req.Header.Set("Content-Type", "application/json")
+
+	res, err := http.DefaultClient.Do(req)
+	if err != nil {
+		panic(err)
+	}
+	defer res.Body.Close()
+
+	body, err := io.ReadAll(res.Body)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Println(res.StatusCode)
+	fmt.Println(string(body))
+}
+"#;
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn test_go_plain_request() {
+        let code = GoGenerator.generate(&plain_request());
+        assert!(code.contains("import (\n\t\"fmt\"\n\t\"io\"\n\t\"net/http\"\n)\n"));
+        assert!(code.contains("\tendpoint := \"https://api.example.com/health\"\n"));
+        assert!(code.contains("http.NewRequest(http.MethodGet, endpoint, nil)"));
+    }
+
+    #[test]
+    fn test_go_custom_method() {
+        let mut request = plain_request();
+        request.method = crate::entities::RequestMethod::Custom(String::from("PROPFIND"));
+        let code = GoGenerator.generate(&request);
+        assert!(code.contains("http.NewRequest(\"PROPFIND\", endpoint, nil)"));
+
+        request.method = crate::entities::RequestMethod::Connect;
+        let code = GoGenerator.generate(&request);
+        assert!(code.contains("http.NewRequest(http.MethodConnect, endpoint, nil)"));
+    }
+
+    #[test]
+    fn test_go_text_body() {
+        let mut request = plain_request();
+        request.method = crate::entities::RequestMethod::Options;
+        request.body = Some(Vec::from("hello"));
+        let code = GoGenerator.generate(&request);
+        assert!(code.contains("\t\"strings\"\n"));
+        assert!(code.contains("\tpayload := strings.NewReader(\"hello\")\n"));
+        assert!(code.contains("http.NewRequest(http.MethodOptions, endpoint, payload)"));
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::client::BoundRequest;
+
+use super::{literal, quote, CodeGenerator, LiteralSyntax, RequestParts, SnippetBody};
+
+const JAVASCRIPT: LiteralSyntax = LiteralSyntax {
+    null: "null",
+    true_: "true",
+    false_: "false",
+    object_open: "{",
+    object_close: "}",
+    array_open: "[",
+    array_close: "]",
+    indent: "  ",
+    quote,
+};
+
+/// Generates code for the `fetch` API available in browsers, Node.js and Deno.
+pub struct JavaScriptGenerator;
+
+impl CodeGenerator for JavaScriptGenerator {
+    fn name(&self) -> &'static str {
+        "JavaScript (fetch)"
+    }
+
+    fn generate(&self, request: &BoundRequest) -> String {
+        let parts = RequestParts::from(request);
+        let mut out = String::new();
+
+        let target = if parts.params.is_empty() {
+            quote(&parts.url)
+        } else {
+            out.push_str(&format!("const url = new URL({});\n", quote(&parts.url)));
+            for (key, value) in &parts.params {
+                out.push_str(&format!(
+                    "url.searchParams.append({}, {});\n",
+                    quote(key),
+                    quote(value)
+                ));
+            }
+            out.push('\n');
+            String::from("url")
+        };
+
+        let mut options = Vec::new();
+        if parts.method != "GET" {
+            options.push(format!("  method: {},\n", quote(&parts.method)));
+        }
+        if !parts.headers.is_empty() {
+            let mut headers = String::from("  headers: {\n");
+            for (name, value) in &parts.headers {
+                headers.push_str(&format!("    {}: {},\n", quote(name), quote(value)));
+            }
+            headers.push_str("  },\n");
+            options.push(headers);
+        }
+        match &parts.body {
+            Some(SnippetBody::Json(json)) => {
+                let json = literal(json, &JAVASCRIPT, 1);
+                options.push(format!("  body: JSON.stringify({json}),\n"));
+            }
+            Some(SnippetBody::Text(text)) => {
+                options.push(format!("  body: {},\n", quote(text)));
+            }
+            None => {}
+        }
+
+        if options.is_empty() {
+            out.push_str(&format!("const response = await fetch({target});\n"));
+        } else {
+            out.push_str(&format!("const response = await fetch({target}, {{\n"));
+            out.push_str(&options.concat());
+            out.push_str("});\n");
+        }
+        out.push_str("console.log(response.status);\n");
+        out.push_str("console.log(await response.text());\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{json_request, plain_request};
+    use super::*;
+
+    #[test]
+    fn test_javascript_json_request() {
+        let code = JavaScriptGenerator.generate(&json_request());
+        assert_eq!(
+            code,
+            r#"const url = new URL("https://api.example.com/v1/books");
+url.searchParams.append("page", "2");
+url.searchParams.append("q", "rust lang");
+
+const response = await fetch(url, {
+  method: "POST",
+  headers: {
+    "Authorization": "Bearer 1234",
+    "Content-Type": "application/json",
+  },
+  body: JSON.stringify({
+    "draft": false,
+    "isbn": null,
+    "price": 9.5,
+    "tags": [
+      "http",
+      "gtk",
+    ],
+    "title": "Cartero",
+  }),
+});
+console.log(response.status);
+console.log(await response.text());
+"#
+        );
+    }
+
+    #[test]
+    fn test_javascript_plain_request() {
+        let code = JavaScriptGenerator.generate(&plain_request());
+        assert_eq!(
+            code,
+            r#"const response = await fetch("https://api.example.com/health");
+console.log(response.status);
+console.log(await response.text());
+"#
+        );
+    }
+
+    #[test]
+    fn test_javascript_text_body() {
+        let mut request = plain_request();
+        request.method = crate::entities::RequestMethod::Put;
+        request.body = Some(Vec::from("line 1\nline \"2\""));
+        let code = JavaScriptGenerator.generate(&request);
+        assert!(code.contains("  method: \"PUT\",\n"));
+        assert!(code.contains("  body: \"line 1\\nline \\\"2\\\"\",\n"));
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+mod go;
+mod javascript;
+mod python;
+pub mod test_stub;
+
+use serde_json::Value;
+use url::Url;
+
+use crate::client::BoundRequest;
+
+pub use go::GoGenerator;
+pub use javascript::JavaScriptGenerator;
+pub use python::PythonGenerator;
+
+/// Turns a request into a snippet of source code that sends the same request.
+///
+/// Implementations must be pure: the same request always generates the same code.
+pub trait CodeGenerator {
+    /// The name of the language and library, as presented to the user.
+    fn name(&self) -> &'static str;
+
+    fn generate(&self, request: &BoundRequest) -> String;
+}
+
+/// Every available code generator, in the order they are offered.
+pub fn generators() -> Vec<Box<dyn CodeGenerator>> {
+    vec![
+        Box::new(PythonGenerator),
+        Box::new(JavaScriptGenerator),
+        Box::new(GoGenerator),
+    ]
+}
+
+/// The body of a request, as it should be written in the generated code.
+#[derive(Debug, Clone, PartialEq)]
+enum SnippetBody {
+    Json(Value),
+    Text(String),
+}
+
+/// The parts of a request, split the way the generators need them.
+#[derive(Debug, Clone, PartialEq)]
+struct RequestParts {
+    /// The URL without the query string, which is moved into `params`.
+    url: String,
+    method: String,
+    params: Vec<(String, String)>,
+    /// The headers, sorted by name so that the output is stable.
+    headers: Vec<(String, String)>,
+    body: Option<SnippetBody>,
+}
+
+impl From<&BoundRequest> for RequestParts {
+    fn from(request: &BoundRequest) -> Self {
+        let (url, params) = match Url::parse(&request.url) {
+            Ok(mut url) if url.query().is_some() => {
+                let params = url.query_pairs().into_owned().collect();
+                url.set_query(None);
+                url.set_fragment(None);
+                (url.to_string(), params)
+            }
+            _ => (request.url.clone(), Vec::new()),
+        };
+
+        let mut headers: Vec<(String, String)> = request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        headers.sort_by_key(|(name, _)| name.to_lowercase());
+
+        let is_json = headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("content-type") && value.contains("json")
+        });
+        let body = match &request.body {
+            Some(body) if !body.is_empty() => {
+                let json = if is_json {
+                    serde_json::from_slice::<Value>(body).ok()
+                } else {
+                    None
+                };
+                Some(match json {
+                    Some(json) => SnippetBody::Json(json),
+                    None => SnippetBody::Text(String::from_utf8_lossy(body).into_owned()),
+                })
+            }
+            _ => None,
+        };
+
+        Self {
+            url,
+            method: request.method.as_str().to_string(),
+            params,
+            headers,
+            body,
+        }
+    }
+}
+
+/// How a language spells the JSON values when they are written as native literals.
+struct LiteralSyntax {
+    null: &'static str,
+    true_: &'static str,
+    false_: &'static str,
+    object_open: &'static str,
+    object_close: &'static str,
+    array_open: &'static str,
+    array_close: &'static str,
+    indent: &'static str,
+    quote: fn(&str) -> String,
+}
+
+/// Quotes a string. JSON escapes are valid in Python, JavaScript and Go strings.
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// Writes a JSON value as a native literal, one item per line with trailing commas.
+///
+/// `level` is the indentation level of the line where the literal starts.
+fn literal(value: &Value, syntax: &LiteralSyntax, level: usize) -> String {
+    let inner = syntax.indent.repeat(level + 1);
+    let outer = syntax.indent.repeat(level);
+    match value {
+        Value::Null => syntax.null.into(),
+        Value::Bool(true) => syntax.true_.into(),
+        Value::Bool(false) => syntax.false_.into(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => (syntax.quote)(text),
+        Value::Array(items) if items.is_empty() => {
+            format!("{}{}", syntax.array_open, syntax.array_close)
+        }
+        Value::Array(items) => {
+            let mut out = format!("{}\n", syntax.array_open);
+            for item in items {
+                let item = literal(item, syntax, level + 1);
+                out.push_str(&format!("{inner}{item},\n"));
+            }
+            out.push_str(&format!("{outer}{}", syntax.array_close));
+            out
+        }
+        Value::Object(map) if map.is_empty() => {
+            format!("{}{}", syntax.object_open, syntax.object_close)
+        }
+        Value::Object(map) => {
+            let mut out = format!("{}\n", syntax.object_open);
+            for (key, item) in map {
+                let item = literal(item, syntax, level + 1);
+                out.push_str(&format!("{inner}{}: {item},\n", (syntax.quote)(key)));
+            }
+            out.push_str(&format!("{outer}{}", syntax.object_close));
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::entities::{
+        EndpointData, KeyValue, KeyValueTable, RequestMethod, SECRET_PLACEHOLDER,
+    };
+
+    use super::*;
+
+    /// A POST request with query params, headers and a JSON body.
+    pub fn json_request() -> BoundRequest {
+        BoundRequest {
+            url: "https://api.example.com/v1/books?page=2&q=rust+lang".into(),
+            method: RequestMethod::Post,
+            headers: HashMap::from([
+                ("Content-Type".into(), "application/json".into()),
+                ("Authorization".into(), "Bearer 1234".into()),
+            ]),
+            body: Some(Vec::from(
+                r#"{"title": "Cartero", "tags": ["http", "gtk"], "price": 9.5, "draft": false, "isbn": null}"#,
+            )),
+            ..Default::default()
+        }
+    }
+
+    /// A GET request without a body nor query params.
+    pub fn plain_request() -> BoundRequest {
+        BoundRequest {
+            url: "https://api.example.com/health".into(),
+            method: RequestMethod::Get,
+            headers: HashMap::new(),
+            body: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_request_parts() {
+        let parts = RequestParts::from(&json_request());
+        assert_eq!(parts.url, "https://api.example.com/v1/books");
+        assert_eq!(parts.method, "POST");
+        assert_eq!(
+            parts.params,
+            vec![
+                ("page".to_string(), "2".to_string()),
+                ("q".to_string(), "rust lang".to_string()),
+            ]
+        );
+        assert_eq!(parts.headers[0].0, "Authorization");
+        assert_eq!(parts.headers[1].0, "Content-Type");
+        assert!(matches!(parts.body, Some(SnippetBody::Json(_))));
+    }
+
+    #[test]
+    fn test_invalid_json_is_kept_as_text() {
+        let mut request = json_request();
+        request.body = Some(Vec::from("{not json"));
+        let parts = RequestParts::from(&request);
+        assert_eq!(parts.body, Some(SnippetBody::Text("{not json".into())));
+    }
+
+    #[test]
+    fn test_every_generator_has_a_name() {
+        let names: Vec<&str> = generators().iter().map(|g| g.name()).collect();
+        assert_eq!(
+            names,
+            vec!["Python (requests)", "JavaScript (fetch)", "Go (net/http)"]
+        );
+    }
+
+    #[test]
+    fn test_masked_secrets_are_not_generated() {
+        let mut token = KeyValue::from(("TOKEN", "s3cr3t"));
+        token.secret = true;
+        let mut api_key = KeyValue::from(("X-Api-Key", "hunter2"));
+        api_key.secret = true;
+        let endpoint = EndpointData {
+            url: "https://api.example.com/me".into(),
+            headers: KeyValueTable::new(&[("Authorization", "Bearer {{TOKEN}}").into(), api_key]),
+            variables: KeyValueTable::new(&[token]),
+            ..Default::default()
+        };
+        let request = BoundRequest::bind(endpoint.masking_secrets(), &[]).unwrap();
+
+        for generator in generators() {
+            let code = generator.generate(&request);
+            assert!(!code.contains("s3cr3t"), "{}", generator.name());
+            assert!(!code.contains("hunter2"), "{}", generator.name());
+            assert!(code.contains(SECRET_PLACEHOLDER), "{}", generator.name());
+        }
+    }
+}
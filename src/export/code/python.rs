@@ -0,0 +1,175 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::client::BoundRequest;
+
+use super::{literal, quote, CodeGenerator, LiteralSyntax, RequestParts, SnippetBody};
+
+pub(super) const PYTHON: LiteralSyntax = LiteralSyntax {
+    null: "None",
+    true_: "True",
+    false_: "False",
+    object_open: "{",
+    object_close: "}",
+    array_open: "[",
+    array_close: "]",
+    indent: "    ",
+    quote,
+};
+
+/// Generates code for the Python `requests` library.
+pub struct PythonGenerator;
+
+/// Writes pairs as a dict, or as a list of tuples if a key is repeated.
+fn pairs(name: &str, pairs: &[(String, String)]) -> String {
+    let repeated = pairs
+        .iter()
+        .enumerate()
+        .any(|(i, (key, _))| pairs[..i].iter().any(|(other, _)| other == key));
+    let mut out = String::new();
+    if repeated {
+        out.push_str(&format!("{name} = [\n"));
+        for (key, value) in pairs {
+            out.push_str(&format!("    ({}, {}),\n", quote(key), quote(value)));
+        }
+        out.push_str("]\n");
+    } else {
+        out.push_str(&format!("{name} = {{\n"));
+        for (key, value) in pairs {
+            out.push_str(&format!("    {}: {},\n", quote(key), quote(value)));
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+/// The statements that prepare the arguments of a request and the expression
+/// that sends it, so that the request can be embedded in other code.
+pub(super) fn request_code(parts: &RequestParts) -> (String, String) {
+    let mut out = String::new();
+    let mut args = vec![String::from("url")];
+
+    out.push_str(&format!("url = {}\n", quote(&parts.url)));
+    if !parts.params.is_empty() {
+        out.push_str(&pairs("params", &parts.params));
+        args.push("params=params".into());
+    }
+    if !parts.headers.is_empty() {
+        out.push_str(&pairs("headers", &parts.headers));
+        args.push("headers=headers".into());
+    }
+    match &parts.body {
+        Some(SnippetBody::Json(json)) => {
+            out.push_str(&format!("payload = {}\n", literal(json, &PYTHON, 0)));
+            args.push("json=payload".into());
+        }
+        Some(SnippetBody::Text(text)) => {
+            out.push_str(&format!("payload = {}\n", quote(text)));
+            args.push("data=payload".into());
+        }
+        None => {}
+    }
+
+    let call = match parts.method.as_str() {
+        method @ ("GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" | "OPTIONS") => {
+            format!("requests.{}({})", method.to_lowercase(), args.join(", "))
+        }
+        method => format!("requests.request({}, {})", quote(method), args.join(", ")),
+    };
+    (out, call)
+}
+
+impl CodeGenerator for PythonGenerator {
+    fn name(&self) -> &'static str {
+        "Python (requests)"
+    }
+
+    fn generate(&self, request: &BoundRequest) -> String {
+        let parts = RequestParts::from(request);
+        let (setup, call) = request_code(&parts);
+        let mut out = String::from("import requests\n\n");
+        out.push_str(&setup);
+        out.push_str(&format!("\nresponse = {call}\n"));
+        out.push_str("print(response.status_code)\n");
+        out.push_str("print(response.text)\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{json_request, plain_request};
+    use super::*;
+
+    #[test]
+    fn test_python_json_request() {
+        let code = PythonGenerator.generate(&json_request());
+        assert_eq!(
+            code,
+            r#"import requests
+
+url = "https://api.example.com/v1/books"
+params = {
+    "page": "2",
+    "q": "rust lang",
+}
+headers = {
+    "Authorization": "Bearer 1234",
+    "Content-Type": "application/json",
+}
+payload = {
+    "draft": False,
+    "isbn": None,
+    "price": 9.5,
+    "tags": [
+        "http",
+        "gtk",
+    ],
+    "title": "Cartero",
+}
+
+response = requests.post(url, params=params, headers=headers, json=payload)
+print(response.status_code)
+print(response.text)
+"#
+        );
+    }
+
+    #[test]
+    fn test_python_plain_request() {
+        let code = PythonGenerator.generate(&plain_request());
+        assert_eq!(
+            code,
+            r#"import requests
+
+url = "https://api.example.com/health"
+
+response = requests.get(url)
+print(response.status_code)
+print(response.text)
+"#
+        );
+    }
+
+    #[test]
+    fn test_python_repeated_params() {
+        let mut request = plain_request();
+        request.url = "https://api.example.com/health?tag=a&tag=b".into();
+        let code = PythonGenerator.generate(&request);
+        assert!(code.contains("params = [\n    (\"tag\", \"a\"),\n    (\"tag\", \"b\"),\n]\n"));
+    }
+}
@@ -0,0 +1,499 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde_json::Value;
+use url::Url;
+
+use crate::client::BoundRequest;
+use crate::entities::ResponseData;
+use crate::utils::{parse_json_path, JsonTree, PathSegment};
+
+use super::python::{self, PYTHON};
+use super::{literal, quote, LiteralSyntax, RequestParts, SnippetBody};
+
+/// Values written inside the `serde_json::json!` macro.
+const RUST_JSON: LiteralSyntax = LiteralSyntax {
+    null: "null",
+    true_: "true",
+    false_: "false",
+    object_open: "{",
+    object_close: "}",
+    array_open: "[",
+    array_close: "]",
+    indent: "    ",
+    quote: rust_quote,
+};
+
+/// The longest beginning of the body that is asserted, in characters.
+const BODY_SNIPPET_LENGTH: usize = 60;
+
+/// The languages a test can be generated for, in the order they are offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestLanguage {
+    Python,
+    Rust,
+}
+
+impl TestLanguage {
+    pub const ALL: [TestLanguage; 2] = [TestLanguage::Python, TestLanguage::Rust];
+
+    /// The name of the language and test framework, as presented to the user.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestLanguage::Python => "Python (pytest + requests)",
+            TestLanguage::Rust => "Rust (tokio + reqwest)",
+        }
+    }
+
+    pub fn generate(
+        &self,
+        request: &BoundRequest,
+        response: &ResponseData,
+        assertions: &TestAssertions,
+    ) -> String {
+        match self {
+            TestLanguage::Python => generate_python_test(request, response, assertions),
+            TestLanguage::Rust => generate_rust_test(request, response, assertions),
+        }
+    }
+}
+
+/// What a generated test checks about the response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestAssertions {
+    pub status: bool,
+    /// The names of the headers whose values are asserted.
+    pub headers: Vec<String>,
+    /// Whether to assert that the body starts the same way.
+    pub body: bool,
+    /// JSON path expressions whose values in the body are asserted.
+    pub json_paths: Vec<String>,
+}
+
+/// A JSON path expression, with the value it had in the response.
+enum JsonExpectation {
+    Value(Vec<PathSegment>, Value),
+    /// The expression selects many values, or nothing at all.
+    Unsupported(String),
+}
+
+/// The values of the response that the test expects to see again.
+struct Expectations {
+    status: Option<u32>,
+    headers: Vec<(String, Vec<String>)>,
+    body: Option<String>,
+    json: Vec<JsonExpectation>,
+}
+
+impl Expectations {
+    fn new(response: &ResponseData, assertions: &TestAssertions) -> Self {
+        let headers = assertions
+            .headers
+            .iter()
+            .filter_map(|name| {
+                let values = response.headers.header(name)?;
+                let values = values.into_iter().map(String::from).collect();
+                Some((name.clone(), values))
+            })
+            .collect();
+
+        let text = response.body_str();
+        let body = assertions
+            .body
+            .then(|| {
+                let line = text.lines().next().unwrap_or_default().trim_end();
+                line.chars().take(BODY_SNIPPET_LENGTH).collect::<String>()
+            })
+            .filter(|snippet| !snippet.is_empty());
+
+        let tree = if assertions.json_paths.is_empty() {
+            None
+        } else {
+            JsonTree::parse(&text).ok()
+        };
+        let json = assertions
+            .json_paths
+            .iter()
+            .map(|expression| {
+                parse_json_path(expression)
+                    .and_then(|path| {
+                        let value = tree.as_ref()?.value(&path)?.clone();
+                        Some(JsonExpectation::Value(path, value))
+                    })
+                    .unwrap_or_else(|| JsonExpectation::Unsupported(expression.clone()))
+            })
+            .collect();
+
+        Self {
+            status: assertions.status.then_some(response.status_code),
+            headers,
+            body,
+            json,
+        }
+    }
+}
+
+/// Quotes a string with the escapes of a Rust string literal.
+fn rust_quote(text: &str) -> String {
+    format!("{text:?}")
+}
+
+/// The name of the test function, made of the method and the words of the path.
+fn test_name(parts: &RequestParts) -> String {
+    let path = Url::parse(&parts.url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_default();
+    let method: String = parts
+        .method
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    let mut name = format!("test_{}", method.to_lowercase());
+    for word in path
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        name.push('_');
+        name.push_str(&word.to_lowercase());
+    }
+    name
+}
+
+/// Indexes into a parsed JSON document. Python dicts and `serde_json::Value`
+/// are indexed the same way.
+fn json_access(path: &[PathSegment], quote: fn(&str) -> String) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => format!("[{}]", quote(key)),
+            PathSegment::Index(index) => format!("[{index}]"),
+        })
+        .collect()
+}
+
+/// Generates a `pytest` test that sends the request with `requests` and
+/// asserts the selected parts of the response.
+pub fn generate_python_test(
+    request: &BoundRequest,
+    response: &ResponseData,
+    assertions: &TestAssertions,
+) -> String {
+    let parts = RequestParts::from(request);
+    let expected = Expectations::new(response, assertions);
+    let (setup, call) = python::request_code(&parts);
+
+    let mut lines: Vec<String> = setup.lines().map(String::from).collect();
+    lines.push(String::new());
+    lines.push(format!("response = {call}"));
+    lines.push(String::new());
+    if let Some(status) = expected.status {
+        lines.push(format!("assert response.status_code == {status}"));
+    }
+    for (name, values) in &expected.headers {
+        lines.push(format!(
+            "assert response.headers[{}] == {}",
+            quote(name),
+            quote(&values.join(", "))
+        ));
+    }
+    if let Some(snippet) = &expected.body {
+        lines.push(format!(
+            "assert response.text.startswith({})",
+            quote(snippet)
+        ));
+    }
+    if !expected.json.is_empty() {
+        lines.push("data = response.json()".into());
+    }
+    for json in &expected.json {
+        let line = match json {
+            JsonExpectation::Value(path, value) => format!(
+                "assert data{} == {}",
+                json_access(path, quote),
+                literal(value, &PYTHON, 0)
+            ),
+            JsonExpectation::Unsupported(expression) => {
+                format!("# Not asserted: {expression} does not point to a single value")
+            }
+        };
+        lines.push(line);
+    }
+
+    let mut out = format!("import requests\n\n\ndef {}():\n", test_name(&parts));
+    for line in lines.join("\n").trim_end().lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(&format!("    {line}\n"));
+        }
+    }
+    out
+}
+
+/// Generates a `#[tokio::test]` that sends the request with `reqwest` and
+/// asserts the selected parts of the response.
+pub fn generate_rust_test(
+    request: &BoundRequest,
+    response: &ResponseData,
+    assertions: &TestAssertions,
+) -> String {
+    let parts = RequestParts::from(request);
+    let expected = Expectations::new(response, assertions);
+    let url = rust_quote(&parts.url);
+
+    let mut out = format!("#[tokio::test]\nasync fn {}() {{\n", test_name(&parts));
+    out.push_str("    let client = reqwest::Client::new();\n");
+    out.push_str("    let response = client\n");
+    match parts.method.as_str() {
+        method @ ("GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD") => {
+            out.push_str(&format!("        .{}({url})\n", method.to_lowercase()))
+        }
+        method @ ("OPTIONS" | "TRACE" | "CONNECT") => out.push_str(&format!(
+            "        .request(reqwest::Method::{method}, {url})\n"
+        )),
+        method => out.push_str(&format!(
+            "        .request(reqwest::Method::from_bytes(b{}).unwrap(), {url})\n",
+            rust_quote(method)
+        )),
+    }
+    if !parts.params.is_empty() {
+        let params: Vec<String> = parts
+            .params
+            .iter()
+            .map(|(key, value)| format!("({}, {})", rust_quote(key), rust_quote(value)))
+            .collect();
+        out.push_str(&format!("        .query(&[{}])\n", params.join(", ")));
+    }
+    for (name, value) in &parts.headers {
+        out.push_str(&format!(
+            "        .header({}, {})\n",
+            rust_quote(name),
+            rust_quote(value)
+        ));
+    }
+    match &parts.body {
+        Some(SnippetBody::Json(json)) => out.push_str(&format!(
+            "        .json(&serde_json::json!({}))\n",
+            literal(json, &RUST_JSON, 2)
+        )),
+        Some(SnippetBody::Text(text)) => {
+            out.push_str(&format!("        .body({})\n", rust_quote(text)));
+        }
+        None => {}
+    }
+    out.push_str("        .send()\n        .await\n        .unwrap();\n");
+
+    if expected.status.is_some() || !expected.headers.is_empty() {
+        out.push('\n');
+    }
+    if let Some(status) = expected.status {
+        out.push_str(&format!("    assert_eq!(response.status(), {status});\n"));
+    }
+    for (name, values) in &expected.headers {
+        out.push_str(&format!(
+            "    assert_eq!(response.headers()[{}], {});\n",
+            rust_quote(&name.to_lowercase()),
+            rust_quote(&values[0])
+        ));
+    }
+    if expected.body.is_some() || !expected.json.is_empty() {
+        out.push_str("\n    let body = response.text().await.unwrap();\n");
+    }
+    if let Some(snippet) = &expected.body {
+        out.push_str(&format!(
+            "    assert!(body.starts_with({}));\n",
+            rust_quote(snippet)
+        ));
+    }
+    if !expected.json.is_empty() {
+        out.push_str("    let data: serde_json::Value = serde_json::from_str(&body).unwrap();\n");
+    }
+    for json in &expected.json {
+        match json {
+            JsonExpectation::Value(path, value) => out.push_str(&format!(
+                "    assert_eq!(data{}, serde_json::json!({}));\n",
+                json_access(path, rust_quote),
+                literal(value, &RUST_JSON, 1)
+            )),
+            JsonExpectation::Unsupported(expression) => out.push_str(&format!(
+                "    // Not asserted: {expression} does not point to a single value\n"
+            )),
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::KeyValueTable;
+
+    use super::super::tests::{json_request, plain_request};
+    use super::*;
+
+    fn response() -> ResponseData {
+        ResponseData {
+            status_code: 201,
+            duration: 0,
+            size: 0,
+            transfer_size: 0,
+            headers: KeyValueTable::new(&[
+                ("Content-Type", "application/json").into(),
+                ("Set-Cookie", "a=1").into(),
+                ("Set-Cookie", "b=2").into(),
+            ]),
+            body: Vec::from(r#"{"id": 7, "tags": ["http", "gtk"], "author": {"name": "Ana"}}"#),
+            decode_warning: None,
+            version: None,
+        }
+    }
+
+    fn assertions() -> TestAssertions {
+        TestAssertions {
+            status: true,
+            headers: vec!["content-type".into()],
+            body: false,
+            json_paths: vec!["$.id".into(), "$.tags".into(), "$..name".into()],
+        }
+    }
+
+    #[test]
+    fn test_python_test_stub() {
+        let code = generate_python_test(&json_request(), &response(), &assertions());
+        assert_eq!(
+            code,
+            r#"import requests
+
+
+def test_post_v1_books():
+    url = "https://api.example.com/v1/books"
+    params = {
+        "page": "2",
+        "q": "rust lang",
+    }
+    headers = {
+        "Authorization": "Bearer 1234",
+        "Content-Type": "application/json",
+    }
+    payload = {
+        "draft": False,
+        "isbn": None,
+        "price": 9.5,
+        "tags": [
+            "http",
+            "gtk",
+        ],
+        "title": "Cartero",
+    }
+
+    response = requests.post(url, params=params, headers=headers, json=payload)
+
+    assert response.status_code == 201
+    assert response.headers["content-type"] == "application/json"
+    data = response.json()
+    assert data["id"] == 7
+    assert data["tags"] == [
+        "http",
+        "gtk",
+    ]
+    # Not asserted: $..name does not point to a single value
+"#
+        );
+    }
+
+    #[test]
+    fn test_rust_test_stub() {
+        let code = generate_rust_test(&json_request(), &response(), &assertions());
+        assert_eq!(
+            code,
+            r#"#[tokio::test]
+async fn test_post_v1_books() {
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://api.example.com/v1/books")
+        .query(&[("page", "2"), ("q", "rust lang")])
+        .header("Authorization", "Bearer 1234")
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "draft": false,
+            "isbn": null,
+            "price": 9.5,
+            "tags": [
+                "http",
+                "gtk",
+            ],
+            "title": "Cartero",
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let body = response.text().await.unwrap();
+    let data: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(data["id"], serde_json::json!(7));
+    assert_eq!(data["tags"], serde_json::json!([
+        "http",
+        "gtk",
+    ]));
+    // Not asserted: $..name does not point to a single value
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_body_snippet_and_repeated_headers() {
+        let mut response = response();
+        response.body = Vec::from("hello \"world\"\nsecond line");
+        let assertions = TestAssertions {
+            headers: vec!["set-cookie".into(), "x-missing".into()],
+            body: true,
+            ..Default::default()
+        };
+
+        let python = generate_python_test(&plain_request(), &response, &assertions);
+        assert!(python.contains("def test_get_health():\n"));
+        assert!(python.contains("    assert response.headers[\"set-cookie\"] == \"a=1, b=2\"\n"));
+        assert!(python.contains("    assert response.text.startswith(\"hello \\\"world\\\"\")\n"));
+        assert!(!python.contains("x-missing"));
+        assert!(!python.contains("status_code"));
+
+        let rust = generate_rust_test(&plain_request(), &response, &assertions);
+        assert!(rust.contains("        .get(\"https://api.example.com/health\")\n"));
+        assert!(rust.contains("    assert_eq!(response.headers()[\"set-cookie\"], \"a=1\");\n"));
+        assert!(rust.contains("    assert!(body.starts_with(\"hello \\\"world\\\"\"));\n"));
+        assert!(!rust.contains("response.status()"));
+    }
+
+    #[test]
+    fn test_methods_without_shortcut() {
+        let mut request = plain_request();
+        request.method = crate::entities::RequestMethod::Options;
+        let rust = generate_rust_test(&request, &response(), &TestAssertions::default());
+        assert!(
+            rust.contains(".request(reqwest::Method::OPTIONS, \"https://api.example.com/health\")")
+        );
+        assert!(rust.contains("async fn test_options_health()"));
+
+        request.method = crate::entities::RequestMethod::Custom(String::from("M-SEARCH"));
+        let rust = generate_rust_test(&request, &response(), &TestAssertions::default());
+        assert!(rust.contains(".request(reqwest::Method::from_bytes(b\"M-SEARCH\").unwrap(), "));
+        assert!(rust.contains("async fn test_msearch_health()"));
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use serde_json::{Map, Value};
+
+use crate::entities::KeyValueTable;
+
+/// Set-Cookie cannot be folded into a single line, so it is always kept repeated.
+fn is_set_cookie(name: &str) -> bool {
+    name.eq_ignore_ascii_case("set-cookie")
+}
+
+/// Groups the values of the headers by name, ignoring case.
+///
+/// The groups are kept in the order of their first appearance, using the first
+/// spelling of the name that was seen.
+fn group_headers(headers: &KeyValueTable) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for header in headers.iter() {
+        match groups
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&header.name))
+        {
+            Some((_, values)) => values.push(header.value.clone()),
+            None => groups.push((header.name.clone(), vec![header.value.clone()])),
+        }
+    }
+    groups
+}
+
+/// Writes the headers the way they travel in an HTTP/1.1 message.
+///
+/// Repeated headers are combined into a single line separated by commas, as
+/// allowed by RFC 7230, except for Set-Cookie, which keeps one line per value.
+pub fn headers_to_wire(headers: &KeyValueTable) -> String {
+    let mut text = String::new();
+    for (name, values) in group_headers(headers) {
+        if is_set_cookie(&name) {
+            for value in values {
+                text.push_str(&format!("{name}: {value}\r\n"));
+            }
+        } else {
+            text.push_str(&format!("{name}: {}\r\n", values.join(", ")));
+        }
+    }
+    text
+}
+
+/// The bytes taken by a header in an HTTP/1.1 message, counting the colon and
+/// space after the name and the line break.
+pub fn header_wire_size(name: &str, value: &str) -> usize {
+    name.len() + value.len() + 4
+}
+
+/// Writes the headers as a JSON object that maps every name to its value.
+///
+/// Repeated headers are combined with commas, except for Set-Cookie, which is
+/// written as an array when the response contains more than one cookie.
+pub fn headers_to_json(headers: &KeyValueTable) -> String {
+    let mut object = Map::new();
+    for (name, values) in group_headers(headers) {
+        let value = if is_set_cookie(&name) && values.len() > 1 {
+            Value::Array(values.into_iter().map(Value::String).collect())
+        } else {
+            Value::String(values.join(", "))
+        };
+        object.insert(name, value);
+    }
+    serde_json::to_string_pretty(&Value::Object(object)).unwrap_or_default()
+}
+
+/// Quotes a string so that a POSIX shell reads it as a single word.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Writes the headers as the -H flags that would make curl send them.
+///
+/// Every value gets its own flag, so repeated headers are sent repeated.
+pub fn headers_to_curl_flags(headers: &KeyValueTable) -> String {
+    headers
+        .iter()
+        .map(|header| {
+            let line = format!("{}: {}", header.name, header.value);
+            format!("-H {}", shell_quote(&line))
+        })
+        .collect::<Vec<String>>()
+        .join(" \\\n  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> KeyValueTable {
+        KeyValueTable::new(&[
+            ("Content-Type", "application/json").into(),
+            ("Cache-Control", "no-cache").into(),
+            ("Set-Cookie", "a=1; Path=/").into(),
+            ("cache-control", "no-store").into(),
+            ("Set-Cookie", "b=2").into(),
+        ])
+    }
+
+    #[test]
+    fn test_headers_to_wire() {
+        assert_eq!(
+            headers_to_wire(&sample()),
+            "Content-Type: application/json\r\n\
+             Cache-Control: no-cache, no-store\r\n\
+             Set-Cookie: a=1; Path=/\r\n\
+             Set-Cookie: b=2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_header_wire_size() {
+        let table = KeyValueTable::new(&[("Content-Type", "application/json").into()]);
+        assert_eq!(
+            header_wire_size("Content-Type", "application/json"),
+            headers_to_wire(&table).len()
+        );
+        assert_eq!(header_wire_size("ETag", ""), 6);
+    }
+
+    #[test]
+    fn test_headers_to_json() {
+        let json: Value = serde_json::from_str(&headers_to_json(&sample())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "Content-Type": "application/json",
+                "Cache-Control": "no-cache, no-store",
+                "Set-Cookie": ["a=1; Path=/", "b=2"],
+            })
+        );
+
+        let single = KeyValueTable::new(&[("Set-Cookie", "a=1").into()]);
+        let json: Value = serde_json::from_str(&headers_to_json(&single)).unwrap();
+        assert_eq!(json, serde_json::json!({"Set-Cookie": "a=1"}));
+    }
+
+    #[test]
+    fn test_headers_to_curl_flags() {
+        let headers = KeyValueTable::new(&[
+            ("Accept", "text/html").into(),
+            ("Accept", "application/xml").into(),
+            ("X-Quote", "it's").into(),
+        ]);
+        assert_eq!(
+            headers_to_curl_flags(&headers),
+            "-H 'Accept: text/html' \\\n  \
+             -H 'Accept: application/xml' \\\n  \
+             -H 'X-Quote: it'\\''s'"
+        );
+    }
+
+    #[test]
+    fn test_empty_headers() {
+        let headers = KeyValueTable::default();
+        assert_eq!(headers_to_wire(&headers), "");
+        assert_eq!(headers_to_json(&headers), "{}");
+        assert_eq!(headers_to_curl_flags(&headers), "");
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use super::{headers_to_json, headers_to_wire};
+use crate::entities::{KeyValue, KeyValueTable};
+use crate::objects::KeyValueItem;
+
+/// Collects the rows that are active and have a name, skipping the rest.
+fn active_rows(items: &[KeyValueItem]) -> KeyValueTable {
+    items
+        .iter()
+        .filter(|item| item.is_usable())
+        .map(|item| KeyValue::from(item.clone()))
+        .collect()
+}
+
+/// Writes the active rows as the header lines of an HTTP/1.1 message.
+pub fn kv_to_http_headers(items: &[KeyValueItem]) -> String {
+    headers_to_wire(&active_rows(items))
+}
+
+/// Writes the active rows as a JSON object that maps every name to its value.
+pub fn kv_to_json(items: &[KeyValueItem]) -> String {
+    headers_to_json(&active_rows(items))
+}
+
+/// Writes the active rows as an URL encoded query string, without the leading `?`.
+pub fn kv_to_query_string(items: &[KeyValueItem]) -> String {
+    let pairs: Vec<(String, String)> = active_rows(items)
+        .iter()
+        .map(|kv| (kv.name.clone(), kv.value.clone()))
+        .collect();
+    serde_urlencoded::to_string(pairs).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<KeyValueItem> {
+        let disabled = KeyValueItem::from(("X-Disabled", "true"));
+        disabled.set_active(false);
+        vec![
+            KeyValueItem::from(("Accept", "application/json")),
+            disabled,
+            KeyValueItem::from(("X-Query", "a b&c=d")),
+            KeyValueItem::from(("Accept", "text/html")),
+            KeyValueItem::default(),
+        ]
+    }
+
+    #[test]
+    fn test_kv_to_http_headers() {
+        assert_eq!(
+            kv_to_http_headers(&sample()),
+            "Accept: application/json, text/html\r\nX-Query: a b&c=d\r\n"
+        );
+    }
+
+    #[test]
+    fn test_kv_to_json() {
+        let json: serde_json::Value = serde_json::from_str(&kv_to_json(&sample())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "Accept": "application/json, text/html",
+                "X-Query": "a b&c=d",
+            })
+        );
+    }
+
+    #[test]
+    fn test_kv_to_query_string() {
+        assert_eq!(
+            kv_to_query_string(&sample()),
+            "Accept=application%2Fjson&X-Query=a+b%26c%3Dd&Accept=text%2Fhtml"
+        );
+    }
+
+    #[test]
+    fn test_kv_export_empty() {
+        assert_eq!(kv_to_http_headers(&[]), "");
+        assert_eq!(kv_to_json(&[]), "{}");
+        assert_eq!(kv_to_query_string(&[]), "");
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::Path;
+
+use crate::entities::{KeyValueTable, ResponseData};
+use crate::error::CarteroError;
+
+use super::headers_to_wire;
+
+/// The parts of a response that can be saved into a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseExportFormat {
+    /// The raw bytes of the body, as shown in the response panel.
+    Body,
+    /// The status line and the headers, as they travel in HTTP/1.1.
+    Headers,
+    /// The status line, the headers, a blank line and the body.
+    Full,
+}
+
+impl TryFrom<&str> for ResponseExportFormat {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "body" => Ok(Self::Body),
+            "headers" => Ok(Self::Headers),
+            "full" => Ok(Self::Full),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ResponseExportFormat {
+    /// The name proposed in the save dialog for a response with these headers.
+    pub fn file_name(&self, headers: &KeyValueTable) -> String {
+        match self {
+            Self::Body => format!("response.{}", body_extension(headers)),
+            Self::Headers => String::from("response-headers.txt"),
+            Self::Full => String::from("response.http"),
+        }
+    }
+}
+
+/// Guesses the file extension of a body from its Content-Type header.
+pub fn body_extension(headers: &KeyValueTable) -> &'static str {
+    let content_type = headers
+        .header("content-type")
+        .and_then(|values| values.first().map(|v| v.to_lowercase()))
+        .unwrap_or_default();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    match mime {
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "text/javascript" | "application/javascript" => "js",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        mime if mime.ends_with("+json") => "json",
+        mime if mime.ends_with("+xml") => "xml",
+        mime if mime.starts_with("text/") => "txt",
+        _ => "bin",
+    }
+}
+
+/// The status line and the headers of a response, ending with a blank line.
+pub fn response_head(status: u32, headers: &KeyValueTable) -> String {
+    let reason = u16::try_from(status)
+        .ok()
+        .and_then(|code| isahc::http::StatusCode::from_u16(code).ok())
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or_default();
+    let status_line = format!("HTTP/1.1 {status} {reason}");
+    format!(
+        "{}\r\n{}\r\n",
+        status_line.trim_end(),
+        headers_to_wire(headers)
+    )
+}
+
+pub fn save_response_body(path: &Path, body: &[u8]) -> Result<(), CarteroError> {
+    std::fs::write(path, body)?;
+    Ok(())
+}
+
+pub fn save_response_headers(
+    path: &Path,
+    status: u32,
+    headers: &KeyValueTable,
+) -> Result<(), CarteroError> {
+    std::fs::write(path, response_head(status, headers))?;
+    Ok(())
+}
+
+pub fn save_response_full(
+    path: &Path,
+    status: u32,
+    headers: &KeyValueTable,
+    body: &[u8],
+) -> Result<(), CarteroError> {
+    let mut contents = response_head(status, headers).into_bytes();
+    contents.extend_from_slice(body);
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Saves the requested parts of a response into the given path.
+pub fn save_response(
+    path: &Path,
+    format: ResponseExportFormat,
+    response: &ResponseData,
+) -> Result<(), CarteroError> {
+    match format {
+        ResponseExportFormat::Body => save_response_body(path, &response.body),
+        ResponseExportFormat::Headers => {
+            save_response_headers(path, response.status_code, &response.headers)
+        }
+        ResponseExportFormat::Full => save_response_full(
+            path,
+            response.status_code,
+            &response.headers,
+            &response.body,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::KeyValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> KeyValueTable {
+        let entries: Vec<KeyValue> = pairs.iter().map(|&pair| pair.into()).collect();
+        KeyValueTable::new(&entries)
+    }
+
+    #[test]
+    fn test_body_extension() {
+        let cases = [
+            ("application/json; charset=utf-8", "json"),
+            ("application/problem+json", "json"),
+            ("Text/HTML", "html"),
+            ("application/atom+xml", "xml"),
+            ("text/plain", "txt"),
+            ("image/png", "png"),
+            ("application/octet-stream", "bin"),
+        ];
+        for (content_type, extension) in cases {
+            let headers = headers(&[("Content-Type", content_type)]);
+            assert_eq!(body_extension(&headers), extension, "{content_type}");
+        }
+        assert_eq!(body_extension(&KeyValueTable::default()), "bin");
+    }
+
+    #[test]
+    fn test_file_name() {
+        let headers = headers(&[("Content-Type", "application/json")]);
+        assert_eq!(
+            ResponseExportFormat::Body.file_name(&headers),
+            "response.json"
+        );
+        assert_eq!(
+            ResponseExportFormat::Headers.file_name(&headers),
+            "response-headers.txt"
+        );
+        assert_eq!(
+            ResponseExportFormat::Full.file_name(&headers),
+            "response.http"
+        );
+    }
+
+    #[test]
+    fn test_response_head() {
+        let headers = headers(&[("Content-Type", "text/plain"), ("Content-Length", "5")]);
+        assert_eq!(
+            response_head(200, &headers),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\n"
+        );
+        assert_eq!(
+            response_head(599, &KeyValueTable::default()),
+            "HTTP/1.1 599\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_save_response_full() {
+        let response = ResponseData {
+            status_code: 404,
+            duration: 0,
+            size: 9,
+            transfer_size: 9,
+            headers: headers(&[("Content-Type", "text/plain")]),
+            body: Vec::from("Not found"),
+            decode_warning: None,
+            version: None,
+        };
+        let path = std::env::temp_dir().join("cartero-test-save-response-full.http");
+        save_response(&path, ResponseExportFormat::Full, &response).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            contents,
+            b"HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\n\r\nNot found"
+        );
+    }
+}
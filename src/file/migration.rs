@@ -0,0 +1,203 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use toml::Value;
+
+use super::{KeyValuedFileRows, KeyValuedFileTable};
+use crate::entities::KeyValueTable;
+use crate::error::CarteroError;
+
+/// The version of the schema of the files written by this version of Cartero.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+type Migration = fn(Value) -> Result<Value, CarteroError>;
+
+/// The migration from each version into the next one, starting at version 0.
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// The version of the schema of the given document. Documents without one are
+/// version 0.
+pub fn schema_version(raw: &Value) -> Result<u32, CarteroError> {
+    match raw.get("version") {
+        Some(version) => Ok(version.clone().try_into::<u32>()?),
+        None => Ok(0),
+    }
+}
+
+/// Brings the given document to the current version of the schema, running
+/// the migrations in order, so that the parser only has to know about the
+/// latest schema.
+///
+/// Documents written by a newer version of Cartero are rejected, since they
+/// may contain settings that would be lost if they were saved again.
+pub fn migrate(mut raw: Value) -> Result<Value, CarteroError> {
+    let version = schema_version(&raw)?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(CarteroError::UnsupportedSchemaVersion(version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        raw = migration(raw)?;
+    }
+    Ok(raw)
+}
+
+/// Version 0 is the layout used before files were versioned. Its fields are
+/// the same ones of version 1, so only the version has to be set.
+pub fn migrate_v0_to_v1(mut raw: Value) -> Result<Value, CarteroError> {
+    if let Some(table) = raw.as_table_mut() {
+        table.insert("version".into(), Value::Integer(1));
+    }
+    Ok(raw)
+}
+
+/// Version 2 stores the key-value tables as arrays of rows instead of tables keyed
+/// by name, so that the order of the rows survives saving the file. The rows of a
+/// version 1 table are sorted, since that format never kept their order.
+pub fn migrate_v1_to_v2(mut raw: Value) -> Result<Value, CarteroError> {
+    if let Some(table) = raw.as_table_mut() {
+        for key in ["headers", "variables"] {
+            migrate_key_value_table(table.get_mut(key))?;
+        }
+        if let Some(body) = table.get_mut("body").and_then(Value::as_table_mut) {
+            migrate_key_value_table(body.get_mut("variables"))?;
+        }
+        table.insert("version".into(), Value::Integer(2));
+    }
+    Ok(raw)
+}
+
+fn migrate_key_value_table(value: Option<&mut Value>) -> Result<(), CarteroError> {
+    if let Some(value) = value.filter(|value| value.is_table()) {
+        let table = KeyValueTable::from(value.clone().try_into::<KeyValuedFileTable>()?);
+        *value = Value::try_from(KeyValuedFileRows::from(table))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(contents: &str) -> Value {
+        toml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_v0_to_v1() {
+        let v0 = document(
+            "
+url = 'https://www.google.com'
+method = 'POST'
+body = 'hello'
+
+[headers]
+Accept = 'text/html'
+",
+        );
+        let v1 = migrate_v0_to_v1(v0.clone()).unwrap();
+
+        assert_eq!(schema_version(&v0).unwrap(), 0);
+        assert_eq!(schema_version(&v1).unwrap(), 1);
+        assert_eq!(v1.get("url"), v0.get("url"));
+        assert_eq!(v1.get("method"), v0.get("method"));
+        assert_eq!(v1.get("body"), v0.get("body"));
+        assert_eq!(v1.get("headers"), v0.get("headers"));
+    }
+
+    #[test]
+    fn test_migrate_v1_to_v2() {
+        let v1 = document(
+            "
+version = 1
+url = 'https://www.google.com'
+method = 'POST'
+
+[headers]
+User-Agent = 'Cartero'
+Accept = ['text/html', { value = 'application/json', active = false, secret = false }]
+
+[body]
+type = 'urlencoded'
+
+[body.variables]
+q = 'rust'
+",
+        );
+        let v2 = migrate(v1).unwrap();
+        let expected = document(
+            "
+version = 2
+url = 'https://www.google.com'
+method = 'POST'
+
+[[headers]]
+name = 'Accept'
+value = 'application/json'
+active = false
+
+[[headers]]
+name = 'Accept'
+value = 'text/html'
+
+[[headers]]
+name = 'User-Agent'
+value = 'Cartero'
+
+[body]
+type = 'urlencoded'
+
+[[body.variables]]
+name = 'q'
+value = 'rust'
+",
+        );
+
+        assert_eq!(v2, expected);
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let v2 = document(
+            "
+version = 2
+url = 'https://www.google.com'
+
+[[headers]]
+name = 'Accept'
+value = 'text/html'
+",
+        );
+        assert_eq!(migrate(v2.clone()).unwrap(), v2);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let v3 = document("version = 3\nurl = 'https://www.google.com'\n");
+        assert!(matches!(
+            migrate(v3),
+            Err(CarteroError::UnsupportedSchemaVersion(3))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_version_is_rejected() {
+        for contents in ["version = 'one'", "version = -1"] {
+            assert!(migrate(document(contents)).is_err());
+        }
+    }
+}
@@ -0,0 +1,1856 @@
+mod migration;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use gtk::gio;
+use gtk::prelude::FileExtManual;
+use serde::{Deserialize, Serialize};
+
+use crate::client::RequestError;
+use crate::entities::{
+    AwsSigV4, BodyCharset, CompressionOptions, EndpointData, HttpVersionPreference, KeyValue,
+    KeyValueTable, RawEncoding, RequestMethod, RequestPayload, RetryPolicy, WebSocketData,
+    URL_HISTORY_LENGTH,
+};
+use crate::error::CarteroError;
+use migration::CURRENT_SCHEMA_VERSION;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct KeyValueDetail {
+    value: String,
+    active: bool,
+    secret: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    comment: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    validation_rule: String,
+}
+
+impl Default for KeyValueDetail {
+    fn default() -> Self {
+        Self {
+            value: String::default(),
+            active: true,
+            secret: false,
+            comment: String::new(),
+            required: false,
+            validation_rule: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum KeyValuedValue {
+    Simple(String),
+    Complex(KeyValueDetail),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum KeyValuedValueContainer {
+    Unique(KeyValuedValue),
+    Multiple(Vec<KeyValuedValue>),
+}
+
+impl Default for KeyValuedValue {
+    fn default() -> Self {
+        Self::Simple(String::default())
+    }
+}
+
+impl From<KeyValuedValue> for KeyValue {
+    fn from(value: KeyValuedValue) -> KeyValue {
+        match value {
+            KeyValuedValue::Simple(str) => KeyValue {
+                name: String::default(),
+                value: str.clone(),
+                active: true,
+                secret: false,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+            },
+            KeyValuedValue::Complex(kd) => KeyValue {
+                name: String::default(),
+                value: kd.value.clone(),
+                active: kd.active,
+                secret: kd.secret,
+                comment: kd.comment.clone(),
+                required: kd.required,
+                validation_rule: kd.validation_rule.clone(),
+            },
+        }
+    }
+}
+
+fn extract_kv_entry(value: KeyValuedValue, key: &str) -> KeyValue {
+    let mut value = KeyValue::from(value);
+    value.name = key.into();
+    value
+}
+
+/// The key-value tables of version 1 of the schema, keyed by name. Rows sharing a
+/// name are grouped, so the order of the rows is lost and they are sorted instead.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct KeyValuedFileTable(BTreeMap<String, KeyValuedValueContainer>);
+
+impl From<KeyValuedFileTable> for KeyValueTable {
+    fn from(value: KeyValuedFileTable) -> Self {
+        let mut vector: Vec<KeyValue> = value
+            .0
+            .into_iter()
+            .flat_map(|(header, values)| match values {
+                KeyValuedValueContainer::Unique(x) => vec![extract_kv_entry(x, &header)],
+                KeyValuedValueContainer::Multiple(mult) => mult
+                    .into_iter()
+                    .map(|v| extract_kv_entry(v, &header))
+                    .collect(),
+            })
+            .collect();
+        vector.sort();
+        KeyValueTable::new(&vector)
+    }
+}
+
+fn default_active() -> bool {
+    true
+}
+
+fn is_active(active: &bool) -> bool {
+    *active
+}
+
+/// A row of a key-value table. Since version 2 of the schema the rows are stored as
+/// an array of tables, which keeps the order given by the user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct KeyValuedFileRow {
+    name: String,
+    value: String,
+    #[serde(default = "default_active", skip_serializing_if = "is_active")]
+    active: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    secret: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    comment: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    validation_rule: String,
+}
+
+impl From<KeyValuedFileRow> for KeyValue {
+    fn from(value: KeyValuedFileRow) -> Self {
+        KeyValue {
+            name: value.name,
+            value: value.value,
+            active: value.active,
+            secret: value.secret,
+            comment: value.comment,
+            required: value.required,
+            validation_rule: value.validation_rule,
+        }
+    }
+}
+
+impl From<KeyValue> for KeyValuedFileRow {
+    fn from(value: KeyValue) -> Self {
+        Self {
+            name: value.name,
+            value: value.value,
+            active: value.active,
+            secret: value.secret,
+            comment: value.comment,
+            required: value.required,
+            validation_rule: value.validation_rule,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct KeyValuedFileRows(Vec<KeyValuedFileRow>);
+
+impl From<KeyValuedFileRows> for KeyValueTable {
+    fn from(value: KeyValuedFileRows) -> Self {
+        value.0.into_iter().map(KeyValue::from).collect()
+    }
+}
+
+impl From<KeyValueTable> for KeyValuedFileRows {
+    fn from(value: KeyValueTable) -> Self {
+        Self(value.iter().cloned().map(KeyValuedFileRow::from).collect())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub enum FilePayloadRawFormat {
+    #[default]
+    #[serde(rename = "octet-stream")]
+    OctetStream,
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "xml")]
+    Xml,
+}
+
+impl From<RawEncoding> for FilePayloadRawFormat {
+    fn from(value: RawEncoding) -> Self {
+        match value {
+            RawEncoding::Json => Self::Json,
+            RawEncoding::OctetStream => Self::OctetStream,
+            RawEncoding::Xml => Self::Xml,
+        }
+    }
+}
+
+impl From<FilePayloadRawFormat> for RawEncoding {
+    fn from(value: FilePayloadRawFormat) -> Self {
+        match value {
+            FilePayloadRawFormat::Json => Self::Json,
+            FilePayloadRawFormat::OctetStream => Self::OctetStream,
+            FilePayloadRawFormat::Xml => Self::Xml,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum FilePayload {
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "urlencoded")]
+    UrlEncoded {
+        variables: Option<KeyValuedFileRows>,
+    },
+    #[serde(rename = "multipart")]
+    Multipart {
+        variables: Option<KeyValuedFileRows>,
+    },
+    #[serde(rename = "raw")]
+    Raw {
+        format: Option<FilePayloadRawFormat>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        charset: Option<String>,
+        body: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body_file_path: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Body {
+    ClassicRaw(String),
+    Structured(FilePayload),
+}
+
+impl From<RequestPayload> for FilePayload {
+    fn from(value: RequestPayload) -> Self {
+        match value {
+            RequestPayload::None => Self::None,
+            RequestPayload::Urlencoded(payload) => Self::UrlEncoded {
+                variables: Some(payload.into()),
+            },
+            RequestPayload::Multipart { params } => Self::Multipart {
+                variables: Some(params.into()),
+            },
+            RequestPayload::Raw {
+                encoding,
+                charset,
+                content,
+                file,
+            } => Self::Raw {
+                format: Some(encoding.into()),
+                charset: match charset {
+                    BodyCharset::Utf8 => None,
+                    charset => Some(charset.label().into()),
+                },
+                // A binary file is read again when the request is opened, so it is not copied.
+                body: match file {
+                    Some(_) => String::from_utf8(content).unwrap_or_default(),
+                    None => String::from_utf8_lossy(&content.clone()).to_string(),
+                },
+                body_file_path: file.map(|path| path.to_string_lossy().to_string()),
+            },
+        }
+    }
+}
+
+impl From<FilePayload> for RequestPayload {
+    fn from(value: FilePayload) -> Self {
+        match value {
+            FilePayload::None => Self::None,
+            FilePayload::Multipart { variables } => Self::Multipart {
+                params: variables.unwrap_or_default().into(),
+            },
+            FilePayload::UrlEncoded { variables } => {
+                Self::Urlencoded(variables.unwrap_or_default().into())
+            }
+            FilePayload::Raw {
+                format,
+                charset,
+                body,
+                body_file_path,
+            } => Self::Raw {
+                encoding: format.unwrap_or_default().into(),
+                charset: charset
+                    .and_then(|label| BodyCharset::from_label(&label))
+                    .unwrap_or_default(),
+                content: Vec::from(body.clone()),
+                file: body_file_path.map(PathBuf::from),
+            },
+        }
+    }
+}
+
+impl From<RequestPayload> for Body {
+    fn from(value: RequestPayload) -> Self {
+        Self::Structured(value.into())
+    }
+}
+
+impl From<Body> for RequestPayload {
+    fn from(value: Body) -> Self {
+        match value {
+            Body::ClassicRaw(payload) => Self::Raw {
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(payload.clone().as_str()),
+                file: None,
+            },
+            Body::Structured(payload) => payload.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct RetryFile {
+    max_attempts: u32,
+    backoff_ms: u64,
+    #[serde(default)]
+    exponential_backoff: bool,
+    #[serde(default)]
+    on_connection_error: bool,
+    #[serde(default)]
+    on_gateway_error: bool,
+    #[serde(default)]
+    on_server_error: bool,
+}
+
+impl From<RetryPolicy> for RetryFile {
+    fn from(value: RetryPolicy) -> Self {
+        Self {
+            max_attempts: value.max_attempts,
+            backoff_ms: value.backoff,
+            exponential_backoff: value.exponential_backoff,
+            on_connection_error: value.on_connection_error,
+            on_gateway_error: value.on_gateway_error,
+            on_server_error: value.on_server_error,
+        }
+    }
+}
+
+impl From<RetryFile> for RetryPolicy {
+    fn from(value: RetryFile) -> Self {
+        Self {
+            max_attempts: value.max_attempts.max(1),
+            backoff: value.backoff_ms,
+            exponential_backoff: value.exponential_backoff,
+            on_connection_error: value.on_connection_error,
+            on_gateway_error: value.on_gateway_error,
+            on_server_error: value.on_server_error,
+        }
+    }
+}
+
+fn default_accept_compressed() -> bool {
+    CompressionOptions::default().accept_compressed
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CompressionFile {
+    #[serde(default = "default_accept_compressed")]
+    accept_compressed: bool,
+    #[serde(default)]
+    compress_body: bool,
+}
+
+impl From<CompressionOptions> for CompressionFile {
+    fn from(value: CompressionOptions) -> Self {
+        Self {
+            accept_compressed: value.accept_compressed,
+            compress_body: value.compress_body,
+        }
+    }
+}
+
+impl From<CompressionFile> for CompressionOptions {
+    fn from(value: CompressionFile) -> Self {
+        Self {
+            accept_compressed: value.accept_compressed,
+            compress_body: value.compress_body,
+        }
+    }
+}
+
+/// The version of HTTP forced by a request. Automatic negotiation is not stored.
+#[derive(Deserialize, Serialize, Debug)]
+enum HttpVersionFile {
+    #[serde(rename = "http1")]
+    Http1,
+    #[serde(rename = "http2")]
+    Http2,
+}
+
+impl HttpVersionFile {
+    fn from_preference(value: HttpVersionPreference) -> Option<Self> {
+        match value {
+            HttpVersionPreference::Auto => None,
+            HttpVersionPreference::Http1Only => Some(Self::Http1),
+            HttpVersionPreference::Http2Prior => Some(Self::Http2),
+        }
+    }
+}
+
+impl From<HttpVersionFile> for HttpVersionPreference {
+    fn from(value: HttpVersionFile) -> Self {
+        match value {
+            HttpVersionFile::Http1 => Self::Http1Only,
+            HttpVersionFile::Http2 => Self::Http2Prior,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct AwsSigV4File {
+    access_key: String,
+    secret_key: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    session_token: String,
+    region: String,
+    service: String,
+}
+
+impl From<AwsSigV4> for AwsSigV4File {
+    fn from(value: AwsSigV4) -> Self {
+        Self {
+            access_key: value.access_key,
+            secret_key: value.secret_key,
+            session_token: value.session_token,
+            region: value.region,
+            service: value.service,
+        }
+    }
+}
+
+impl From<AwsSigV4File> for AwsSigV4 {
+    fn from(value: AwsSigV4File) -> Self {
+        Self {
+            access_key: value.access_key,
+            secret_key: value.secret_key,
+            session_token: value.session_token,
+            region: value.region,
+            service: value.service,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct RequestFile {
+    version: u32,
+    url: String,
+    method: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pre_request_script: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    post_response_script: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    url_history: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http_version: Option<HttpVersionFile>,
+    body: Option<Body>,
+    headers: Option<KeyValuedFileRows>,
+    variables: Option<KeyValuedFileRows>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<RetryFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aws_sigv4: Option<AwsSigV4File>,
+}
+
+impl TryFrom<RequestFile> for EndpointData {
+    type Error = CarteroError;
+
+    fn try_from(value: RequestFile) -> Result<EndpointData, Self::Error> {
+        let Ok(method) = RequestMethod::try_from(value.method.as_str()) else {
+            return Err(RequestError::InvalidHttpVerb.into());
+        };
+        let body = value.body.map(RequestPayload::from).unwrap_or_default();
+        let headers = value.headers.unwrap_or_default().into();
+        let variables = value.variables.unwrap_or_default().into();
+        let retry = value.retry.map(RetryPolicy::from).unwrap_or_default();
+        let compression = value
+            .compression
+            .map(CompressionOptions::from)
+            .unwrap_or_default();
+        let http_version = value
+            .http_version
+            .map(HttpVersionPreference::from)
+            .unwrap_or_default();
+        let aws_sigv4 = value.aws_sigv4.map(AwsSigV4::from);
+        let mut url_history = value.url_history;
+        url_history.truncate(URL_HISTORY_LENGTH);
+
+        let request = EndpointData {
+            url: value.url.clone(),
+            method,
+            body,
+            variables,
+            headers,
+            retry,
+            compression,
+            http_version,
+            aws_sigv4,
+            notes: value.notes,
+            name: value.name,
+            pre_request_script: value.pre_request_script,
+            post_response_script: value.post_response_script,
+            url_history,
+        };
+        Ok(request)
+    }
+}
+
+impl From<EndpointData> for RequestFile {
+    fn from(value: EndpointData) -> RequestFile {
+        let method = value.method.as_str();
+        let body = match value.body {
+            RequestPayload::None => None,
+            otherwise => Some(otherwise.into()),
+        };
+        let headers = value.headers.into();
+        let variables = value.variables.into();
+        let retry = if value.retry == RetryPolicy::default() {
+            None
+        } else {
+            Some(value.retry.into())
+        };
+        let compression = if value.compression == CompressionOptions::default() {
+            None
+        } else {
+            Some(value.compression.into())
+        };
+        RequestFile {
+            version: CURRENT_SCHEMA_VERSION,
+            url: value.url.clone(),
+            method: method.to_owned(),
+            body,
+            headers: Some(headers),
+            variables: Some(variables),
+            retry,
+            compression,
+            http_version: HttpVersionFile::from_preference(value.http_version),
+            aws_sigv4: value.aws_sigv4.map(AwsSigV4File::from),
+            notes: value.notes,
+            name: value.name,
+            pre_request_script: value.pre_request_script,
+            post_response_script: value.post_response_script,
+            url_history: value.url_history,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct WebSocketFile {
+    version: u32,
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    headers: Option<KeyValuedFileRows>,
+}
+
+const WEBSOCKET_KIND: &str = "websocket";
+
+impl TryFrom<WebSocketFile> for WebSocketData {
+    type Error = CarteroError;
+
+    fn try_from(value: WebSocketFile) -> Result<Self, Self::Error> {
+        Ok(WebSocketData {
+            url: value.url,
+            headers: value.headers.unwrap_or_default().into(),
+        })
+    }
+}
+
+impl From<WebSocketData> for WebSocketFile {
+    fn from(value: WebSocketData) -> Self {
+        WebSocketFile {
+            version: CURRENT_SCHEMA_VERSION,
+            kind: WEBSOCKET_KIND.into(),
+            url: value.url,
+            headers: Some(value.headers.into()),
+        }
+    }
+}
+
+/// Whether the given file contents describe a WebSocket session instead of a request.
+pub fn is_websocket_toml(file: &str) -> bool {
+    toml::from_str::<toml::Table>(file)
+        .ok()
+        .and_then(|table| table.get("type").and_then(|t| t.as_str().map(String::from)))
+        .is_some_and(|kind| kind == WEBSOCKET_KIND)
+}
+
+pub fn parse_websocket_toml(file: &str) -> Result<WebSocketData, CarteroError> {
+    let contents: WebSocketFile = parse_versioned(file)?;
+    WebSocketData::try_from(contents)
+}
+
+pub fn store_websocket_toml(session: &WebSocketData) -> Result<String, CarteroError> {
+    let file = WebSocketFile::from(session.clone());
+    to_document(&file)
+}
+
+/// Serializes a file so that storing the same contents always gives the same bytes,
+/// which keeps the diffs small for files kept under version control. The keys follow
+/// the order of the fields and the rows of the key-value tables keep their order; the
+/// lines end with LF and the document ends with a newline.
+fn to_document<T: Serialize>(file: &T) -> Result<String, CarteroError> {
+    let mut document = toml::to_string(file)?;
+    if !document.ends_with('\n') {
+        document.push('\n');
+    }
+    Ok(document)
+}
+
+/// Parses the contents of a file, migrating them first if they were written
+/// with an older version of the schema.
+fn parse_versioned<T: serde::de::DeserializeOwned>(file: &str) -> Result<T, CarteroError> {
+    let raw = toml::from_str::<toml::Value>(file)?;
+    Ok(migration::migrate(raw)?.try_into()?)
+}
+
+pub fn parse_toml(file: &str) -> Result<EndpointData, CarteroError> {
+    let contents: RequestFile = parse_versioned(file)?;
+    EndpointData::try_from(contents)
+}
+
+pub fn store_toml(endpoint: &EndpointData) -> Result<String, CarteroError> {
+    let file = RequestFile::from(endpoint.clone());
+    to_document(&file)
+}
+
+pub async fn read_file(file: &gio::File) -> Result<String, CarteroError> {
+    file.load_contents_future()
+        .await
+        .map(|data| String::from_utf8_lossy(&data.0).to_string())
+        .map_err(|err| {
+            println!("{err:?}");
+            CarteroError::FileDialogError
+        })
+}
+
+/// Writes the contents into the file, unless the file already holds the same bytes.
+/// Returns whether the file was written.
+pub async fn write_file(file: &gio::File, contents: &str) -> Result<bool, CarteroError> {
+    if let Ok((current, _)) = file.load_contents_future().await {
+        if current.as_ref() == contents.as_bytes() {
+            return Ok(false);
+        }
+    }
+    file.replace_contents_future(contents.to_string(), None, true, gio::FileCreateFlags::NONE)
+        .await
+        .map_err(|result| {
+            let error = result.1;
+            println!("{error:?}");
+            CarteroError::FileDialogError
+        })?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use gtk::gio;
+
+    use crate::{
+        entities::{
+            AwsSigV4, BodyCharset, CompressionOptions, EndpointData, HttpVersionPreference,
+            KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload, RetryPolicy,
+            WebSocketData, URL_HISTORY_LENGTH,
+        },
+        error::CarteroError,
+        file::KeyValueDetail,
+    };
+
+    use super::{KeyValuedFileTable, KeyValuedValueContainer};
+
+    #[test]
+    pub fn test_key_valued_file_table_to_key_value_table_sorts_simple() {
+        let map = BTreeMap::from([
+            (
+                "User-Agent".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Simple(
+                    "Cartero/0.1".into(),
+                )),
+            ),
+            (
+                "Host".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Simple(
+                    "www.google.com".into(),
+                )),
+            ),
+        ]);
+        let file = KeyValuedFileTable(map);
+
+        let table = KeyValueTable::from(file);
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[
+                ("Host", "www.google.com").into(),
+                ("User-Agent", "Cartero/0.1").into()
+            ])
+        );
+    }
+
+    #[test]
+    pub fn test_key_valued_file_table_to_key_value_table_sorts_complex() {
+        let map = BTreeMap::from([
+            (
+                "User-Agent".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Complex(KeyValueDetail {
+                    value: "Cartero/0.1".into(),
+                    active: false,
+                    secret: true,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                })),
+            ),
+            (
+                "Host".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Simple(
+                    "www.google.com".into(),
+                )),
+            ),
+        ]);
+        let file = KeyValuedFileTable(map);
+
+        let table = KeyValueTable::from(file);
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&[
+                ("Host", "www.google.com").into(),
+                KeyValue {
+                    name: "User-Agent".into(),
+                    value: "Cartero/0.1".into(),
+                    active: false,
+                    secret: true,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                }
+            ])
+        );
+    }
+
+    #[test]
+    pub fn test_key_valued_file_table_to_key_value_table_sorts_multiple() {
+        let map = BTreeMap::from([
+            (
+                "User-Agent".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Simple(
+                    "Cartero/0.1".into(),
+                )),
+            ),
+            (
+                "Accept".into(),
+                KeyValuedValueContainer::Multiple(vec![
+                    super::KeyValuedValue::Simple("*/*".into()),
+                    super::KeyValuedValue::Simple("application/json".into()),
+                    super::KeyValuedValue::Simple("application/ld+json".into()),
+                ]),
+            ),
+            (
+                "Host".into(),
+                KeyValuedValueContainer::Unique(super::KeyValuedValue::Simple(
+                    "www.google.com".into(),
+                )),
+            ),
+        ]);
+        let file = KeyValuedFileTable(map);
+
+        let table = KeyValueTable::from(file);
+
+        assert_eq!(table.len(), 5);
+        assert_eq!(
+            table,
+            KeyValueTable::new(&vec![
+                ("Accept", "*/*").into(),
+                ("Accept", "application/json").into(),
+                ("Accept", "application/ld+json").into(),
+                ("Host", "www.google.com").into(),
+                ("User-Agent", "Cartero/0.1").into(),
+            ])
+        );
+    }
+
+    #[test]
+    pub fn test_can_deserialize_classic() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+
+[headers]
+Accept = 'text/html'
+Accept-Encoding = 'gzip'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(b"hello"),
+                file: None,
+            }
+        );
+        assert_eq!(endpoint.headers.len(), 2);
+
+        let mut given_headers = endpoint.headers.clone();
+        given_headers.sort();
+        assert_eq!(
+            given_headers,
+            KeyValueTable::new(&[
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "text/html".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept-Encoding".into(),
+                    value: "gzip".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                }
+            ]),
+        );
+    }
+
+    #[test]
+    pub fn test_can_deserialize_complex_headers() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+
+[headers]
+Accept = { value = 'text/html', secret = true, active = false }
+Accept-Encoding = 'gzip'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(b"hello"),
+                file: None,
+            }
+        );
+        assert_eq!(endpoint.headers.len(), 2);
+
+        let mut given_headers = endpoint.headers.clone();
+        given_headers.sort();
+        assert_eq!(
+            given_headers,
+            KeyValueTable::new(&[
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "text/html".into(),
+                    active: false,
+                    secret: true,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept-Encoding".into(),
+                    value: "gzip".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                }
+            ]),
+        );
+    }
+
+    #[test]
+    pub fn test_can_deserialize_header_arrays() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+
+[headers]
+Accept = ['application/json', 'text/html']
+Accept-Encoding = 'gzip'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(b"hello"),
+                file: None,
+            }
+        );
+        assert_eq!(endpoint.headers.len(), 3);
+
+        let mut given_headers = endpoint.headers.clone();
+        given_headers.sort();
+        assert_eq!(
+            given_headers,
+            KeyValueTable::new(&[
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "application/json".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "text/html".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept-Encoding".into(),
+                    value: "gzip".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                }
+            ]),
+        );
+    }
+
+    #[test]
+    pub fn test_deserialize_complex_header_arrays() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+
+[headers]
+Accept = [
+    { value = 'application/json', active = false, secret = false },
+    { value = 'text/html', active = false, secret = false },
+]
+X-Client-Id = [
+    { value = '123412341234', active = true, secret = true },
+    { value = '{{CLIENT_ID}}', active = false, secret = false },
+]
+Accept-Encoding = 'gzip'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                content: Vec::from(b"hello"),
+                file: None,
+            }
+        );
+        assert_eq!(endpoint.headers.len(), 5);
+
+        let mut given_headers = endpoint.headers.clone();
+        given_headers.sort();
+        assert_eq!(
+            given_headers,
+            KeyValueTable::new(&vec![
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "application/json".into(),
+                    active: false,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept".into(),
+                    value: "text/html".into(),
+                    active: false,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "Accept-Encoding".into(),
+                    value: "gzip".into(),
+                    active: true,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "X-Client-Id".into(),
+                    value: "123412341234".into(),
+                    active: true,
+                    secret: true,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+                KeyValue {
+                    name: "X-Client-Id".into(),
+                    value: "{{CLIENT_ID}}".into(),
+                    active: false,
+                    secret: false,
+                    comment: String::new(),
+                    required: false,
+                    validation_rule: String::new(),
+                },
+            ]),
+        );
+    }
+
+    #[test]
+    pub fn test_deserialization_error() {
+        let toml = "
+version = 3
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+";
+        assert!(matches!(
+            super::parse_toml(toml),
+            Err(CarteroError::UnsupportedSchemaVersion(3))
+        ));
+    }
+
+    #[test]
+    pub fn test_unversioned_file_is_migrated() {
+        let toml = "
+url = 'https://www.google.com'
+method = 'GET'
+body = 'hello'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert!(super::store_toml(&endpoint)
+            .unwrap()
+            .starts_with("version = 2\n"));
+    }
+
+    #[test]
+    pub fn test_method_error() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'THROW'
+";
+        assert!(super::parse_toml(toml).is_err());
+    }
+
+    #[test]
+    pub fn test_empty_url() {
+        let toml = "
+version = 1
+method = 'POST'
+body = 'hello'
+
+[headers]
+Accept = 'text/html'
+";
+        assert!(super::parse_toml(toml).is_err());
+    }
+
+    #[test]
+    pub fn test_empty_method() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+body = 'hello'
+
+[headers]
+Accept = 'text/html'
+";
+        assert!(super::parse_toml(toml).is_err());
+    }
+
+    #[test]
+    pub fn test_empty_body() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+
+[headers]
+Accept = 'text/html'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(endpoint.body, RequestPayload::None);
+    }
+
+    #[test]
+    pub fn test_multiple_headers_serialization() {
+        let headers = vec![
+            ("Host", "google.com").into(),
+            ("User-Agent", "Cartero").into(),
+            ("User-Agent", "Cartero/0.1").into(),
+        ];
+        let headers = KeyValueTable::new(&headers);
+        let body = RequestPayload::None;
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            headers,
+            variables: KeyValueTable::default(),
+            body,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        let content = content.as_str();
+        assert!(content.contains("url = \"https://www.google.com\""));
+        assert!(content.contains("name = \"Host\"\nvalue = \"google.com\"\n"));
+        assert!(content.contains("name = \"User-Agent\"\nvalue = \"Cartero/0.1\"\n"));
+    }
+
+    #[test]
+    pub fn test_multiple_headers_serialization_with_meta() {
+        let headers = vec![
+            ("Host", "google.com").into(),
+            ("User-Agent", "Cartero").into(),
+            KeyValue {
+                name: "User-Agent".into(),
+                value: "Cartero/devel".into(),
+                active: false,
+                secret: false,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+            },
+            ("User-Agent", "Cartero/0.1").into(),
+        ];
+        let headers = KeyValueTable::new(&headers);
+        let body = RequestPayload::None;
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            headers,
+            variables: KeyValueTable::default(),
+            body,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        let content = content.as_str();
+        assert!(content.contains("url = \"https://www.google.com\""));
+        assert!(content.contains("name = \"Host\"\nvalue = \"google.com\"\n"));
+        assert!(content.contains("name = \"User-Agent\"\nvalue = \"Cartero/0.1\"\n"));
+        assert!(content.contains("active = false"));
+    }
+
+    #[test]
+    pub fn test_empty_headers() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'POST'
+body = 'hello'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.url, "https://www.google.com");
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                content: Vec::from(b"hello"),
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                file: None,
+            }
+        );
+        assert_eq!(endpoint.headers.len(), 0);
+    }
+
+    #[test]
+    pub fn test_serialize_correctly() {
+        let headers = vec![
+            ("User-Agent", "Cartero").into(),
+            ("Host", "google.com").into(),
+        ];
+        let headers = KeyValueTable::new(&headers);
+        let body = RequestPayload::Raw {
+            content: Vec::from(b"Hello"),
+            encoding: RawEncoding::OctetStream,
+            charset: BodyCharset::Utf8,
+            file: None,
+        };
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            headers,
+            variables: KeyValueTable::default(),
+            body,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content
+            .as_str()
+            .contains("url = \"https://www.google.com\""));
+        assert!(content.as_str().contains("method = \"POST\""));
+        assert!(content.as_str().contains("body = \"Hello\""));
+        assert!(content
+            .as_str()
+            .contains("name = \"User-Agent\"\nvalue = \"Cartero\"\n"));
+    }
+
+    #[test]
+    pub fn test_serializes_complex_example() {
+        let headers = KeyValueTable::new(&vec![
+            KeyValue {
+                name: "X-Client-Id".into(),
+                value: "123412341234".into(),
+                secret: true,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+                active: true,
+            },
+            ("Host", "google.com").into(),
+            ("User-Agent", "Cartero").into(),
+            KeyValue {
+                name: "X-Client-Id".into(),
+                value: "{{CLIENT_ID}}".into(),
+                secret: false,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+                active: false,
+            },
+        ]);
+        let variables = KeyValueTable::new(&[
+            KeyValue {
+                name: "CLIENT_SECRET".into(),
+                value: "101010".into(),
+                secret: true,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+                active: true,
+            },
+            ("CLIENT_ID", "123412341234").into(),
+            KeyValue {
+                name: "CLIENT_SECRET".into(),
+                value: "202020".into(),
+                secret: true,
+                comment: String::new(),
+                required: false,
+                validation_rule: String::new(),
+                active: true,
+            },
+        ]);
+        let body = RequestPayload::Raw {
+            content: Vec::from(b"Hello"),
+            encoding: RawEncoding::OctetStream,
+            charset: BodyCharset::Utf8,
+            file: None,
+        };
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            headers,
+            variables,
+            body,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(r.url, parsed.url);
+        assert_eq!(r.method, parsed.method);
+        assert_eq!(r.body, parsed.body);
+
+        assert_eq!(r.headers, parsed.headers);
+        assert_eq!(r.variables, parsed.variables);
+    }
+
+    #[test]
+    pub fn test_reordered_rows_keep_their_order() {
+        let mut headers = KeyValueTable::new(&[
+            ("Accept", "text/html").into(),
+            ("X-Trace", "1").into(),
+            ("Accept", "application/json").into(),
+            ("Authorization", "Bearer 1234").into(),
+        ]);
+        // Moving the last row to the top, as dragging it there does.
+        let moved = headers.remove(3);
+        headers.insert(0, moved);
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            headers,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        let parsed = super::parse_toml(&content).unwrap();
+        let names: Vec<&str> = parsed.headers.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["Authorization", "Accept", "X-Trace", "Accept"]);
+        assert_eq!(parsed.headers, r.headers);
+        assert_eq!(super::store_toml(&parsed).unwrap(), content);
+    }
+
+    #[test]
+    pub fn test_custom_method_is_stored_as_written() {
+        let endpoint = EndpointData {
+            url: "https://www.example.com/dav".to_string(),
+            method: RequestMethod::Custom("PROPFIND".to_string()),
+            ..Default::default()
+        };
+        let content = super::store_toml(&endpoint).unwrap();
+        assert!(content.contains("method = \"PROPFIND\""));
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.method, endpoint.method);
+    }
+
+    #[test]
+    pub fn test_notes_roundtrip() {
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            notes: "Needs a \"valid\" token.\nSee the wiki.".into(),
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("notes = "));
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.notes, r.notes);
+
+        let r = EndpointData {
+            notes: String::new(),
+            ..r
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(!content.contains("notes"));
+    }
+
+    #[test]
+    pub fn test_name_roundtrip() {
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            name: "Search users".into(),
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("name = \"Search users\""));
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.name, r.name);
+
+        let r = EndpointData {
+            name: String::new(),
+            ..r
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(!content.contains("name ="));
+    }
+
+    #[test]
+    pub fn test_scripts_roundtrip() {
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            pre_request_script: "request.set_header(\"X-Trace\", \"1\");".into(),
+            post_response_script: "variables.token = response.json().token;".into(),
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.pre_request_script, r.pre_request_script);
+        assert_eq!(parsed.post_response_script, r.post_response_script);
+
+        let r = EndpointData {
+            pre_request_script: String::new(),
+            post_response_script: String::new(),
+            ..r
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(!content.contains("_script"));
+    }
+
+    #[test]
+    pub fn test_url_history_roundtrip() {
+        let r = EndpointData {
+            url: "https://example.com/users/2".to_string(),
+            method: RequestMethod::Get,
+            url_history: vec![
+                "https://example.com/users/2".into(),
+                "https://example.com/users/1".into(),
+            ],
+            ..Default::default()
+        };
+        let content = super::store_toml(&r).unwrap();
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.url_history, r.url_history);
+
+        let r = EndpointData {
+            url_history: Vec::new(),
+            ..r
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(!content.contains("url_history"));
+    }
+
+    #[test]
+    pub fn test_url_history_is_truncated() {
+        let urls: Vec<String> = (0..15)
+            .map(|i| format!("\"https://example.com/{i}\""))
+            .collect();
+        let toml = format!(
+            "version = 1\nurl = \"https://example.com\"\nmethod = \"GET\"\nurl_history = [{}]\n",
+            urls.join(", ")
+        );
+        let parsed = super::parse_toml(&toml).unwrap();
+        assert_eq!(parsed.url_history.len(), URL_HISTORY_LENGTH);
+        assert_eq!(parsed.url_history[0], "https://example.com/0");
+    }
+
+    #[test]
+    pub fn test_header_comments_roundtrip() {
+        let headers = KeyValueTable::new(&[
+            KeyValue {
+                name: "Authorization".into(),
+                value: "Bearer 1234".into(),
+                active: true,
+                secret: false,
+                comment: "Expires every hour".into(),
+                required: false,
+                validation_rule: String::new(),
+            },
+            ("Accept", "text/html").into(),
+        ]);
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            headers,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("comment = \"Expires every hour\""));
+        assert!(content.contains("name = \"Accept\"\nvalue = \"text/html\"\n"));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        let authorization = parsed.headers.iter().find(|h| h.name == "Authorization");
+        assert_eq!(authorization.unwrap().comment, "Expires every hour");
+    }
+
+    #[test]
+    pub fn test_variable_rules_roundtrip() {
+        let variables = KeyValueTable::new(&[
+            KeyValue {
+                name: "user_id".into(),
+                value: "42".into(),
+                active: true,
+                secret: false,
+                comment: String::new(),
+                required: true,
+                validation_rule: "[0-9]+".into(),
+            },
+            ("host", "example.com").into(),
+        ]);
+        let r = EndpointData {
+            url: "https://{{host}}/users/{{user_id}}".to_string(),
+            method: RequestMethod::Get,
+            variables,
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("required = true"));
+        assert!(content.contains("validation_rule = \"[0-9]+\""));
+        assert!(content.contains("name = \"host\"\nvalue = \"example.com\"\n"));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        let user_id = parsed
+            .variables
+            .iter()
+            .find(|v| v.name == "user_id")
+            .unwrap();
+        assert!(user_id.required);
+        assert_eq!(user_id.validation_rule, "[0-9]+");
+        let host = parsed.variables.iter().find(|v| v.name == "host").unwrap();
+        assert!(!host.required);
+        assert!(host.validation_rule.is_empty());
+    }
+
+    #[test]
+    pub fn test_retry_policy_is_optional() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.retry, RetryPolicy::default());
+        assert!(!endpoint.retry.is_enabled());
+
+        let content = super::store_toml(&endpoint).unwrap();
+        assert!(!content.contains("[retry]"));
+    }
+
+    #[test]
+    pub fn test_retry_policy_roundtrip() {
+        let r = EndpointData {
+            url: "https://staging.example.com".to_string(),
+            method: RequestMethod::Get,
+            retry: RetryPolicy {
+                max_attempts: 3,
+                backoff: 250,
+                exponential_backoff: true,
+                on_connection_error: true,
+                on_gateway_error: false,
+                on_server_error: true,
+            },
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("[retry]"));
+        assert!(content.contains("max_attempts = 3"));
+        assert!(content.contains("backoff_ms = 250"));
+        assert!(content.contains("exponential_backoff = true"));
+        assert!(content.contains("on_server_error = true"));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.retry, r.retry);
+    }
+
+    #[test]
+    pub fn test_compression_is_optional() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.compression, CompressionOptions::default());
+        assert!(endpoint.compression.accept_compressed);
+
+        let content = super::store_toml(&endpoint).unwrap();
+        assert!(!content.contains("[compression]"));
+    }
+
+    #[test]
+    pub fn test_compression_roundtrip() {
+        let r = EndpointData {
+            url: "https://staging.example.com".to_string(),
+            method: RequestMethod::Post,
+            compression: CompressionOptions {
+                accept_compressed: false,
+                compress_body: true,
+            },
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("[compression]"));
+        assert!(content.contains("accept_compressed = false"));
+        assert!(content.contains("compress_body = true"));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.compression, r.compression);
+
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'POST'
+
+[compression]
+compress_body = true
+";
+        let parsed = super::parse_toml(toml).unwrap();
+        assert!(parsed.compression.accept_compressed);
+        assert!(parsed.compression.compress_body);
+    }
+
+    #[test]
+    pub fn test_http_version_roundtrip() {
+        let r = EndpointData {
+            url: "http://localhost:8080".to_string(),
+            method: RequestMethod::Get,
+            http_version: HttpVersionPreference::Http2Prior,
+            ..Default::default()
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("http_version = \"http2\""));
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.http_version, HttpVersionPreference::Http2Prior);
+
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+http_version = 'http1'
+";
+        let parsed = super::parse_toml(toml).unwrap();
+        assert_eq!(parsed.http_version, HttpVersionPreference::Http1Only);
+
+        let r = EndpointData {
+            http_version: HttpVersionPreference::Auto,
+            ..parsed
+        };
+        let content = super::store_toml(&r).unwrap();
+        assert!(!content.contains("http_version"));
+    }
+
+    #[test]
+    pub fn test_aws_sigv4_roundtrip() {
+        let r = EndpointData {
+            url: "https://examplebucket.s3.amazonaws.com".to_string(),
+            method: RequestMethod::Get,
+            aws_sigv4: Some(AwsSigV4 {
+                access_key: "AKIDEXAMPLE".into(),
+                secret_key: "{{AWS_SECRET}}".into(),
+                session_token: String::new(),
+                region: "eu-west-1".into(),
+                service: "s3".into(),
+            }),
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("[aws_sigv4]"));
+        assert!(content.contains("region = \"eu-west-1\""));
+        assert!(!content.contains("session_token"));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.aws_sigv4, r.aws_sigv4);
+
+        let without = EndpointData {
+            aws_sigv4: None,
+            ..r
+        };
+        let content = super::store_toml(&without).unwrap();
+        assert!(!content.contains("[aws_sigv4]"));
+        assert_eq!(super::parse_toml(&content).unwrap().aws_sigv4, None);
+    }
+
+    #[test]
+    pub fn test_body_charset_roundtrip() {
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            body: RequestPayload::Raw {
+                content: Vec::from("niño"),
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Latin1,
+                file: None,
+            },
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("charset = \"windows-1252\""));
+
+        let parsed = super::parse_toml(&content).unwrap();
+        assert_eq!(parsed.body, r.body);
+    }
+
+    #[test]
+    pub fn test_body_file_path_roundtrip() {
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Post,
+            body: RequestPayload::Raw {
+                content: Vec::from("{}"),
+                encoding: RawEncoding::Json,
+                charset: BodyCharset::Utf8,
+                file: Some("/tmp/payload.json".into()),
+            },
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.contains("body_file_path = \"/tmp/payload.json\""));
+        assert_eq!(super::parse_toml(&content).unwrap().body, r.body);
+
+        let binary = EndpointData {
+            body: RequestPayload::Raw {
+                content: vec![0x89, 0x50, 0x4e, 0x47, 0xff],
+                encoding: RawEncoding::OctetStream,
+                charset: BodyCharset::Utf8,
+                file: Some("/tmp/image.png".into()),
+            },
+            ..r
+        };
+        let content = super::store_toml(&binary).unwrap();
+        let RequestPayload::Raw { content, file, .. } = super::parse_toml(&content).unwrap().body
+        else {
+            panic!("Not a raw body");
+        };
+        assert!(content.is_empty());
+        assert_eq!(file, Some("/tmp/image.png".into()));
+    }
+
+    #[test]
+    pub fn test_websocket_roundtrip() {
+        let session = WebSocketData {
+            url: "wss://echo.example.com/socket".into(),
+            headers: KeyValueTable::new(&[("Authorization", "Bearer 1234").into()]),
+        };
+
+        let content = super::store_websocket_toml(&session).unwrap();
+        assert!(content.contains("type = \"websocket\""));
+        assert!(super::is_websocket_toml(&content));
+
+        let parsed = super::parse_websocket_toml(&content).unwrap();
+        assert_eq!(parsed, session);
+    }
+
+    #[test]
+    pub fn test_requests_are_not_websockets() {
+        let toml = "
+version = 1
+url = 'https://www.google.com'
+method = 'GET'
+";
+        assert!(!super::is_websocket_toml(toml));
+        assert!(!super::is_websocket_toml("this is not toml"));
+        assert!(super::parse_websocket_toml(toml).is_err());
+    }
+
+    #[test]
+    pub fn test_store_toml_is_byte_stable() {
+        let r = EndpointData {
+            url: "https://www.google.com/search".to_string(),
+            method: RequestMethod::Post,
+            headers: KeyValueTable::new(&[
+                ("X-Trace", "2").into(),
+                ("Accept", "text/html").into(),
+                ("User-Agent", "Cartero").into(),
+                ("X-Trace", "1").into(),
+                ("Cookie", "a=1").into(),
+            ]),
+            variables: KeyValueTable::new(&[
+                ("token", "1234").into(),
+                ("base", "https://www.google.com").into(),
+                ("page", "2").into(),
+            ]),
+            body: RequestPayload::Raw {
+                content: Vec::from("first line\r\nsecond line\r\n"),
+                encoding: RawEncoding::Json,
+                charset: BodyCharset::Utf8,
+                file: None,
+            },
+            notes: "Line one\nLine two".into(),
+            ..Default::default()
+        };
+
+        let content = super::store_toml(&r).unwrap();
+        assert!(content.ends_with('\n'));
+        assert!(!content.contains('\r'));
+        for _ in 0..10 {
+            assert_eq!(super::store_toml(&r).unwrap(), content);
+        }
+
+        let parsed = super::parse_toml(&content).unwrap();
+        let stored = super::store_toml(&parsed).unwrap();
+        assert_eq!(stored.as_bytes(), content.as_bytes());
+        assert_eq!(super::parse_toml(&stored).unwrap(), parsed);
+    }
+
+    #[test]
+    pub fn test_write_file_skips_unchanged_contents() {
+        let path = std::env::temp_dir().join(format!(
+            "cartero-test-write-file-{}.cartero",
+            std::process::id()
+        ));
+        let file = gio::File::for_path(&path);
+        let r = EndpointData {
+            url: "https://www.google.com".to_string(),
+            method: RequestMethod::Get,
+            headers: KeyValueTable::new(&[("Accept", "text/html").into()]),
+            ..Default::default()
+        };
+        let content = super::store_toml(&r).unwrap();
+        let saved_again = super::store_toml(&super::parse_toml(&content).unwrap()).unwrap();
+        let changed = super::store_toml(&EndpointData {
+            url: "https://www.google.com/search".to_string(),
+            ..r
+        })
+        .unwrap();
+
+        let ctx = glib::MainContext::new();
+        let written = ctx.block_on(async {
+            [
+                super::write_file(&file, &content).await.unwrap(),
+                super::write_file(&file, &saved_again).await.unwrap(),
+                super::write_file(&file, &changed).await.unwrap(),
+            ]
+        });
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(written, [true, false, true]);
+        assert_eq!(on_disk, changed);
+    }
+}
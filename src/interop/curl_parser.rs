@@ -0,0 +1,601 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use gettextrs::gettext;
+use url::{form_urlencoded, Url};
+
+use crate::entities::{
+    EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+};
+use crate::error::CarteroError;
+
+/// Short options that take a value, which may be written right after the letter.
+const SHORT_OPTIONS_WITH_VALUE: &str = "XHduAebFomxEwc";
+
+/// Options that take a value but do not change the request, such as where
+/// the response is written or how long to wait for it.
+const IGNORED_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "-x",
+    "--proxy",
+    "--cacert",
+    "-E",
+    "--cert",
+    "--key",
+    "-w",
+    "--write-out",
+    "--resolve",
+    "-c",
+    "--cookie-jar",
+    "--limit-rate",
+];
+
+/// What the options of a curl command ask for.
+#[derive(Default)]
+struct CurlCommand {
+    url: Option<String>,
+    method: Option<RequestMethod>,
+    headers: Vec<KeyValue>,
+    /// Every piece of data given with -d and friends, joined with & when sent.
+    data: Vec<String>,
+    form: Vec<KeyValue>,
+    /// Set by -G, which moves the data into the query string.
+    get: bool,
+    /// Set by -I, which sends a HEAD request.
+    head: bool,
+    /// Set by --json, which also sets the content type and the accepted type.
+    json: bool,
+}
+
+impl CurlCommand {
+    fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.name.eq_ignore_ascii_case(name))
+    }
+
+    fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("content-type"))
+            .map(|header| header.value.as_str())
+    }
+}
+
+fn unclosed_quote() -> CarteroError {
+    CarteroError::CurlParseError(gettext("A quote is not closed"))
+}
+
+/// Reads up to `digits` hexadecimal digits as the code of a character.
+fn read_hex(chars: &mut Peekable<Chars>, digits: usize) -> Option<char> {
+    let mut code = 0;
+    let mut read = 0;
+    while read < digits {
+        let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) else {
+            break;
+        };
+        code = code * 16 + digit;
+        chars.next();
+        read += 1;
+    }
+    if read == 0 {
+        None
+    } else {
+        char::from_u32(code)
+    }
+}
+
+/// Reads the rest of a `$'...'` string, which web browsers use when copying
+/// requests whose body has line breaks or quotes.
+fn read_ansi_c_quoted(chars: &mut Peekable<Chars>, word: &mut String) -> Result<(), CarteroError> {
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => {
+                let escaped = match chars.next() {
+                    Some('n') => Some('\n'),
+                    Some('t') => Some('\t'),
+                    Some('r') => Some('\r'),
+                    Some('e') => Some('\u{1b}'),
+                    Some('x') => read_hex(chars, 2),
+                    Some('u') => read_hex(chars, 4),
+                    Some('U') => read_hex(chars, 8),
+                    Some(c @ ('\\' | '\'' | '"' | '?')) => Some(c),
+                    Some(c) => {
+                        word.push('\\');
+                        Some(c)
+                    }
+                    None => return Err(unclosed_quote()),
+                };
+                word.extend(escaped);
+            }
+            Some(c) => word.push(c),
+            None => return Err(unclosed_quote()),
+        }
+    }
+}
+
+/// Splits a command line into words the way a POSIX shell would, taking care
+/// of quotes, escaped characters and lines that end with a backslash.
+pub fn split_words(command: &str) -> Result<Vec<String>, CarteroError> {
+    let mut words = Vec::new();
+    // None between words, so that quoted empty strings are still words.
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(next) => word.get_or_insert_with(String::new).push(next),
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed_quote()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unclosed_quote()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed_quote()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                read_ansi_c_quoted(&mut chars, word.get_or_insert_with(String::new))?;
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn is_curl(program: &str) -> bool {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    name == "curl" || name.eq_ignore_ascii_case("curl.exe")
+}
+
+/// Splits a short option from the value written right after it, as in `-XPOST`.
+fn split_attached(word: &str) -> (&str, Option<&str>) {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some('-'), Some(letter))
+            if SHORT_OPTIONS_WITH_VALUE.contains(letter) && word.len() > 2 =>
+        {
+            (&word[..2], Some(&word[2..]))
+        }
+        _ => (word, None),
+    }
+}
+
+fn header(text: &str) -> Result<KeyValue, CarteroError> {
+    match text.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok(KeyValue::from((name.trim(), value.trim())))
+        }
+        _ => Err(CarteroError::CurlParseError(
+            gettext("The header {header} is not valid").replace("{header}", text),
+        )),
+    }
+}
+
+/// Encodes the data of --data-urlencode, which may be preceded by a name.
+fn urlencoded_data(text: &str) -> String {
+    let encode = |content: &str| form_urlencoded::byte_serialize(content.as_bytes()).collect();
+    match text.split_once('=') {
+        Some(("", content)) => encode(content),
+        Some((name, content)) => format!("{name}={}", encode(content)),
+        None => encode(text),
+    }
+}
+
+fn form_field(text: &str, literal: bool) -> Result<KeyValue, CarteroError> {
+    let Some((name, value)) = text.split_once('=') else {
+        return Err(CarteroError::CurlParseError(
+            gettext("The form field {field} is not valid").replace("{field}", text),
+        ));
+    };
+    if !literal && (value.starts_with('@') || value.starts_with('<')) {
+        return Err(CarteroError::CurlParseError(
+            gettext("The form field {field} sends a file, which is not supported")
+                .replace("{field}", name),
+        ));
+    }
+    Ok(KeyValue::from((name, value)))
+}
+
+/// The Authorization header for the credentials given to -u.
+pub(crate) fn basic_auth(credentials: &str) -> KeyValue {
+    let credentials = if credentials.contains(':') {
+        credentials.to_string()
+    } else {
+        format!("{credentials}:")
+    };
+    let mut header = KeyValue::from((
+        "Authorization",
+        format!("Basic {}", BASE64.encode(credentials)).as_str(),
+    ));
+    header.secret = true;
+    header
+}
+
+fn read_command(command: &str) -> Result<CurlCommand, CarteroError> {
+    let mut words = split_words(command)?.into_iter();
+    match words.next() {
+        Some(program) if is_curl(&program) => {}
+        _ => {
+            return Err(CarteroError::CurlParseError(gettext(
+                "The command does not start with curl",
+            )))
+        }
+    }
+
+    let mut curl = CurlCommand::default();
+    while let Some(word) = words.next() {
+        let (option, attached) = split_attached(&word);
+        let mut value = || {
+            attached
+                .map(String::from)
+                .or_else(|| words.next())
+                .ok_or_else(|| {
+                    CarteroError::CurlParseError(
+                        gettext("The option {option} needs a value").replace("{option}", option),
+                    )
+                })
+        };
+
+        match option {
+            "-X" | "--request" => {
+                let method = value()?;
+                let parsed = RequestMethod::try_from(method.as_str()).map_err(|_| {
+                    CarteroError::CurlParseError(
+                        gettext("The method {method} is not supported")
+                            .replace("{method}", &method),
+                    )
+                })?;
+                curl.method = Some(parsed);
+            }
+            "-H" | "--header" => curl.headers.push(header(&value()?)?),
+            "-d" | "--data" | "--data-ascii" | "--data-binary" | "--data-raw" => {
+                curl.data.push(value()?);
+            }
+            "--data-urlencode" => curl.data.push(urlencoded_data(&value()?)),
+            "--json" => {
+                curl.data.push(value()?);
+                curl.json = true;
+            }
+            "-F" | "--form" => curl.form.push(form_field(&value()?, false)?),
+            "--form-string" => curl.form.push(form_field(&value()?, true)?),
+            "-u" | "--user" => curl.headers.push(basic_auth(&value()?)),
+            "-A" | "--user-agent" => curl.headers.push(("User-Agent", value()?.as_str()).into()),
+            "-e" | "--referer" => curl.headers.push(("Referer", value()?.as_str()).into()),
+            "-b" | "--cookie" => curl.headers.push(("Cookie", value()?.as_str()).into()),
+            "--url" => curl.url = Some(value()?),
+            "-G" | "--get" => curl.get = true,
+            "-I" | "--head" => curl.head = true,
+            option if IGNORED_OPTIONS.contains(&option) => {
+                value()?;
+            }
+            // Every other option is a flag that does not change the request.
+            option if option.starts_with('-') && option.len() > 1 => {}
+            _ => {
+                curl.url.get_or_insert(word);
+            }
+        }
+    }
+    Ok(curl)
+}
+
+/// The body of the request, as an editable form when curl would send one.
+fn payload(curl: &CurlCommand) -> RequestPayload {
+    if !curl.form.is_empty() {
+        return RequestPayload::Multipart {
+            params: KeyValueTable::new(&curl.form),
+        };
+    }
+    if curl.data.is_empty() || curl.get {
+        return RequestPayload::None;
+    }
+
+    let content = curl.data.join("&");
+    let content_type = curl.content_type().unwrap_or(if curl.json {
+        "application/json"
+    } else {
+        "application/x-www-form-urlencoded"
+    });
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        let params: KeyValueTable = form_urlencoded::parse(content.as_bytes())
+            .map(|(name, value)| KeyValue::from((name.into_owned(), value.into_owned())))
+            .collect();
+        return RequestPayload::Urlencoded(params);
+    }
+
+    let encoding = if content_type.contains("json") {
+        RawEncoding::Json
+    } else if content_type.contains("xml") {
+        RawEncoding::Xml
+    } else {
+        RawEncoding::OctetStream
+    };
+    RequestPayload::Raw {
+        encoding,
+        charset: Default::default(),
+        content: content.into_bytes(),
+        file: None,
+    }
+}
+
+/// Reads a curl command, such as the ones copied from the developer tools of
+/// a web browser, and turns it into an endpoint that sends the same request.
+pub fn parse_curl(command: &str) -> Result<EndpointData, CarteroError> {
+    let mut curl = read_command(command)?;
+    let Some(url) = curl.url.take() else {
+        return Err(CarteroError::CurlParseError(gettext(
+            "The command has no URL",
+        )));
+    };
+    let mut endpoint = EndpointData::from_url(&url)?;
+
+    if curl.get && !curl.data.is_empty() {
+        let mut url = Url::parse(&endpoint.url)?;
+        let data = curl.data.join("&");
+        let query = match url.query() {
+            Some(query) if !query.is_empty() => format!("{query}&{data}"),
+            _ => data,
+        };
+        url.set_query(Some(&query));
+        endpoint.url = url.to_string();
+    }
+
+    let sends_body = !curl.form.is_empty() || (!curl.data.is_empty() && !curl.get);
+    endpoint.method = match curl.method.take() {
+        Some(method) => method,
+        None if curl.head => RequestMethod::Head,
+        None if sends_body => RequestMethod::Post,
+        None => RequestMethod::Get,
+    };
+
+    if curl.json {
+        for (name, value) in [
+            ("Content-Type", "application/json"),
+            ("Accept", "application/json"),
+        ] {
+            if !curl.has_header(name) {
+                curl.headers.push((name, value).into());
+            }
+        }
+    }
+
+    endpoint.body = payload(&curl);
+    if let RequestPayload::Urlencoded(_) | RequestPayload::Multipart { .. } = endpoint.body {
+        // The content type of forms is set when the request is sent.
+        curl.headers
+            .retain(|header| !header.name.eq_ignore_ascii_case("content-type"));
+    }
+    endpoint.headers = KeyValueTable::new(&curl.headers);
+    Ok(endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_message(command: &str) -> String {
+        match parse_curl(command) {
+            Err(CarteroError::CurlParseError(message)) => message,
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_split_words() {
+        let words = split_words(
+            "curl 'https://example.com/a b' \\\n  -H \"X-Name: \\\"quoted\\\" \\$HOME\" \
+             --data $'line\\none\\'s\\x21' plain\\ word ''",
+        )
+        .unwrap();
+        assert_eq!(
+            words,
+            vec![
+                "curl",
+                "https://example.com/a b",
+                "-H",
+                "X-Name: \"quoted\" $HOME",
+                "--data",
+                "line\none's!",
+                "plain word",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_words_windows_line_endings() {
+        let words = split_words("curl \\\r\n  https://example.com").unwrap();
+        assert_eq!(words, vec!["curl", "https://example.com"]);
+    }
+
+    #[test]
+    fn test_parse_devtools_command() {
+        let endpoint = parse_curl(
+            "curl 'https://api.example.com/v1/users?page=2' \\\n  \
+             -H 'accept: application/json' \\\n  \
+             -H 'content-type: application/json' \\\n  \
+             -H 'x-trace: 1' \\\n  \
+             --data-raw '{\"name\":\"Ana\"}' \\\n  \
+             --compressed",
+        )
+        .unwrap();
+        assert_eq!(endpoint.url, "https://api.example.com/v1/users?page=2");
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.headers,
+            KeyValueTable::new(&[
+                ("accept", "application/json").into(),
+                ("content-type", "application/json").into(),
+                ("x-trace", "1").into(),
+            ])
+        );
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                charset: Default::default(),
+                content: Vec::from("{\"name\":\"Ana\"}"),
+                file: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_method_and_auth() {
+        let endpoint = parse_curl("curl -XDELETE -u user:pass -sSL example.com/items/1").unwrap();
+        assert_eq!(endpoint.url, "https://example.com/items/1");
+        assert_eq!(endpoint.method, RequestMethod::Delete);
+        let auth = &endpoint.headers[0];
+        assert_eq!(auth.name, "Authorization");
+        assert_eq!(auth.value, "Basic dXNlcjpwYXNz");
+        assert!(auth.secret);
+    }
+
+    #[test]
+    fn test_parse_urlencoded_data() {
+        let endpoint = parse_curl(
+            "curl https://example.com/login -d user=ana --data-urlencode 'pass=a b&c' \
+             -H 'Content-Type: application/x-www-form-urlencoded'",
+        )
+        .unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert!(endpoint.headers.is_empty());
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[
+                ("user", "ana").into(),
+                ("pass", "a b&c").into(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_get_moves_data_to_query() {
+        let endpoint = parse_curl("curl -G https://example.com/search?lang=es -d q=rust").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(endpoint.url, "https://example.com/search?lang=es&q=rust");
+        assert_eq!(endpoint.body, RequestPayload::None);
+    }
+
+    #[test]
+    fn test_parse_form_and_json() {
+        let endpoint = parse_curl("curl https://example.com/upload -F name=ana -F tag=x").unwrap();
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&[("name", "ana").into(), ("tag", "x").into()]),
+            }
+        );
+
+        let endpoint = parse_curl("curl --url https://example.com --json '{}' -X PUT").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Put);
+        assert_eq!(
+            endpoint.headers.header("content-type").unwrap(),
+            vec!["application/json"]
+        );
+        assert_eq!(
+            endpoint.headers.header("accept").unwrap(),
+            vec!["application/json"]
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_method() {
+        let endpoint = parse_curl("curl -X PROPFIND https://example.com/dav").unwrap();
+        assert_eq!(
+            endpoint.method,
+            RequestMethod::Custom(String::from("PROPFIND"))
+        );
+        let endpoint = parse_curl("curl -X connect https://example.com").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Connect);
+    }
+
+    #[test]
+    fn test_parse_head_and_ignored_options() {
+        let endpoint =
+            parse_curl("curl -I -o /dev/null --max-time 5 -k https://example.com").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Head);
+        assert_eq!(endpoint.url, "https://example.com/");
+    }
+
+    #[test]
+    fn test_malformed_commands() {
+        assert_eq!(
+            error_message("wget https://example.com"),
+            "The command does not start with curl"
+        );
+        assert_eq!(error_message(""), "The command does not start with curl");
+        assert_eq!(
+            error_message("curl 'https://example.com"),
+            "A quote is not closed"
+        );
+        assert_eq!(error_message("curl -H"), "The option -H needs a value");
+        assert_eq!(
+            error_message("curl -H 'no colon' example.com"),
+            "The header no colon is not valid"
+        );
+        assert_eq!(
+            error_message("curl -X 'FE TCH' example.com"),
+            "The method FE TCH is not supported"
+        );
+        assert_eq!(error_message("curl -s"), "The command has no URL");
+        assert_eq!(
+            error_message("curl -F file=@photo.png example.com"),
+            "The form field file sends a file, which is not supported"
+        );
+    }
+}
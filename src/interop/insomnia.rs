@@ -0,0 +1,482 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use gettextrs::gettext;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::entities::{
+    EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+};
+use crate::error::CarteroError;
+
+use super::curl_parser::basic_auth;
+
+/// Resources that only give structure to the export and are not requests by themselves.
+const STRUCTURAL_RESOURCES: &[&str] = &[
+    "workspace",
+    "request_group",
+    "environment",
+    "cookie_jar",
+    "api_spec",
+];
+
+#[derive(Deserialize)]
+struct InsomniaExport {
+    #[serde(rename = "_type")]
+    kind: String,
+    #[serde(rename = "__export_format")]
+    format: u32,
+    #[serde(default)]
+    resources: Vec<Resource>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Resource {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "_type")]
+    kind: String,
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>,
+    name: String,
+    url: String,
+    method: String,
+    description: String,
+    headers: Vec<Pair>,
+    parameters: Vec<Pair>,
+    body: Body,
+    authentication: Authentication,
+    /// The variables of an environment.
+    data: Option<Map<String, Value>>,
+    /// The variables that a request group gives to its requests.
+    environment: Option<Map<String, Value>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Pair {
+    name: String,
+    value: String,
+    disabled: bool,
+    /// Set to "file" for the multipart fields that upload a file.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Body {
+    #[serde(rename = "mimeType")]
+    mime_type: Option<String>,
+    text: Option<String>,
+    params: Vec<Pair>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Authentication {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    disabled: bool,
+    token: String,
+    prefix: String,
+    username: String,
+    password: String,
+}
+
+/// The requests found in an Insomnia export.
+#[derive(Debug, Default)]
+pub struct InsomniaImport {
+    pub endpoints: Vec<EndpointData>,
+    /// How many resources could not be imported, such as gRPC or WebSocket requests.
+    pub skipped: usize,
+}
+
+fn import_error(message: impl ToString) -> CarteroError {
+    CarteroError::InsomniaImportError(message.to_string())
+}
+
+/// Rewrites the `{{ _.variable }}` tags of Insomnia as `{{variable}}`. Other
+/// tags, such as `{% response %}`, have no equivalent and are kept as they are.
+pub fn convert_template_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let inner = after[..end].trim();
+        let name = inner.strip_prefix("_.").unwrap_or(inner);
+        out.push_str(&format!("{{{{{name}}}}}"));
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The text of an environment value, as variables in Cartero can only hold text.
+fn variable_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => convert_template_tags(text),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn key_values<'a>(pairs: impl IntoIterator<Item = &'a Pair>) -> Vec<KeyValue> {
+    pairs
+        .into_iter()
+        .filter(|pair| !pair.name.is_empty() || !pair.value.is_empty())
+        .map(|pair| {
+            let mut item = KeyValue::from((
+                convert_template_tags(&pair.name),
+                convert_template_tags(&pair.value),
+            ));
+            item.active = !pair.disabled;
+            item
+        })
+        .collect()
+}
+
+/// Adds the enabled query parameters to the URL. They are kept as written,
+/// since they may contain variables.
+fn url_with_parameters(url: &str, parameters: &[Pair]) -> String {
+    let query: Vec<String> = parameters
+        .iter()
+        .filter(|pair| !pair.disabled && !pair.name.is_empty())
+        .map(|pair| {
+            format!(
+                "{}={}",
+                convert_template_tags(&pair.name),
+                convert_template_tags(&pair.value)
+            )
+        })
+        .collect();
+    let url = convert_template_tags(url);
+    if query.is_empty() {
+        url
+    } else if url.contains('?') {
+        format!("{url}&{}", query.join("&"))
+    } else {
+        format!("{url}?{}", query.join("&"))
+    }
+}
+
+fn payload(body: &Body) -> RequestPayload {
+    let Some(mime_type) = body.mime_type.as_deref() else {
+        return RequestPayload::None;
+    };
+    match mime_type {
+        "application/x-www-form-urlencoded" => {
+            RequestPayload::Urlencoded(KeyValueTable::new(&key_values(&body.params)))
+        }
+        "multipart/form-data" => {
+            // Uploading files from a form is not supported yet.
+            let params = body
+                .params
+                .iter()
+                .filter(|pair| pair.kind.as_deref() != Some("file"));
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&key_values(params)),
+            }
+        }
+        mime_type => {
+            let encoding = if mime_type.contains("json") || mime_type == "application/graphql" {
+                RawEncoding::Json
+            } else if mime_type.contains("xml") {
+                RawEncoding::Xml
+            } else {
+                RawEncoding::OctetStream
+            };
+            let text = body.text.as_deref().unwrap_or_default();
+            RequestPayload::Raw {
+                encoding,
+                charset: Default::default(),
+                content: convert_template_tags(text).into_bytes(),
+                file: None,
+            }
+        }
+    }
+}
+
+fn authorization(auth: &Authentication) -> Option<KeyValue> {
+    if auth.disabled {
+        return None;
+    }
+    match auth.kind.as_deref()? {
+        "bearer" => {
+            let prefix = match auth.prefix.trim() {
+                "" => "Bearer",
+                prefix => prefix,
+            };
+            let token = convert_template_tags(&auth.token);
+            let mut header =
+                KeyValue::from(("Authorization", format!("{prefix} {token}").as_str()));
+            header.secret = true;
+            Some(header)
+        }
+        "basic" => {
+            let credentials = format!("{}:{}", auth.username, auth.password);
+            Some(basic_auth(&convert_template_tags(&credentials)))
+        }
+        _ => None,
+    }
+}
+
+fn add_variables(variables: &mut Vec<KeyValue>, data: Option<&Map<String, Value>>) {
+    for (name, value) in data.into_iter().flatten() {
+        let value = variable_value(value);
+        match variables.iter_mut().find(|item| &item.name == name) {
+            Some(item) => item.value = value,
+            None => variables.push(KeyValue::from((name.as_str(), value.as_str()))),
+        }
+    }
+}
+
+/// Reads an Insomnia export in the version 4 format.
+///
+/// There are no collections in Cartero, so the name of every request starts
+/// with the folders it was in. The base environment and the environments of
+/// the request groups become the variables of the requests.
+pub fn import_insomnia_v4(src: &str) -> Result<InsomniaImport, CarteroError> {
+    let export: InsomniaExport = serde_json::from_str(src).map_err(import_error)?;
+    if export.kind != "export" {
+        return Err(import_error(gettext("the file is not an Insomnia export")));
+    }
+    if export.format != 4 {
+        return Err(import_error(
+            gettext("version {version} of the format is not supported")
+                .replace("{version}", &export.format.to_string()),
+        ));
+    }
+
+    let by_id: HashMap<&str, &Resource> = export
+        .resources
+        .iter()
+        .map(|resource| (resource.id.as_str(), resource))
+        .collect();
+
+    // Sub environments are alternatives to pick from, so only the base ones are used.
+    let mut base_variables = Vec::new();
+    for resource in &export.resources {
+        let parent = resource
+            .parent_id
+            .as_deref()
+            .and_then(|parent| by_id.get(parent));
+        let is_base = resource.kind == "environment"
+            && !matches!(parent, Some(parent) if parent.kind == "environment");
+        if is_base {
+            add_variables(&mut base_variables, resource.data.as_ref());
+        }
+    }
+
+    let mut import = InsomniaImport::default();
+    for resource in &export.resources {
+        if resource.kind != "request" {
+            if !STRUCTURAL_RESOURCES.contains(&resource.kind.as_str()) {
+                import.skipped += 1;
+            }
+            continue;
+        }
+        let Ok(method) = RequestMethod::try_from(resource.method.as_str()) else {
+            import.skipped += 1;
+            continue;
+        };
+
+        // The request groups from the outermost one to the one holding the request.
+        let mut groups = Vec::new();
+        let mut parent = resource.parent_id.as_deref();
+        while let Some(group) = parent.and_then(|id| by_id.get(id)) {
+            if group.kind != "request_group" || groups.len() >= by_id.len() {
+                break;
+            }
+            groups.insert(0, *group);
+            parent = group.parent_id.as_deref();
+        }
+
+        let mut variables = base_variables.clone();
+        for group in &groups {
+            add_variables(&mut variables, group.environment.as_ref());
+        }
+        let mut headers = key_values(&resource.headers);
+        if let Some(header) = authorization(&resource.authentication) {
+            headers.push(header);
+        }
+        let name = groups
+            .iter()
+            .map(|group| group.name.as_str())
+            .chain(std::iter::once(resource.name.as_str()))
+            .collect::<Vec<_>>()
+            .join(" / ");
+
+        import.endpoints.push(EndpointData {
+            url: url_with_parameters(&resource.url, &resource.parameters),
+            method,
+            headers: KeyValueTable::new(&headers),
+            variables: KeyValueTable::new(&variables),
+            body: payload(&resource.body),
+            notes: resource.description.clone(),
+            name,
+            ..Default::default()
+        });
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "_type": "export",
+        "__export_format": 4,
+        "resources": [
+            { "_id": "wrk_1", "_type": "workspace", "parentId": null, "name": "API" },
+            {
+                "_id": "env_1", "_type": "environment", "parentId": "wrk_1",
+                "data": { "base_url": "https://example.com", "retries": 3 }
+            },
+            {
+                "_id": "env_2", "_type": "environment", "parentId": "env_1",
+                "data": { "base_url": "https://staging.example.com" }
+            },
+            {
+                "_id": "fld_1", "_type": "request_group", "parentId": "wrk_1",
+                "name": "Users", "environment": { "version": "v2" }
+            },
+            {
+                "_id": "req_1", "_type": "request", "parentId": "fld_1",
+                "name": "Create user", "method": "POST",
+                "url": "{{ _.base_url }}/{{ _.version }}/users",
+                "description": "Creates an user",
+                "headers": [
+                    { "name": "Content-Type", "value": "application/json" },
+                    { "name": "X-Debug", "value": "1", "disabled": true }
+                ],
+                "parameters": [
+                    { "name": "notify", "value": "true" },
+                    { "name": "dry_run", "value": "1", "disabled": true }
+                ],
+                "body": { "mimeType": "application/json", "text": "{\"name\": \"{{ _.name }}\"}" },
+                "authentication": { "type": "bearer", "token": "{{ _.token }}" }
+            },
+            {
+                "_id": "req_2", "_type": "request", "parentId": "wrk_1",
+                "name": "Login", "method": "post", "url": "https://example.com/login",
+                "body": {
+                    "mimeType": "application/x-www-form-urlencoded",
+                    "params": [{ "name": "user", "value": "{{user}}" }]
+                },
+                "authentication": { "type": "basic", "username": "a", "password": "b" }
+            },
+            { "_id": "ws_1", "_type": "websocket_request", "parentId": "wrk_1", "name": "Live" },
+            { "_id": "jar_1", "_type": "cookie_jar", "parentId": "wrk_1" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_convert_template_tags() {
+        assert_eq!(
+            convert_template_tags("{{ _.base_url }}/a/{{token}}"),
+            "{{base_url}}/a/{{token}}"
+        );
+        assert_eq!(
+            convert_template_tags("{% response 'body', 'req_1' %} {{ open"),
+            "{% response 'body', 'req_1' %} {{ open"
+        );
+    }
+
+    #[test]
+    fn test_import_requests() {
+        let import = import_insomnia_v4(EXPORT).unwrap();
+        assert_eq!(import.skipped, 1);
+        assert_eq!(import.endpoints.len(), 2);
+
+        let create = &import.endpoints[0];
+        assert_eq!(create.name, "Users / Create user");
+        assert_eq!(create.method, RequestMethod::Post);
+        assert_eq!(create.url, "{{base_url}}/{{version}}/users?notify=true");
+        assert_eq!(create.notes, "Creates an user");
+        assert_eq!(
+            create.headers.header("content-type"),
+            Some(vec!["application/json"])
+        );
+        let debug = create.headers.iter().find(|item| item.name == "X-Debug");
+        assert!(debug.is_some_and(|item| !item.active));
+        assert_eq!(
+            create.headers.header("authorization"),
+            Some(vec!["Bearer {{token}}"])
+        );
+        assert_eq!(
+            create.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                charset: Default::default(),
+                content: Vec::from("{\"name\": \"{{name}}\"}"),
+                file: None,
+            }
+        );
+
+        let variables: Vec<(String, String)> = create
+            .variables
+            .iter()
+            .map(|item| (item.name.clone(), item.value.clone()))
+            .collect();
+        assert_eq!(
+            variables,
+            vec![
+                (
+                    String::from("base_url"),
+                    String::from("https://example.com")
+                ),
+                (String::from("retries"), String::from("3")),
+                (String::from("version"), String::from("v2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_form_and_basic_auth() {
+        let import = import_insomnia_v4(EXPORT).unwrap();
+        let login = &import.endpoints[1];
+        assert_eq!(login.name, "Login");
+        assert_eq!(login.method, RequestMethod::Post);
+        assert_eq!(
+            login.body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[KeyValue::from(("user", "{{user}}"))]))
+        );
+        assert_eq!(
+            login.headers.header("authorization"),
+            Some(vec!["Basic YTpi"])
+        );
+        assert_eq!(login.variables.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_not_an_export() {
+        assert!(import_insomnia_v4("{}").is_err());
+        assert!(import_insomnia_v4(r#"{"_type": "export", "__export_format": 3}"#).is_err());
+        assert!(import_insomnia_v4(r#"{"_type": "something", "__export_format": 4}"#).is_err());
+    }
+}
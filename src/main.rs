@@ -17,10 +17,17 @@
 
 #![windows_subsystem = "windows"]
 
+mod analysis;
 mod app;
 mod client;
+mod diff;
 mod error;
+mod export;
 mod file;
+mod interop;
+mod scripts;
+mod templates;
+mod tools;
 mod widgets;
 #[rustfmt::skip]
 mod config;
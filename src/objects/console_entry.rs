@@ -0,0 +1,121 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{prelude::*, Object};
+
+use crate::client::TraceEvent;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use glib::Properties;
+    use gtk::glib;
+    use gtk::glib::prelude::*;
+    use gtk::glib::subclass::prelude::*;
+
+    #[derive(Default, Debug, Properties)]
+    #[properties(wrapper_type = super::ConsoleEntry)]
+    pub struct ConsoleEntry {
+        /// The tab that sent the request, used to filter the console.
+        #[property(get, set)]
+        origin: glib::WeakRef<glib::Object>,
+        /// The title of the tab when the entry was logged.
+        #[property(get, set)]
+        source: RefCell<String>,
+        #[property(get, set)]
+        time: RefCell<String>,
+        #[property(get, set)]
+        kind: RefCell<String>,
+        #[property(get, set)]
+        message: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ConsoleEntry {
+        const NAME: &'static str = "CarteroConsoleEntry";
+        type Type = super::ConsoleEntry;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for ConsoleEntry {}
+}
+
+glib::wrapper! {
+    pub struct ConsoleEntry(ObjectSubclass<imp::ConsoleEntry>);
+}
+
+impl ConsoleEntry {
+    pub fn new(origin: &impl IsA<Object>, source: &str, event: TraceEvent) -> Self {
+        let time = glib::DateTime::now_local()
+            .and_then(|now| now.format("%H:%M:%S"))
+            .map(|time| time.to_string())
+            .unwrap_or_default();
+        Object::builder()
+            .property("origin", origin.upcast_ref::<Object>())
+            .property("source", source)
+            .property("time", time)
+            .property("kind", event.kind.tag())
+            .property("message", event.message)
+            .build()
+    }
+
+    /// Whether this entry was logged by the given tab.
+    pub fn is_from(&self, origin: &impl IsA<Object>) -> bool {
+        self.origin()
+            .is_some_and(|o| o == *origin.upcast_ref::<Object>())
+    }
+
+    /// Formats the entry as a line of plain text.
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} [{}] [{}] {}",
+            self.time(),
+            self.source(),
+            self.kind(),
+            self.message()
+        )
+    }
+}
+
+impl Default for ConsoleEntry {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{TraceEvent, TraceKind};
+
+    use super::ConsoleEntry;
+
+    #[test]
+    pub fn test_console_entry() {
+        let origin = glib::Object::new::<glib::Object>();
+        let other = glib::Object::new::<glib::Object>();
+        let event = TraceEvent {
+            kind: TraceKind::Header,
+            message: String::from("Accept: */*"),
+        };
+        let entry = ConsoleEntry::new(&origin, "request.cartero", event);
+        assert!(entry.is_from(&origin));
+        assert!(!entry.is_from(&other));
+        assert!(entry
+            .to_text()
+            .ends_with("[request.cartero] [header] Accept: */*"));
+    }
+}
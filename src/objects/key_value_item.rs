@@ -44,6 +44,12 @@ mod imp {
         #[property(get, set)]
         header_value: RefCell<String>,
         #[property(get, set)]
+        comment: RefCell<String>,
+        #[property(get, set)]
+        required: RefCell<bool>,
+        #[property(get, set)]
+        validation_rule: RefCell<String>,
+        #[property(get, set)]
         dirty: RefCell<bool>,
     }
 
@@ -95,6 +101,15 @@ impl KeyValueItem {
         self.connect_secret_notify(|item| {
             item.emit_by_name::<()>("changed", &[]);
         });
+        self.connect_comment_notify(|item| {
+            item.emit_by_name::<()>("changed", &[]);
+        });
+        self.connect_required_notify(|item| {
+            item.emit_by_name::<()>("changed", &[]);
+        });
+        self.connect_validation_rule_notify(|item| {
+            item.emit_by_name::<()>("changed", &[]);
+        });
     }
 
     pub fn new() -> Self {
@@ -122,6 +137,9 @@ impl From<KeyValue> for KeyValueItem {
         header.set_header_value(value.value.clone());
         header.set_active(value.active);
         header.set_secret(value.secret);
+        header.set_comment(value.comment.clone());
+        header.set_required(value.required);
+        header.set_validation_rule(value.validation_rule.clone());
         header
     }
 }
@@ -15,6 +15,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod console_entry;
 mod key_value_item;
 
+pub use console_entry::ConsoleEntry;
 pub use key_value_item::KeyValueItem;
@@ -0,0 +1,338 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope};
+
+use crate::{client::BoundRequest, entities::ResponseData};
+
+/// How many operations a script may run before it is stopped. Scripts run in
+/// the main thread, so an endless loop must not freeze the application.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The lines printed by a script, and the error that stopped it, if any.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScriptLog {
+    pub lines: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// The request as seen by a pre-request script, available as `request`.
+#[derive(Debug, Clone)]
+struct RequestContext {
+    url: String,
+    method: String,
+    headers: Map,
+    body: String,
+}
+
+impl RequestContext {
+    fn set_header(&mut self, name: &str, value: &str) {
+        self.remove_header(name);
+        self.headers.insert(name.into(), value.into());
+    }
+
+    fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|key, _| !key.eq_ignore_ascii_case(name));
+    }
+}
+
+/// The response as seen by a post-response script, available as `response`.
+#[derive(Debug, Clone)]
+struct ResponseContext {
+    status: i64,
+    headers: Map,
+    body: String,
+}
+
+impl ResponseContext {
+    fn json(&mut self) -> Result<Dynamic, Box<EvalAltResult>> {
+        let value: serde_json::Value = serde_json::from_str(&self.body)
+            .map_err(|e| -> Box<EvalAltResult> { e.to_string().into() })?;
+        rhai::serde::to_dynamic(value)
+    }
+}
+
+/// Creates an engine whose printed lines are collected in the given list.
+fn engine(lines: &Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let sink = lines.clone();
+    engine.on_print(move |text| sink.borrow_mut().push(text.to_string()));
+    let sink = lines.clone();
+    engine.on_debug(move |text, _, _| sink.borrow_mut().push(text.to_string()));
+
+    engine
+        .register_type_with_name::<RequestContext>("Request")
+        .register_get_set(
+            "url",
+            |r: &mut RequestContext| r.url.clone(),
+            |r: &mut RequestContext, url: String| r.url = url,
+        )
+        .register_get("method", |r: &mut RequestContext| r.method.clone())
+        .register_get_set(
+            "headers",
+            |r: &mut RequestContext| r.headers.clone(),
+            |r: &mut RequestContext, headers: Map| r.headers = headers,
+        )
+        .register_get_set(
+            "body",
+            |r: &mut RequestContext| r.body.clone(),
+            |r: &mut RequestContext, body: String| r.body = body,
+        )
+        .register_fn("set_header", RequestContext::set_header)
+        .register_fn("remove_header", RequestContext::remove_header);
+
+    engine
+        .register_type_with_name::<ResponseContext>("Response")
+        .register_get("status", |r: &mut ResponseContext| r.status)
+        .register_get("headers", |r: &mut ResponseContext| r.headers.clone())
+        .register_get("body", |r: &mut ResponseContext| r.body.clone())
+        .register_fn("json", ResponseContext::json);
+
+    engine
+}
+
+fn variables_map(variables: &HashMap<String, String>) -> Map {
+    variables
+        .iter()
+        .map(|(name, value)| (name.as_str().into(), value.clone().into()))
+        .collect()
+}
+
+fn take_log(lines: Rc<RefCell<Vec<String>>>, error: Option<String>) -> ScriptLog {
+    ScriptLog {
+        lines: lines.take(),
+        error,
+    }
+}
+
+/// Runs a script that may change the request before it is sent.
+///
+/// The script sees the request as `request` and the variables of the endpoint
+/// as `variables`. The request is left untouched if the script fails.
+pub fn run_pre_request_script(
+    script: &str,
+    request: &mut BoundRequest,
+    variables: &HashMap<String, String>,
+) -> ScriptLog {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(&lines);
+
+    let method = request.method.as_str();
+    let body = request
+        .body
+        .as_ref()
+        .map(|body| String::from_utf8_lossy(body).to_string())
+        .unwrap_or_default();
+    let context = RequestContext {
+        url: request.url.clone(),
+        method: method.to_string(),
+        headers: variables_map(&request.headers),
+        body: body.clone(),
+    };
+    let mut scope = Scope::new();
+    scope.push("request", context);
+    scope.push("variables", variables_map(variables));
+
+    if let Err(e) = engine.run_with_scope(&mut scope, script) {
+        return take_log(lines, Some(e.to_string()));
+    }
+    if let Some(context) = scope.get_value::<RequestContext>("request") {
+        request.url = context.url;
+        request.headers = context
+            .headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        // The body is only replaced if the script changed it, to keep binary bodies intact.
+        if context.body != body {
+            request.body = Some(context.body.into_bytes()).filter(|b| !b.is_empty());
+        }
+    }
+    take_log(lines, None)
+}
+
+/// Runs a script after a response is received, which may extract values from it.
+///
+/// The script sees the response as `response` and the variables of the endpoint
+/// as `variables`. Returns the variables that the script added or changed.
+pub fn run_post_response_script(
+    script: &str,
+    response: &ResponseData,
+    variables: &HashMap<String, String>,
+) -> (ScriptLog, Vec<(String, String)>) {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(&lines);
+
+    let mut headers = Map::new();
+    for header in response.headers.iter() {
+        let key = header.name.to_lowercase();
+        let value = match headers.get(key.as_str()) {
+            Some(previous) => format!("{previous}, {}", header.value),
+            None => header.value.clone(),
+        };
+        headers.insert(key.into(), value.into());
+    }
+    let context = ResponseContext {
+        status: response.status_code as i64,
+        headers,
+        body: String::from_utf8_lossy(&response.body).to_string(),
+    };
+    let mut scope = Scope::new();
+    scope.push("response", context);
+    scope.push("variables", variables_map(variables));
+
+    if let Err(e) = engine.run_with_scope(&mut scope, script) {
+        return (take_log(lines, Some(e.to_string())), Vec::new());
+    }
+    let changes = scope
+        .get_value::<Map>("variables")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .filter(|(name, value)| variables.get(name) != Some(value))
+        .collect();
+    (take_log(lines, None), changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::{KeyValue, KeyValueTable, RequestMethod};
+
+    use super::*;
+
+    fn request() -> BoundRequest {
+        BoundRequest {
+            url: String::from("https://example.com/api"),
+            method: RequestMethod::Post,
+            headers: HashMap::from([(String::from("Accept"), String::from("*/*"))]),
+            body: Some(Vec::from("{}")),
+            ..Default::default()
+        }
+    }
+
+    fn response(body: &str) -> ResponseData {
+        ResponseData {
+            status_code: 200,
+            duration: 0,
+            size: body.len(),
+            transfer_size: body.len(),
+            headers: KeyValueTable::new(&[KeyValue::from(("Content-Type", "application/json"))]),
+            body: Vec::from(body),
+            decode_warning: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_pre_request_script_changes_request() {
+        let mut request = request();
+        let variables = HashMap::from([(String::from("TOKEN"), String::from("1234"))]);
+        let script = r#"
+            request.url += "?page=2";
+            request.set_header("accept", "application/json");
+            request.set_header("Authorization", "Bearer " + variables.TOKEN);
+            request.body = "{\"page\": 2}";
+            print(request.method);
+        "#;
+        let log = run_pre_request_script(script, &mut request, &variables);
+
+        assert_eq!(log.error, None);
+        assert_eq!(log.lines, vec![String::from("POST")]);
+        assert_eq!(request.url, "https://example.com/api?page=2");
+        assert_eq!(request.headers.len(), 2);
+        assert_eq!(request.headers["accept"], "application/json");
+        assert_eq!(request.headers["Authorization"], "Bearer 1234");
+        assert_eq!(request.body, Some(Vec::from("{\"page\": 2}")));
+    }
+
+    #[test]
+    fn test_pre_request_script_keeps_binary_body() {
+        let mut request = request();
+        request.body = Some(vec![0xff, 0x00, 0xfe]);
+        let log = run_pre_request_script(
+            "request.remove_header(\"ACCEPT\");",
+            &mut request,
+            &HashMap::new(),
+        );
+
+        assert_eq!(log.error, None);
+        assert!(request.headers.is_empty());
+        assert_eq!(request.body, Some(vec![0xff, 0x00, 0xfe]));
+    }
+
+    #[test]
+    fn test_failed_script_leaves_request_untouched() {
+        let mut request = request();
+        let script = "print(\"before\"); request.url = \"x\"; throw \"broken\";";
+        let log = run_pre_request_script(script, &mut request, &HashMap::new());
+
+        assert_eq!(log.lines, vec![String::from("before")]);
+        assert!(log.error.unwrap().contains("broken"));
+        assert_eq!(request.url, "https://example.com/api");
+    }
+
+    #[test]
+    fn test_endless_script_is_stopped() {
+        let mut request = request();
+        let log = run_pre_request_script("loop {}", &mut request, &HashMap::new());
+        assert!(log.error.is_some());
+    }
+
+    #[test]
+    fn test_post_response_script_sets_variables() {
+        let variables = HashMap::from([
+            (String::from("TOKEN"), String::from("old")),
+            (String::from("USER"), String::from("john")),
+        ]);
+        let script = r#"
+            if response.status == 200 && response.headers["content-type"] == "application/json" {
+                let data = response.json();
+                variables.TOKEN = data.token;
+                variables.EXPIRES = data.expires;
+            }
+        "#;
+        let (log, changes) = run_post_response_script(
+            script,
+            &response("{\"token\": \"new\", \"expires\": 3600}"),
+            &variables,
+        );
+
+        assert_eq!(log.error, None);
+        let mut changes = changes;
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                (String::from("EXPIRES"), String::from("3600")),
+                (String::from("TOKEN"), String::from("new")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_post_response_script_invalid_json() {
+        let (log, changes) =
+            run_post_response_script("response.json()", &response("<html>"), &HashMap::new());
+        assert!(log.error.is_some());
+        assert!(changes.is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::entities::EndpointData;
+use crate::error::CarteroError;
+use crate::file::{parse_toml, store_toml};
+
+/// The extension of the files that hold a template.
+const TEMPLATE_EXTENSION: &str = "cartero";
+
+/// Whether a name can be used for a template, since it becomes a file name.
+pub fn is_valid_template_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+/// The endpoints saved as a starting point for new requests.
+///
+/// Every template is a request file stored in a directory, so they use the
+/// same format as the requests saved by the user.
+pub struct TemplateStore {
+    dir: PathBuf,
+}
+
+impl Default for TemplateStore {
+    /// The templates directory inside the data directory of the user.
+    fn default() -> Self {
+        Self::new(glib::user_data_dir().join("cartero").join("templates"))
+    }
+}
+
+impl TemplateStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, CarteroError> {
+        if !is_valid_template_name(name) {
+            return Err(CarteroError::InvalidTemplateName);
+        }
+        let file = format!("{}.{TEMPLATE_EXTENSION}", name.trim());
+        Ok(self.dir.join(file))
+    }
+
+    /// The names of every template, sorted alphabetically.
+    pub fn list(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+            .collect();
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.path(name).is_ok_and(|path| path.exists())
+    }
+
+    /// Saves an endpoint as a template, replacing the template with the same name.
+    pub fn save(&self, name: &str, endpoint: &EndpointData) -> Result<(), CarteroError> {
+        let path = self.path(name)?;
+        let contents = store_toml(endpoint)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Reads a template. The endpoint is read from disk every time, so the
+    /// request built out of it never shares anything with the template.
+    pub fn load(&self, name: &str) -> Result<EndpointData, CarteroError> {
+        let contents = fs::read_to_string(self.path(name)?)?;
+        parse_toml(&contents)
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<(), CarteroError> {
+        let from = self.path(name)?;
+        let to = self.path(new_name)?;
+        if from == to {
+            return Ok(());
+        }
+        if to.exists() {
+            return Err(CarteroError::TemplateExists);
+        }
+        fs::rename(from, to)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), CarteroError> {
+        match fs::remove_file(self.path(name)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::{KeyValue, KeyValueTable, RequestMethod};
+
+    use super::*;
+
+    fn store(test: &str) -> TemplateStore {
+        let dir = std::env::temp_dir().join(format!("cartero-test-templates-{test}"));
+        let _ = fs::remove_dir_all(&dir);
+        TemplateStore::new(dir)
+    }
+
+    fn endpoint() -> EndpointData {
+        EndpointData {
+            url: String::from("https://api.example.com/users"),
+            method: RequestMethod::Post,
+            headers: KeyValueTable::new(&[KeyValue::from(("Authorization", "Bearer {{token}}"))]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_valid_template_names() {
+        assert!(is_valid_template_name("Create user"));
+        assert!(is_valid_template_name("  v2 (staging)  "));
+        assert!(!is_valid_template_name(""));
+        assert!(!is_valid_template_name("   "));
+        assert!(!is_valid_template_name(".hidden"));
+        assert!(!is_valid_template_name("users/create"));
+        assert!(!is_valid_template_name("users\\create"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let store = store("save-and-load");
+        assert!(store.list().is_empty());
+
+        store.save("Create user", &endpoint()).unwrap();
+        assert!(store.exists("Create user"));
+        assert_eq!(store.list(), vec!["Create user"]);
+
+        let loaded = store.load("Create user").unwrap();
+        assert_eq!(loaded.url, "https://api.example.com/users");
+        assert_eq!(loaded.method, RequestMethod::Post);
+        assert_eq!(
+            loaded.headers.header("Authorization"),
+            Some(vec!["Bearer {{token}}"])
+        );
+    }
+
+    #[test]
+    fn test_list_is_sorted() {
+        let store = store("list-is-sorted");
+        for name in ["beta", "Alpha", "gamma"] {
+            store.save(name, &endpoint()).unwrap();
+        }
+        fs::write(store.dir.join("notes.txt"), "not a template").unwrap();
+        assert_eq!(store.list(), vec!["Alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn test_rename_and_delete() {
+        let store = store("rename-and-delete");
+        store.save("one", &endpoint()).unwrap();
+        store.save("two", &endpoint()).unwrap();
+
+        assert!(matches!(
+            store.rename("one", "two"),
+            Err(CarteroError::TemplateExists)
+        ));
+        assert!(matches!(
+            store.rename("one", "a/b"),
+            Err(CarteroError::InvalidTemplateName)
+        ));
+        store.rename("one", "three").unwrap();
+        assert_eq!(store.list(), vec!["three", "two"]);
+
+        store.delete("three").unwrap();
+        store.delete("three").unwrap();
+        assert_eq!(store.list(), vec!["two"]);
+    }
+}
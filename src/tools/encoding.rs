@@ -0,0 +1,128 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::prelude::*;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::error::CarteroError;
+
+/// Every character but the unreserved ones of RFC 3986, so that the result
+/// can be used as any part of an URL.
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Decoding accepts text with and without the trailing padding.
+const DECODE_CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Base64Alphabet {
+    /// Uses `+` and `/`, and pads the result with `=`.
+    #[default]
+    Standard,
+    /// Uses `-` and `_` instead, without padding, as done in tokens and URLs.
+    UrlSafe,
+}
+
+pub fn base64_encode(text: &str, alphabet: Base64Alphabet) -> String {
+    match alphabet {
+        Base64Alphabet::Standard => BASE64_STANDARD.encode(text),
+        Base64Alphabet::UrlSafe => BASE64_URL_SAFE_NO_PAD.encode(text),
+    }
+}
+
+/// Decodes Base64 text. Whitespace is ignored, as long values are usually wrapped.
+pub fn base64_decode(text: &str, alphabet: Base64Alphabet) -> Result<String, CarteroError> {
+    let engine = match alphabet {
+        Base64Alphabet::Standard => GeneralPurpose::new(&base64::alphabet::STANDARD, DECODE_CONFIG),
+        Base64Alphabet::UrlSafe => GeneralPurpose::new(&base64::alphabet::URL_SAFE, DECODE_CONFIG),
+    };
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = engine
+        .decode(text)
+        .map_err(|e| CarteroError::DecodeError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|_| {
+        CarteroError::DecodeError(String::from("the decoded data is not valid UTF-8 text"))
+    })
+}
+
+pub fn url_encode(text: &str) -> String {
+    utf8_percent_encode(text, URL_COMPONENT).to_string()
+}
+
+/// Decodes percent-encoded text. A `+` is taken as a space, as done in query strings.
+pub fn url_decode(text: &str) -> Result<String, CarteroError> {
+    let text = text.replace('+', " ");
+    percent_decode_str(&text)
+        .decode_utf8()
+        .map(|text| text.to_string())
+        .map_err(|_| {
+            CarteroError::DecodeError(String::from("the decoded data is not valid UTF-8 text"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(
+            base64_encode("user:pass", Base64Alphabet::Standard),
+            "dXNlcjpwYXNz"
+        );
+        assert_eq!(base64_encode("¿?>", Base64Alphabet::Standard), "wr8/Pg==");
+        assert_eq!(base64_encode("¿?>", Base64Alphabet::UrlSafe), "wr8_Pg");
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(
+            base64_decode("wr8/Pg==", Base64Alphabet::Standard).unwrap(),
+            "¿?>"
+        );
+        assert_eq!(
+            base64_decode("wr8/\nPg", Base64Alphabet::Standard).unwrap(),
+            "¿?>"
+        );
+        assert_eq!(
+            base64_decode("wr8_Pg==", Base64Alphabet::UrlSafe).unwrap(),
+            "¿?>"
+        );
+        assert!(base64_decode("wr8_Pg", Base64Alphabet::Standard).is_err());
+        assert!(base64_decode("/w==", Base64Alphabet::Standard).is_err());
+    }
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(url_encode("a b&c=d/é~"), "a%20b%26c%3Dd%2F%C3%A9~");
+        assert_eq!(url_encode("safe-._~"), "safe-._~");
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("a%20b%26c%3Dd%2F%C3%A9~").unwrap(), "a b&c=d/é~");
+        assert_eq!(url_decode("a+b%2B").unwrap(), "a b+");
+        assert_eq!(url_decode("100%").unwrap(), "100%");
+        assert!(url_decode("%FF").is_err());
+    }
+}
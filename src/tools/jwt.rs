@@ -0,0 +1,214 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use base64::prelude::*;
+use serde_json::Value;
+
+use crate::entities::ResponseData;
+use crate::error::CarteroError;
+
+/// The fields of a JSON body that usually hold a token.
+const TOKEN_FIELDS: [&str; 2] = ["access_token", "token"];
+
+/// The parts of a JSON Web Token. The signature is not verified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jwt {
+    pub token: String,
+    pub header: Value,
+    pub payload: Value,
+    /// The signature as found in the token, still encoded in Base64.
+    pub signature: String,
+}
+
+impl Jwt {
+    /// The Unix timestamp given in the `exp` claim, if any.
+    pub fn expires_at(&self) -> Option<i64> {
+        self.payload
+            .get("exp")
+            .and_then(Value::as_f64)
+            .map(|exp| exp as i64)
+    }
+
+    /// Whether the token had already expired at the given Unix timestamp.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at().is_some_and(|exp| exp < now)
+    }
+}
+
+/// Whether the text has the shape of a JWT: three Base64URL parts joined by dots,
+/// where only the signature can be empty.
+pub fn looks_like_jwt(text: &str) -> bool {
+    let parts: Vec<&str> = text.split('.').collect();
+    let is_base64url = |part: &str| {
+        part.bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+    };
+    parts.len() == 3
+        && !parts[0].is_empty()
+        && !parts[1].is_empty()
+        && parts.iter().all(|part| is_base64url(part))
+}
+
+fn decode_part(part: &str, name: &str) -> Result<Value, CarteroError> {
+    let error = |e: String| CarteroError::DecodeError(format!("{name}: {e}"));
+    let bytes = BASE64_URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| error(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| error(e.to_string()))
+}
+
+/// Splits a token into its parts and decodes its header and payload.
+pub fn decode_jwt(token: &str) -> Result<Jwt, CarteroError> {
+    let token = token.trim();
+    if !looks_like_jwt(token) {
+        return Err(CarteroError::DecodeError(String::from(
+            "the token is not made of three parts separated by dots",
+        )));
+    }
+    let mut parts = token.split('.');
+    let header = decode_part(parts.next().unwrap_or_default(), "header")?;
+    let payload = decode_part(parts.next().unwrap_or_default(), "payload")?;
+    let signature = parts.next().unwrap_or_default().to_string();
+    Ok(Jwt {
+        token: token.to_string(),
+        header,
+        payload,
+        signature,
+    })
+}
+
+fn find_in_json(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) => {
+            let direct = TOKEN_FIELDS.iter().find_map(|field| match map.get(*field) {
+                Some(Value::String(token)) if looks_like_jwt(token) => Some(token.clone()),
+                _ => None,
+            });
+            direct.or_else(|| map.values().find_map(find_in_json))
+        }
+        Value::Array(values) => values.iter().find_map(find_in_json),
+        _ => None,
+    }
+}
+
+/// Looks for a token in the Authorization header of a response, and then in
+/// the `access_token` or `token` fields of its body.
+pub fn find_jwt(response: &ResponseData) -> Option<Jwt> {
+    let from_header = response
+        .headers
+        .header("authorization")
+        .into_iter()
+        .flatten()
+        .map(|value| {
+            let value = value.trim();
+            match value.split_once(' ') {
+                Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => token.trim(),
+                _ => value,
+            }
+        })
+        .find(|token| looks_like_jwt(token))
+        .map(str::to_string);
+    let token = from_header.or_else(|| {
+        serde_json::from_slice::<Value>(&response.body)
+            .ok()
+            .and_then(|body| find_in_json(&body))
+    })?;
+    decode_jwt(&token).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::entities::{KeyValue, KeyValueTable};
+
+    use super::*;
+
+    fn token(header: &Value, payload: &Value) -> String {
+        format!(
+            "{}.{}.c2lnbmF0dXJl",
+            BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(payload.to_string()),
+        )
+    }
+
+    fn response(headers: &[(&str, &str)], body: &str) -> ResponseData {
+        let headers: Vec<KeyValue> = headers.iter().map(|&pair| pair.into()).collect();
+        ResponseData {
+            status_code: 200,
+            duration: 0,
+            size: body.len(),
+            transfer_size: body.len(),
+            headers: KeyValueTable::new(&headers),
+            body: Vec::from(body),
+            decode_warning: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_looks_like_jwt() {
+        assert!(looks_like_jwt("eyJh.eyJz.c2ln"));
+        assert!(looks_like_jwt("eyJh.eyJz."));
+        assert!(!looks_like_jwt("eyJh.eyJz"));
+        assert!(!looks_like_jwt(".eyJz.c2ln"));
+        assert!(!looks_like_jwt("eyJh.eyJz.c2ln.more"));
+        assert!(!looks_like_jwt("eyJh.ey/z.c2ln"));
+    }
+
+    #[test]
+    fn test_decode_jwt() {
+        let header = json!({"alg": "HS256", "typ": "JWT"});
+        let payload = json!({"sub": "1234", "exp": 1700000000});
+        let jwt = decode_jwt(&token(&header, &payload)).unwrap();
+        assert_eq!(jwt.header, header);
+        assert_eq!(jwt.payload, payload);
+        assert_eq!(jwt.signature, "c2lnbmF0dXJl");
+        assert_eq!(jwt.expires_at(), Some(1700000000));
+        assert!(jwt.is_expired_at(1700000001));
+        assert!(!jwt.is_expired_at(1699999999));
+
+        assert!(decode_jwt("not-a-token").is_err());
+        assert!(decode_jwt("bm90IGpzb24.eyJzIjoxfQ.").is_err());
+    }
+
+    #[test]
+    fn test_no_exp_never_expires() {
+        let jwt = decode_jwt(&token(&json!({"alg": "none"}), &json!({"sub": "a"}))).unwrap();
+        assert_eq!(jwt.expires_at(), None);
+        assert!(!jwt.is_expired_at(i64::MAX));
+    }
+
+    #[test]
+    fn test_find_jwt_in_header() {
+        let token = token(&json!({"alg": "none"}), &json!({"sub": "header"}));
+        let bearer = format!("Bearer {token}");
+        let found = find_jwt(&response(&[("Authorization", &bearer)], ""));
+        assert_eq!(found.unwrap().token, token);
+    }
+
+    #[test]
+    fn test_find_jwt_in_body() {
+        let token = token(&json!({"alg": "none"}), &json!({"sub": "body"}));
+        let body = json!({"data": {"token_type": "bearer", "access_token": token}});
+        let found = find_jwt(&response(&[], &body.to_string()));
+        assert_eq!(found.unwrap().token, token);
+
+        let found = find_jwt(&response(&[], r#"{"token": "opaque"}"#));
+        assert!(found.is_none());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use std::sync::OnceLock;
+
+use gettextrs::gettext;
+
+/// The decimal separator of the current locale.
+///
+/// Rust formatting always uses a dot, so the separator is taken from a size
+/// formatted by GLib, which follows the numeric conventions of the locale.
+fn decimal_separator() -> &'static str {
+    static SEPARATOR: OnceLock<String> = OnceLock::new();
+    SEPARATOR.get_or_init(|| {
+        let sample = glib::format_size(1500);
+        let separator: String = sample
+            .chars()
+            .skip_while(char::is_ascii_digit)
+            .take_while(|c| !c.is_ascii_digit())
+            .collect();
+        if separator.is_empty() {
+            String::from(".")
+        } else {
+            separator
+        }
+    })
+}
+
+fn format_decimal_with(value: f64, decimals: usize, separator: &str) -> String {
+    format!("{value:.decimals$}").replace('.', separator)
+}
+
+/// Formats a number with the given amount of decimals, using the separator of the locale.
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    format_decimal_with(value, decimals, decimal_separator())
+}
+
+/// Formats an amount of bytes using binary units, such as KiB or MiB.
+pub fn format_bytes(count: usize) -> String {
+    glib::format_size_full(count as u64, glib::FormatSizeFlags::IEC_UNITS).to_string()
+}
+
+/// Formats a duration in milliseconds, switching to seconds past the first one.
+pub fn format_duration(millis: u128) -> String {
+    if millis < 1000 {
+        gettext("{count} ms").replace("{count}", &millis.to_string())
+    } else {
+        let seconds = format_decimal(millis as f64 / 1000.0, 2);
+        gettext("{count} s").replace("{count}", &seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_decimal_with() {
+        assert_eq!(format_decimal_with(1.5, 2, "."), "1.50");
+        assert_eq!(format_decimal_with(1.5, 2, ","), "1,50");
+        assert_eq!(format_decimal_with(12.0, 0, ","), "12");
+        assert_eq!(format_decimal_with(0.75, 1, "٫"), "0٫8");
+    }
+
+    #[test]
+    fn test_format_duration_in_milliseconds() {
+        assert_eq!(format_duration(0), "0 ms");
+        assert_eq!(format_duration(999), "999 ms");
+    }
+}
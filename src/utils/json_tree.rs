@@ -0,0 +1,572 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use serde_json::Value;
+
+/// Containers with more children than this show them in groups of consecutive children.
+pub const CHUNK_SIZE: usize = 100;
+
+/// A step in the way from the root of a document to one of its values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+
+impl JsonKind {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Object(_) => JsonKind::Object,
+            Value::Array(_) => JsonKind::Array,
+            Value::String(_) => JsonKind::String,
+            Value::Number(_) => JsonKind::Number,
+            Value::Bool(_) => JsonKind::Boolean,
+            Value::Null => JsonKind::Null,
+        }
+    }
+}
+
+/// A row of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonNode {
+    /// The value found at the given path.
+    Value { path: Vec<PathSegment> },
+
+    /// The children between start (included) and end (excluded) of the
+    /// container found at the given path, when it has too many to list them.
+    Chunk {
+        path: Vec<PathSegment>,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl JsonNode {
+    /// The path of the value, or of the container the chunk belongs to.
+    pub fn path(&self) -> &[PathSegment] {
+        match self {
+            JsonNode::Value { path } => path,
+            JsonNode::Chunk { path, .. } => path,
+        }
+    }
+
+    /// The path of the container and the range of its children under this node.
+    fn span(&self) -> (&[PathSegment], usize, usize) {
+        match self {
+            JsonNode::Value { path } => (path, 0, usize::MAX),
+            JsonNode::Chunk { path, start, end } => (path, *start, *end),
+        }
+    }
+}
+
+/// How many children go in each chunk of a container with the given number of them.
+///
+/// Chunks are nested, so that no row ever has more than CHUNK_SIZE children:
+/// an array with 100 000 items is split in 10 chunks of 10 000 items, each one
+/// split in 100 chunks of 100 items.
+fn chunk_span(len: usize) -> usize {
+    let mut span = CHUNK_SIZE;
+    while len.div_ceil(span) > CHUNK_SIZE {
+        span *= CHUNK_SIZE;
+    }
+    span
+}
+
+/// A parsed JSON document, presented as a tree whose rows are built on demand.
+#[derive(Debug)]
+pub struct JsonTree {
+    root: Value,
+}
+
+impl JsonTree {
+    pub fn new(root: Value) -> Self {
+        Self { root }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text).map(Self::new)
+    }
+
+    pub fn value(&self, path: &[PathSegment]) -> Option<&Value> {
+        path.iter()
+            .try_fold(&self.root, |value, segment| match segment {
+                PathSegment::Key(key) => value.get(key),
+                PathSegment::Index(index) => value.get(index),
+            })
+    }
+
+    /// The rows at the top of the tree: the children of the root, or the
+    /// root itself if it is not a container.
+    pub fn roots(&self, search: Option<&JsonSearch>) -> Vec<JsonNode> {
+        if search.is_some_and(JsonSearch::is_empty) {
+            return Vec::new();
+        }
+        let root = JsonNode::Value { path: Vec::new() };
+        match self.root {
+            Value::Object(_) | Value::Array(_) => self.children(&root, search),
+            _ => vec![root],
+        }
+    }
+
+    /// The rows under a row, which are empty for values that are not containers.
+    ///
+    /// While searching, only the rows that lead to a match are listed. The
+    /// children of a match that does not contain other matches are all listed.
+    pub fn children(&self, node: &JsonNode, search: Option<&JsonSearch>) -> Vec<JsonNode> {
+        let (path, start, end) = match node {
+            JsonNode::Value { path } => match self.value(path) {
+                Some(Value::Object(map)) => (path, 0, map.len()),
+                Some(Value::Array(items)) => (path, 0, items.len()),
+                _ => return Vec::new(),
+            },
+            JsonNode::Chunk { path, start, end } => (path, *start, *end),
+        };
+        let hits = search.and_then(|search| search.hits.get(path));
+        let visible = |from: usize, to: usize| match hits {
+            Some(hits) => hits.range(from..to).next().is_some(),
+            None => true,
+        };
+
+        if end - start > CHUNK_SIZE {
+            let span = chunk_span(end - start);
+            (start..end)
+                .step_by(span)
+                .map(|from| (from, (from + span).min(end)))
+                .filter(|(from, to)| visible(*from, *to))
+                .map(|(from, to)| JsonNode::Chunk {
+                    path: path.clone(),
+                    start: from,
+                    end: to,
+                })
+                .collect()
+        } else {
+            self.segments(path, start, end)
+                .into_iter()
+                .zip(start..end)
+                .filter(|(_, position)| visible(*position, position + 1))
+                .map(|(segment, _)| {
+                    let mut path = path.clone();
+                    path.push(segment);
+                    JsonNode::Value { path }
+                })
+                .collect()
+        }
+    }
+
+    /// The segments that lead to the children between start and end of a container.
+    fn segments(&self, path: &[PathSegment], start: usize, end: usize) -> Vec<PathSegment> {
+        match self.value(path) {
+            Some(Value::Object(map)) => map
+                .keys()
+                .skip(start)
+                .take(end - start)
+                .map(|key| PathSegment::Key(key.clone()))
+                .collect(),
+            Some(Value::Array(_)) => (start..end).map(PathSegment::Index).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The value at the given path, rendered back as indented JSON.
+    pub fn subtree_json(&self, path: &[PathSegment]) -> Option<String> {
+        let value = self.value(path)?;
+        serde_json::to_string_pretty(value).ok()
+    }
+}
+
+/// Whether a key can be written after a dot in a JSON path.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Writes a path as a JSON path expression, such as `$.users[0].name`.
+pub fn json_path(path: &[PathSegment]) -> String {
+    let mut text = String::from("$");
+    for segment in path {
+        let _ = match segment {
+            PathSegment::Index(index) => write!(text, "[{index}]"),
+            PathSegment::Key(key) if is_identifier(key) => write!(text, ".{key}"),
+            PathSegment::Key(key) => write!(text, "[{}]", Value::String(key.clone())),
+        };
+    }
+    text
+}
+
+/// Reads a JSON path expression that points to a single value, such as the
+/// ones written by [`json_path`]. Returns `None` for expressions that can
+/// select many values, such as wildcards, slices, filters or recursive descent.
+pub fn parse_json_path(expression: &str) -> Option<Vec<PathSegment>> {
+    let mut rest = expression.trim().strip_prefix('$')?;
+    let mut path = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let key = &after[..end];
+            if !is_identifier(key) {
+                return None;
+            }
+            path.push(PathSegment::Key(key.into()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (segment, end) = if after.starts_with('"') {
+                let mut strings = serde_json::Deserializer::from_str(after).into_iter::<String>();
+                let key = strings.next()?.ok()?;
+                (PathSegment::Key(key), strings.byte_offset())
+            } else if let Some(quoted) = after.strip_prefix('\'') {
+                let end = quoted.find('\'')?;
+                (PathSegment::Key(quoted[..end].into()), end + 2)
+            } else {
+                let end = after.find(']')?;
+                let index = after[..end].trim().parse().ok()?;
+                (PathSegment::Index(index), end)
+            };
+            path.push(segment);
+            rest = after[end..].strip_prefix(']')?;
+        } else {
+            return None;
+        }
+    }
+    Some(path)
+}
+
+/// The text of a value that is not a container, as it is shown in the tree.
+pub fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(_) | Value::Array(_) => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// The values of a document that match a filter, by key name or by value.
+#[derive(Debug, Default)]
+pub struct JsonSearch {
+    /// For the path of each container, the positions of its children that
+    /// either match or contain a match.
+    hits: HashMap<Vec<PathSegment>, BTreeSet<usize>>,
+
+    /// Whether anything in the document matches.
+    found: bool,
+}
+
+impl JsonSearch {
+    /// Finds the matches of a query, ignoring case.
+    pub fn new(tree: &JsonTree, query: &str) -> Self {
+        let mut search = Self::default();
+        let query = query.to_lowercase();
+        search.found = search.collect(&tree.root, &mut Vec::new(), &query);
+        search
+    }
+
+    /// Records the children of a value that match or contain a match.
+    /// Returns whether there is any match within the value.
+    fn collect(&mut self, value: &Value, path: &mut Vec<PathSegment>, query: &str) -> bool {
+        let children: Box<dyn Iterator<Item = (PathSegment, &Value)>> = match value {
+            Value::Object(map) => Box::new(
+                map.iter()
+                    .map(|(key, value)| (PathSegment::Key(key.clone()), value)),
+            ),
+            Value::Array(items) => Box::new(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| (PathSegment::Index(index), value)),
+            ),
+            value => {
+                let text = match value {
+                    Value::String(text) => text.to_lowercase(),
+                    value => value.to_string(),
+                };
+                return text.contains(query);
+            }
+        };
+
+        let mut found = false;
+        for (position, (segment, child)) in children.enumerate() {
+            let key_matches = match &segment {
+                PathSegment::Key(key) => key.to_lowercase().contains(query),
+                PathSegment::Index(_) => false,
+            };
+            path.push(segment);
+            let below = self.collect(child, path, query);
+            path.pop();
+            if key_matches || below {
+                self.hits.entry(path.clone()).or_default().insert(position);
+                found = true;
+            }
+        }
+        found
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.found
+    }
+
+    /// Whether a row contains matches, so that it has to be expanded to show them.
+    pub fn leads_to_match(&self, node: &JsonNode) -> bool {
+        let (path, start, end) = node.span();
+        self.hits
+            .get(path)
+            .is_some_and(|hits| hits.range(start..end).next().is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn key(name: &str) -> PathSegment {
+        PathSegment::Key(name.to_string())
+    }
+
+    fn value_node(path: &[PathSegment]) -> JsonNode {
+        JsonNode::Value {
+            path: path.to_vec(),
+        }
+    }
+
+    fn large_array() -> JsonTree {
+        JsonTree::new(Value::Array((0..100_000).map(Value::from).collect()))
+    }
+
+    #[test]
+    fn test_small_containers_are_not_chunked() {
+        let tree = JsonTree::new(json!({"name": "Cartero", "tags": ["gtk", "http"]}));
+        assert_eq!(
+            tree.roots(None),
+            vec![value_node(&[key("name")]), value_node(&[key("tags")])]
+        );
+        assert_eq!(
+            tree.children(&value_node(&[key("tags")]), None),
+            vec![
+                value_node(&[key("tags"), PathSegment::Index(0)]),
+                value_node(&[key("tags"), PathSegment::Index(1)]),
+            ]
+        );
+        assert!(tree.children(&value_node(&[key("name")]), None).is_empty());
+    }
+
+    #[test]
+    fn test_scalar_root() {
+        let tree = JsonTree::new(json!("plain"));
+        assert_eq!(tree.roots(None), vec![value_node(&[])]);
+    }
+
+    #[test]
+    fn test_large_array_is_chunked() {
+        let tree = large_array();
+        let roots = tree.roots(None);
+        assert_eq!(roots.len(), 10);
+        assert_eq!(
+            roots[1],
+            JsonNode::Chunk {
+                path: vec![],
+                start: 10_000,
+                end: 20_000
+            }
+        );
+
+        let chunks = tree.children(&roots[1], None);
+        assert_eq!(chunks.len(), 100);
+        assert_eq!(
+            chunks[1],
+            JsonNode::Chunk {
+                path: vec![],
+                start: 10_100,
+                end: 10_200
+            }
+        );
+
+        let items = tree.children(&chunks[1], None);
+        assert_eq!(items.len(), 100);
+        assert_eq!(items[0], value_node(&[PathSegment::Index(10_100)]));
+        assert_eq!(items[99], value_node(&[PathSegment::Index(10_199)]));
+        assert_eq!(tree.value(items[99].path()), Some(&json!(10_199)));
+    }
+
+    #[test]
+    fn test_uneven_chunks() {
+        let tree = JsonTree::new(Value::Array((0..150).map(Value::from).collect()));
+        let roots = tree.roots(None);
+        assert_eq!(
+            roots,
+            vec![
+                JsonNode::Chunk {
+                    path: vec![],
+                    start: 0,
+                    end: 100
+                },
+                JsonNode::Chunk {
+                    path: vec![],
+                    start: 100,
+                    end: 150
+                },
+            ]
+        );
+        assert_eq!(tree.children(&roots[1], None).len(), 50);
+    }
+
+    #[test]
+    fn test_large_object_is_chunked() {
+        let map = (0..250)
+            .map(|i| (format!("key{i:03}"), Value::from(i)))
+            .collect();
+        let tree = JsonTree::new(Value::Object(map));
+        let roots = tree.roots(None);
+        assert_eq!(roots.len(), 3);
+        let items = tree.children(&roots[2], None);
+        assert_eq!(items.len(), 50);
+        assert_eq!(items[0], value_node(&[key("key200")]));
+    }
+
+    #[test]
+    fn test_search_by_key_and_value() {
+        let tree = JsonTree::new(json!({
+            "user": {"name": "Ana", "email": "ana@example.com"},
+            "tags": ["admin", "Example"],
+            "active": true
+        }));
+
+        let search = JsonSearch::new(&tree, "example");
+        assert_eq!(
+            tree.roots(Some(&search)),
+            vec![value_node(&[key("tags")]), value_node(&[key("user")])]
+        );
+        let user = value_node(&[key("user")]);
+        assert!(search.leads_to_match(&user));
+        assert_eq!(
+            tree.children(&user, Some(&search)),
+            vec![value_node(&[key("user"), key("email")])]
+        );
+
+        let search = JsonSearch::new(&tree, "USER");
+        assert_eq!(tree.roots(Some(&search)), vec![user.clone()]);
+        // The children of a match are all shown, but it does not have to be expanded.
+        assert!(!search.leads_to_match(&user));
+        assert_eq!(tree.children(&user, Some(&search)).len(), 2);
+
+        let search = JsonSearch::new(&tree, "nothing");
+        assert!(search.is_empty());
+        assert!(tree.roots(Some(&search)).is_empty());
+    }
+
+    #[test]
+    fn test_search_in_large_array() {
+        let tree = large_array();
+        let search = JsonSearch::new(&tree, "99999");
+
+        let roots = tree.roots(Some(&search));
+        assert_eq!(
+            roots,
+            vec![JsonNode::Chunk {
+                path: vec![],
+                start: 90_000,
+                end: 100_000
+            }]
+        );
+        assert!(search.leads_to_match(&roots[0]));
+
+        let chunks = tree.children(&roots[0], Some(&search));
+        assert_eq!(chunks.len(), 1);
+        let items = tree.children(&chunks[0], Some(&search));
+        assert_eq!(items, vec![value_node(&[PathSegment::Index(99_999)])]);
+    }
+
+    #[test]
+    fn test_json_path() {
+        assert_eq!(json_path(&[]), "$");
+        assert_eq!(
+            json_path(&[key("users"), PathSegment::Index(0), key("name")]),
+            "$.users[0].name"
+        );
+        assert_eq!(
+            json_path(&[key("content-type"), key("2fa"), key("_id")]),
+            "$[\"content-type\"][\"2fa\"]._id"
+        );
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(parse_json_path("$"), Some(vec![]));
+        assert_eq!(
+            parse_json_path("$.users[0].name"),
+            Some(vec![key("users"), PathSegment::Index(0), key("name")])
+        );
+        assert_eq!(
+            parse_json_path("$[\"content-type\"]['2fa']._id"),
+            Some(vec![key("content-type"), key("2fa"), key("_id")])
+        );
+        assert_eq!(
+            parse_json_path("$[\"a \\\"quoted\\\" ]key\"]"),
+            Some(vec![key("a \"quoted\" ]key")])
+        );
+        for path in [
+            "users",
+            "$..name",
+            "$.users[*]",
+            "$.users[-1]",
+            "$.users[0:2]",
+            "$.users[?(@.id)]",
+            "$.content-type",
+            "$.users[0",
+        ] {
+            assert_eq!(parse_json_path(path), None, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_parse_json_path_round_trip() {
+        let path = vec![key("data"), PathSegment::Index(3), key("x-y"), key("z")];
+        assert_eq!(parse_json_path(&json_path(&path)), Some(path));
+    }
+
+    #[test]
+    fn test_subtree_json() {
+        let tree = JsonTree::new(json!({"user": {"id": 1}}));
+        assert_eq!(
+            tree.subtree_json(&[key("user")]).unwrap(),
+            "{\n  \"id\": 1\n}"
+        );
+        assert_eq!(tree.subtree_json(&[key("missing")]), None);
+    }
+
+    #[test]
+    fn test_scalar_text() {
+        assert_eq!(scalar_text(&json!("a \"b\"")).unwrap(), "\"a \\\"b\\\"\"");
+        assert_eq!(scalar_text(&json!(1.5)).unwrap(), "1.5");
+        assert_eq!(scalar_text(&json!(null)).unwrap(), "null");
+        assert_eq!(scalar_text(&json!([])), None);
+    }
+}
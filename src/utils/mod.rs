@@ -15,6 +15,22 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod format;
+mod json_tree;
+mod pretty;
+mod shortcuts;
+mod undo;
+mod viewer;
+
+pub use format::*;
+pub use json_tree::{
+    json_path, parse_json_path, scalar_text, JsonKind, JsonNode, JsonSearch, JsonTree, PathSegment,
+};
+pub use pretty::{compact_json, equivalent_offset, pretty_json, pretty_xml};
+pub use shortcuts::CustomShortcuts;
+pub use undo::{Edit, KeyValueEdit, UndoStack};
+pub use viewer::*;
+
 use std::cell::RefCell;
 
 use glib::property::PropertySet;
@@ -0,0 +1,263 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+
+const INDENT: &str = "  ";
+
+/// Why a document could not be formatted, and where. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn at_offset(text: &str, offset: usize, message: String) -> Self {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        Self {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+fn newline(output: &mut String, depth: usize) {
+    output.push('\n');
+    output.push_str(&INDENT.repeat(depth));
+}
+
+fn validate_json(text: &str) -> Result<(), SyntaxError> {
+    match serde_json::from_str::<serde::de::IgnoredAny>(text) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            // The position is already given apart, so it is removed from the message.
+            let message = e.to_string();
+            let message = match message.rsplit_once(" at line ") {
+                Some((message, _)) => message.to_string(),
+                None => message,
+            };
+            Err(SyntaxError {
+                line: e.line(),
+                column: e.column(),
+                message,
+            })
+        }
+    }
+}
+
+/// Indents a JSON document.
+///
+/// The document is only validated by serde_json. The output is written from the
+/// original text, so that the order of the keys and the numbers are kept as is.
+pub fn pretty_json(text: &str) -> Result<String, SyntaxError> {
+    validate_json(text)?;
+
+    let mut output = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' | '[' => {
+                output.push(c);
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                match chars.peek() {
+                    Some('}' | ']') => output.push(chars.next().unwrap()),
+                    _ => {
+                        depth += 1;
+                        newline(&mut output, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                newline(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            c => output.push(c),
+        }
+    }
+    Ok(output)
+}
+
+/// Removes the whitespace between the tokens of a JSON document.
+///
+/// As with [`pretty_json`], the order of the keys and the numbers are kept as is.
+pub fn compact_json(text: &str) -> Result<String, SyntaxError> {
+    validate_json(text)?;
+
+    let mut output = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c.is_whitespace() {
+            continue;
+        }
+        output.push(c);
+    }
+    Ok(output)
+}
+
+/// Indents an XML document, dropping the whitespace between its elements.
+pub fn pretty_xml(text: &str) -> Result<String, SyntaxError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', INDENT.len());
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => event,
+            Err(e) => {
+                let offset = reader.error_position() as usize;
+                return Err(SyntaxError::at_offset(text, offset, e.to_string()));
+            }
+        };
+        if let Err(e) = writer.write_event(event) {
+            let offset = reader.buffer_position() as usize;
+            return Err(SyntaxError::at_offset(text, offset, e.to_string()));
+        }
+    }
+    Ok(String::from_utf8_lossy(&writer.into_inner()).to_string())
+}
+
+/// Finds the place in `new` that matches the character `offset` of `old`, where
+/// both texts only differ in their whitespace, in order to keep the cursor in place.
+pub fn equivalent_offset(old: &str, offset: usize, new: &str) -> usize {
+    let visible = old
+        .chars()
+        .take(offset)
+        .filter(|c| !c.is_whitespace())
+        .count();
+    if visible == 0 {
+        return 0;
+    }
+    let mut seen = 0;
+    for (position, c) in new.chars().enumerate() {
+        if !c.is_whitespace() {
+            seen += 1;
+            if seen == visible {
+                return position + 1;
+            }
+        }
+    }
+    new.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_json() {
+        let text = r#"{"b": [1, 2.50, {}], "a" :{"key": "va, {l}: \"ue\""},"c":[ ]}"#;
+        assert_eq!(
+            pretty_json(text).unwrap(),
+            "{\n  \"b\": [\n    1,\n    2.50,\n    {}\n  ],\n  \"a\": {\n    \"key\": \"va, {l}: \\\"ue\\\"\"\n  },\n  \"c\": []\n}"
+        );
+        assert_eq!(pretty_json("  42 ").unwrap(), "42");
+    }
+
+    #[test]
+    fn test_pretty_json_error() {
+        let error = pretty_json("{\n  \"a\": 1,\n  \"b\" 2\n}").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 7);
+        assert!(error.message.contains("expected `:`"));
+    }
+
+    #[test]
+    fn test_compact_json() {
+        let text = "{\n  \"b\": [1, 2.50, {}],\n  \"a\": \"va, \\\" {l} \"\n}\n";
+        assert_eq!(
+            compact_json(text).unwrap(),
+            "{\"b\":[1,2.50,{}],\"a\":\"va, \\\" {l} \"}"
+        );
+        assert_eq!(compact_json("{\"a\" 1}").unwrap_err().column, 6);
+    }
+
+    #[test]
+    fn test_pretty_xml() {
+        let text = "<?xml version=\"1.0\"?><root a=\"1\">  <item>text</item><empty/></root>";
+        assert_eq!(
+            pretty_xml(text).unwrap(),
+            "<?xml version=\"1.0\"?>\n<root a=\"1\">\n  <item>text</item>\n  <empty/>\n</root>"
+        );
+    }
+
+    #[test]
+    fn test_pretty_xml_error() {
+        let error = pretty_xml("<root>\n  <item></other>\n</root>").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("other"));
+    }
+
+    #[test]
+    fn test_equivalent_offset() {
+        let old = "{\"a\":[1,2]}";
+        let new = pretty_json(old).unwrap();
+        // Right after the 2, which is at the end of its line once indented.
+        assert_eq!(equivalent_offset(old, 9, &new), new.find('2').unwrap() + 1);
+        assert_eq!(equivalent_offset(old, 0, &new), 0);
+        assert_eq!(equivalent_offset(old, old.len(), &new), new.len());
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+/// Actions that were renamed, along with their current name, so that the shortcuts
+/// customized before the rename keep working.
+const RENAMED_ACTIONS: &[(&str, &str)] = &[("win.close-tabs-right", "win.close-tabs-to-right")];
+
+/// The keyboard shortcuts customized by the user, as stored in the settings.
+///
+/// Maps an action name, such as `win.save`, to an accelerator string. An empty
+/// accelerator means that the user has disabled the shortcut for that action.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CustomShortcuts(BTreeMap<String, String>);
+
+impl CustomShortcuts {
+    /// Parses the JSON object kept in the settings. Anything that is not a valid
+    /// map of strings is treated as if no shortcut had been customized. Shortcuts
+    /// stored under the old name of a renamed action are moved to the new name.
+    pub fn from_json(json: &str) -> Self {
+        let mut shortcuts: BTreeMap<String, String> =
+            serde_json::from_str(json).unwrap_or_default();
+        for (old, new) in RENAMED_ACTIONS {
+            if let Some(accel) = shortcuts.remove(*old) {
+                shortcuts.entry(new.to_string()).or_insert(accel);
+            }
+        }
+        Self(shortcuts)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).unwrap_or_else(|_| String::from("{}"))
+    }
+
+    pub fn get(&self, action: &str) -> Option<&str> {
+        self.0.get(action).map(String::as_str)
+    }
+
+    pub fn set(&mut self, action: &str, accel: &str) {
+        self.0.insert(action.to_string(), accel.to_string());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CustomShortcuts;
+
+    #[test]
+    fn test_custom_shortcuts_roundtrip() {
+        let mut shortcuts = CustomShortcuts::default();
+        shortcuts.set("win.save", "<Primary>g");
+        shortcuts.set("win.close", "");
+        let json = shortcuts.to_json();
+        assert_eq!(json, "{\"win.close\":\"\",\"win.save\":\"<Primary>g\"}");
+
+        let parsed = CustomShortcuts::from_json(&json);
+        assert_eq!(parsed, shortcuts);
+        assert_eq!(parsed.get("win.save"), Some("<Primary>g"));
+        assert_eq!(parsed.get("win.close"), Some(""));
+        assert_eq!(parsed.get("win.open"), None);
+    }
+
+    #[test]
+    fn test_custom_shortcuts_renamed_action() {
+        let parsed = CustomShortcuts::from_json("{\"win.close-tabs-right\":\"<Primary>k\"}");
+        assert_eq!(parsed.get("win.close-tabs-right"), None);
+        assert_eq!(parsed.get("win.close-tabs-to-right"), Some("<Primary>k"));
+
+        let parsed = CustomShortcuts::from_json(
+            "{\"win.close-tabs-right\":\"<Primary>k\",\"win.close-tabs-to-right\":\"\"}",
+        );
+        assert_eq!(parsed.get("win.close-tabs-to-right"), Some(""));
+    }
+
+    #[test]
+    fn test_custom_shortcuts_invalid_json() {
+        assert_eq!(CustomShortcuts::from_json(""), CustomShortcuts::default());
+        assert_eq!(
+            CustomShortcuts::from_json("[\"win.save\"]"),
+            CustomShortcuts::default()
+        );
+        assert_eq!(
+            CustomShortcuts::from_json("{\"win.save\": 3}"),
+            CustomShortcuts::default()
+        );
+    }
+}
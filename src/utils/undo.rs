@@ -0,0 +1,314 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::entities::KeyValue;
+
+/// How many edits are remembered by default.
+pub const UNDO_LIMIT: usize = 200;
+
+/// Edits made closer than this in time may be merged into a single undo step.
+const COALESCE_WINDOW: Duration = Duration::from_millis(1000);
+
+/// A change that can be undone.
+pub trait Edit: Clone {
+    /// The edit that reverts this one.
+    fn inverse(&self) -> Self;
+
+    /// Tries to absorb an edit made right after this one, so that both are
+    /// undone at once. Returns false if they have to remain separate steps.
+    fn merge(&mut self, next: &Self) -> bool;
+}
+
+/// Remembers the edits made to a document so that they can be undone and redone.
+///
+/// The oldest edits are forgotten once the limit is reached. Making a new edit
+/// forgets the edits that were undone, since they cannot be redone anymore.
+#[derive(Debug)]
+pub struct UndoStack<E: Edit> {
+    undo: VecDeque<E>,
+    redo: Vec<E>,
+    limit: usize,
+    last_push: Option<Instant>,
+}
+
+impl<E: Edit> Default for UndoStack<E> {
+    fn default() -> Self {
+        Self::new(UNDO_LIMIT)
+    }
+}
+
+impl<E: Edit> UndoStack<E> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+            last_push: None,
+        }
+    }
+
+    /// Records an edit that has just been made.
+    pub fn push(&mut self, edit: E, now: Instant) {
+        self.redo.clear();
+        let recent = self
+            .last_push
+            .is_some_and(|last| now.duration_since(last) < COALESCE_WINDOW);
+        self.last_push = Some(now);
+        if recent {
+            if let Some(last) = self.undo.back_mut() {
+                if last.merge(&edit) {
+                    return;
+                }
+            }
+        }
+        self.undo.push_back(edit);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Returns the edit that has to be reverted, which can be redone later.
+    pub fn undo(&mut self) -> Option<E> {
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        self.last_push = None;
+        Some(edit)
+    }
+
+    /// Returns the edit that has to be applied again, which can be undone later.
+    pub fn redo(&mut self) -> Option<E> {
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        self.last_push = None;
+        Some(edit)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_push = None;
+    }
+}
+
+/// A change made to the rows of a list of key-value pairs.
+#[derive(Debug, Clone, PartialEq, Eq, glib::Boxed)]
+#[boxed_type(name = "CarteroKeyValueEdit")]
+pub enum KeyValueEdit {
+    Insert {
+        position: u32,
+        rows: Vec<KeyValue>,
+    },
+    Remove {
+        position: u32,
+        rows: Vec<KeyValue>,
+    },
+    Update {
+        position: u32,
+        old: KeyValue,
+        new: KeyValue,
+    },
+    Move {
+        from: u32,
+        to: u32,
+    },
+}
+
+impl Edit for KeyValueEdit {
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            KeyValueEdit::Insert { position, rows } => KeyValueEdit::Remove { position, rows },
+            KeyValueEdit::Remove { position, rows } => KeyValueEdit::Insert { position, rows },
+            KeyValueEdit::Update { position, old, new } => KeyValueEdit::Update {
+                position,
+                old: new,
+                new: old,
+            },
+            KeyValueEdit::Move { from, to } => KeyValueEdit::Move { from: to, to: from },
+        }
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (
+                KeyValueEdit::Update { position, new, .. },
+                KeyValueEdit::Update {
+                    position: next_position,
+                    new: next_new,
+                    ..
+                },
+            ) if position == next_position => {
+                *new = next_new.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sets a text to a new value.
+    #[derive(Debug, Clone, PartialEq)]
+    struct SetText {
+        old: String,
+        new: String,
+    }
+
+    impl SetText {
+        fn new(old: &str, new: &str) -> Self {
+            Self {
+                old: old.to_string(),
+                new: new.to_string(),
+            }
+        }
+    }
+
+    impl Edit for SetText {
+        fn inverse(&self) -> Self {
+            Self::new(&self.new, &self.old)
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            self.new = next.new.clone();
+            true
+        }
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let now = Instant::now();
+        let mut stack = UndoStack::default();
+        stack.push(SetText::new("", "a"), now);
+        stack.push(SetText::new("a", "b"), now + Duration::from_secs(5));
+
+        assert_eq!(stack.undo(), Some(SetText::new("a", "b")));
+        assert!(!stack.redo.is_empty());
+        assert_eq!(stack.redo(), Some(SetText::new("a", "b")));
+        assert_eq!(stack.undo(), Some(SetText::new("a", "b")));
+        assert_eq!(stack.undo(), Some(SetText::new("", "a")));
+        assert_eq!(stack.undo(), None);
+        assert!(stack.undo.is_empty());
+    }
+
+    #[test]
+    fn test_push_forgets_redo() {
+        let now = Instant::now();
+        let mut stack = UndoStack::default();
+        stack.push(SetText::new("", "a"), now);
+        stack.undo();
+        stack.push(SetText::new("", "b"), now + Duration::from_secs(5));
+        assert!(stack.redo.is_empty());
+    }
+
+    #[test]
+    fn test_coalesce_rapid_edits() {
+        let now = Instant::now();
+        let mut stack = UndoStack::default();
+        stack.push(SetText::new("", "a"), now);
+        stack.push(SetText::new("a", "ab"), now + Duration::from_millis(200));
+        stack.push(SetText::new("ab", "abc"), now + Duration::from_millis(400));
+        stack.push(SetText::new("abc", "abcd"), now + Duration::from_secs(5));
+
+        assert_eq!(stack.undo(), Some(SetText::new("abc", "abcd")));
+        assert_eq!(stack.undo(), Some(SetText::new("", "abc")));
+        assert_eq!(stack.undo(), None);
+    }
+
+    #[test]
+    fn test_undo_stops_coalescing() {
+        let now = Instant::now();
+        let mut stack = UndoStack::default();
+        stack.push(SetText::new("", "a"), now);
+        stack.push(SetText::new("a", "ab"), now);
+        stack.undo();
+        stack.push(SetText::new("", "x"), now);
+        assert_eq!(stack.undo(), Some(SetText::new("", "x")));
+        assert_eq!(stack.undo(), None);
+    }
+
+    #[test]
+    fn test_limit() {
+        let now = Instant::now();
+        let mut stack = UndoStack::new(3);
+        for i in 0..5 {
+            let edit = SetText::new(&i.to_string(), &(i + 1).to_string());
+            stack.push(edit, now + Duration::from_secs(i * 5));
+        }
+        assert_eq!(stack.undo(), Some(SetText::new("4", "5")));
+        assert_eq!(stack.undo(), Some(SetText::new("3", "4")));
+        assert_eq!(stack.undo(), Some(SetText::new("2", "3")));
+        assert_eq!(stack.undo(), None);
+    }
+
+    #[test]
+    fn test_key_value_edit_inverse() {
+        let row = KeyValue::from(("Accept", "*/*"));
+        let insert = KeyValueEdit::Insert {
+            position: 1,
+            rows: vec![row.clone()],
+        };
+        assert_eq!(
+            insert.inverse(),
+            KeyValueEdit::Remove {
+                position: 1,
+                rows: vec![row]
+            }
+        );
+        assert_eq!(insert.inverse().inverse(), insert);
+
+        let moved = KeyValueEdit::Move { from: 0, to: 2 };
+        assert_eq!(moved.inverse(), KeyValueEdit::Move { from: 2, to: 0 });
+    }
+
+    #[test]
+    fn test_key_value_edit_merge() {
+        let old = KeyValue::from(("Accept", ""));
+        let mid = KeyValue::from(("Accept", "a"));
+        let new = KeyValue::from(("Accept", "ap"));
+        let mut edit = KeyValueEdit::Update {
+            position: 0,
+            old: old.clone(),
+            new: mid.clone(),
+        };
+        let next = KeyValueEdit::Update {
+            position: 0,
+            old: mid.clone(),
+            new: new.clone(),
+        };
+        assert!(edit.merge(&next));
+        assert_eq!(
+            edit,
+            KeyValueEdit::Update {
+                position: 0,
+                old,
+                new
+            }
+        );
+
+        let other_row = KeyValueEdit::Update {
+            position: 1,
+            old: mid.clone(),
+            new: mid,
+        };
+        assert!(!edit.merge(&other_row));
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Write;
+
+use encoding_rs::Encoding;
+
+use crate::entities::ResponseData;
+
+/// How many bytes are inspected when guessing whether a body is binary.
+const SNIFF_LENGTH: usize = 8 * 1024;
+
+/// The share of control characters above which a body is taken as binary, even
+/// if it is valid UTF-8. Plain text rarely contains any besides whitespace.
+const MAX_CONTROL_RATIO: f64 = 0.05;
+
+/// How many bytes of the body are rendered in each line of a hex dump.
+const HEX_LINE_WIDTH: usize = 16;
+
+/// The encodings offered to decode a response body again, in the order of the dropdown.
+pub fn display_encodings() -> [&'static Encoding; 14] {
+    [
+        encoding_rs::UTF_8,
+        encoding_rs::UTF_16LE,
+        encoding_rs::UTF_16BE,
+        encoding_rs::WINDOWS_1252,
+        encoding_rs::ISO_8859_15,
+        encoding_rs::ISO_8859_2,
+        encoding_rs::WINDOWS_1251,
+        encoding_rs::KOI8_R,
+        encoding_rs::SHIFT_JIS,
+        encoding_rs::EUC_JP,
+        encoding_rs::GBK,
+        encoding_rs::GB18030,
+        encoding_rs::BIG5,
+        encoding_rs::EUC_KR,
+    ]
+}
+
+/// The ways a response body can be presented in the response panel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseViewer {
+    #[default]
+    Text,
+    Tree,
+    Image,
+    Html,
+    Hex,
+}
+
+impl ResponseViewer {
+    /// Every viewer, in the order they are offered in the viewer dropdown.
+    pub const ALL: [ResponseViewer; 5] = [
+        ResponseViewer::Text,
+        ResponseViewer::Tree,
+        ResponseViewer::Image,
+        ResponseViewer::Html,
+        ResponseViewer::Hex,
+    ];
+
+    /// Picks the viewer that suits the body of a response.
+    pub fn detect(response: &ResponseData) -> Self {
+        let mime = response
+            .headers
+            .header("content-type")
+            .and_then(|values| values.first().map(|v| v.to_lowercase()))
+            .unwrap_or_default();
+        let mime = mime.split(';').next().unwrap_or_default().trim();
+
+        if mime.starts_with("image/") && mime != "image/svg+xml" {
+            ResponseViewer::Image
+        } else if mime == "text/html" || mime == "application/xhtml+xml" {
+            ResponseViewer::Html
+        } else if response.charset().is_none()
+            && Encoding::for_bom(&response.body).is_none()
+            && is_binary(&response.body)
+        {
+            ResponseViewer::Hex
+        } else {
+            ResponseViewer::Text
+        }
+    }
+
+    pub fn position(&self) -> u32 {
+        Self::ALL.iter().position(|v| v == self).unwrap_or_default() as u32
+    }
+
+    pub fn from_position(position: u32) -> Option<Self> {
+        Self::ALL.get(position as usize).copied()
+    }
+}
+
+/// Whether the beginning of a body looks like binary data rather than text.
+pub fn is_binary(body: &[u8]) -> bool {
+    let sample = &body[..body.len().min(SNIFF_LENGTH)];
+    if sample.contains(&0) {
+        return true;
+    }
+    // The escape character is left out, as it is used by text with terminal colors.
+    let controls = sample
+        .iter()
+        .filter(|&&byte| byte.is_ascii_control() && !byte.is_ascii_whitespace() && byte != 0x1b)
+        .count();
+    if controls as f64 > sample.len() as f64 * MAX_CONTROL_RATIO {
+        return true;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        // A multibyte character may have been cut at the end of the sample.
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+/// Renders bytes as lines holding an offset, the bytes in hex and their ASCII value.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::with_capacity(bytes.len() * 4 + bytes.len() / HEX_LINE_WIDTH * 12);
+    for (line, chunk) in bytes.chunks(HEX_LINE_WIDTH).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * HEX_LINE_WIDTH);
+        for i in 0..HEX_LINE_WIDTH {
+            if i == HEX_LINE_WIDTH / 2 {
+                dump.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(dump, " {byte:02x}");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        for &byte in chunk {
+            let c = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            dump.push(c);
+        }
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::{KeyValue, KeyValueTable};
+
+    use super::*;
+
+    fn response(ctype: &str, body: &[u8]) -> ResponseData {
+        ResponseData {
+            status_code: 200,
+            duration: 0,
+            size: body.len(),
+            transfer_size: body.len(),
+            headers: KeyValueTable::new(&[KeyValue::from(("Content-Type", ctype))]),
+            body: body.to_vec(),
+            decode_warning: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_viewer() {
+        let cases: [(&str, &[u8], ResponseViewer); 10] = [
+            ("application/json", b"{}", ResponseViewer::Text),
+            ("image/png", b"\x89PNG", ResponseViewer::Image),
+            ("image/svg+xml", b"<svg/>", ResponseViewer::Text),
+            ("text/html; charset=utf-8", b"<p>", ResponseViewer::Html),
+            ("application/octet-stream", b"\x00\x01", ResponseViewer::Hex),
+            ("application/octet-stream", b"plain", ResponseViewer::Text),
+            (
+                "text/plain; charset=latin1",
+                b"ni\xf1o",
+                ResponseViewer::Text,
+            ),
+            (
+                "text/plain; charset=ISO-8859-1",
+                b"Espa\xf1a",
+                ResponseViewer::Text,
+            ),
+            (
+                "text/plain",
+                b"\xff\xfeE\x00s\x00p\x00a\x00\xf1\x00a\x00",
+                ResponseViewer::Text,
+            ),
+            ("text/plain", b"caf\xe9 \xc3\x28", ResponseViewer::Hex),
+        ];
+        for (ctype, body, expected) in cases {
+            assert_eq!(ResponseViewer::detect(&response(ctype, body)), expected);
+        }
+    }
+
+    #[test]
+    fn test_viewer_position() {
+        for viewer in ResponseViewer::ALL {
+            assert_eq!(
+                ResponseViewer::from_position(viewer.position()),
+                Some(viewer)
+            );
+        }
+        assert_eq!(ResponseViewer::from_position(10), None);
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b""));
+        assert!(!is_binary("caña".as_bytes()));
+        assert!(!is_binary(&"ñ".as_bytes()[..1]));
+        assert!(is_binary(b"\xff\xfe"));
+        assert!(is_binary(b"GIF\x00"));
+        assert!(is_binary(b"\x01\x02\x03 some data \x04\x05"));
+        assert!(!is_binary(b"line\r\n\ttabbed\x0c\n\x1b[1mbold\x1b[0m"));
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(hex_dump(b""), "");
+        assert_eq!(
+            hex_dump(b"Hello, world!\n\x00\xff!"),
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|\n\
+             00000010  21                                                |!|\n"
+        );
+    }
+}
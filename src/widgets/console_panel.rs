@@ -0,0 +1,209 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::client::TraceEvent;
+use crate::objects::ConsoleEntry;
+
+/// How many entries are kept in the console before dropping the older ones.
+const MAX_ENTRIES: u32 = 1000;
+
+mod imp {
+    use std::cell::OnceCell;
+
+    use adw::subclass::bin::BinImpl;
+    use glib::subclass::InitializingObject;
+    use gtk::gio;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{CompositeTemplate, Label, TemplateChild};
+
+    use crate::objects::ConsoleEntry;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/es/danirod/Cartero/console_panel.ui")]
+    pub struct ConsolePanel {
+        #[template_child]
+        pub current_only: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub entries_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub entries: TemplateChild<gtk::ListBox>,
+
+        pub store: OnceCell<gio::ListStore>,
+        pub filter: OnceCell<gtk::CustomFilter>,
+        pub model: OnceCell<gtk::FilterListModel>,
+
+        /// The tab currently visible in the window, used by the filter.
+        pub current: glib::WeakRef<glib::Object>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ConsolePanel {
+        const NAME: &'static str = "CarteroConsolePanel";
+        type Type = super::ConsolePanel;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ConsolePanel {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let store = gio::ListStore::new::<ConsoleEntry>();
+            let filter = gtk::CustomFilter::new(
+                glib::clone!(@weak self as panel => @default-return true, move |item| {
+                    let entry = item.downcast_ref::<ConsoleEntry>().unwrap();
+                    panel.is_visible_entry(entry)
+                }),
+            );
+            let model = gtk::FilterListModel::new(Some(store.clone()), Some(filter.clone()));
+            self.entries.bind_model(Some(&model), |item| {
+                let entry = item.downcast_ref::<ConsoleEntry>().unwrap();
+                let label = Label::builder()
+                    .label(entry.to_text())
+                    .xalign(0.0)
+                    .wrap(true)
+                    .wrap_mode(gtk::pango::WrapMode::WordChar)
+                    .selectable(true)
+                    .margin_start(6)
+                    .margin_end(6)
+                    .margin_top(3)
+                    .margin_bottom(3)
+                    .build();
+                if entry.kind() == "warning" {
+                    label.add_css_class("warning");
+                }
+                label.upcast()
+            });
+
+            let _ = self.store.set(store);
+            let _ = self.filter.set(filter);
+            let _ = self.model.set(model);
+        }
+    }
+
+    impl WidgetImpl for ConsolePanel {}
+
+    impl BinImpl for ConsolePanel {}
+
+    #[gtk::template_callbacks]
+    impl ConsolePanel {
+        #[template_callback]
+        fn on_filter_toggled(&self) {
+            self.refilter();
+        }
+
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let text = self.obj().text();
+            self.obj().clipboard().set_text(&text);
+        }
+
+        #[template_callback]
+        fn on_clear_clicked(&self) {
+            if let Some(store) = self.store.get() {
+                store.remove_all();
+            }
+        }
+    }
+
+    impl ConsolePanel {
+        fn is_visible_entry(&self, entry: &ConsoleEntry) -> bool {
+            if !self.current_only.is_active() {
+                return true;
+            }
+            match self.current.upgrade() {
+                Some(current) => entry.is_from(&current),
+                None => false,
+            }
+        }
+
+        pub(super) fn refilter(&self) {
+            if let Some(filter) = self.filter.get() {
+                filter.changed(gtk::FilterChange::Different);
+            }
+        }
+
+        pub(super) fn scroll_to_bottom(&self) {
+            glib::idle_add_local_once(glib::clone!(@weak self as panel => move || {
+                let adjustment = panel.entries_window.vadjustment();
+                adjustment.set_value(adjustment.upper());
+            }));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ConsolePanel(ObjectSubclass<imp::ConsolePanel>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for ConsolePanel {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl ConsolePanel {
+    /// Logs an event emitted while the given tab was sending a request.
+    pub fn append(&self, origin: &impl IsA<Object>, source: &str, event: TraceEvent) {
+        let imp = self.imp();
+        let Some(store) = imp.store.get() else {
+            return;
+        };
+        store.append(&ConsoleEntry::new(origin, source, event));
+        while store.n_items() > MAX_ENTRIES {
+            store.remove(0);
+        }
+        imp.scroll_to_bottom();
+    }
+
+    /// Tells the console which tab is visible, so that it can filter by it.
+    pub fn set_current(&self, origin: Option<&impl IsA<Object>>) {
+        let imp = self.imp();
+        imp.current.set(origin.map(|o| o.upcast_ref::<Object>()));
+        imp.refilter();
+    }
+
+    /// The entries that pass the current filter, one per line.
+    pub fn text(&self) -> String {
+        let imp = self.imp();
+        let Some(model) = imp.model.get() else {
+            return String::new();
+        };
+        model
+            .iter::<ConsoleEntry>()
+            .filter_map(Result::ok)
+            .map(|entry| entry.to_text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::app::CarteroApplication;
+
+/// The conversions that can be done with the dialog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncodingTool {
+    #[default]
+    Base64,
+    Url,
+}
+
+impl EncodingTool {
+    fn title(&self) -> String {
+        match self {
+            EncodingTool::Base64 => gettext("Base64 Encode/Decode"),
+            EncodingTool::Url => gettext("URL Encode/Decode"),
+        }
+    }
+
+    /// The settings key that remembers the last input given to the tool.
+    fn settings_key(&self) -> &'static str {
+        match self {
+            EncodingTool::Base64 => "base64-tool-input",
+            EncodingTool::Url => "url-encoding-tool-input",
+        }
+    }
+}
+
+mod imp {
+    use std::cell::Cell;
+
+    use adw::subclass::dialog::AdwDialogImplExt;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::prelude::*;
+    use gtk::CompositeTemplate;
+
+    use crate::tools::encoding::{
+        base64_decode, base64_encode, url_decode, url_encode, Base64Alphabet,
+    };
+
+    use super::EncodingTool;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/encoding_tool_dialog.ui")]
+    pub struct EncodingToolDialog {
+        #[template_child]
+        pub encode_toggle: TemplateChild<gtk::ToggleButton>,
+
+        #[template_child]
+        pub alphabet_dropdown: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub input: TemplateChild<gtk::TextView>,
+
+        #[template_child]
+        pub result: TemplateChild<gtk::TextView>,
+
+        #[template_child]
+        pub error: TemplateChild<gtk::Label>,
+
+        pub tool: Cell<EncodingTool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EncodingToolDialog {
+        const NAME: &'static str = "CarteroEncodingToolDialog";
+        type Type = super::EncodingToolDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for EncodingToolDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.input
+                .buffer()
+                .connect_changed(glib::clone!(@weak self as dialog => move |_| {
+                    dialog.convert();
+                }));
+        }
+    }
+
+    impl WidgetImpl for EncodingToolDialog {}
+
+    impl AdwDialogImpl for EncodingToolDialog {
+        fn closed(&self) {
+            self.obj().save_input();
+            self.parent_closed();
+        }
+    }
+
+    #[gtk::template_callbacks]
+    impl EncodingToolDialog {
+        #[template_callback]
+        fn on_options_changed(&self) {
+            self.convert();
+        }
+
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let buffer = self.result.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            self.obj().clipboard().set_text(&text);
+        }
+
+        /// Fills the result with the input converted in the selected direction.
+        pub(super) fn convert(&self) {
+            let input = self.obj().input();
+            let encode = self.encode_toggle.is_active();
+            let alphabet = match self.alphabet_dropdown.selected() {
+                1 => Base64Alphabet::UrlSafe,
+                _ => Base64Alphabet::Standard,
+            };
+            let result = match (self.tool.get(), encode) {
+                (EncodingTool::Base64, true) => Ok(base64_encode(&input, alphabet)),
+                (EncodingTool::Base64, false) => base64_decode(&input, alphabet),
+                (EncodingTool::Url, true) => Ok(url_encode(&input)),
+                (EncodingTool::Url, false) => url_decode(&input),
+            };
+            match result {
+                Ok(text) => {
+                    self.result.buffer().set_text(&text);
+                    self.error.set_visible(false);
+                }
+                Err(e) => {
+                    self.result.buffer().set_text("");
+                    self.error.set_text(&e.to_string());
+                    self.error.set_visible(true);
+                }
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct EncodingToolDialog(ObjectSubclass<imp::EncodingToolDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for EncodingToolDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl EncodingToolDialog {
+    pub fn new(tool: EncodingTool) -> Self {
+        let dialog = Self::default();
+        let imp = dialog.imp();
+        imp.tool.set(tool);
+        dialog.set_title(&tool.title());
+        imp.alphabet_dropdown
+            .set_visible(tool == EncodingTool::Base64);
+
+        let app = CarteroApplication::get();
+        let input = app.settings().string(tool.settings_key());
+        imp.input.buffer().set_text(&input);
+        imp.convert();
+        dialog
+    }
+
+    pub fn input(&self) -> String {
+        let buffer = self.imp().input.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, false).to_string()
+    }
+
+    /// Remembers the input, so that it is there the next time the tool is open.
+    fn save_input(&self) {
+        let key = self.imp().tool.get().settings_key();
+        let app = CarteroApplication::get();
+        let _ = app.settings().set_string(key, &self.input());
+    }
+}
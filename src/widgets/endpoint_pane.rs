@@ -15,30 +15,145 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::path::Path;
+
+use gettextrs::gettext;
 use glib::{subclass::types::ObjectSubclassIsExt, Object};
-use gtk::glib;
+use gtk::{glib, prelude::*, ClosureExpression};
+
+use crate::{
+    client::{BoundRequest, RequestError, Tracer},
+    entities::{EndpointData, RequestMethod, ResponseData},
+    error::CarteroError,
+    utils::{format_bytes, format_duration, Edit},
+};
+
+/// What has happened to the last request sent by an endpoint pane.
+#[derive(Debug, Default, Clone, PartialEq, Eq, glib::Boxed)]
+#[boxed_type(name = "CarteroRequestState")]
+pub enum RequestState {
+    #[default]
+    Idle,
+    Sending,
+    Done {
+        status: u32,
+        /// Milliseconds taken to receive the response.
+        elapsed: u128,
+        size: usize,
+    },
+    Failed,
+    Cancelled,
+}
+
+impl RequestState {
+    /// Describes the state in a single line, such as `200 OK · 123 ms · 4.2 kB`.
+    pub fn summary(&self) -> String {
+        match self {
+            RequestState::Idle => gettext("Ready"),
+            RequestState::Sending => gettext("Sending…"),
+            RequestState::Done {
+                status,
+                elapsed,
+                size,
+            } => {
+                let reason = u16::try_from(*status)
+                    .ok()
+                    .and_then(|code| isahc::http::StatusCode::from_u16(code).ok())
+                    .and_then(|code| code.canonical_reason());
+                let status = match reason {
+                    Some(reason) => format!("{status} {reason}"),
+                    None => status.to_string(),
+                };
+                format!(
+                    "{status} · {} · {}",
+                    format_duration(*elapsed),
+                    format_bytes(*size)
+                )
+            }
+            RequestState::Failed => gettext("Request failed"),
+            RequestState::Cancelled => gettext("Request cancelled"),
+        }
+    }
+}
 
-use crate::{entities::EndpointData, error::CarteroError};
+/// A change made to the request line being edited, which can be undone.
+///
+/// Changes to the query parameters are recorded as changes to the URL,
+/// since the parameters are always extracted from it. The rows of the
+/// headers and the variables keep their own history in their panes.
+#[derive(Debug, Clone)]
+enum EndpointEdit {
+    Url {
+        old: String,
+        new: String,
+    },
+    Method {
+        old: RequestMethod,
+        new: RequestMethod,
+    },
+}
+
+impl Edit for EndpointEdit {
+    fn inverse(&self) -> Self {
+        match self {
+            EndpointEdit::Url { old, new } => EndpointEdit::Url {
+                old: new.clone(),
+                new: old.clone(),
+            },
+            EndpointEdit::Method { old, new } => EndpointEdit::Method {
+                old: new.clone(),
+                new: old.clone(),
+            },
+        }
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (EndpointEdit::Url { new, .. }, EndpointEdit::Url { new: next_new, .. }) => {
+                *new = next_new.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::path::Path;
     use std::sync::{Arc, Mutex};
     use std::time::Instant;
 
+    use adw::prelude::BreakpointBinExt;
     use adw::subclass::breakpoint_bin::BreakpointBinImpl;
+    use futures_lite::future;
+    use gettextrs::gettext;
     use glib::subclass::InitializingObject;
     use glib::Properties;
+    use gtk::gio;
     use gtk::subclass::prelude::*;
     use gtk::{prelude::*, CompositeTemplate};
     use isahc::RequestExt;
-    use url::Url;
 
     use crate::app::CarteroApplication;
-    use crate::client::{BoundRequest, RequestError};
-    use crate::entities::{EndpointData, KeyValue};
+    use crate::client::{BoundRequest, ConnectionInfo, RequestError, Tracer};
+    use crate::entities::{
+        AwsSigV4, CompressionOptions, EndpointData, HttpVersionPreference, KeyValue, RequestMethod,
+        ResponseData, RetryPolicy, VariableRule, SECRET_PLACEHOLDER,
+    };
     use crate::error::CarteroError;
     use crate::objects::KeyValueItem;
-    use crate::widgets::{ItemPane, KeyValuePane, MethodDropdown, PayloadTab, ResponsePanel};
+    use crate::scripts::{run_post_response_script, run_pre_request_script};
+    use crate::utils::{Edit, UndoStack};
+    use crate::win::CarteroWindow;
+
+    use super::{EndpointEdit, RequestState};
+    use crate::widgets::{
+        ItemPane, KeyValuePane, MethodDropdown, PayloadTab, ResponsePanel, ScriptsPane,
+    };
 
     #[derive(CompositeTemplate, Properties, Default)]
     #[template(resource = "/es/danirod/Cartero/endpoint_pane.ui")]
@@ -47,6 +162,12 @@ mod imp {
         #[template_child(id = "send")]
         pub send_button: TemplateChild<gtk::Button>,
 
+        #[template_child]
+        pub send_stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        pub cancel: TemplateChild<gtk::Button>,
+
         #[template_child]
         pub parameter_pane: TemplateChild<KeyValuePane>,
 
@@ -62,19 +183,127 @@ mod imp {
         #[template_child(id = "url")]
         pub request_url: TemplateChild<gtk::Entry>,
 
+        #[template_child]
+        url_preview: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub url_history_button: TemplateChild<gtk::MenuButton>,
+
+        #[template_child]
+        pub url_history_popover: TemplateChild<gtk::Popover>,
+
+        #[template_child]
+        pub url_history_list: TemplateChild<gtk::ListBox>,
+
         #[template_child]
         pub payload_pane: TemplateChild<PayloadTab>,
 
+        #[template_child]
+        pub scripts_pane: TemplateChild<ScriptsPane>,
+
         #[template_child]
         pub response: TemplateChild<ResponsePanel>,
 
         #[template_child]
         pub paned: TemplateChild<gtk::Paned>,
 
+        #[template_child]
+        pub request_pane: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub response_pane: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub retry_attempts: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub retry_backoff: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub retry_exponential: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub retry_connection: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub retry_gateway: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub retry_server: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub retry_expander: TemplateChild<gtk::Expander>,
+
+        #[template_child]
+        pub transport_options: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub accept_compressed: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub stream_body: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub http_version: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub aws_expander: TemplateChild<gtk::Expander>,
+
+        #[template_child]
+        pub aws_enabled: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub aws_access_key: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub aws_secret_key: TemplateChild<gtk::PasswordEntry>,
+
+        #[template_child]
+        pub aws_session_token: TemplateChild<gtk::PasswordEntry>,
+
+        #[template_child]
+        pub aws_region: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub aws_service: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub notes_expander: TemplateChild<gtk::Expander>,
+
+        #[template_child]
+        pub notes: TemplateChild<gtk::TextView>,
+
+        /// The title given to the endpoint by the user, empty if there is none.
+        pub name: RefCell<String>,
+
+        /// Allows to cancel the request currently in progress, if any.
+        pub cancellable: RefCell<Option<gio::Cancellable>>,
+
+        /// Allows to close the event stream currently being received, if any.
+        pub stream_stop: RefCell<Option<gio::Cancellable>>,
+
+        /// The connection of the last response, used to tell if the next one reuses it.
+        pub connection: RefCell<Option<ConnectionInfo>>,
+
+        /// The URLs recently sent from this pane, most recent first.
+        url_history: RefCell<VecDeque<String>>,
+
         #[property(get, set, nullable)]
         pub item_pane: RefCell<Option<ItemPane>>,
 
         variable_changing: Arc<Mutex<bool>>,
+
+        undo_stack: RefCell<UndoStack<EndpointEdit>>,
+
+        /// The URL and the method before the last change, to know what an edit replaced.
+        last_url: RefCell<String>,
+        last_method: RefCell<RequestMethod>,
+
+        /// Set while the widgets are filled by the pane itself, so that it is not recorded.
+        replaying: Cell<bool>,
+
+        #[property(get, set)]
+        request_state: RefCell<RequestState>,
     }
 
     #[glib::object_subclass]
@@ -100,6 +329,15 @@ mod imp {
 
             self.init_dirty_events();
             self.init_settings();
+            self.init_undo();
+            self.init_cancel_shortcut();
+
+            let obj = self.obj();
+            obj.connect_item_pane_notify(|pane| {
+                pane.imp().sync_notes();
+                pane.imp().sync_name();
+            });
+            obj.connect_request_state_notify(|pane| pane.imp().sync_pending());
             self.variable_pane.assert_always_placeholder();
             self.header_pane.assert_always_placeholder();
             self.parameter_pane.assert_always_placeholder();
@@ -111,9 +349,7 @@ mod imp {
                     // is_ok() does not capture the mutex and will cause sync issues.
                     #[allow(clippy::redundant_pattern_matching)]
                     if let Ok(_) = url_arc.try_lock() {
-                        if let Err(err) = window.update_query_params() {
-                            println!("{err}");
-                        }
+                        window.update_query_params();
                     }
                 }));
 
@@ -124,9 +360,7 @@ mod imp {
                     // is_ok() does not capture the mutex and will cause sync issues.
                     #[allow(clippy::redundant_pattern_matching)]
                     if let Ok(_) = parameter_arc.try_lock() {
-                        if let Err(err) = window.update_url_from_query_params() {
-                            println!("{err}");
-                        }
+                        window.update_url_from_query_params();
                     }
                 }));
         }
@@ -138,44 +372,50 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl EndpointPane {
-        fn update_url_from_query_params(&self) -> Result<(), url::ParseError> {
-            let table = self.parameter_pane.get_entries();
-
-            let parsed_url = self.request_url.text().to_string();
-            let mut url = Url::parse(&parsed_url)?;
-            {
-                let mut pairs = url.query_pairs_mut();
-                pairs.clear();
-                for item in table {
-                    if item.active() {
-                        let key = item.header_name();
-                        let value = item.header_value();
-                        pairs.append_pair(&key, &value);
-                    }
-                }
-            }
-            self.request_url.set_text(url.as_str());
-            Ok(())
+        fn update_url_from_query_params(&self) {
+            let params: Vec<(String, String)> = self
+                .parameter_pane
+                .get_entries()
+                .iter()
+                .filter(|item| item.active())
+                .map(|item| (item.header_name(), item.header_value()))
+                .collect();
+            let url = self.request_url.text();
+            let url = crate::client::build_url_with_params(&url, &params);
+            self.request_url.set_text(&url);
         }
 
-        fn update_query_params(&self) -> Result<(), url::ParseError> {
-            let parsed_url = self.request_url.text().to_string();
-            let url = Url::parse(&parsed_url)?;
-            let pairs = url.query_pairs();
-
-            let entries: Vec<KeyValueItem> = pairs
-                .map(|(key, value)| {
-                    let key = String::from(key);
-                    let value = String::from(value);
-                    let entry = KeyValue::from((key, value));
-                    let value = KeyValueItem::from(entry);
+        fn update_query_params(&self) {
+            let url = self.request_url.text();
+            let entries: Vec<KeyValueItem> = crate::client::parse_query_params(&url)
+                .into_iter()
+                .map(|pair| {
+                    let value = KeyValueItem::from(KeyValue::from(pair));
                     value.set_active(true);
                     value.set_secret(false);
                     value
                 })
                 .collect();
             self.parameter_pane.set_entries(&entries);
-            Ok(())
+        }
+
+        /// Shows the URL that will be requested, with the variables replaced, below the
+        /// entry. It is hidden when the URL has no variables. Secrets are not revealed.
+        fn sync_url_preview(&self) {
+            let url = self.request_url.text();
+            let preview = self
+                .extract_endpoint()
+                .ok()
+                .map(|endpoint| endpoint.masking_secrets())
+                .and_then(|endpoint| endpoint.template_processor().render(url.as_str()).ok())
+                .filter(|preview| preview != url.as_str());
+            match preview {
+                Some(preview) => {
+                    self.url_preview.set_label(&preview);
+                    self.url_preview.set_visible(true);
+                }
+                None => self.url_preview.set_visible(false),
+            }
         }
 
         fn mark_dirty(&self) {
@@ -191,23 +431,245 @@ mod imp {
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
             self.payload_pane
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.scripts_pane
+                .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.payload_pane.connect_content_type_pasted(
+                glib::clone!(@weak self as pane => move |_, content_type| {
+                    pane.store_header("Content-Type", content_type);
+                }),
+            );
+            self.response.connect_extract_variable(
+                glib::clone!(@weak self as pane => move |_, name, value| {
+                    pane.store_variables(vec![(name.to_string(), value.to_string())]);
+                }),
+            );
+            self.response.connect_set_bearer_token(
+                glib::clone!(@weak self as pane => move |_, token| {
+                    pane.store_header("Authorization", &format!("Bearer {token}"));
+                }),
+            );
             self.header_pane
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
             self.variable_pane
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.variable_pane.connect_changed(
+                glib::clone!(@weak self as pane => move |_| pane.sync_url_preview()),
+            );
+            self.retry_attempts.connect_value_changed(
+                glib::clone!(@weak self as pane => move |_| pane.mark_dirty()),
+            );
+            self.retry_backoff.connect_value_changed(
+                glib::clone!(@weak self as pane => move |_| pane.mark_dirty()),
+            );
+            self.retry_exponential
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.retry_connection
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.retry_gateway
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.retry_server
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.accept_compressed
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.http_version.connect_selected_notify(
+                glib::clone!(@weak self as pane => move |_| pane.mark_dirty()),
+            );
+            self.aws_enabled
+                .connect_toggled(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            let aws_fields: [&gtk::Editable; 5] = [
+                self.aws_access_key.upcast_ref(),
+                self.aws_secret_key.upcast_ref(),
+                self.aws_session_token.upcast_ref(),
+                self.aws_region.upcast_ref(),
+                self.aws_service.upcast_ref(),
+            ];
+            for field in aws_fields {
+                field.connect_changed(
+                    glib::clone!(@weak self as pane => move |_| pane.mark_dirty()),
+                );
+            }
+            self.notes
+                .buffer()
+                .connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    pane.mark_dirty();
+                    pane.sync_notes();
+                }));
+        }
+
+        fn init_undo(&self) {
+            self.request_url
+                .connect_changed(glib::clone!(@weak self as pane => move |entry| {
+                    let new = entry.text().to_string();
+                    let old = pane.last_url.replace(new.clone());
+                    if old != new {
+                        pane.record(EndpointEdit::Url { old, new });
+                    }
+                }));
+            self.request_method.connect_changed(
+                glib::clone!(@weak self as pane => move |dropdown| {
+                    let new = dropdown.request_method();
+                    let old = pane.last_method.replace(new.clone());
+                    if old != new {
+                        pane.record(EndpointEdit::Method { old, new });
+                    }
+                }),
+            );
+        }
+
+        /// Cancels the pending request with Escape. The shortcut runs in the bubble
+        /// phase, so the focused widget gets to use the key first, and it lets the key
+        /// through when there is no request to cancel.
+        fn init_cancel_shortcut(&self) {
+            let controller = gtk::ShortcutController::new();
+            controller.set_propagation_phase(gtk::PropagationPhase::Bubble);
+            let trigger = gtk::ShortcutTrigger::parse_string("Escape");
+            let action = gtk::NamedAction::new("win.cancel-request");
+            controller.add_shortcut(gtk::Shortcut::new(trigger, Some(action)));
+            self.obj().add_controller(controller);
+        }
+
+        fn record(&self, edit: EndpointEdit) {
+            if !self.replaying.get() {
+                self.undo_stack.borrow_mut().push(edit, Instant::now());
+            }
+        }
+
+        fn apply_edit(&self, edit: &EndpointEdit) {
+            self.replaying.set(true);
+            match edit {
+                EndpointEdit::Url { new, .. } => self.request_url.set_text(new),
+                EndpointEdit::Method { new, .. } => {
+                    self.request_method.set_request_method(new.clone())
+                }
+            }
+            self.replaying.set(false);
+        }
+
+        pub(super) fn undo(&self) -> bool {
+            let Some(edit) = self.undo_stack.borrow_mut().undo() else {
+                return false;
+            };
+            self.apply_edit(&edit.inverse());
+            true
+        }
+
+        pub(super) fn redo(&self) -> bool {
+            let Some(edit) = self.undo_stack.borrow_mut().redo() else {
+                return false;
+            };
+            self.apply_edit(&edit);
+            true
+        }
+
+        fn notes_text(&self) -> String {
+            let buffer = self.notes.buffer();
+            let (start, end) = buffer.bounds();
+            buffer.text(&start, &end, false).to_string()
+        }
+
+        /// Keeps the notes of the item pane up to date, since they are shown in the tab.
+        fn sync_notes(&self) {
+            if let Some(item_pane) = self.obj().item_pane() {
+                item_pane.set_notes(self.notes_text());
+            }
+        }
+
+        /// Keeps the title of the item pane up to date, since it is shown in the tab.
+        fn sync_name(&self) {
+            if let Some(item_pane) = self.obj().item_pane() {
+                item_pane.set_custom_title(self.name.borrow().clone());
+            }
+        }
+
+        pub(super) fn set_request_name(&self, name: &str) {
+            if *self.name.borrow() == name {
+                return;
+            }
+            self.name.replace(name.to_string());
+            self.sync_name();
+            self.mark_dirty();
         }
 
         fn init_settings(&self) {
             let app = CarteroApplication::get();
             let settings = app.settings();
-            let initial_position = SettingsExtManual::get(settings, "paned-position");
-            self.paned.set_position(initial_position);
+            self.update_layout();
 
             self.paned
                 .connect_position_notify(glib::clone!(@weak settings => move |paned| {
-                    let new_position = paned.position();
-                    let _ = settings.set("paned-position", new_position);
+                    let key = match paned.orientation() {
+                        gtk::Orientation::Vertical => "split-position-vertical",
+                        _ => "split-position-horizontal",
+                    };
+                    let _ = settings.set(key, paned.position());
                 }));
+            settings.connect_changed(
+                Some("split-view-mode"),
+                glib::clone!(@weak self as pane => move |_, _| {
+                    pane.update_layout();
+                }),
+            );
+            self.obj().connect_current_breakpoint_notify(
+                glib::clone!(@weak self as pane => move |_| {
+                    pane.update_layout();
+                }),
+            );
+        }
+
+        /// Places the response next to the request or below it.
+        ///
+        /// Only the orientation of the split changes, so the widgets and the
+        /// response they hold are kept. Each orientation remembers its own position.
+        fn update_layout(&self) {
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            let stacked = self.obj().current_breakpoint().is_some()
+                || settings.string("split-view-mode") == "vertical";
+            let (orientation, key) = if stacked {
+                (gtk::Orientation::Vertical, "split-position-vertical")
+            } else {
+                (gtk::Orientation::Horizontal, "split-position-horizontal")
+            };
+
+            self.paned.set_orientation(orientation);
+            if stacked {
+                self.request_pane.set_size_request(-1, 170);
+                self.response_pane.set_size_request(-1, 170);
+            } else {
+                self.request_pane.set_size_request(500, -1);
+                self.response_pane.set_size_request(300, -1);
+            }
+            let position = SettingsExtManual::get::<i32>(settings, key);
+            self.paned.set_position(position);
+        }
+
+        /// Swaps the Send button for the Cancel button while the request is pending,
+        /// and prevents editing the request until it is done. The response panel is
+        /// kept sensitive, so that the request can also be stopped from there.
+        fn sync_pending(&self) {
+            let pending = *self.request_state.borrow() == RequestState::Sending;
+            let page = if pending { "cancel" } else { "send" };
+            self.send_stack.set_visible_child_name(page);
+            let label = if self.stream_body.is_active() {
+                gettext("Stop")
+            } else {
+                gettext("Cancel")
+            };
+            self.cancel.set_label(&label);
+
+            let editors: [&gtk::Widget; 7] = [
+                self.request_method.upcast_ref(),
+                self.request_url.upcast_ref(),
+                self.retry_expander.upcast_ref(),
+                self.transport_options.upcast_ref(),
+                self.aws_expander.upcast_ref(),
+                self.notes_expander.upcast_ref(),
+                self.request_pane.upcast_ref(),
+            ];
+            for widget in editors {
+                widget.set_sensitive(!pending);
+            }
+            self.sync_url_history_button();
         }
 
         /// Syncs whether the Send button can be clicked based on whether the request is formed.
@@ -223,6 +685,7 @@ mod imp {
         #[template_callback]
         fn on_url_changed(&self) {
             self.update_send_button_sensitivity();
+            self.sync_url_preview();
         }
 
         #[template_callback]
@@ -230,8 +693,56 @@ mod imp {
             let _ = self.obj().activate_action("win.request", None);
         }
 
+        #[template_callback]
+        fn on_url_history_activated(&self, row: &gtk::ListBoxRow) {
+            let Ok(index) = usize::try_from(row.index()) else {
+                return;
+            };
+            let url = self.url_history.borrow().get(index).cloned();
+            if let Some(url) = url {
+                self.request_url.buffer().set_text(url);
+            }
+            self.url_history_popover.popdown();
+        }
+
+        /// Adds the URL in the entry to the recently sent URLs. The history is saved
+        /// with the request, but changing it does not mark the request as modified.
+        pub(super) fn remember_url(&self) {
+            let url = self.request_url.buffer().text();
+            crate::entities::remember_url(&mut self.url_history.borrow_mut(), &url);
+            self.sync_url_history();
+        }
+
+        fn sync_url_history(&self) {
+            self.url_history_list.remove_all();
+            for url in self.url_history.borrow().iter() {
+                let label = gtk::Label::builder()
+                    .label(url)
+                    .tooltip_text(url)
+                    .xalign(0.0)
+                    .ellipsize(gtk::pango::EllipsizeMode::Middle)
+                    .max_width_chars(60)
+                    .margin_top(6)
+                    .margin_bottom(6)
+                    .margin_start(6)
+                    .margin_end(6)
+                    .build();
+                self.url_history_list.append(&label);
+            }
+            self.sync_url_history_button();
+        }
+
+        /// The history can only be opened if there is something in it, and not
+        /// while the request is pending, since the URL cannot be edited then.
+        fn sync_url_history_button(&self) {
+            let pending = *self.request_state.borrow() == RequestState::Sending;
+            let empty = self.url_history.borrow().is_empty();
+            self.url_history_button.set_sensitive(!pending && !empty);
+        }
+
         /// Sets the value of every widget in the pane into whatever is set by the given endpoint.
         pub fn assign_request(&self, endpoint: &EndpointData) {
+            self.replaying.set(true);
             self.request_url.buffer().set_text(endpoint.url.clone());
             self.request_method
                 .set_request_method(endpoint.method.clone());
@@ -248,6 +759,100 @@ mod imp {
             self.header_pane.set_entries(&headers);
             self.variable_pane.set_entries(&variables);
             self.payload_pane.set_payload(&endpoint.body);
+            self.assign_retry_policy(&endpoint.retry);
+            self.assign_compression(&endpoint.compression);
+            self.assign_http_version(endpoint.http_version);
+            self.assign_aws_sigv4(endpoint.aws_sigv4.as_ref());
+            self.notes.buffer().set_text(&endpoint.notes);
+            self.notes_expander.set_expanded(!endpoint.notes.is_empty());
+            self.scripts_pane
+                .set_pre_request_script(&endpoint.pre_request_script);
+            self.scripts_pane
+                .set_post_response_script(&endpoint.post_response_script);
+            self.name.replace(endpoint.name.clone());
+            self.sync_name();
+            self.url_history
+                .replace(endpoint.url_history.iter().cloned().collect());
+            self.sync_url_history();
+            self.replaying.set(false);
+            self.undo_stack.borrow_mut().clear();
+        }
+
+        fn assign_retry_policy(&self, policy: &RetryPolicy) {
+            self.retry_attempts.set_value(policy.max_attempts as f64);
+            self.retry_backoff.set_value(policy.backoff as f64);
+            self.retry_exponential
+                .set_active(policy.exponential_backoff);
+            self.retry_connection.set_active(policy.on_connection_error);
+            self.retry_gateway.set_active(policy.on_gateway_error);
+            self.retry_server.set_active(policy.on_server_error);
+        }
+
+        fn extract_retry_policy(&self) -> RetryPolicy {
+            RetryPolicy {
+                max_attempts: self.retry_attempts.value_as_int() as u32,
+                backoff: self.retry_backoff.value_as_int() as u64,
+                exponential_backoff: self.retry_exponential.is_active(),
+                on_connection_error: self.retry_connection.is_active(),
+                on_gateway_error: self.retry_gateway.is_active(),
+                on_server_error: self.retry_server.is_active(),
+            }
+        }
+
+        fn assign_compression(&self, compression: &CompressionOptions) {
+            self.accept_compressed
+                .set_active(compression.accept_compressed);
+            self.payload_pane
+                .set_compress_body(compression.compress_body);
+        }
+
+        fn extract_compression(&self) -> CompressionOptions {
+            CompressionOptions {
+                accept_compressed: self.accept_compressed.is_active(),
+                compress_body: self.payload_pane.compress_body(),
+            }
+        }
+
+        fn assign_http_version(&self, version: HttpVersionPreference) {
+            let position = match version {
+                HttpVersionPreference::Auto => 0,
+                HttpVersionPreference::Http1Only => 1,
+                HttpVersionPreference::Http2Prior => 2,
+            };
+            self.http_version.set_selected(position);
+        }
+
+        fn extract_http_version(&self) -> HttpVersionPreference {
+            match self.http_version.selected() {
+                1 => HttpVersionPreference::Http1Only,
+                2 => HttpVersionPreference::Http2Prior,
+                _ => HttpVersionPreference::Auto,
+            }
+        }
+
+        fn assign_aws_sigv4(&self, aws: Option<&AwsSigV4>) {
+            let default = AwsSigV4::default();
+            let fields = aws.unwrap_or(&default);
+            self.aws_enabled.set_active(aws.is_some());
+            self.aws_access_key.set_text(&fields.access_key);
+            self.aws_secret_key.set_text(&fields.secret_key);
+            self.aws_session_token.set_text(&fields.session_token);
+            self.aws_region.set_text(&fields.region);
+            self.aws_service.set_text(&fields.service);
+            self.aws_expander.set_expanded(aws.is_some());
+        }
+
+        fn extract_aws_sigv4(&self) -> Option<AwsSigV4> {
+            if !self.aws_enabled.is_active() {
+                return None;
+            }
+            Some(AwsSigV4 {
+                access_key: self.aws_access_key.text().to_string(),
+                secret_key: self.aws_secret_key.text().to_string(),
+                session_token: self.aws_session_token.text().to_string(),
+                region: self.aws_region.text().to_string(),
+                service: self.aws_service.text().to_string(),
+            })
         }
 
         /// Takes the current state of the pane and extracts it into an Endpoint value.
@@ -265,6 +870,9 @@ mod imp {
                     value: pair.header_value(),
                     active: pair.active(),
                     secret: pair.secret(),
+                    comment: pair.comment(),
+                    required: pair.required(),
+                    validation_rule: pair.validation_rule(),
                 })
                 .collect();
             let variables = variable_list
@@ -274,40 +882,367 @@ mod imp {
                     value: pair.header_value(),
                     active: pair.active(),
                     secret: pair.secret(),
+                    comment: pair.comment(),
+                    required: pair.required(),
+                    validation_rule: pair.validation_rule(),
                 })
                 .collect();
 
             let body = self.payload_pane.payload();
+            let retry = self.extract_retry_policy();
+            let compression = self.extract_compression();
+            let http_version = self.extract_http_version();
+            let aws_sigv4 = self.extract_aws_sigv4();
+            let notes = self.notes_text();
             Ok(EndpointData {
                 url,
                 method,
                 headers,
                 variables,
                 body,
+                retry,
+                compression,
+                http_version,
+                aws_sigv4,
+                notes,
+                name: self.name.borrow().clone(),
+                pre_request_script: self.scripts_pane.pre_request_script(),
+                post_response_script: self.scripts_pane.post_response_script(),
+                url_history: self.url_history.borrow().iter().cloned().collect(),
             })
         }
 
+        /// Builds the request for the current contents of the pane, without sending it.
+        ///
+        /// Unlike bind_request, the variable rules are not checked and the pre-request
+        /// script is not run, so the request sent may still differ from this one.
+        pub(super) fn build_request(&self) -> Result<BoundRequest, CarteroError> {
+            let endpoint = self.extract_endpoint()?;
+            let default_headers = CarteroApplication::get().default_headers();
+            BoundRequest::bind(endpoint, &default_headers)
+        }
+
+        /// Builds the request to be shown to the user, hiding the secrets if configured so.
+        /// As with build_request, the pre-request script is not run.
+        pub(super) fn export_request(&self) -> Result<BoundRequest, CarteroError> {
+            let app = CarteroApplication::get();
+            let mut endpoint = self.extract_endpoint()?;
+            if app.settings().boolean("mask-secrets-in-export") {
+                endpoint = endpoint.masking_secrets();
+            }
+            BoundRequest::bind(endpoint, &app.default_headers())
+        }
+
         /// Executes an HTTP request based on the current contents of the pane.
-        pub(super) async fn perform_request(&self) -> Result<(), CarteroError> {
-            let request = self.extract_endpoint()?;
-            let request = BoundRequest::try_from(request)?;
-            let request_obj = isahc::Request::try_from(request)?;
+        pub(super) async fn perform_request(
+            &self,
+            tracer: &Tracer,
+        ) -> Result<ResponseData, CarteroError> {
+            let policy = self.extract_retry_policy();
+            let streaming = self.stream_body.is_active();
+            let mut endpoint = self.extract_endpoint()?;
+            if streaming {
+                // Compressed chunks could not be shown until the whole body is there.
+                endpoint.compression.accept_compressed = false;
+            }
+            let method = endpoint.method.clone();
+            let http_version = endpoint.http_version;
+            let request = self.bind_request(endpoint)?;
+            tracer.trace_request(&request);
 
-            let start = Instant::now();
-            let mut response_obj = request_obj
-                .send_async()
-                .await
-                .map_err(RequestError::NetworkError)?;
-            let response = crate::client::extract_isahc_response(&mut response_obj, &start).await?;
+            let cancellable = gio::Cancellable::new();
+            self.cancellable.replace(Some(cancellable.clone()));
+
+            let send = || {
+                let request = request.clone();
+                async move {
+                    let request_obj = isahc::Request::try_from(request)?;
+                    let start = Instant::now();
+                    let mut response_obj = match request_obj.send_async().await {
+                        Ok(response_obj) => response_obj,
+                        Err(e) => {
+                            let error = RequestError::NetworkError(e);
+                            tracer.trace_error(&error);
+                            return Err(error);
+                        }
+                    };
+                    let head = crate::client::extract_isahc_head(&response_obj, &start);
+                    if !head.is_event_stream() && !streaming {
+                        let outcome =
+                            crate::client::extract_isahc_response(&mut response_obj, &start).await;
+                        match &outcome {
+                            Ok(response) => tracer.trace_response(response),
+                            Err(e) => tracer.trace_error(e),
+                        }
+                        let (remote, timings) = crate::client::extract_isahc_timings(&response_obj);
+                        tracer.trace_timings(remote, &timings);
+                        self.track_connection(&response_obj);
+                        return outcome;
+                    }
+
+                    let stop = gio::Cancellable::new();
+                    self.stream_stop.replace(Some(stop.clone()));
+                    let outcome = if head.is_event_stream() {
+                        self.response.start_stream(&head);
+                        crate::client::read_isahc_event_stream(
+                            &mut response_obj,
+                            &start,
+                            &stop,
+                            |event| self.response.append_event(&event),
+                        )
+                        .await
+                    } else {
+                        self.response.start_body_stream(&head);
+                        crate::client::read_isahc_body_stream(
+                            &mut response_obj,
+                            &start,
+                            &stop,
+                            |chunk| self.response.append_body_chunk(chunk),
+                        )
+                        .await
+                    };
+                    self.stream_stop.replace(None);
+                    match &outcome {
+                        Ok(response) => tracer.trace_response(response),
+                        Err(e) => tracer.trace_error(e),
+                    }
+                    let (remote, timings) = crate::client::extract_isahc_timings(&response_obj);
+                    tracer.trace_timings(remote, &timings);
+                    self.track_connection(&response_obj);
+                    outcome
+                }
+            };
+            let attempts = Cell::new(1);
+            let outcome =
+                crate::client::send_with_retries(&policy, &cancellable, send, |attempt| {
+                    attempts.set(attempt);
+                    self.response.set_attempt(attempt, policy.max_attempts);
+                })
+                .await;
+            self.cancellable.replace(None);
+            self.stream_stop.replace(None);
+
+            let (response, attempts) = match outcome {
+                Ok(outcome) => outcome,
+                // Every attempt failed before getting a response, so no single error tells the
+                // whole story. The console keeps the error of each attempt.
+                Err(e) if attempts.get() > 1 && e.is_connection_error() => {
+                    return Err(CarteroError::MaxRetriesExceeded(attempts.get()));
+                }
+                Err(e) => return Err(CarteroError::from_request_error(e, http_version)),
+            };
+            self.response.assign_from_response(&response);
+            self.response.show_method_notes(&method);
+            self.response.set_attempts(attempts);
+            if let Some(connection) = self.connection.borrow().as_ref() {
+                self.response.set_connection(connection);
+            }
+            self.run_post_response_script(&response)?;
+            Ok(response)
+        }
+
+        /// Checks the rules of the variables used by the request before sending it.
+        ///
+        /// Every broken rule is shown in its own toast, and the first one is returned
+        /// so that the response panel tells why the request was not sent.
+        fn validate_variables(&self, endpoint: &EndpointData) -> Result<(), CarteroError> {
+            let broken = endpoint.broken_variable_rules();
+            let error =
+                |(item, rule): &(KeyValue, VariableRule)| CarteroError::VariableValidationFailed {
+                    name: item.name.clone(),
+                    rule: rule.clone(),
+                    value: if item.secret {
+                        SECRET_PLACEHOLDER.into()
+                    } else {
+                        item.value.clone()
+                    },
+                };
+            let Some(first) = broken.first() else {
+                return Ok(());
+            };
+            if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                for violation in &broken {
+                    window.toast_error(error(violation));
+                }
+            }
+            Err(error(first))
+        }
+
+        /// Binds the endpoint into the request to send, running the pre-request script.
+        fn bind_request(&self, endpoint: EndpointData) -> Result<BoundRequest, CarteroError> {
+            self.validate_variables(&endpoint)?;
+            let script = endpoint.pre_request_script.clone();
+            let variables = endpoint.variable_values();
+            let default_headers = CarteroApplication::get().default_headers();
+            BoundRequest::bind_with(endpoint, &default_headers, |request| {
+                if script.trim().is_empty() {
+                    return Ok(());
+                }
+                let log = run_pre_request_script(&script, request, &variables);
+                self.scripts_pane.append_log(&log);
+                match log.error {
+                    Some(e) => Err(CarteroError::ScriptError(e)),
+                    None => Ok(()),
+                }
+            })
+        }
+
+        /// Sends the request and writes the body of the response into the given file.
+        ///
+        /// The server is not asked to compress the body, so that the file holds the
+        /// document itself. The returned response has no body.
+        pub(super) async fn download_request(
+            &self,
+            tracer: &Tracer,
+            path: &Path,
+        ) -> Result<ResponseData, CarteroError> {
+            let mut endpoint = self.extract_endpoint()?;
+            endpoint.compression.accept_compressed = false;
+            let http_version = endpoint.http_version;
+            let request = self.bind_request(endpoint)?;
+            tracer.trace_request(&request);
+
+            let cancellable = gio::Cancellable::new();
+            self.cancellable.replace(Some(cancellable.clone()));
+            let outcome = self
+                .download_response(request, path, tracer, &cancellable)
+                .await;
+            self.cancellable.replace(None);
+
+            let response =
+                outcome.map_err(|e| CarteroError::from_request_error(e, http_version))?;
             self.response.assign_from_response(&response);
+            if let Some(connection) = self.connection.borrow().as_ref() {
+                self.response.set_connection(connection);
+            }
+            Ok(response)
+        }
+
+        async fn download_response(
+            &self,
+            request: BoundRequest,
+            path: &Path,
+            tracer: &Tracer,
+            stop: &gio::Cancellable,
+        ) -> Result<ResponseData, RequestError> {
+            let request_obj = isahc::Request::try_from(request)?;
+            let start = Instant::now();
+            let sent = future::or(async { Some(request_obj.send_async().await) }, async {
+                stop.future().await;
+                None
+            })
+            .await;
+            let mut response_obj = match sent {
+                Some(Ok(response_obj)) => response_obj,
+                Some(Err(e)) => {
+                    let error = RequestError::NetworkError(e);
+                    tracer.trace_error(&error);
+                    return Err(error);
+                }
+                None => return Err(RequestError::Cancelled),
+            };
+            let head = crate::client::extract_isahc_head(&response_obj, &start);
+
+            self.response.start_download();
+            let mut output = BufWriter::new(File::create(path)?);
+            let outcome = crate::client::download_isahc_body(
+                &mut response_obj,
+                &mut output,
+                stop,
+                |progress| self.response.set_download_progress(progress),
+            )
+            .await;
+            drop(output);
+            let progress = match outcome {
+                Ok(progress) => progress,
+                Err(e) => {
+                    // Half a file is worse than no file at all.
+                    let _ = std::fs::remove_file(path);
+                    tracer.trace_error(&e);
+                    return Err(e);
+                }
+            };
+
+            let response = ResponseData {
+                duration: start.elapsed().as_millis(),
+                size: progress.received as usize,
+                transfer_size: progress.received as usize,
+                ..head
+            };
+            tracer.trace_response(&response);
+            let (remote, timings) = crate::client::extract_isahc_timings(&response_obj);
+            tracer.trace_timings(remote, &timings);
+            self.track_connection(&response_obj);
+            Ok(response)
+        }
+
+        fn track_connection<T>(&self, response: &isahc::Response<T>) {
+            let previous = *self.connection.borrow();
+            let connection = crate::client::extract_isahc_connection(response, previous.as_ref());
+            self.connection.replace(Some(connection));
+        }
+
+        /// Runs the post-response script, storing the variables it sets in the variables pane.
+        fn run_post_response_script(&self, response: &ResponseData) -> Result<(), CarteroError> {
+            let script = self.scripts_pane.post_response_script();
+            if script.trim().is_empty() {
+                return Ok(());
+            }
+            let variables = self.extract_endpoint()?.variable_values();
+            let (log, changes) = run_post_response_script(&script, response, &variables);
+            self.scripts_pane.append_log(&log);
+            self.store_variables(changes);
             Ok(())
         }
+
+        /// Sets the value of the given variables, adding the ones that do not exist yet.
+        fn store_variables(&self, changes: Vec<(String, String)>) {
+            if changes.is_empty() {
+                return;
+            }
+            let mut entries = self.variable_pane.get_entries();
+            for (name, value) in changes {
+                match entries.iter().find(|item| item.header_name() == name) {
+                    Some(item) => item.set_header_value(value),
+                    None => entries.push(KeyValueItem::from((name.as_str(), value.as_str()))),
+                }
+            }
+            self.variable_pane.set_entries(&entries);
+            self.mark_dirty();
+        }
+
+        /// Sets the value of a header, enabling it, or adds it if there is no header with that name.
+        fn store_header(&self, name: &str, value: &str) {
+            let mut entries = self.header_pane.get_entries();
+            match entries
+                .iter()
+                .find(|item| item.header_name().eq_ignore_ascii_case(name))
+            {
+                Some(item) => {
+                    item.set_header_value(value);
+                    item.set_active(true);
+                }
+                None => entries.push(KeyValueItem::from((name, value))),
+            }
+            self.header_pane.set_entries(&entries);
+            self.mark_dirty();
+        }
+
+        /// Cancels the request in progress. If an event stream is being received,
+        /// it is closed instead, keeping the events that have already arrived.
+        pub(super) fn cancel_request(&self) {
+            if let Some(stop) = self.stream_stop.borrow().as_ref() {
+                stop.cancel();
+            } else if let Some(cancellable) = self.cancellable.borrow().as_ref() {
+                cancellable.cancel();
+            }
+        }
     }
 }
 
 glib::wrapper! {
     pub struct EndpointPane(ObjectSubclass<imp::EndpointPane>)
-        @extends gtk::Widget, gtk::Box;
+        @extends adw::BreakpointBin, gtk::Widget;
 }
 
 impl Default for EndpointPane {
@@ -330,16 +1265,155 @@ impl EndpointPane {
         imp.extract_endpoint()
     }
 
+    /// Gives a title to the endpoint, shown instead of the file name. An empty
+    /// name restores the file name. The endpoint is marked as modified.
+    pub fn set_request_name(&self, name: &str) {
+        let imp = self.imp();
+        imp.set_request_name(name);
+    }
+
+    /// Reverts the last change made to the request. Returns false if there was nothing to undo.
+    pub fn undo(&self) -> bool {
+        let imp = self.imp();
+        imp.undo()
+    }
+
+    /// Makes again the last change that was undone. Returns false if there was nothing to redo.
+    pub fn redo(&self) -> bool {
+        let imp = self.imp();
+        imp.redo()
+    }
+
+    /// Builds the request for the current contents of the pane, without sending it or
+    /// running its pre-request script.
+    pub fn build_request(&self) -> Result<BoundRequest, CarteroError> {
+        let imp = self.imp();
+        imp.build_request()
+    }
+
+    /// Builds the request for previewing or generating code, which may hide the secrets.
+    pub fn export_request(&self) -> Result<BoundRequest, CarteroError> {
+        let imp = self.imp();
+        imp.export_request()
+    }
+
+    /// The response currently shown in the response panel, if any.
+    pub fn last_response(&self) -> Option<ResponseData> {
+        let imp = self.imp();
+        imp.response.current_response()
+    }
+
+    /// Suggests a name for the file where the response is downloaded.
+    pub fn download_file_name(&self) -> String {
+        let url = self
+            .build_request()
+            .map(|request| request.url)
+            .unwrap_or_default();
+        crate::client::download_file_name(&url)
+    }
+
+    /// Indents the request body if it is JSON or XML.
+    pub fn format_body(&self) {
+        self.imp().payload_pane.format_body();
+    }
+
+    /// Stops the request in progress, including any wait between retries,
+    /// or closes the event stream being received.
+    pub fn cancel_request(&self) {
+        let imp = self.imp();
+        imp.cancel_request();
+    }
+
     /// Executes an HTTP request based on the current contents of the pane.
     ///
     /// TODO: Should actually the EndpointPane do the requests? This method
     /// will probably change once collections are correctly implemented,
     /// since the EndpointPane would be probably bound to an Endpoint object.
-    pub async fn perform_request(&self) -> Result<(), CarteroError> {
+    ///
+    /// The steps taken to send the request are reported to the given tracer.
+    /// If the request fails, the error is shown in the response panel.
+    pub async fn perform_request(&self, tracer: &Tracer) -> Result<(), CarteroError> {
+        let imp = self.imp();
+        imp.response.set_spinning(true);
+        self.set_request_state(RequestState::Sending);
+        let outcome = imp.perform_request(tracer).await;
+        imp.response.set_spinning(false);
+        if outcome.is_ok() {
+            imp.remember_url();
+        }
+        let state = match &outcome {
+            Ok(response) => RequestState::Done {
+                status: response.status_code,
+                elapsed: response.duration,
+                size: response.size,
+            },
+            Err(CarteroError::Request(RequestError::Cancelled)) => {
+                imp.response.show_cancelled();
+                RequestState::Cancelled
+            }
+            Err(e) => {
+                imp.response.show_error(e);
+                RequestState::Failed
+            }
+        };
+        self.set_request_state(state);
+        outcome.map(|_| ())
+    }
+
+    /// Sends the request, writing the body of the response into the given file
+    /// instead of showing it. Returns how many bytes were written.
+    ///
+    /// The partial file is removed if the download fails or is cancelled. If the
+    /// request fails, the error is shown in the response panel.
+    pub async fn download_request(
+        &self,
+        tracer: &Tracer,
+        path: &Path,
+    ) -> Result<usize, CarteroError> {
         let imp = self.imp();
         imp.response.set_spinning(true);
-        let outcome = imp.perform_request().await;
+        self.set_request_state(RequestState::Sending);
+        let outcome = imp.download_request(tracer, path).await;
         imp.response.set_spinning(false);
-        outcome
+        let state = match &outcome {
+            Ok(response) => RequestState::Done {
+                status: response.status_code,
+                elapsed: response.duration,
+                size: response.size,
+            },
+            Err(CarteroError::Request(RequestError::Cancelled)) => {
+                imp.response.show_cancelled();
+                RequestState::Cancelled
+            }
+            Err(e) => {
+                imp.response.show_error(e);
+                RequestState::Failed
+            }
+        };
+        self.set_request_state(state);
+        outcome.map(|response| response.size)
+    }
+
+    /// The line describing the state of the request, for the status bar of the window.
+    pub fn request_status_binding(&self) -> ClosureExpression {
+        ClosureExpression::new::<String>(
+            [&self.property_expression("request-state")],
+            glib::closure!(|_: EndpointPane, state: RequestState| state.summary()),
+        )
+    }
+
+    /// Whether a request has been sent and its response is still being waited for.
+    pub fn is_request_pending(&self) -> bool {
+        self.request_state() == RequestState::Sending
+    }
+
+    /// Whether the request is being sent, to show a spinner while waiting.
+    pub fn request_sending_binding(&self) -> ClosureExpression {
+        ClosureExpression::new::<bool>(
+            [&self.property_expression("request-state")],
+            glib::closure!(|_: EndpointPane, state: RequestState| {
+                state == RequestState::Sending
+            }),
+        )
     }
 }
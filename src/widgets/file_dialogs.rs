@@ -124,6 +124,70 @@ pub async fn open_files(win: &CarteroWindow) -> Result<Vec<gio::File>, CarteroEr
     }
 }
 
+/// Asks for a file whose contents will be sent as the body of a request.
+pub async fn open_body_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Load"))
+        .title(gettext("Load request body"))
+        .modal(true)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-open-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    dialog.open_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })
+}
+
+/// Asks for a collection exported from Insomnia, in its JSON format.
+pub async fn open_insomnia_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
+    let filters = ListStore::with_type(FileFilter::static_type());
+    let json = FileFilter::new();
+    json.add_suffix("json");
+    json.set_name(Some(&gettext("Insomnia export (.json)")));
+    filters.append(&json);
+
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Import"))
+        .title(gettext("Import Insomnia collection"))
+        .filters(&filters)
+        .default_filter(&json)
+        .modal(true)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-open-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    dialog.open_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })
+}
+
 pub async fn save_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
     let filters = ListStore::with_type(FileFilter::static_type());
     let cartero = get_cartero_file_filter();
@@ -168,3 +232,35 @@ pub async fn save_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
 
     Ok(file)
 }
+
+/// Asks for the file where a response will be saved. Any kind of file is accepted.
+pub async fn save_response_file(
+    win: &CarteroWindow,
+    initial_name: &str,
+) -> Result<gio::File, CarteroError> {
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Save"))
+        .title(gettext("Save response"))
+        .modal(true)
+        .initial_name(initial_name)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-save-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    dialog.save_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })
+}
@@ -0,0 +1,124 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::client::BoundRequest;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::prelude::*;
+    use gtk::CompositeTemplate;
+
+    use crate::client::BoundRequest;
+    use crate::export::generators;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/generate_code_dialog.ui")]
+    pub struct GenerateCodeDialog {
+        #[template_child]
+        pub language: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub code: TemplateChild<gtk::TextView>,
+
+        pub request: RefCell<Option<BoundRequest>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for GenerateCodeDialog {
+        const NAME: &'static str = "CarteroGenerateCodeDialog";
+        type Type = super::GenerateCodeDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for GenerateCodeDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let names: Vec<&str> = generators().iter().map(|g| g.name()).collect();
+            let model = gtk::StringList::new(&names);
+            self.language.set_model(Some(&model));
+        }
+    }
+
+    impl WidgetImpl for GenerateCodeDialog {}
+
+    impl AdwDialogImpl for GenerateCodeDialog {}
+
+    #[gtk::template_callbacks]
+    impl GenerateCodeDialog {
+        #[template_callback]
+        pub(super) fn on_language_changed(&self) {
+            let request = self.request.borrow();
+            let Some(request) = request.as_ref() else {
+                return;
+            };
+            let generators = generators();
+            if let Some(generator) = generators.get(self.language.selected() as usize) {
+                self.code.buffer().set_text(&generator.generate(request));
+            }
+        }
+
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let buffer = self.code.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            self.obj().clipboard().set_text(&text);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct GenerateCodeDialog(ObjectSubclass<imp::GenerateCodeDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for GenerateCodeDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl GenerateCodeDialog {
+    pub fn new(request: &BoundRequest) -> Self {
+        let dialog = Self::default();
+        dialog.set_request(request);
+        dialog
+    }
+
+    pub fn set_request(&self, request: &BoundRequest) {
+        let imp = self.imp();
+        imp.request.replace(Some(request.clone()));
+        imp.on_language_changed();
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use adw::prelude::*;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::client::BoundRequest;
+use crate::entities::ResponseData;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::client::BoundRequest;
+    use crate::entities::ResponseData;
+    use crate::export::test_stub::{TestAssertions, TestLanguage};
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/generate_test_dialog.ui")]
+    pub struct GenerateTestDialog {
+        #[template_child]
+        pub language: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub status_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        pub headers_row: TemplateChild<adw::ExpanderRow>,
+
+        #[template_child]
+        pub body_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        pub json_paths_row: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        pub code: TemplateChild<gtk::TextView>,
+
+        /// One row per header of the response, titled with the header name.
+        pub header_switches: RefCell<Vec<adw::SwitchRow>>,
+
+        pub request: RefCell<Option<BoundRequest>>,
+
+        pub response: RefCell<Option<ResponseData>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for GenerateTestDialog {
+        const NAME: &'static str = "CarteroGenerateTestDialog";
+        type Type = super::GenerateTestDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for GenerateTestDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let names: Vec<&str> = TestLanguage::ALL.iter().map(|l| l.name()).collect();
+            let model = gtk::StringList::new(&names);
+            self.language.set_model(Some(&model));
+        }
+    }
+
+    impl WidgetImpl for GenerateTestDialog {}
+
+    impl AdwDialogImpl for GenerateTestDialog {}
+
+    impl GenerateTestDialog {
+        fn assertions(&self) -> TestAssertions {
+            let headers = self
+                .header_switches
+                .borrow()
+                .iter()
+                .filter(|row| row.is_active())
+                .map(|row| row.title().to_string())
+                .collect();
+            let json_paths = self
+                .json_paths_row
+                .text()
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+            TestAssertions {
+                status: self.status_row.is_active(),
+                headers,
+                body: self.body_row.is_active(),
+                json_paths,
+            }
+        }
+    }
+
+    #[gtk::template_callbacks]
+    impl GenerateTestDialog {
+        #[template_callback]
+        pub(super) fn on_assertions_changed(&self) {
+            let request = self.request.borrow();
+            let response = self.response.borrow();
+            let (Some(request), Some(response)) = (request.as_ref(), response.as_ref()) else {
+                return;
+            };
+            if let Some(language) = TestLanguage::ALL.get(self.language.selected() as usize) {
+                let code = language.generate(request, response, &self.assertions());
+                self.code.buffer().set_text(&code);
+            }
+        }
+
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let buffer = self.code.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            self.obj().clipboard().set_text(&text);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct GenerateTestDialog(ObjectSubclass<imp::GenerateTestDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for GenerateTestDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl GenerateTestDialog {
+    pub fn new(request: &BoundRequest, response: &ResponseData) -> Self {
+        let dialog = Self::default();
+        dialog.set_response(response);
+        dialog.set_request(request);
+        dialog
+    }
+
+    pub fn set_request(&self, request: &BoundRequest) {
+        let imp = self.imp();
+        imp.request.replace(Some(request.clone()));
+        imp.on_assertions_changed();
+    }
+
+    /// Offers a switch for every header of the response. The content type is
+    /// asserted by default, as it is rarely expected to change.
+    pub fn set_response(&self, response: &ResponseData) {
+        let imp = self.imp();
+        for row in imp.header_switches.take() {
+            imp.headers_row.remove(&row);
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        for header in response.headers.iter() {
+            if names.iter().any(|n| n.eq_ignore_ascii_case(&header.name)) {
+                continue;
+            }
+            names.push(header.name.clone());
+        }
+
+        let mut switches = Vec::new();
+        for name in names {
+            let values = response.headers.header(&name).unwrap_or_default();
+            let row = adw::SwitchRow::builder()
+                .title(&name)
+                .subtitle(values.join(", "))
+                .use_markup(false)
+                .active(name.eq_ignore_ascii_case("content-type"))
+                .build();
+            row.connect_active_notify(glib::clone!(@weak self as dialog => move |_| {
+                dialog.imp().on_assertions_changed();
+            }));
+            imp.headers_row.add_row(&row);
+            switches.push(row);
+        }
+        imp.headers_row.set_sensitive(!switches.is_empty());
+        imp.header_switches.replace(switches);
+
+        imp.response.replace(Some(response.clone()));
+        imp.on_assertions_changed();
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::prelude::*;
+
+use crate::entities::EndpointData;
+use crate::error::CarteroError;
+use crate::interop::curl_parser::parse_curl;
+
+mod imp {
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::interop::curl_parser::parse_curl;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/import_curl_dialog.ui")]
+    pub struct ImportCurlDialog {
+        #[template_child]
+        pub command: TemplateChild<gtk::TextView>,
+
+        #[template_child]
+        pub error: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportCurlDialog {
+        const NAME: &'static str = "CarteroImportCurlDialog";
+        type Type = super::ImportCurlDialog;
+        type ParentType = adw::AlertDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ImportCurlDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.command
+                .buffer()
+                .connect_changed(glib::clone!(@weak self as dialog => move |_| {
+                    dialog.validate();
+                }));
+        }
+    }
+
+    impl WidgetImpl for ImportCurlDialog {}
+
+    impl AdwDialogImpl for ImportCurlDialog {}
+
+    impl AdwAlertDialogImpl for ImportCurlDialog {}
+
+    impl ImportCurlDialog {
+        /// Only lets the command be imported when it can be read, telling why otherwise.
+        fn validate(&self) {
+            let command = self.obj().command();
+            let result = parse_curl(&command);
+            self.obj().set_response_enabled("import", result.is_ok());
+            match result {
+                Err(e) if !command.trim().is_empty() => {
+                    self.error.set_text(&e.to_string());
+                    self.error.set_visible(true);
+                }
+                _ => self.error.set_visible(false),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ImportCurlDialog(ObjectSubclass<imp::ImportCurlDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::AlertDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for ImportCurlDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl ImportCurlDialog {
+    pub fn command(&self) -> String {
+        let buffer = self.imp().command.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, false).to_string()
+    }
+
+    /// The endpoint that sends the request of the pasted command.
+    pub fn endpoint(&self) -> Result<EndpointData, CarteroError> {
+        parse_curl(&self.command())
+    }
+}
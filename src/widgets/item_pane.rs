@@ -16,30 +16,72 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use adw::prelude::*;
+use adw::subclass::prelude::*;
 use gettextrs::gettext;
 use glib::Object;
 use gtk::{gio, ClosureExpression};
 
+use crate::entities::notes_preview;
 use crate::error::CarteroError;
 
-use super::EndpointPane;
+use super::{EndpointPane, WebSocketPane};
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::time::Duration;
 
     use adw::prelude::*;
     use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
     use glib::Properties;
-    use gtk::{gio, glib};
+    use gtk::{gio, glib, CompositeTemplate};
 
-    #[derive(Default, Properties)]
+    use crate::diff::{diff_text, DiffLineKind};
+    use crate::win::CarteroWindow;
+
+    /// How long to wait for more changes in the file before reading it again.
+    const CHANGE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+    #[derive(CompositeTemplate, Default, Properties)]
     #[properties(wrapper_type = super::ItemPane)]
+    #[template(resource = "/es/danirod/Cartero/item_pane.ui")]
     pub struct ItemPane {
+        #[template_child]
+        pub toolbar: TemplateChild<adw::ToolbarView>,
+        #[template_child]
+        file_changed: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        diff_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        diff_dialog: TemplateChild<adw::Dialog>,
+        #[template_child]
+        diff_view: TemplateChild<gtk::TextView>,
+
         #[property(get, set, nullable)]
         file: RefCell<Option<gio::File>>,
 
         #[property(get, set)]
         pub dirty: RefCell<bool>,
+
+        /// The notes of the endpoint, if the pane holds one.
+        #[property(get, set)]
+        notes: RefCell<String>,
+
+        /// The title given to the endpoint by the user, used instead of the file name.
+        #[property(get, set)]
+        custom_title: RefCell<String>,
+
+        monitor: RefCell<Option<gio::FileMonitor>>,
+        pending_check: RefCell<Option<glib::SourceId>>,
+
+        /// The contents of the file the last time this pane read or wrote it.
+        pub disk_contents: RefCell<Option<String>>,
+
+        /// The contents written to the file by another program, until the user decides.
+        changed_contents: RefCell<Option<String>>,
+
+        /// Set when saving the pane automatically failed, until it is saved by hand.
+        pub auto_save_blocked: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -47,16 +89,179 @@ mod imp {
         const NAME: &'static str = "CarteroItemPane";
         type Type = super::ItemPane;
         type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for ItemPane {}
+    impl ObjectImpl for ItemPane {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.watch_file();
+            self.obj()
+                .connect_file_notify(glib::clone!(@weak self as pane => move |_| {
+                    pane.watch_file();
+                }));
+        }
+    }
 
     impl WidgetImpl for ItemPane {}
 
     impl BinImpl for ItemPane {}
 
-    impl ItemPane {}
+    #[gtk::template_callbacks]
+    impl ItemPane {
+        #[template_callback]
+        fn on_reload_clicked(&self) {
+            let Some(contents) = self.changed_contents.take() else {
+                return;
+            };
+            self.file_changed.set_reveal_child(false);
+            if let Err(e) = self.obj().load(&contents) {
+                if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                    window.toast_error(e);
+                }
+                return;
+            }
+            self.disk_contents.replace(Some(contents));
+            self.obj().set_dirty(false);
+        }
+
+        #[template_callback]
+        fn on_keep_clicked(&self) {
+            // Remember what is in the file now so that it is not reported again.
+            let contents = self.changed_contents.take();
+            self.disk_contents.replace(contents);
+            self.file_changed.set_reveal_child(false);
+            self.obj().set_dirty(true);
+        }
+
+        #[template_callback]
+        fn on_diff_clicked(&self) {
+            let Some(theirs) = self.changed_contents.borrow().clone() else {
+                return;
+            };
+            let Ok(Some(ours)) = self.obj().serialize() else {
+                return;
+            };
+
+            let text: String = diff_text(&ours, &theirs)
+                .iter()
+                .map(|line| {
+                    let prefix = match line.kind {
+                        DiffLineKind::Hunk => "",
+                        DiffLineKind::Context => " ",
+                        DiffLineKind::Added => "+",
+                        DiffLineKind::Removed => "-",
+                    };
+                    format!("{prefix}{}\n", line.text)
+                })
+                .collect();
+            self.diff_view.buffer().set_text(&text);
+            self.diff_dialog.present(&*self.obj());
+        }
+    }
+
+    impl ItemPane {
+        /// Forgets the changes made to the file by another program, if any.
+        pub(super) fn dismiss_file_changed(&self) {
+            self.changed_contents.take();
+            self.file_changed.set_reveal_child(false);
+        }
+
+        /// Starts monitoring the file of the pane, replacing the previous monitor.
+        fn watch_file(&self) {
+            if let Some(monitor) = self.monitor.take() {
+                monitor.cancel();
+            }
+            if let Some(source) = self.pending_check.take() {
+                source.remove();
+            }
+            self.changed_contents.take();
+            self.file_changed.set_reveal_child(false);
+
+            let Some(file) = self.obj().file() else {
+                return;
+            };
+            let Ok(monitor) =
+                file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+            else {
+                return;
+            };
+            monitor.connect_changed(glib::clone!(@weak self as pane => move |_, _, _, event| {
+                match event {
+                    gio::FileMonitorEvent::Changed
+                    | gio::FileMonitorEvent::ChangesDoneHint
+                    | gio::FileMonitorEvent::Created
+                    | gio::FileMonitorEvent::Deleted
+                    | gio::FileMonitorEvent::MovedIn
+                    | gio::FileMonitorEvent::MovedOut
+                    | gio::FileMonitorEvent::Renamed => pane.schedule_check(),
+                    _ => {}
+                }
+            }));
+            self.monitor.replace(Some(monitor));
+        }
+
+        /// Checks the file once it stops receiving changes for a while.
+        ///
+        /// Editors and version control tools tend to write a file in several
+        /// steps, so every event restarts the countdown.
+        fn schedule_check(&self) {
+            if let Some(source) = self.pending_check.take() {
+                source.remove();
+            }
+            let source = glib::timeout_add_local_once(
+                CHANGE_DEBOUNCE,
+                glib::clone!(@weak self as pane => move || {
+                    pane.pending_check.take();
+                    glib::spawn_future_local(glib::clone!(@weak pane => async move {
+                        pane.check_file().await;
+                    }));
+                }),
+            );
+            self.pending_check.replace(Some(source));
+        }
+
+        async fn check_file(&self) {
+            let Some(file) = self.obj().file() else {
+                return;
+            };
+            if !file.query_exists(gio::Cancellable::NONE) {
+                // Saving the pane again will create the file back.
+                self.changed_contents.take();
+                self.file_changed.set_reveal_child(false);
+                self.obj().set_dirty(true);
+                return;
+            }
+            let Ok(contents) = crate::file::read_file(&file).await else {
+                return;
+            };
+            if self.disk_contents.borrow().as_deref() == Some(contents.as_str()) {
+                return;
+            }
+
+            let ours = self.obj().serialize().ok().flatten();
+            if ours.as_deref() == Some(contents.as_str()) {
+                self.disk_contents.replace(Some(contents));
+                self.changed_contents.take();
+                self.file_changed.set_reveal_child(false);
+                self.obj().set_dirty(false);
+                return;
+            }
+            self.diff_button.set_visible(ours.is_some());
+            self.changed_contents.replace(Some(contents));
+            self.file_changed.set_reveal_child(true);
+        }
+    }
 }
 
 glib::wrapper! {
@@ -69,12 +274,31 @@ impl ItemPane {
         let pane: Self = Object::builder().property("file", file).build();
 
         let child_pane = EndpointPane::default();
-        pane.set_child(Some(&child_pane));
+        pane.imp().toolbar.set_content(Some(&child_pane));
 
         if let Some(path) = file {
             let contents = crate::file::read_file(path).await?;
             let endpoint = crate::file::parse_toml(&contents)?;
             child_pane.assign_endpoint(&endpoint);
+            pane.imp().disk_contents.replace(Some(contents));
+        }
+
+        child_pane.set_item_pane(Some(&pane));
+
+        Ok(pane)
+    }
+
+    pub async fn new_for_websocket(file: Option<&gio::File>) -> Result<Self, CarteroError> {
+        let pane: Self = Object::builder().property("file", file).build();
+
+        let child_pane = WebSocketPane::default();
+        pane.imp().toolbar.set_content(Some(&child_pane));
+
+        if let Some(path) = file {
+            let contents = crate::file::read_file(path).await?;
+            let session = crate::file::parse_websocket_toml(&contents)?;
+            child_pane.assign_session(&session);
+            pane.imp().disk_contents.replace(Some(contents));
         }
 
         child_pane.set_item_pane(Some(&pane));
@@ -82,8 +306,76 @@ impl ItemPane {
         Ok(pane)
     }
 
+    /// Opens a file, creating the kind of pane that matches its contents.
+    pub async fn open(file: &gio::File) -> Result<Self, CarteroError> {
+        let contents = crate::file::read_file(file).await?;
+        if crate::file::is_websocket_toml(&contents) {
+            Self::new_for_websocket(Some(file)).await
+        } else {
+            Self::new_for_endpoint(Some(file)).await
+        }
+    }
+
     pub fn endpoint(&self) -> Option<EndpointPane> {
-        self.child().and_downcast::<EndpointPane>()
+        self.imp().toolbar.content().and_downcast::<EndpointPane>()
+    }
+
+    pub fn websocket(&self) -> Option<WebSocketPane> {
+        self.imp().toolbar.content().and_downcast::<WebSocketPane>()
+    }
+
+    /// Serializes the contents of the pane into the format used to store it in a file.
+    pub fn serialize(&self) -> Result<Option<String>, CarteroError> {
+        if let Some(endpoint) = self.endpoint() {
+            let endpoint = endpoint.extract_endpoint()?;
+            crate::file::store_toml(&endpoint).map(Some)
+        } else if let Some(websocket) = self.websocket() {
+            let session = websocket.extract_session();
+            crate::file::store_websocket_toml(&session).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Replaces the contents of the pane with what is stored in its file,
+    /// discarding the changes that have not been saved.
+    pub async fn revert(&self) -> Result<(), CarteroError> {
+        let Some(file) = self.file() else {
+            return Ok(());
+        };
+        let contents = crate::file::read_file(&file).await?;
+        self.load(&contents)?;
+        self.imp().dismiss_file_changed();
+        self.mark_saved(contents);
+        Ok(())
+    }
+
+    /// Replaces the contents of the pane with the contents of a file.
+    fn load(&self, contents: &str) -> Result<(), CarteroError> {
+        if let Some(endpoint) = self.endpoint() {
+            let data = crate::file::parse_toml(contents)?;
+            endpoint.assign_endpoint(&data);
+        } else if let Some(websocket) = self.websocket() {
+            let session = crate::file::parse_websocket_toml(contents)?;
+            websocket.assign_session(&session);
+        }
+        Ok(())
+    }
+
+    /// Records that the pane was written into its file with the given contents.
+    pub fn mark_saved(&self, contents: String) {
+        self.imp().disk_contents.replace(Some(contents));
+        self.imp().auto_save_blocked.set(false);
+        self.set_dirty(false);
+    }
+
+    /// Whether the pane is left out of auto-save because writing it failed before.
+    pub fn auto_save_blocked(&self) -> bool {
+        self.imp().auto_save_blocked.get()
+    }
+
+    pub fn block_auto_save(&self) {
+        self.imp().auto_save_blocked.set(true);
     }
 
     pub fn window_title_binding(&self) -> ClosureExpression {
@@ -91,12 +383,19 @@ impl ItemPane {
             [
                 &self.property_expression("file"),
                 &self.property_expression("dirty"),
+                &self.property_expression("custom-title"),
             ],
-            glib::closure!(|_: ItemPane, file: Option<gio::File>, dirty: bool| {
-                let title = file
-                    .and_then(|f| f.basename())
-                    .map(|bn| bn.file_stem().unwrap().to_str().unwrap().to_string())
-                    .unwrap_or(gettext("(untitled)"));
+            glib::closure!(|_: ItemPane,
+                            file: Option<gio::File>,
+                            dirty: bool,
+                            custom_title: String| {
+                let title = if custom_title.is_empty() {
+                    file.and_then(|f| f.basename())
+                        .map(|bn| bn.file_stem().unwrap().to_str().unwrap().to_string())
+                        .unwrap_or(gettext("(untitled)"))
+                } else {
+                    custom_title
+                };
                 if dirty {
                     format!("• {}", &title)
                 } else {
@@ -108,11 +407,21 @@ impl ItemPane {
 
     pub fn window_subtitle_binding(&self) -> ClosureExpression {
         ClosureExpression::new::<String>(
-            [&self.property_expression("file")],
-            glib::closure!(|_: ItemPane, file: Option<gio::File>| {
-                file.and_then(|f| f.path())
+            [
+                &self.property_expression("file"),
+                &self.property_expression("notes"),
+            ],
+            glib::closure!(|_: ItemPane, file: Option<gio::File>, notes: String| {
+                let path = file
+                    .and_then(|f| f.path())
                     .map(|bn| bn.display().to_string())
-                    .unwrap_or(gettext("Draft"))
+                    .unwrap_or(gettext("Draft"));
+                let tooltip = match notes_preview(&notes) {
+                    Some(preview) => format!("{path}\n{preview}"),
+                    None => path,
+                };
+                // The tooltip of a tab page is parsed as Pango markup.
+                glib::markup_escape_text(&tooltip).to_string()
             }),
         )
     }
@@ -0,0 +1,88 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use adw::prelude::*;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+mod imp {
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::prelude::*;
+    use gtk::CompositeTemplate;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/key_value_export_dialog.ui")]
+    pub struct KeyValueExportDialog {
+        #[template_child]
+        pub output: TemplateChild<gtk::TextView>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for KeyValueExportDialog {
+        const NAME: &'static str = "CarteroKeyValueExportDialog";
+        type Type = super::KeyValueExportDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for KeyValueExportDialog {}
+
+    impl WidgetImpl for KeyValueExportDialog {}
+
+    impl AdwDialogImpl for KeyValueExportDialog {}
+
+    #[gtk::template_callbacks]
+    impl KeyValueExportDialog {
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let buffer = self.output.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            self.obj().clipboard().set_text(&text);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct KeyValueExportDialog(ObjectSubclass<imp::KeyValueExportDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for KeyValueExportDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl KeyValueExportDialog {
+    pub fn new(title: &str, text: &str) -> Self {
+        let dialog = Self::default();
+        dialog.set_title(title);
+        dialog.imp().output.buffer().set_text(text);
+        dialog
+    }
+}
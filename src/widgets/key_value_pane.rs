@@ -17,28 +17,87 @@
 
 use std::collections::HashMap;
 
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
 use glib::Object;
-use gtk::{gio::ListStore, prelude::*};
+use gtk::gio::{ListStore, SimpleAction, SimpleActionGroup};
 
+use crate::entities::{KeyValue, KeyValueTable};
+use crate::export::{kv_to_http_headers, kv_to_json, kv_to_query_string};
 use crate::objects::KeyValueItem;
+use crate::utils::{Edit, KeyValueEdit};
+use crate::widgets::KeyValueExportDialog;
+
+/// How many changes to the rows can be undone.
+const UNDO_LIMIT: usize = 50;
+
+/// Turns the rows of a pane into the text of one of the export formats.
+type ExportFormat = fn(&[KeyValueItem]) -> String;
+
+/// What the rows of a pane are used for, which decides how they are validated.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "CarteroKeyValuePaneMode")]
+pub enum PaneMode {
+    Headers,
+    QueryParams,
+    Variables,
+    #[default]
+    FormFields,
+}
+
+impl PaneMode {
+    /// The placeholders shown in the name and value entries of a row.
+    pub fn placeholders(&self) -> (String, String) {
+        match self {
+            PaneMode::Headers => (gettext("Header name"), gettext("Header value")),
+            PaneMode::QueryParams => (gettext("Parameter name"), gettext("Parameter value")),
+            PaneMode::Variables => (gettext("Variable name"), gettext("Variable value")),
+            PaneMode::FormFields => (gettext("Name"), gettext("Value")),
+        }
+    }
+
+    /// Whether a row can use the given name. Empty names are accepted, as they are
+    /// used by rows that are still being written.
+    pub fn accepts_name(&self, name: &str) -> bool {
+        match self {
+            // Header names must be a token, as described in RFC 9110.
+            PaneMode::Headers => name
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)),
+            PaneMode::Variables => !name.chars().any(char::is_whitespace),
+            PaneMode::QueryParams | PaneMode::FormFields => true,
+        }
+    }
+
+    /// Whether many rows can share the same name, as in `?a=1&a=2`.
+    pub fn allows_duplicates(&self) -> bool {
+        matches!(self, PaneMode::QueryParams)
+    }
+}
 
 mod imp {
     use adw::subclass::bin::BinImpl;
     use gtk::prelude::*;
     use gtk::subclass::prelude::*;
 
-    use std::cell::{OnceCell, RefCell};
+    use std::cell::{Cell, OnceCell, RefCell};
     use std::sync::OnceLock;
+    use std::time::Instant;
 
     use glib::subclass::{InitializingObject, Signal};
     use glib::{closure_local, Properties};
     use gtk::gio::ListStore;
     use gtk::subclass::widget::{CompositeTemplateClass, WidgetImpl};
-    use gtk::{glib, CompositeTemplate};
+    use gtk::{gdk, glib, CompositeTemplate};
 
+    use crate::entities::KeyValue;
     use crate::objects::KeyValueItem;
+    use crate::utils::{KeyValueEdit, UndoStack};
     use crate::widgets::KeyValueRow;
 
+    use super::PaneMode;
+
     #[derive(Default, CompositeTemplate, Properties)]
     #[properties(wrapper_type = super::KeyValuePane)]
     #[template(resource = "/es/danirod/Cartero/key_value_pane.ui")]
@@ -52,8 +111,26 @@ mod imp {
         #[property(get, set)]
         avoid_duplicates: RefCell<bool>,
 
+        #[property(get, set)]
+        case_sensitive: RefCell<bool>,
+
+        /// Whether the rows can be exported to other formats, as done with headers.
+        #[property(get, set)]
+        exportable: RefCell<bool>,
+
         #[property(get)]
         valid: RefCell<bool>,
+
+        #[property(get, set, builder(PaneMode::default()))]
+        pane_mode: RefCell<PaneMode>,
+
+        /// The contents of every row, used to know what a row held before it changed.
+        pub rows: RefCell<Vec<KeyValue>>,
+
+        /// Set while an edit is undone or redone, so that it is not recorded again.
+        pub replaying: Cell<bool>,
+
+        pub undo_stack: RefCell<UndoStack<KeyValueEdit>>,
     }
 
     #[gtk::template_callbacks]
@@ -63,6 +140,13 @@ mod imp {
             let items = Vec::from_iter(model.iter::<glib::Object>().map(Result::unwrap));
             this_model.remove_all();
             this_model.splice(0, 0, &items);
+            self.obj().refresh_rows();
+            self.undo_stack.borrow_mut().clear();
+        }
+
+        /// Records a change made by the user, so that it can be undone later.
+        pub(super) fn push_edit(&self, edit: KeyValueEdit) {
+            self.undo_stack.borrow_mut().push(edit, Instant::now());
         }
     }
 
@@ -86,16 +170,32 @@ mod imp {
     impl ObjectImpl for KeyValuePane {
         fn signals() -> &'static [Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
-            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed").build(),
+                    Signal::builder("edited")
+                        .param_types([KeyValueEdit::static_type()])
+                        .build(),
+                ]
+            })
         }
 
         fn constructed(&self) {
             self.parent_constructed();
 
+            self.undo_stack.replace(UndoStack::new(super::UNDO_LIMIT));
+            self.obj().setup_actions();
+
             let obj = self.obj();
             obj.connect_avoid_duplicates_notify(glib::clone!(@weak obj as pane => move |_| {
                 pane.mark_duplicates();
             }));
+            obj.connect_case_sensitive_notify(glib::clone!(@weak obj as pane => move |_| {
+                pane.mark_duplicates();
+            }));
+            obj.connect_pane_mode_notify(glib::clone!(@weak obj as pane => move |_| {
+                pane.mark_duplicates();
+            }));
 
             self.model
                 .set(ListStore::with_type(KeyValueItem::static_type()))
@@ -104,6 +204,9 @@ mod imp {
             glib::clone!(@weak self as pane => @default-panic, move |item| {
                 let item = item.downcast_ref::<KeyValueItem>().unwrap();
                 let row = KeyValueRow::default();
+                row.add_binding(pane.obj().bind_property("pane-mode", &row, "pane-mode")
+                    .sync_create()
+                    .build());
                 row.add_binding(item.bind_property("header-name", &row, "header-name")
                     .bidirectional()
                     .sync_create()
@@ -124,22 +227,84 @@ mod imp {
                     .bidirectional()
                     .sync_create()
                     .build());
+                row.add_binding(item.bind_property("comment", &row, "comment")
+                    .bidirectional()
+                    .sync_create()
+                    .build());
+                row.add_binding(item.bind_property("required", &row, "required")
+                    .bidirectional()
+                    .sync_create()
+                    .build());
+                row.add_binding(item.bind_property("validation-rule", &row, "validation-rule")
+                    .bidirectional()
+                    .sync_create()
+                    .build());
+                if !item.comment().is_empty() {
+                    row.set_show_comment(true);
+                }
+                if item.required() || !item.validation_rule().is_empty() {
+                    row.set_show_validation(true);
+                }
                 let pane_delete = pane.clone();
                 row.connect_closure("delete", false, closure_local!(@strong item => move |_: KeyValueRow| {
                     let model = pane_delete.model.get().unwrap();
                     if let Some(pos) = model.find(&item) {
+                        let row = KeyValue::from(item.clone());
                         model.remove(pos);
 
                         let obj = pane_delete.obj();
                         obj.assert_always_placeholder();
+                        obj.record_edit(KeyValueEdit::Remove { position: pos, rows: vec![row] });
                         obj.emit_by_name::<()>("changed", &[]);
+
+                        // Keep the focus in the pane, so that the row can be restored.
+                        pane_delete.list_box.grab_focus();
+                    }
+                }));
+
+                let pane_paste = pane.clone();
+                row.connect_closure("paste-entries", false, closure_local!(@strong item => move |_: KeyValueRow, text: String| {
+                    let model = pane_paste.model.get().unwrap();
+                    if let Some(pos) = model.find(&item) {
+                        // Pasting over the placeholder fills it, otherwise add after the row.
+                        let is_placeholder = item.header_name().is_empty() && item.header_value().is_empty();
+                        let position = if is_placeholder { pos } else { pos + 1 };
+                        pane_paste.obj().paste_entries(position, &text);
+                    }
+                }));
+
+                let drag_source = gtk::DragSource::builder()
+                    .actions(gdk::DragAction::MOVE)
+                    .content(&gdk::ContentProvider::for_value(&item.to_value()))
+                    .build();
+                drag_source.connect_drag_begin(glib::clone!(@weak row => move |source, _| {
+                    let paintable = gtk::WidgetPaintable::new(Some(&row));
+                    source.set_icon(Some(&paintable), 0, 0);
+                }));
+                row.drag_handle().add_controller(drag_source);
+
+                let drop_target = gtk::DropTarget::new(KeyValueItem::static_type(), gdk::DragAction::MOVE);
+                let pane_drop = pane.clone();
+                drop_target.connect_drop(glib::clone!(@strong item => move |_, value, _, _| {
+                    let Ok(source) = value.get::<KeyValueItem>() else {
+                        return false;
+                    };
+                    let model = pane_drop.model.get().unwrap();
+                    match (model.find(&source), model.find(&item)) {
+                        (Some(from), Some(to)) if from != to => {
+                            pane_drop.obj().move_entry(from, to);
+                            true
+                        }
+                        _ => false,
                     }
                 }));
+                row.add_controller(drop_target);
 
                 let pane_changed = pane.clone();
-                item.connect_closure("changed", false, closure_local!(move |_: KeyValueItem| {
+                item.connect_closure("changed", false, closure_local!(move |item: KeyValueItem| {
                     let obj = pane_changed.obj();
                     obj.mark_duplicates();
+                    obj.record_update(&item);
                     obj.assert_always_placeholder();
                     obj.emit_by_name::<()>("changed", &[]);
                 }));
@@ -180,6 +345,136 @@ impl KeyValuePane {
         )
     }
 
+    /// Connects to the changes made by the user, which carry what is needed to revert them.
+    pub fn connect_edited<F: Fn(&Self, &KeyValueEdit) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "edited",
+            true,
+            glib::closure_local!(|ref pane, edit: KeyValueEdit| {
+                f(pane, &edit);
+            }),
+        )
+    }
+
+    fn setup_actions(&self) {
+        let ag = SimpleActionGroup::new();
+        self.insert_action_group("key-value", Some(&ag));
+
+        let formats: [(&str, String, ExportFormat); 3] = [
+            (
+                "export-headers",
+                gettext("HTTP Headers"),
+                kv_to_http_headers,
+            ),
+            ("export-json", gettext("JSON Object"), kv_to_json),
+            ("export-query", gettext("Query String"), kv_to_query_string),
+        ];
+        for (name, title, format) in formats {
+            let action = SimpleAction::new(name, None);
+            action.connect_activate(glib::clone!(@weak self as pane => move |_, _| {
+                let text = format(&pane.get_entries());
+                KeyValueExportDialog::new(&title, &text).present(&pane);
+            }));
+            ag.add_action(&action);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<KeyValue> {
+        self.model()
+            .iter::<KeyValueItem>()
+            .flatten()
+            .map(KeyValue::from)
+            .collect()
+    }
+
+    fn refresh_rows(&self) {
+        let rows = self.snapshot();
+        self.imp().rows.replace(rows);
+    }
+
+    fn record_edit(&self, edit: KeyValueEdit) {
+        self.refresh_rows();
+        if !self.imp().replaying.get() {
+            self.emit_by_name::<()>("edited", &[&edit]);
+            self.imp().push_edit(edit);
+        }
+    }
+
+    /// Reverts the last change made to the rows. Returns false if there was nothing to undo.
+    pub fn undo(&self) -> bool {
+        let Some(edit) = self.imp().undo_stack.borrow_mut().undo() else {
+            return false;
+        };
+        self.apply_edit(&edit.inverse());
+        true
+    }
+
+    /// Makes again the last change that was undone. Returns false if there was nothing to redo.
+    pub fn redo(&self) -> bool {
+        let Some(edit) = self.imp().undo_stack.borrow_mut().redo() else {
+            return false;
+        };
+        self.apply_edit(&edit);
+        true
+    }
+
+    fn record_update(&self, item: &KeyValueItem) {
+        let Some(position) = self.model().find(item) else {
+            return;
+        };
+        let new = KeyValue::from(item.clone());
+        let old = self.imp().rows.borrow().get(position as usize).cloned();
+        match old {
+            Some(old) if old != new => {
+                self.record_edit(KeyValueEdit::Update { position, old, new });
+            }
+            _ => self.refresh_rows(),
+        }
+    }
+
+    /// Applies an edit without recording it, as done when undoing or redoing it.
+    pub fn apply_edit(&self, edit: &KeyValueEdit) {
+        let imp = self.imp();
+        let model = &self.model();
+        imp.replaying.set(true);
+        match edit {
+            KeyValueEdit::Insert { position, rows } => {
+                let items: Vec<KeyValueItem> = rows
+                    .iter()
+                    .map(|row| KeyValueItem::from(row.clone()))
+                    .collect();
+                model.splice((*position).min(model.n_items()), 0, &items);
+            }
+            KeyValueEdit::Remove { position, rows } => {
+                for _ in 0..rows.len() {
+                    if *position < model.n_items() {
+                        model.remove(*position);
+                    }
+                }
+            }
+            KeyValueEdit::Update { position, new, .. } => {
+                if let Some(item) = model.item(*position).and_downcast::<KeyValueItem>() {
+                    item.set_header_name(new.name.clone());
+                    item.set_header_value(new.value.clone());
+                    item.set_active(new.active);
+                    item.set_secret(new.secret);
+                    item.set_comment(new.comment.clone());
+                    item.set_required(new.required);
+                    item.set_validation_rule(new.validation_rule.clone());
+                }
+            }
+            KeyValueEdit::Move { from, to } => self.move_entry(*from, *to),
+        }
+        self.mark_duplicates();
+        self.assert_always_placeholder();
+        self.refresh_rows();
+        imp.replaying.set(false);
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
     pub fn mark_duplicates(&self) {
         let model = &self.model();
         let mut headers: HashMap<String, KeyValueItem> = HashMap::new();
@@ -189,16 +484,66 @@ impl KeyValuePane {
                 // First, reset the ignored bit for this header.
                 header.set_ignored(false);
 
-                if self.avoid_duplicates() && header.is_usable() {
-                    let name = header.header_name().to_lowercase();
-                    if let Some(old) = headers.insert(name, header.clone()) {
-                        old.set_ignored(true);
+                if self.avoid_duplicates()
+                    && !self.pane_mode().allows_duplicates()
+                    && header.is_usable()
+                {
+                    let name = if self.case_sensitive() {
+                        header.header_name()
+                    } else {
+                        header.header_name().to_lowercase()
+                    };
+                    // The first row with a name wins, the later ones are ignored.
+                    let first = headers.entry(name).or_insert_with(|| header.clone());
+                    if *first != header {
+                        header.set_ignored(true);
                     }
                 }
             }
         }
     }
 
+    /// Moves the row at position `from` so that it ends up at position `to`.
+    pub fn move_entry(&self, from: u32, to: u32) {
+        let model = &self.model();
+        if from == to || to >= model.n_items() {
+            return;
+        }
+        let Some(item) = model.item(from) else {
+            return;
+        };
+        model.remove(from);
+        model.insert(to, &item);
+
+        self.mark_duplicates();
+        self.assert_always_placeholder();
+        self.record_edit(KeyValueEdit::Move { from, to });
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
+    /// Parses a block of `KEY=VALUE` or `KEY: VALUE` lines and inserts every pair
+    /// as a new row at the given position. Returns the number of rows inserted.
+    pub fn paste_entries(&self, position: u32, text: &str) -> u32 {
+        let entries: Vec<KeyValueItem> = KeyValueTable::parse_lines(text)
+            .iter()
+            .map(|kv| KeyValueItem::from((kv.name.as_str(), kv.value.as_str())))
+            .collect();
+        if entries.is_empty() {
+            return 0;
+        }
+
+        let model = &self.model();
+        let position = position.min(model.n_items());
+        model.splice(position, 0, &entries);
+
+        self.mark_duplicates();
+        self.assert_always_placeholder();
+        let rows = entries.iter().cloned().map(KeyValue::from).collect();
+        self.record_edit(KeyValueEdit::Insert { position, rows });
+        self.emit_by_name::<()>("changed", &[]);
+        entries.len() as u32
+    }
+
     pub fn assert_always_placeholder(&self) {
         let model = &self.model();
         let empty = model.iter::<KeyValueItem>().any(|row| {
@@ -210,6 +555,7 @@ impl KeyValuePane {
         if !empty {
             let new_row = KeyValueItem::new();
             model.append(&new_row);
+            self.refresh_rows();
         }
     }
 
@@ -232,7 +578,7 @@ impl KeyValuePane {
 
 #[cfg(test)]
 mod tests {
-    use std::cell::Cell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
 
     use gtk::gio::{prelude::*, ListStore};
@@ -349,4 +695,145 @@ mod tests {
         assert_eq!("Content-Type", entries[0].header_name());
         assert_eq!("Content-Length", entries[1].header_name());
     }
+
+    #[gtk::test]
+    pub fn test_move_entry() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_entries(&[
+            KeyValueItem::from(("A", "1")),
+            KeyValueItem::from(("B", "2")),
+            KeyValueItem::from(("C", "3")),
+        ]);
+        pane.move_entry(0, 2);
+
+        let keys: Vec<String> = pane.get_entries().iter().map(|e| e.header_name()).collect();
+        assert_eq!(keys, vec!["B", "C", "A"]);
+    }
+
+    #[gtk::test]
+    pub fn test_paste_entries() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_entries(&[KeyValueItem::from(("A", "1"))]);
+        let count = pane.paste_entries(1, "B=2\n\nC: \"3\"\n");
+        assert_eq!(count, 2);
+
+        let entries = pane.get_entries();
+        let keys: Vec<String> = entries.iter().map(|e| e.header_name()).collect();
+        assert_eq!(keys, vec!["A", "B", "C"]);
+        assert_eq!(entries[2].header_value(), "3");
+
+        // The placeholder is still the last row.
+        assert_eq!(pane.model().n_items(), 4);
+    }
+
+    #[gtk::test]
+    pub fn test_mark_duplicates_case_sensitive() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_avoid_duplicates(true);
+        pane.set_case_sensitive(true);
+        pane.set_entries(&[
+            KeyValueItem::from(("TYPE", "text/html")),
+            KeyValueItem::from(("type", "text/plain")),
+            KeyValueItem::from(("TYPE", "application/json")),
+        ]);
+        pane.mark_duplicates();
+
+        let ignored: Vec<bool> = pane.get_entries().iter().map(|e| e.ignored()).collect();
+        assert_eq!(ignored, vec![false, false, true]);
+    }
+
+    #[gtk::test]
+    pub fn test_query_params_allow_duplicates() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_avoid_duplicates(true);
+        pane.set_entries(&[
+            KeyValueItem::from(("a", "1")),
+            KeyValueItem::from(("a", "2")),
+        ]);
+        pane.mark_duplicates();
+        let ignored: Vec<bool> = pane.get_entries().iter().map(|e| e.ignored()).collect();
+        assert_eq!(ignored, vec![false, true]);
+
+        pane.set_pane_mode(PaneMode::QueryParams);
+        let ignored: Vec<bool> = pane.get_entries().iter().map(|e| e.ignored()).collect();
+        assert_eq!(ignored, vec![false, false]);
+    }
+
+    #[test]
+    pub fn test_pane_mode_accepts_name() {
+        assert!(PaneMode::Headers.accepts_name("Content-Type"));
+        assert!(PaneMode::Headers.accepts_name("X-Custom_Header.v2"));
+        assert!(PaneMode::Headers.accepts_name(""));
+        assert!(!PaneMode::Headers.accepts_name("Content Type"));
+        assert!(!PaneMode::Headers.accepts_name("Content-Type:"));
+        assert!(!PaneMode::Headers.accepts_name("Cabecera-Ñ"));
+
+        assert!(PaneMode::Variables.accepts_name("base_url"));
+        assert!(!PaneMode::Variables.accepts_name("base url"));
+        assert!(!PaneMode::Variables.accepts_name("token\t"));
+
+        assert!(PaneMode::QueryParams.accepts_name("a b"));
+        assert!(PaneMode::FormFields.accepts_name("file[]"));
+    }
+
+    #[gtk::test]
+    pub fn test_edits_can_be_reverted() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_entries(&[KeyValueItem::from(("A", "1"))]);
+        let edits = Rc::new(RefCell::new(Vec::new()));
+        pane.connect_edited(glib::clone!(@strong edits => move |_, edit| {
+            edits.borrow_mut().push(edit.clone());
+        }));
+
+        pane.paste_entries(1, "B=2\nC=3");
+        pane.move_entry(0, 2);
+        pane.get_entries()[0].set_header_value("changed");
+        assert_eq!(edits.borrow().len(), 3);
+
+        for edit in edits.borrow().iter().rev() {
+            pane.apply_edit(&edit.inverse());
+        }
+        let entries = pane.get_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].header_name(), "A");
+        assert_eq!(entries[0].header_value(), "1");
+
+        // Reverting the edits does not record them again.
+        assert_eq!(edits.borrow().len(), 3);
+    }
+
+    #[gtk::test]
+    pub fn test_undo_and_redo() {
+        crate::init_test_resources();
+
+        let pane = KeyValuePane::default();
+        pane.set_entries(&[KeyValueItem::from(("A", "1"))]);
+        assert!(!pane.undo());
+
+        pane.paste_entries(1, "B=2");
+        let keys = |pane: &KeyValuePane| -> Vec<String> {
+            pane.get_entries().iter().map(|e| e.header_name()).collect()
+        };
+        assert_eq!(keys(&pane), vec!["A", "B"]);
+
+        assert!(pane.undo());
+        assert_eq!(keys(&pane), vec!["A"]);
+        assert!(pane.redo());
+        assert_eq!(keys(&pane), vec!["A", "B"]);
+        assert!(!pane.redo());
+
+        // Replacing the rows forgets their history.
+        pane.set_entries(&[KeyValueItem::from(("C", "3"))]);
+        assert!(!pane.undo());
+    }
 }
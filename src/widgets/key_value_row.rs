@@ -15,6 +15,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use gettextrs::gettext;
 use glib::object::ObjectExt;
 use glib::property::PropertySet;
 use glib::subclass::types::ObjectSubclassIsExt;
@@ -24,16 +25,21 @@ use gtk::glib::Object;
 use gtk::prelude::WidgetExt;
 use gtk::prelude::*;
 
+use crate::widgets::PaneMode;
+
 mod imp {
     use std::cell::RefCell;
     use std::sync::OnceLock;
 
     use glib::subclass::{InitializingObject, Signal};
     use glib::{Binding, Properties, SignalHandlerId};
+    use gtk::gio::SimpleAction;
     use gtk::subclass::prelude::*;
     use gtk::Entry;
     use gtk::{prelude::*, CompositeTemplate};
 
+    use crate::widgets::PaneMode;
+
     #[derive(CompositeTemplate, Default, Properties)]
     #[properties(wrapper_type = super::KeyValueRow)]
     #[template(resource = "/es/danirod/Cartero/key_value_row.ui")]
@@ -49,11 +55,31 @@ mod imp {
         header_name: RefCell<String>,
         #[property(get, set)]
         header_value: RefCell<String>,
+        #[property(get, set)]
+        comment: RefCell<String>,
+        #[property(get, set)]
+        show_comment: RefCell<bool>,
+        #[property(get, set)]
+        required: RefCell<bool>,
+        #[property(get, set)]
+        validation_rule: RefCell<String>,
+        #[property(get, set)]
+        show_validation: RefCell<bool>,
+
+        /// What the pane holding this row is used for.
+        #[property(get, set, builder(PaneMode::default()))]
+        pane_mode: RefCell<PaneMode>,
 
+        #[template_child]
+        pub drag_handle: TemplateChild<gtk::Image>,
         #[template_child]
         pub entry_key: TemplateChild<Entry>,
         #[template_child]
         pub entry_value: TemplateChild<Entry>,
+        #[template_child]
+        pub entry_validation: TemplateChild<Entry>,
+
+        pub toggle_validation: RefCell<Option<SimpleAction>>,
 
         pub bindings: RefCell<Vec<Binding>>,
         pub delete_signal: RefCell<Option<SignalHandlerId>>,
@@ -85,7 +111,14 @@ mod imp {
 
         fn signals() -> &'static [Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
-            SIGNALS.get_or_init(|| vec![Signal::builder("delete").build()])
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("delete").build(),
+                    Signal::builder("paste-entries")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
         }
     }
 
@@ -116,6 +149,20 @@ impl KeyValueRow {
                 row.add_css_class("inactive-header");
             }
         });
+        self.connect_show_comment_notify(|row| {
+            // Hiding the comment entry discards the comment, as it would not be visible anymore.
+            if !row.show_comment() {
+                row.set_comment("");
+            }
+        });
+        self.connect_show_validation_notify(|row| {
+            // Likewise, hiding the validation removes the rules of the variable.
+            if !row.show_validation() {
+                row.set_required(false);
+                row.set_validation_rule("");
+            }
+        });
+        self.connect_validation_rule_notify(|row| row.sync_validation_rule());
         self.connect_ignored_notify(|row| {
             if row.ignored() {
                 row.add_css_class("ignored-header");
@@ -123,6 +170,90 @@ impl KeyValueRow {
                 row.remove_css_class("ignored-header");
             }
         });
+
+        // The placeholders and the accepted names depend on the pane holding the row.
+        self.connect_pane_mode_notify(|row| row.sync_pane_mode());
+        self.connect_header_name_notify(|row| row.sync_name_validity());
+        self.sync_pane_mode();
+
+        // Secret values are hidden, but can be revealed while the eye icon is held.
+        self.connect_secret_notify(|row| row.sync_secret_icon());
+        self.sync_secret_icon();
+        let imp = self.imp();
+        imp.entry_value.connect_icon_press(|entry, _| {
+            entry.set_visibility(true);
+        });
+        imp.entry_value
+            .connect_icon_release(glib::clone!(@weak self as row => move |entry, _| {
+                entry.set_visibility(!row.secret());
+            }));
+
+        // Pasting multiple lines in the name entry is handled by the pane, which
+        // will split them into multiple rows. Anything else is pasted as usual.
+        if let Some(text) = imp.entry_key.delegate().and_downcast::<gtk::Text>() {
+            text.connect_paste_clipboard(glib::clone!(@weak self as row => move |text| {
+                text.stop_signal_emission_by_name("paste-clipboard");
+                let clipboard = text.clipboard();
+                glib::spawn_future_local(glib::clone!(@weak row, @weak text => async move {
+                    let Ok(Some(content)) = clipboard.read_text_future().await else {
+                        return;
+                    };
+                    if content.trim().contains('\n') {
+                        row.emit_by_name::<()>("paste-entries", &[&content.to_string()]);
+                    } else {
+                        text.delete_selection();
+                        let mut position = text.position();
+                        text.insert_text(&content, &mut position);
+                        text.set_position(position);
+                    }
+                }));
+            }));
+        }
+    }
+
+    fn sync_pane_mode(&self) {
+        let imp = self.imp();
+        let (name, value) = self.pane_mode().placeholders();
+        imp.entry_key.set_placeholder_text(Some(&name));
+        imp.entry_value.set_placeholder_text(Some(&value));
+        self.sync_name_validity();
+
+        // Only variables can be given rules, since they are checked before sending.
+        if let Some(action) = imp.toggle_validation.borrow().as_ref() {
+            action.set_enabled(self.pane_mode() == PaneMode::Variables);
+        }
+    }
+
+    fn sync_validation_rule(&self) {
+        let entry = &self.imp().entry_validation;
+        let rule = self.validation_rule();
+        if rule.is_empty() || regex::Regex::new(&rule).is_ok() {
+            entry.remove_css_class("error");
+        } else {
+            entry.add_css_class("error");
+        }
+    }
+
+    fn sync_name_validity(&self) {
+        let entry = &self.imp().entry_key;
+        if self.pane_mode().accepts_name(&self.header_name()) {
+            entry.remove_css_class("error");
+            entry.set_tooltip_text(None);
+        } else {
+            entry.add_css_class("error");
+            entry.set_tooltip_text(Some(&gettext("This name is not valid here")));
+        }
+    }
+
+    fn sync_secret_icon(&self) {
+        let entry = &self.imp().entry_value;
+        if self.secret() {
+            entry.set_secondary_icon_name(Some("view-reveal-symbolic"));
+            entry.set_secondary_icon_tooltip_text(Some(&gettext("Hold to reveal")));
+        } else {
+            entry.set_secondary_icon_name(None);
+            entry.set_secondary_icon_tooltip_text(None);
+        }
     }
 
     pub(self) fn setup_actions(&self) {
@@ -130,6 +261,20 @@ impl KeyValueRow {
         self.insert_action_group("row", Some(&ag));
 
         let toggle_secret = PropertyAction::new("toggle-secret", self, "secret");
+        let toggle_comment = PropertyAction::new("toggle-comment", self, "show-comment");
+        // A property action cannot be disabled, and rules only make sense for variables.
+        let toggle_validation = SimpleAction::new_stateful(
+            "toggle-validation",
+            None,
+            &self.show_validation().to_variant(),
+        );
+        toggle_validation.set_enabled(self.pane_mode() == PaneMode::Variables);
+        toggle_validation.connect_activate(glib::clone!(@weak self as row => move |_, _| {
+            row.set_show_validation(!row.show_validation());
+        }));
+        self.connect_show_validation_notify(glib::clone!(@weak toggle_validation => move |row| {
+            toggle_validation.set_state(&row.show_validation().to_variant());
+        }));
 
         let delete = SimpleAction::new("delete", None);
         delete.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
@@ -137,6 +282,11 @@ impl KeyValueRow {
         }));
 
         ag.add_action(&toggle_secret);
+        ag.add_action(&toggle_comment);
+        ag.add_action(&toggle_validation);
+        self.imp()
+            .toggle_validation
+            .replace(Some(toggle_validation));
         ag.add_action(&delete);
     }
 
@@ -156,6 +306,10 @@ impl KeyValueRow {
         }
     }
 
+    pub fn drag_handle(&self) -> gtk::Widget {
+        self.imp().drag_handle.get().upcast()
+    }
+
     pub fn set_delete_closure(&self, hnd: SignalHandlerId) {
         let imp = self.imp();
         imp.delete_signal.set(Some(hnd));
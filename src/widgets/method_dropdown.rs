@@ -27,7 +27,8 @@ mod imp {
         object::{Cast, ObjectExt},
         subclass::{InitializingObject, Signal},
     };
-    use gtk::{prelude::ListModelExt, CompositeTemplate, StringObject, TemplateChild};
+    use gtk::prelude::{EditableExt, ListModelExt, WidgetExt};
+    use gtk::{CompositeTemplate, StringObject, TemplateChild};
 
     use crate::entities::RequestMethod;
 
@@ -39,6 +40,9 @@ mod imp {
 
         #[template_child]
         verbs_string_list: TemplateChild<gtk::StringList>,
+
+        #[template_child]
+        custom_method: TemplateChild<gtk::Entry>,
     }
 
     #[glib::object_subclass]
@@ -72,10 +76,43 @@ mod imp {
     impl MethodDropdown {
         #[template_callback]
         fn on_selection_changed(&self) {
+            let custom = self.is_custom_selected();
+            self.custom_method.set_visible(custom);
+            if custom {
+                self.custom_method.grab_focus();
+            }
             self.obj().emit_by_name::<()>("changed", &[]);
         }
 
+        #[template_callback]
+        fn on_custom_method_changed(&self) {
+            let valid = RequestMethod::try_from(self.custom_method.text().trim()).is_ok();
+            if valid {
+                self.custom_method.remove_css_class("error");
+            } else {
+                self.custom_method.add_css_class("error");
+            }
+            if self.is_custom_selected() {
+                self.obj().emit_by_name::<()>("changed", &[]);
+            }
+        }
+
+        /// The last item of the list lets the user type the name of the method.
+        fn custom_position(&self) -> u32 {
+            self.verbs_string_list.n_items() - 1
+        }
+
+        fn is_custom_selected(&self) -> bool {
+            self.dropdown.selected() == self.custom_position()
+        }
+
         pub(super) fn request_method(&self) -> RequestMethod {
+            if self.is_custom_selected() {
+                let method = self.custom_method.text().trim().to_string();
+                // Typing the name of a standard method gives that method.
+                return RequestMethod::try_from(method.as_str())
+                    .unwrap_or(RequestMethod::Custom(method));
+            }
             let method = self
                 .dropdown
                 .selected_item()
@@ -90,8 +127,13 @@ mod imp {
         }
 
         pub(super) fn set_request_method(&self, rm: RequestMethod) {
+            if let RequestMethod::Custom(method) = &rm {
+                self.custom_method.set_text(method);
+                self.dropdown.set_selected(self.custom_position());
+                return;
+            }
             let verb_to_find = String::from(rm);
-            let element_count = self.dropdown.model().unwrap().n_items();
+            let element_count = self.custom_position();
             let target_position = (0..element_count).find(|i| {
                 if let Some(verb) = self.verbs_string_list.string(*i) {
                     if verb == verb_to_find {
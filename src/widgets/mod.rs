@@ -15,24 +15,52 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod console_panel;
+mod encoding_tool_dialog;
 mod endpoint_pane;
 mod file_dialogs;
+mod generate_code_dialog;
+mod generate_test_dialog;
+mod import_curl_dialog;
 mod item_pane;
+mod key_value_export_dialog;
 mod key_value_pane;
 mod key_value_row;
 mod method_dropdown;
+mod new_from_url_dialog;
+mod preferences_dialog;
 mod request_body;
+mod request_preview_dialog;
 mod response_headers;
 mod response_panel;
 mod save_dialog;
+mod save_template_dialog;
+mod scripts_pane;
+mod shortcuts_dialog;
+mod templates_dialog;
+mod websocket_pane;
 
-pub use endpoint_pane::EndpointPane;
+pub use console_panel::ConsolePanel;
+pub use encoding_tool_dialog::{EncodingTool, EncodingToolDialog};
+pub use endpoint_pane::{EndpointPane, RequestState};
 pub use file_dialogs::*;
+pub use generate_code_dialog::GenerateCodeDialog;
+pub use generate_test_dialog::GenerateTestDialog;
+pub use import_curl_dialog::ImportCurlDialog;
 pub use item_pane::ItemPane;
-pub use key_value_pane::KeyValuePane;
+pub use key_value_export_dialog::KeyValueExportDialog;
+pub use key_value_pane::{KeyValuePane, PaneMode};
 pub use key_value_row::KeyValueRow;
 pub use method_dropdown::MethodDropdown;
+pub use new_from_url_dialog::NewFromUrlDialog;
+pub use preferences_dialog::PreferencesDialog;
 pub use request_body::*;
+pub use request_preview_dialog::RequestPreviewDialog;
 pub use response_headers::ResponseHeaders;
 pub use response_panel::ResponsePanel;
 pub use save_dialog::SaveDialog;
+pub use save_template_dialog::SaveTemplateDialog;
+pub use scripts_pane::ScriptsPane;
+pub use shortcuts_dialog::ShortcutsDialog;
+pub use templates_dialog::TemplatesDialog;
+pub use websocket_pane::WebSocketPane;
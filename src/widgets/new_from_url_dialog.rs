@@ -0,0 +1,135 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use adw::prelude::*;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::app::CarteroApplication;
+
+/// How many URLs are remembered by the dialog.
+const RECENT_URLS_LIMIT: usize = 5;
+
+mod imp {
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::app::CarteroApplication;
+    use crate::entities::EndpointData;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/new_from_url_dialog.ui")]
+    pub struct NewFromUrlDialog {
+        #[template_child]
+        pub url: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub recent_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub recent: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NewFromUrlDialog {
+        const NAME: &'static str = "CarteroNewFromUrlDialog";
+        type Type = super::NewFromUrlDialog;
+        type ParentType = adw::AlertDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for NewFromUrlDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let app = CarteroApplication::get();
+            let recent = app.settings().get::<Vec<String>>("recent-new-from-url");
+            for url in &recent {
+                let row = adw::ActionRow::builder()
+                    .title(glib::markup_escape_text(url))
+                    .activatable(true)
+                    .build();
+                self.recent.append(&row);
+            }
+            self.recent_box.set_visible(!recent.is_empty());
+        }
+    }
+
+    impl WidgetImpl for NewFromUrlDialog {}
+
+    impl AdwDialogImpl for NewFromUrlDialog {}
+
+    impl AdwAlertDialogImpl for NewFromUrlDialog {}
+
+    #[gtk::template_callbacks]
+    impl NewFromUrlDialog {
+        #[template_callback]
+        fn on_url_changed(&self) {
+            let valid = EndpointData::from_url(&self.url.text()).is_ok();
+            self.obj().set_response_enabled("open", valid);
+        }
+
+        #[template_callback]
+        fn on_recent_activated(&self, row: &gtk::ListBoxRow) {
+            let app = CarteroApplication::get();
+            let recent = app.settings().get::<Vec<String>>("recent-new-from-url");
+            if let Some(url) = recent.get(row.index() as usize) {
+                self.url.set_text(url);
+                self.url.grab_focus();
+                self.url.set_position(-1);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct NewFromUrlDialog(ObjectSubclass<imp::NewFromUrlDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::AlertDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for NewFromUrlDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl NewFromUrlDialog {
+    pub fn url(&self) -> String {
+        self.imp().url.text().to_string()
+    }
+
+    /// Puts the given URL at the top of the recent list, dropping the oldest ones.
+    pub fn remember_url(url: &str) {
+        let app = CarteroApplication::get();
+        let settings = app.settings();
+        let mut recent = settings.get::<Vec<String>>("recent-new-from-url");
+        recent.retain(|u| u != url);
+        recent.insert(0, url.to_string());
+        recent.truncate(RECENT_URLS_LIMIT);
+        let _ = settings.set("recent-new-from-url", recent);
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::Object;
+use gtk::glib;
+
+mod imp {
+    use std::cell::Cell;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::app::CarteroApplication;
+    use crate::utils::display_encodings;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/preferences_dialog.ui")]
+    pub struct PreferencesDialog {
+        #[template_child]
+        pub default_headers: TemplateChild<gtk::TextView>,
+
+        #[template_child]
+        pub response_encoding: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub auto_save_delay: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        pub mask_secrets: TemplateChild<adw::SwitchRow>,
+
+        /// Set while the text view is filled from the settings, so it is not saved back.
+        loading: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PreferencesDialog {
+        const NAME: &'static str = "CarteroPreferencesDialog";
+        type Type = super::PreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PreferencesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.load_default_headers();
+            self.default_headers.buffer().connect_changed(
+                glib::clone!(@weak self as dialog => move |_| {
+                    dialog.save_default_headers();
+                }),
+            );
+            self.init_response_encoding();
+
+            let app = CarteroApplication::get();
+            app.settings()
+                .bind("auto-save-delay-secs", &*self.auto_save_delay, "value")
+                .build();
+            app.settings()
+                .bind("mask-secrets-in-export", &*self.mask_secrets, "active")
+                .build();
+        }
+    }
+
+    impl WidgetImpl for PreferencesDialog {}
+
+    impl AdwDialogImpl for PreferencesDialog {}
+
+    impl PreferencesDialogImpl for PreferencesDialog {}
+
+    #[gtk::template_callbacks]
+    impl PreferencesDialog {
+        #[template_callback]
+        fn on_reset_clicked(&self) {
+            let app = CarteroApplication::get();
+            app.settings().reset("default-headers");
+            self.load_default_headers();
+        }
+    }
+
+    impl PreferencesDialog {
+        fn init_response_encoding(&self) {
+            let encodings = display_encodings();
+            let names: Vec<&str> = encodings.iter().map(|encoding| encoding.name()).collect();
+            self.response_encoding
+                .set_model(Some(&gtk::StringList::new(&names)));
+
+            let app = CarteroApplication::get();
+            let current = app.settings().string("response-encoding");
+            if let Some(position) = names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(&current))
+            {
+                self.response_encoding.set_selected(position as u32);
+            }
+            self.response_encoding.connect_selected_notify(move |row| {
+                if let Some(encoding) = encodings.get(row.selected() as usize) {
+                    let app = CarteroApplication::get();
+                    let _ = app
+                        .settings()
+                        .set_string("response-encoding", encoding.name());
+                }
+            });
+        }
+
+        fn load_default_headers(&self) {
+            let app = CarteroApplication::get();
+            let lines = app.settings().strv("default-headers");
+            let lines: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+            self.loading.set(true);
+            self.default_headers.buffer().set_text(&lines.join("\n"));
+            self.loading.set(false);
+        }
+
+        fn save_default_headers(&self) {
+            if self.loading.get() {
+                return;
+            }
+            let buffer = self.default_headers.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            let lines: Vec<&str> = text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+            let app = CarteroApplication::get();
+            let _ = app.settings().set_strv("default-headers", lines.as_slice());
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PreferencesDialog(ObjectSubclass<imp::PreferencesDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::PreferencesDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for PreferencesDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
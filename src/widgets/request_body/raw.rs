@@ -17,15 +17,17 @@
 
 use glib::{object::ObjectExt, subclass::types::ObjectSubclassIsExt};
 
-use crate::entities::{RawEncoding, RequestPayload};
+use crate::entities::{BodyCharset, RawEncoding, RequestPayload};
 
 use super::{BasePayloadPaneExt, PayloadType};
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::path::PathBuf;
     use std::sync::OnceLock;
 
     use adw::subclass::bin::BinImpl;
+    use gettextrs::gettext;
     use glib::subclass::{InitializingObject, Signal};
     use glib::Properties;
     use gtk::subclass::prelude::*;
@@ -35,7 +37,11 @@ mod imp {
     use sourceview5::{Buffer, StyleSchemeManager, View};
 
     use crate::app::CarteroApplication;
-    use crate::widgets::{BasePayloadPane, BasePayloadPaneImpl, PayloadType};
+    use crate::entities::RawEncoding;
+    use crate::error::CarteroError;
+    use crate::utils::{equivalent_offset, pretty_json, pretty_xml};
+    use crate::widgets::{open_body_file, BasePayloadPane, BasePayloadPaneImpl, PayloadType};
+    use crate::win::CarteroWindow;
 
     #[derive(Default, CompositeTemplate, Properties)]
     #[properties(wrapper_type = super::RawPayloadPane)]
@@ -47,6 +53,27 @@ mod imp {
         #[template_child]
         buffer: TemplateChild<Buffer>,
 
+        #[template_child]
+        file_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        unlink_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        format_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        format_error: TemplateChild<gtk::Label>,
+
+        /// The file the body was loaded from, if any.
+        file: RefCell<Option<PathBuf>>,
+
+        /// The contents of a loaded file that is not text, which cannot be shown in the view.
+        binary: RefCell<Option<Vec<u8>>>,
+
+        /// Set while the buffer is filled by the pane itself rather than by the user.
+        loading: Cell<bool>,
+
         #[property(get = Self::format, set = Self::set_format, builder(PayloadType::default()))]
         _format: RefCell<PayloadType>,
     }
@@ -60,6 +87,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -81,8 +109,17 @@ mod imp {
 
             self.buffer
                 .connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    // Once edited, the body no longer matches the file it was loaded from.
+                    if !pane.loading.get() && pane.binary.borrow().is_none() {
+                        pane.forget_file();
+                    }
+                    pane.format_error.set_visible(false);
                     pane.obj().emit_by_name::<()>("changed", &[]);
                 }));
+            self.buffer
+                .connect_language_notify(glib::clone!(@weak self as pane => move |_| {
+                    pane.sync_format_button();
+                }));
         }
     }
 
@@ -92,16 +129,178 @@ mod imp {
 
     impl BasePayloadPaneImpl for RawPayloadPane {}
 
+    #[gtk::template_callbacks]
+    impl RawPayloadPane {
+        #[template_callback]
+        fn on_load_file_clicked(&self) {
+            glib::spawn_future_local(glib::clone!(@weak self as pane => async move {
+                let Some(window) = pane.obj().root().and_downcast::<CarteroWindow>() else {
+                    return;
+                };
+                match pane.load_file(&window).await {
+                    Ok(_) | Err(CarteroError::NoFilePicked) => {}
+                    Err(e) => window.toast_error(e),
+                }
+            }));
+        }
+
+        #[template_callback]
+        fn on_format_clicked(&self) {
+            self.format_body();
+        }
+
+        #[template_callback]
+        fn on_unlink_clicked(&self) {
+            if self.binary.take().is_some() {
+                self.replace_text("");
+            }
+            self.forget_file();
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+    }
+
     impl RawPayloadPane {
         pub(super) fn payload(&self) -> Vec<u8> {
+            if let Some(binary) = &*self.binary.borrow() {
+                return binary.clone();
+            }
             let (start, end) = self.buffer.bounds();
             let text = self.buffer.text(&start, &end, true);
             Vec::from(text)
         }
 
         pub(super) fn set_payload(&self, payload: &[u8]) {
-            let body = String::from_utf8_lossy(payload);
-            self.buffer.set_text(&body);
+            self.set_file_contents(None, payload);
+        }
+
+        pub(super) fn file(&self) -> Option<PathBuf> {
+            self.file.borrow().clone()
+        }
+
+        /// Shows the contents of a file, or a placeholder if they are not text.
+        pub(super) fn set_file_contents(&self, file: Option<PathBuf>, contents: &[u8]) {
+            let binary = file.is_some() && std::str::from_utf8(contents).is_err();
+            if binary {
+                self.binary.replace(Some(contents.to_vec()));
+                self.replace_text(&gettext("(binary file loaded)"));
+            } else {
+                self.binary.replace(None);
+                self.replace_text(&String::from_utf8_lossy(contents));
+            }
+            self.view.set_editable(!binary);
+            self.file.replace(file);
+            self.sync_file();
+        }
+
+        async fn load_file(&self, window: &CarteroWindow) -> Result<(), CarteroError> {
+            let file = open_body_file(window).await?;
+            let (contents, _) = file
+                .load_contents_future()
+                .await
+                .map_err(|_| CarteroError::BodyFileError)?;
+            let path = file.path().ok_or(CarteroError::BodyFileError)?;
+
+            let format = match RawEncoding::from_extension(&path) {
+                Some(RawEncoding::Json) => Some(PayloadType::Json),
+                Some(RawEncoding::Xml) => Some(PayloadType::Xml),
+                _ if std::str::from_utf8(&contents).is_err() => Some(PayloadType::Raw),
+                _ => None,
+            };
+            self.set_file_contents(Some(path), &contents);
+            if let Some(format) = format {
+                self.obj().set_format(format);
+            }
+            Ok(())
+        }
+
+        /// Indents the JSON or XML in the view, keeping the cursor close to where it was.
+        ///
+        /// The text is replaced in a single user action, so that it can be undone at once.
+        pub(super) fn format_body(&self) {
+            if !self.view.is_editable() {
+                return;
+            }
+            let (start, end) = self.buffer.bounds();
+            let text = self.buffer.text(&start, &end, true).to_string();
+            let result = match self.format() {
+                PayloadType::Json => pretty_json(&text),
+                PayloadType::Xml => pretty_xml(&text),
+                _ => return,
+            };
+            let pretty = match result {
+                Ok(pretty) => pretty,
+                Err(e) => {
+                    let message = gettext("Line {line}, column {column}: {message}")
+                        .replace("{line}", &e.line.to_string())
+                        .replace("{column}", &e.column.to_string())
+                        .replace("{message}", &e.message);
+                    self.format_error.set_label(&message);
+                    self.format_error.set_visible(true);
+                    return;
+                }
+            };
+            if pretty == text {
+                return;
+            }
+
+            let cursor = self.buffer.iter_at_mark(&self.buffer.get_insert());
+            let offset = equivalent_offset(&text, cursor.offset() as usize, &pretty);
+            self.buffer.begin_user_action();
+            let (mut start, mut end) = self.buffer.bounds();
+            self.buffer.delete(&mut start, &mut end);
+            self.buffer.insert(&mut start, &pretty);
+            self.buffer.end_user_action();
+            self.buffer
+                .place_cursor(&self.buffer.iter_at_offset(offset as i32));
+        }
+
+        /// Inserts text at the cursor as if it was pasted, replacing the selection.
+        ///
+        /// Returns false if the body cannot be edited, such as when a binary file is loaded.
+        pub(super) fn paste_text(&self, text: &str) -> bool {
+            if !self.view.is_editable() {
+                return false;
+            }
+            self.buffer.begin_user_action();
+            self.buffer.delete_selection(true, true);
+            self.buffer.insert_at_cursor(text);
+            self.buffer.end_user_action();
+            true
+        }
+
+        pub(super) fn paste_clipboard(&self) {
+            self.view.emit_paste_clipboard();
+        }
+
+        fn replace_text(&self, text: &str) {
+            self.loading.set(true);
+            self.buffer.set_text(text);
+            self.loading.set(false);
+        }
+
+        fn forget_file(&self) {
+            if self.file.take().is_some() {
+                self.view.set_editable(true);
+                self.sync_file();
+            }
+        }
+
+        fn sync_file(&self) {
+            let file = self.file.borrow();
+            let label = file
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+            self.file_label.set_label(&label);
+            self.file_label.set_tooltip_text(Some(&label));
+            self.unlink_button.set_visible(file.is_some());
+            self.sync_format_button();
+        }
+
+        fn sync_format_button(&self) {
+            let formattable = matches!(self.format(), PayloadType::Json | PayloadType::Xml);
+            self.format_button
+                .set_visible(formattable && self.view.is_editable());
         }
 
         fn format(&self) -> PayloadType {
@@ -230,6 +429,19 @@ impl RawPayloadPane {
             }),
         )
     }
+
+    pub fn format_body(&self) {
+        self.imp().format_body();
+    }
+
+    pub fn paste_text(&self, text: &str) -> bool {
+        self.imp().paste_text(text)
+    }
+
+    /// Pastes the contents of the clipboard in the usual way.
+    pub fn paste_clipboard(&self) {
+        self.imp().paste_clipboard();
+    }
 }
 
 impl BasePayloadPaneExt for RawPayloadPane {
@@ -241,13 +453,31 @@ impl BasePayloadPaneExt for RawPayloadPane {
             super::PayloadType::Xml => RawEncoding::Xml,
             _ => RawEncoding::OctetStream,
         };
-        RequestPayload::Raw { encoding, content }
+        RequestPayload::Raw {
+            encoding,
+            charset: BodyCharset::default(),
+            content,
+            file: imp.file(),
+        }
     }
 
     fn set_payload(&self, payload: &RequestPayload) {
-        if let RequestPayload::Raw { encoding, content } = payload {
+        if let RequestPayload::Raw {
+            encoding,
+            content,
+            file,
+            ..
+        } = payload
+        {
             let imp = self.imp();
-            imp.set_payload(content);
+            match file {
+                // Read the file again in case it changed, keeping the saved copy if it is gone.
+                Some(path) => {
+                    let contents = std::fs::read(path).unwrap_or_else(|_| content.clone());
+                    imp.set_file_contents(Some(path.clone()), &contents);
+                }
+                None => imp.set_payload(content),
+            }
             let format = match encoding {
                 RawEncoding::Json => PayloadType::Json,
                 RawEncoding::Xml => PayloadType::Xml,
@@ -18,9 +18,11 @@
 use std::sync::OnceLock;
 
 use glib::object::{CastNone, ObjectExt};
+use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
-use crate::entities::{RawEncoding, RequestPayload};
+use crate::entities::{BodyCharset, RawEncoding, RequestPayload};
+use crate::utils::{pretty_json, pretty_xml};
 
 use super::{BasePayloadPaneExt, FormdataPayloadPane, RawPayloadPane, UrlencodedPayloadPane};
 
@@ -52,25 +54,35 @@ impl PayloadType {
     }
 }
 
+/// The character sets offered by the encoding dropdown, in the same order.
+const CHARSETS: [BodyCharset; 4] = [
+    BodyCharset::Utf8,
+    BodyCharset::Latin1,
+    BodyCharset::Utf16Le,
+    BodyCharset::Utf16Be,
+];
+
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use std::sync::OnceLock;
 
     use adw::prelude::*;
     use adw::subclass::prelude::*;
-    use adw::ComboRow;
+    use adw::{ComboRow, SwitchRow};
     use glib::subclass::Signal;
     use glib::{subclass::InitializingObject, Properties};
+    use gtk::gdk;
     use gtk::template_callbacks;
     use gtk::Separator;
     use gtk::{CompositeTemplate, Stack};
 
+    use crate::entities::BodyCharset;
     use crate::widgets::BasePayloadPane;
     use crate::widgets::FormdataPayloadPane;
     use crate::widgets::RawPayloadPane;
     use crate::widgets::UrlencodedPayloadPane;
 
-    use super::PayloadType;
+    use super::{PayloadType, CHARSETS};
 
     #[derive(Default, CompositeTemplate, Properties)]
     #[properties(wrapper_type = super::PayloadTab)]
@@ -82,6 +94,12 @@ mod imp {
         #[template_child]
         combo: TemplateChild<ComboRow>,
 
+        #[template_child]
+        charset_combo: TemplateChild<ComboRow>,
+
+        #[template_child]
+        compress_row: TemplateChild<SwitchRow>,
+
         #[template_child]
         sep: TemplateChild<Separator>,
 
@@ -96,6 +114,9 @@ mod imp {
 
         #[property(get = Self::payload_type, set = Self::set_payload_type, builder(PayloadType::default()))]
         _payload_type: RefCell<PayloadType>,
+
+        #[property(get = Self::compress_body, set = Self::set_compress_body)]
+        _compress_body: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -107,6 +128,23 @@ mod imp {
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
             klass.bind_template_callbacks();
+
+            klass.install_action("payload.paste-as-json", None, |tab, _, _| {
+                tab.paste_as(PayloadType::Json);
+            });
+            klass.install_action("payload.paste-as-xml", None, |tab, _, _| {
+                tab.paste_as(PayloadType::Xml);
+            });
+            klass.add_binding_action(
+                gdk::Key::v,
+                gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK,
+                "payload.paste-as-json",
+            );
+            klass.add_binding_action(
+                gdk::Key::v,
+                gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK,
+                "payload.paste-as-xml",
+            );
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -125,11 +163,36 @@ mod imp {
                     pane.obj().emit_by_name::<()>("changed", &[]);
                 }));
 
+            self.charset_combo.connect_selected_notify(
+                glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }),
+            );
+
+            self.compress_row
+                .connect_active_notify(glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().notify_compress_body();
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }));
+
             self.raw
                 .connect_changed(glib::clone!(@weak self as pane => move |_| {
                     pane.obj().emit_by_name::<()>("changed", &[]);
                 }));
 
+            // Loading a file into the raw pane may reveal that the body is JSON or XML.
+            self.raw
+                .connect_format_notify(glib::clone!(@weak self as pane => move |raw| {
+                    let current = pane.payload_type();
+                    let is_raw = matches!(
+                        current,
+                        PayloadType::Json | PayloadType::Xml | PayloadType::Raw
+                    );
+                    if is_raw && raw.format() != current {
+                        pane.set_payload_type(raw.format());
+                    }
+                }));
+
             self.urlencoded
                 .connect_changed(glib::clone!(@weak self as pane => move |_| {
                     pane.obj().emit_by_name::<()>("changed", &[]);
@@ -143,7 +206,14 @@ mod imp {
 
         fn signals() -> &'static [Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
-            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed").build(),
+                    Signal::builder("content-type-pasted")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
         }
     }
 
@@ -167,9 +237,30 @@ mod imp {
             self.stack.set_visible_child_name(tab);
 
             self.sep.set_visible(payload_type != PayloadType::None);
+            self.charset_combo.set_visible(tab == "raw");
+            self.compress_row
+                .set_visible(payload_type != PayloadType::None);
             self.raw.set_format(payload_type);
         }
 
+        pub(super) fn charset(&self) -> BodyCharset {
+            let n_item = self.charset_combo.selected() as usize;
+            CHARSETS.get(n_item).copied().unwrap_or_default()
+        }
+
+        pub(super) fn set_charset(&self, charset: BodyCharset) {
+            let pos = CHARSETS.iter().position(|&c| c == charset).unwrap_or(0);
+            self.charset_combo.set_selected(pos as u32);
+        }
+
+        fn compress_body(&self) -> bool {
+            self.compress_row.is_active()
+        }
+
+        fn set_compress_body(&self, compress: bool) {
+            self.compress_row.set_active(compress);
+        }
+
         fn payload_type(&self) -> PayloadType {
             let n_item = self.combo.selected();
             PayloadType::types()[n_item as usize]
@@ -180,6 +271,10 @@ mod imp {
             self.combo.set_selected(pos as u32);
         }
 
+        pub(super) fn raw(&self) -> RawPayloadPane {
+            self.raw.clone()
+        }
+
         pub(super) fn get_active_widget(&self) -> Option<BasePayloadPane> {
             match self.payload_type() {
                 PayloadType::None => None,
@@ -214,15 +309,61 @@ impl PayloadTab {
         )
     }
 
+    /// Emitted after pasting a JSON or XML body, with the content type of the body.
+    pub fn connect_content_type_pasted<F: Fn(&Self, &str) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "content-type-pasted",
+            true,
+            glib::closure_local!(|ref pane, content_type: String| {
+                f(pane, &content_type);
+            }),
+        )
+    }
+
+    /// Pastes the clipboard as a JSON or XML body, indented, switching the body type.
+    ///
+    /// Text that is not valid in the given format is pasted as usual instead.
+    fn paste_as(&self, format: PayloadType) {
+        let clipboard = self.clipboard();
+        glib::spawn_future_local(glib::clone!(@weak self as tab => async move {
+            let Ok(Some(text)) = clipboard.read_text_future().await else {
+                return;
+            };
+            let (pretty, content_type) = match format {
+                PayloadType::Json => (pretty_json(&text), "application/json"),
+                PayloadType::Xml => (pretty_xml(&text), "application/xml"),
+                _ => return,
+            };
+            let raw = tab.imp().raw();
+            match pretty {
+                Ok(pretty) => {
+                    tab.set_payload_type(format);
+                    if raw.paste_text(&pretty) {
+                        tab.emit_by_name::<()>("content-type-pasted", &[&content_type]);
+                    }
+                }
+                Err(_) => {
+                    let raw_active = matches!(
+                        tab.payload_type(),
+                        PayloadType::Json | PayloadType::Xml | PayloadType::Raw
+                    );
+                    if raw_active {
+                        raw.paste_clipboard();
+                    }
+                }
+            }
+        }));
+    }
+
     pub fn set_payload(&self, payload: &RequestPayload) {
         let payload_type = match payload {
             RequestPayload::None => PayloadType::None,
             RequestPayload::Urlencoded(_) => PayloadType::UrlEncoded,
             RequestPayload::Multipart { params: _ } => PayloadType::MultipartFormData,
-            RequestPayload::Raw {
-                encoding,
-                content: _,
-            } => match encoding {
+            RequestPayload::Raw { encoding, .. } => match encoding {
                 RawEncoding::Json => PayloadType::Json,
                 RawEncoding::Xml => PayloadType::Xml,
                 RawEncoding::OctetStream => PayloadType::Raw,
@@ -244,10 +385,24 @@ impl PayloadTab {
             PayloadType::Json | PayloadType::Xml | PayloadType::Raw => {
                 let widget = widget.and_downcast::<RawPayloadPane>().unwrap();
                 widget.set_payload(payload);
+                if let RequestPayload::Raw { charset, .. } = payload {
+                    self.imp().set_charset(*charset);
+                }
             }
         }
     }
 
+    /// Indents the body if it is JSON or XML.
+    pub fn format_body(&self) {
+        if let Some(raw) = self
+            .imp()
+            .get_active_widget()
+            .and_downcast::<RawPayloadPane>()
+        {
+            raw.format_body();
+        }
+    }
+
     pub fn payload(&self) -> RequestPayload {
         let widget = self.imp().get_active_widget();
         match self.payload_type() {
@@ -262,7 +417,20 @@ impl PayloadTab {
             }
             PayloadType::Json | PayloadType::Xml | PayloadType::Raw => {
                 let widget = widget.and_downcast::<RawPayloadPane>().unwrap();
-                widget.payload()
+                match widget.payload() {
+                    RequestPayload::Raw {
+                        encoding,
+                        content,
+                        file,
+                        ..
+                    } => RequestPayload::Raw {
+                        encoding,
+                        charset: self.imp().charset(),
+                        content,
+                        file,
+                    },
+                    otherwise => otherwise,
+                }
             }
         }
     }
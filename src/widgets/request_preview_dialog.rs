@@ -0,0 +1,95 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::prelude::*;
+
+use crate::client::{BoundRequest, PREVIEW_BODY_LIMIT};
+
+mod imp {
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::prelude::*;
+    use gtk::CompositeTemplate;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/request_preview_dialog.ui")]
+    pub struct RequestPreviewDialog {
+        #[template_child]
+        pub preview: TemplateChild<gtk::TextView>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RequestPreviewDialog {
+        const NAME: &'static str = "CarteroRequestPreviewDialog";
+        type Type = super::RequestPreviewDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for RequestPreviewDialog {}
+
+    impl WidgetImpl for RequestPreviewDialog {}
+
+    impl AdwDialogImpl for RequestPreviewDialog {}
+
+    #[gtk::template_callbacks]
+    impl RequestPreviewDialog {
+        #[template_callback]
+        fn on_copy_clicked(&self) {
+            let buffer = self.preview.buffer();
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, false);
+            self.obj().clipboard().set_text(&text);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct RequestPreviewDialog(ObjectSubclass<imp::RequestPreviewDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for RequestPreviewDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl RequestPreviewDialog {
+    pub fn new(request: &BoundRequest) -> Self {
+        let dialog = Self::default();
+        dialog.set_request(request);
+        dialog
+    }
+
+    pub fn set_request(&self, request: &BoundRequest) {
+        let imp = self.imp();
+        let text = request.to_http_text(PREVIEW_BODY_LIMIT);
+        imp.preview.buffer().set_text(&text);
+    }
+}
@@ -15,27 +15,70 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use glib::subclass::types::ObjectSubclassIsExt;
 use glib::Object;
+use gtk::gio::{SimpleAction, SimpleActionGroup};
+use gtk::prelude::*;
+
+use crate::entities::{KeyValue, KeyValueTable};
+use crate::export::{header_wire_size, headers_to_curl_flags, headers_to_json, headers_to_wire};
+use crate::objects::KeyValueItem;
+
+/// The bytes taken by a header in the response, as shown in the size column.
+fn item_size(item: &KeyValueItem) -> usize {
+    header_wire_size(&item.header_name(), &item.header_value())
+}
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{OnceCell, RefCell};
 
     use adw::prelude::*;
     use adw::subclass::prelude::*;
+    use gettextrs::ngettext;
     use glib::{subclass::InitializingObject, Properties};
-    use gtk::{gio::ListModel, CompositeTemplate, ListBox, TemplateChild};
+    use gtk::{gio, gio::ListModel, CompositeTemplate, TemplateChild};
 
     use crate::objects::KeyValueItem;
+    use crate::utils::format_bytes;
+
+    use super::item_size;
+
+    /// Gives the text shown by a column for a header.
+    type ColumnText = fn(&KeyValueItem) -> String;
 
     #[derive(Default, CompositeTemplate, Properties)]
     #[properties(wrapper_type = super::ResponseHeaders)]
     #[template(resource = "/es/danirod/Cartero/response_headers.ui")]
     pub struct ResponseHeaders {
         #[template_child]
-        list_box: TemplateChild<ListBox>,
+        search: TemplateChild<gtk::SearchEntry>,
+
+        #[template_child]
+        summary: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        column_view: TemplateChild<gtk::ColumnView>,
+
+        #[template_child]
+        name_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        value_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        size_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub(super) header_menu: TemplateChild<gio::MenuModel>,
 
         #[property(name = "headers", set = Self::set_headers, nullable)]
         headers: RefCell<Option<ListModel>>,
+
+        /// Hides the headers whose name and value do not contain the search text.
+        filter: OnceCell<gtk::CustomFilter>,
+
+        /// The header whose context menu was opened last.
+        pub(super) menu_item: RefCell<Option<KeyValueItem>>,
     }
 
     #[glib::object_subclass]
@@ -46,6 +89,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -54,35 +98,158 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for ResponseHeaders {}
+    impl ObjectImpl for ResponseHeaders {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().setup_actions();
+            self.init_columns();
+        }
+    }
 
     impl WidgetImpl for ResponseHeaders {}
 
     impl BinImpl for ResponseHeaders {}
 
+    #[gtk::template_callbacks]
+    impl ResponseHeaders {
+        #[template_callback]
+        fn on_search_changed(&self) {
+            self.filter().changed(gtk::FilterChange::Different);
+        }
+    }
+
     impl ResponseHeaders {
+        fn filter(&self) -> &gtk::CustomFilter {
+            self.filter.get_or_init(|| {
+                let search = self.search.downgrade();
+                gtk::CustomFilter::new(move |item| {
+                    let Some(search) = search.upgrade() else {
+                        return true;
+                    };
+                    let text = search.text().to_lowercase();
+                    let item = item.downcast_ref::<KeyValueItem>().unwrap();
+                    text.is_empty()
+                        || item.header_name().to_lowercase().contains(&text)
+                        || item.header_value().to_lowercase().contains(&text)
+                })
+            })
+        }
+
+        fn init_columns(&self) {
+            let columns: [(&gtk::ColumnViewColumn, ColumnText); 3] = [
+                (&self.name_column, |item| item.header_name()),
+                (&self.value_column, |item| item.header_value()),
+                (&self.size_column, |item| item_size(item).to_string()),
+            ];
+            for (column, text) in columns {
+                let numeric = column == &*self.size_column;
+                let factory = gtk::SignalListItemFactory::new();
+                factory.connect_setup(glib::clone!(@weak self as widget => move |_, item| {
+                    let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                    let label = gtk::Label::builder()
+                        .xalign(if numeric { 1.0 } else { 0.0 })
+                        .ellipsize(gtk::pango::EllipsizeMode::End)
+                        .build();
+                    if numeric {
+                        label.add_css_class("numeric");
+                    }
+
+                    let gesture = gtk::GestureClick::builder()
+                        .button(gtk::gdk::BUTTON_SECONDARY)
+                        .build();
+                    gesture.connect_pressed(
+                        glib::clone!(@weak widget, @weak item, @weak label => move |gesture, _, x, y| {
+                            gesture.set_state(gtk::EventSequenceState::Claimed);
+                            widget.menu_item.replace(item.item().and_downcast::<KeyValueItem>());
+                            widget.popup_menu(label.upcast_ref(), x, y);
+                        }),
+                    );
+                    label.add_controller(gesture);
+                    item.set_child(Some(&label));
+                }));
+                factory.connect_bind(move |_, item| {
+                    let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                    let (Some(label), Some(header)) = (
+                        item.child().and_downcast::<gtk::Label>(),
+                        item.item().and_downcast::<KeyValueItem>(),
+                    ) else {
+                        return;
+                    };
+                    let text = text(&header);
+                    label.set_tooltip_text(Some(&text));
+                    label.set_text(&text);
+                });
+                column.set_factory(Some(&factory));
+            }
+
+            let property = |name: &str| {
+                gtk::PropertyExpression::new(
+                    KeyValueItem::static_type(),
+                    None::<&gtk::Expression>,
+                    name,
+                )
+            };
+            self.name_column
+                .set_sorter(Some(&gtk::StringSorter::new(Some(property("header-name")))));
+            self.value_column
+                .set_sorter(Some(&gtk::StringSorter::new(Some(property(
+                    "header-value",
+                )))));
+            self.size_column
+                .set_sorter(Some(&gtk::CustomSorter::new(|a, b| {
+                    let a = item_size(a.downcast_ref::<KeyValueItem>().unwrap());
+                    let b = item_size(b.downcast_ref::<KeyValueItem>().unwrap());
+                    a.cmp(&b).into()
+                })));
+        }
+
         fn set_headers(&self, model: Option<ListModel>) {
             match model {
                 Some(ref model) => {
-                    self.list_box.bind_model(Some(model), |item| {
-                        let item = item.downcast_ref::<KeyValueItem>().unwrap();
-                        let widget = adw::ActionRow::new();
-                        widget.set_use_markup(false);
-                        widget.set_title(&item.header_name());
-                        widget.set_title_selectable(true);
-                        widget.set_subtitle(&item.header_value());
-                        widget.set_subtitle_selectable(true);
-                        widget.add_css_class("property");
-                        widget.upcast::<gtk::Widget>()
-                    });
-                    self.list_box.set_visible(true);
+                    let filtered =
+                        gtk::FilterListModel::new(Some(model.clone()), Some(self.filter().clone()));
+                    let sorted = gtk::SortListModel::new(Some(filtered), self.column_view.sorter());
+                    let selection = gtk::NoSelection::new(Some(sorted));
+                    self.column_view.set_model(Some(&selection));
                 }
                 None => {
-                    self.list_box.unbind_model();
-                    self.list_box.set_visible(false);
+                    self.column_view.set_model(None::<&gtk::SelectionModel>);
                 }
             }
             *self.headers.borrow_mut() = model;
+            self.update_summary();
+        }
+
+        /// Opens the context menu of a header, at the given coordinates of the cell.
+        fn popup_menu(&self, cell: &gtk::Widget, x: f64, y: f64) {
+            let popover = gtk::PopoverMenu::from_model(Some(&*self.header_menu));
+            popover.set_parent(cell);
+            popover.set_has_arrow(false);
+            popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popover.connect_closed(|popover| {
+                // Wait until the menu action has been activated before dropping the popover.
+                glib::idle_add_local_once(glib::clone!(@weak popover => move || {
+                    popover.unparent();
+                }));
+            });
+            popover.popup();
+        }
+
+        pub(super) fn headers(&self) -> Option<ListModel> {
+            self.headers.borrow().clone()
+        }
+
+        /// Shows how many headers the response has and how many bytes they take.
+        fn update_summary(&self) {
+            let items: Vec<KeyValueItem> = self
+                .headers()
+                .map(|model| model.iter::<KeyValueItem>().flatten().collect())
+                .unwrap_or_default();
+            let size: usize = items.iter().map(item_size).sum();
+            let count = ngettext("{count} header", "{count} headers", items.len() as u32)
+                .replace("{count}", &items.len().to_string());
+            self.summary
+                .set_text(&format!("{count} · {}", format_bytes(size)));
         }
     }
 }
@@ -96,6 +263,50 @@ impl ResponseHeaders {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Collects every header of the response, in order, even if it is filtered out.
+    pub fn table(&self) -> KeyValueTable {
+        let Some(model) = self.imp().headers() else {
+            return KeyValueTable::default();
+        };
+        model
+            .iter::<KeyValueItem>()
+            .flatten()
+            .map(KeyValue::from)
+            .collect()
+    }
+
+    fn copy_text(&self, text: &str) {
+        self.clipboard().set_text(text);
+    }
+
+    fn setup_actions(&self) {
+        let ag = SimpleActionGroup::new();
+        self.insert_action_group("headers", Some(&ag));
+
+        let copy_header = SimpleAction::new("copy-header", None);
+        copy_header.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            let item = widget.imp().menu_item.borrow().clone();
+            if let Some(item) = item {
+                let table = KeyValueTable::new(&[KeyValue::from(item)]);
+                widget.copy_text(headers_to_wire(&table).trim_end());
+            }
+        }));
+
+        let copy_json = SimpleAction::new("copy-json", None);
+        copy_json.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            widget.copy_text(&headers_to_json(&widget.table()));
+        }));
+
+        let copy_curl = SimpleAction::new("copy-curl", None);
+        copy_curl.connect_activate(glib::clone!(@weak self as widget => move |_, _| {
+            widget.copy_text(&headers_to_curl_flags(&widget.table()));
+        }));
+
+        ag.add_action(&copy_header);
+        ag.add_action(&copy_json);
+        ag.add_action(&copy_curl);
+    }
 }
 
 impl Default for ResponseHeaders {
@@ -17,28 +17,36 @@
 
 use std::path::PathBuf;
 
+use gettextrs::{gettext, ngettext};
 use glib::Object;
 use gtk::gio::{ListModel, ListStore};
 use gtk::glib;
 use gtk::prelude::TextViewExt;
 use gtk::prelude::*;
-use serde_json::Value;
 use sourceview5::prelude::BufferExt;
 use sourceview5::LanguageManager;
 
-use crate::entities::ResponseData;
+use crate::client::{ConnectionInfo, DownloadProgress, SseEvent};
+use crate::entities::{RequestMethod, ResponseData};
+use crate::error::CarteroError;
 use crate::objects::KeyValueItem;
+use crate::tools::jwt::find_jwt;
+use crate::utils::{format_bytes, format_duration};
 use glib::subclass::types::ObjectSubclassIsExt;
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::OnceLock;
 
     use adw::prelude::*;
     use adw::subclass::bin::BinImpl;
+    use encoding_rs::Encoding;
+    use gettextrs::{gettext, ngettext};
     use glib::object::Cast;
-    use glib::subclass::InitializingObject;
+    use glib::subclass::{InitializingObject, Signal};
     use glib::Properties;
-    use gtk::gio::SettingsBindFlags;
+    use gtk::gio::{self, SettingsBindFlags};
     use gtk::subclass::prelude::*;
     use gtk::{
         subclass::widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
@@ -48,15 +56,32 @@ mod imp {
     use sourceview5::prelude::BufferExt;
     use sourceview5::StyleSchemeManager;
 
+    use crate::analysis::{extracted_text, select};
     use crate::app::CarteroApplication;
-    use crate::widgets::ResponseHeaders;
+    use crate::client::SseEvent;
+    use crate::diff::{DiffLineKind, HeaderChange, ResponseDiff};
+    use crate::entities::ResponseData;
+    use crate::error::CarteroError;
+    use crate::export::{
+        body_extension, headers_to_wire, response_to_json, save_response, JsonSource,
+        ResponseExportFormat,
+    };
+    use crate::tools::jwt::Jwt;
+    use crate::utils::{
+        display_encodings, hex_dump, json_path, scalar_text, JsonKind, JsonNode, JsonSearch,
+        JsonTree, PathSegment, ResponseViewer,
+    };
+    use crate::widgets::{save_response_file, ResponseHeaders};
+    use crate::win::CarteroWindow;
 
     #[derive(CompositeTemplate, Default, Properties)]
     #[properties(wrapper_type = super::ResponsePanel)]
     #[template(resource = "/es/danirod/Cartero/response_panel.ui")]
     pub struct ResponsePanel {
         #[template_child]
-        stack: TemplateChild<gtk::Stack>,
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub error_page: TemplateChild<adw::StatusPage>,
         #[template_child]
         pub response_headers: TemplateChild<ResponseHeaders>,
         #[template_child]
@@ -66,16 +91,122 @@ mod imp {
         #[template_child]
         pub status_code: TemplateChild<Label>,
         #[template_child]
+        pub http_version: TemplateChild<Label>,
+        #[template_child]
         pub duration: TemplateChild<Label>,
         #[template_child]
         pub response_size: TemplateChild<Label>,
         #[template_child]
+        pub decode_warning: TemplateChild<Label>,
+        #[template_child]
+        pub attempts: TemplateChild<Label>,
+        #[template_child]
+        pub progress: TemplateChild<Box>,
+        #[template_child]
+        pub attempt_status: TemplateChild<Label>,
+        #[template_child]
+        pub download_status: TemplateChild<Label>,
+        #[template_child]
+        pub download_bar: TemplateChild<gtk::ProgressBar>,
+        #[template_child]
         pub spinner: TemplateChild<Spinner>,
         #[template_child]
+        pub stop: TemplateChild<gtk::Button>,
+        #[template_child]
         pub metadata_stack: TemplateChild<Stack>,
+        #[template_child]
+        pub body_stack: TemplateChild<Stack>,
+        #[template_child]
+        pub notebook: TemplateChild<gtk::Notebook>,
+        #[template_child]
+        pub method_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        pub image_size: TemplateChild<Label>,
+        #[template_child]
+        pub response_image: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub hex_view: TemplateChild<gtk::TextView>,
+        #[template_child]
+        pub tree_search: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub tree_view: TemplateChild<gtk::ColumnView>,
+        #[template_child]
+        pub tree_name_column: TemplateChild<gtk::ColumnViewColumn>,
+        #[template_child]
+        pub tree_value_column: TemplateChild<gtk::ColumnViewColumn>,
+        #[template_child]
+        pub viewer_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub encoding_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub diff_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub diff_summary: TemplateChild<Label>,
+        #[template_child]
+        pub diff_view: TemplateChild<gtk::TextView>,
+        #[template_child]
+        pub events_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub events: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub extract_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub extract_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub extract_error: TemplateChild<Label>,
+        #[template_child]
+        pub extract_results: TemplateChild<Label>,
+        #[template_child]
+        pub variable_name: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub set_variable_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub jwt_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub jwt_expiry: TemplateChild<Label>,
+        #[template_child]
+        pub jwt_header: TemplateChild<Label>,
+        #[template_child]
+        pub jwt_payload: TemplateChild<Label>,
+        #[template_child]
+        pub jwt_signature: TemplateChild<Label>,
 
         #[property(get = Self::spinning, set = Self::set_spinning)]
         _spinning: RefCell<bool>,
+
+        pub current_response: RefCell<Option<ResponseData>>,
+        pub previous_response: RefCell<Option<ResponseData>>,
+
+        /// Bumped every time a diff is requested, so that a diff that finishes after a
+        /// newer one was requested is discarded instead of shown.
+        diff_generation: Cell<u64>,
+
+        /// The viewers that already rendered the current response.
+        rendered_viewers: RefCell<Vec<ResponseViewer>>,
+
+        /// The encodings listed in the encoding dropdown, in the same order.
+        encodings: RefCell<Vec<&'static Encoding>>,
+
+        /// Set while the detected encoding is selected, so the body is not decoded twice.
+        selecting_encoding: Cell<bool>,
+
+        /// The body of the current response parsed as JSON, once the tree viewer is shown.
+        json_tree: RefCell<Option<Rc<JsonTree>>>,
+
+        #[cfg(feature = "webkit")]
+        html_view: std::cell::OnceCell<webkit6::WebView>,
+
+        /// How many rows are currently in the list of streamed events.
+        pub event_rows: Cell<u32>,
+
+        /// Decodes the body being streamed, keeping the characters split between chunks.
+        body_decoder: RefCell<Option<encoding_rs::Decoder>>,
+
+        /// The value picked by the JSONPath expression, as it would be stored in a variable.
+        extracted: RefCell<Option<String>>,
+
+        /// The token found in the current response, if any.
+        jwt: RefCell<Option<Jwt>>,
     }
 
     #[glib::object_subclass]
@@ -86,6 +217,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -95,11 +227,30 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for ResponsePanel {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("extract-variable")
+                        .param_types([String::static_type(), String::static_type()])
+                        .build(),
+                    Signal::builder("set-bearer-token")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
         fn constructed(&self) {
             self.parent_constructed();
 
             self.init_settings();
             self.init_source_view_style();
+            self.init_diff_tags();
+            self.init_extract_tags();
+            self.init_actions();
+            self.init_encodings();
+            self.init_tree_columns();
         }
     }
 
@@ -107,7 +258,853 @@ mod imp {
 
     impl BinImpl for ResponsePanel {}
 
+    #[gtk::template_callbacks]
     impl ResponsePanel {
+        #[template_callback]
+        fn on_diff_toggled(&self) {
+            if self.diff_toggle.is_active() {
+                self.body_stack.set_visible_child_name("diff");
+                self.update_diff();
+            } else {
+                self.show_viewer();
+            }
+        }
+
+        #[template_callback]
+        fn on_viewer_selected(&self) {
+            if self.diff_toggle.is_active() {
+                // Untoggling the diff already brings back the selected viewer.
+                self.diff_toggle.set_active(false);
+            } else {
+                self.show_viewer();
+            }
+        }
+
+        #[template_callback]
+        fn on_encoding_selected(&self) {
+            if self.selecting_encoding.get() {
+                return;
+            }
+            self.rendered_viewers
+                .borrow_mut()
+                .retain(|viewer| !matches!(viewer, ResponseViewer::Html | ResponseViewer::Tree));
+            self.render_text();
+            self.show_viewer();
+        }
+
+        #[template_callback]
+        fn on_tree_search_changed(&self) {
+            self.build_tree_model();
+        }
+
+        /// Copies the path of a value, or an object or array as JSON.
+        #[template_callback]
+        fn on_tree_activated(&self, position: u32) {
+            let Some(row) = self
+                .tree_view
+                .model()
+                .and_then(|model| model.item(position))
+                .and_downcast::<gtk::TreeListRow>()
+            else {
+                return;
+            };
+            let Some(node) = tree_node(&row) else {
+                return;
+            };
+            let tree = self.json_tree.borrow().clone();
+            let Some(tree) = tree else {
+                return;
+            };
+            let text = match &node {
+                JsonNode::Chunk { .. } => {
+                    row.set_expanded(!row.is_expanded());
+                    return;
+                }
+                JsonNode::Value { path } => match tree.value(path).map(JsonKind::of) {
+                    Some(JsonKind::Object | JsonKind::Array) => tree.subtree_json(path),
+                    Some(_) => Some(json_path(path)),
+                    None => None,
+                },
+            };
+            if let Some(text) = text {
+                self.obj().clipboard().set_text(&text);
+            }
+        }
+
+        #[template_callback]
+        fn on_extract_toggled(&self) {
+            if self.extract_toggle.is_active() {
+                self.extract_entry.grab_focus();
+            }
+            self.update_extract();
+        }
+
+        #[template_callback]
+        fn on_extract_changed(&self) {
+            self.update_extract();
+        }
+
+        #[template_callback]
+        fn on_variable_name_changed(&self) {
+            self.sync_set_variable();
+        }
+
+        #[template_callback]
+        fn on_set_variable_clicked(&self) {
+            let name = self.variable_name.text().trim().to_string();
+            let Some(value) = self.extracted.borrow().clone() else {
+                return;
+            };
+            if !name.is_empty() {
+                self.obj()
+                    .emit_by_name::<()>("extract-variable", &[&name, &value]);
+            }
+        }
+
+        #[template_callback]
+        fn on_set_bearer_clicked(&self) {
+            let token = self.jwt.borrow().as_ref().map(|jwt| jwt.token.clone());
+            if let Some(token) = token {
+                self.obj().emit_by_name::<()>("set-bearer-token", &[&token]);
+            }
+        }
+
+        #[template_callback]
+        fn on_open_image_clicked(&self) {
+            if let Err(e) = self.open_image() {
+                if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                    window.toast_error(e);
+                }
+            }
+        }
+
+        #[template_callback]
+        fn on_copy_headers_clicked(&self) {
+            let wire = headers_to_wire(&self.response_headers.table());
+            self.obj().clipboard().set_text(&wire);
+        }
+
+        #[template_callback]
+        fn on_copy_json_clicked(&self) {
+            let Some(response) = self.current_response.borrow().clone() else {
+                return;
+            };
+            let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() else {
+                return;
+            };
+            let content_type = response
+                .headers
+                .header("content-type")
+                .and_then(|values| values.first().map(|v| v.to_string()))
+                .unwrap_or_default();
+            match response_to_json(&content_type, &response.body) {
+                Ok(json) => {
+                    self.obj().clipboard().set_text(&json);
+                    let lossy = JsonSource::detect(&content_type, &response.body).is_lossy();
+                    let message = if lossy {
+                        gettext(
+                            "Response copied as JSON, but some details of the document could not be kept",
+                        )
+                    } else {
+                        gettext("Response copied as JSON")
+                    };
+                    window.toast_message(&message);
+                }
+                Err(e) => window.toast_error(e),
+            }
+        }
+    }
+
+    impl ResponsePanel {
+        /// Switches the body tab into the live list of events of a stream.
+        pub(super) fn start_stream(&self) {
+            self.events.remove_all();
+            self.event_rows.set(0);
+            self.diff_toggle.set_active(false);
+            self.diff_toggle.set_sensitive(false);
+            self.viewer_dropdown.set_sensitive(false);
+            self.encoding_dropdown.set_sensitive(false);
+            self.image_size.set_visible(false);
+            self.body_stack.set_visible_child_name("events");
+            self.stop
+                .set_tooltip_text(Some(&gettext("Close the event stream")));
+            self.stop.set_visible(true);
+        }
+
+        /// Empties the text viewer, so that the body can be shown as it arrives.
+        pub(super) fn start_body_stream(&self, encoding: &'static Encoding) {
+            self.body_decoder
+                .replace(Some(encoding.new_decoder_with_bom_removal()));
+            self.response_body.buffer().set_text("");
+            self.diff_toggle.set_active(false);
+            self.diff_toggle.set_sensitive(false);
+            self.viewer_dropdown.set_sensitive(false);
+            self.encoding_dropdown.set_sensitive(false);
+            self.image_size.set_visible(false);
+            self.body_stack.set_visible_child_name("body");
+            self.stop
+                .set_tooltip_text(Some(&gettext("Stop receiving the body")));
+            self.stop.set_visible(true);
+        }
+
+        pub(super) fn append_body_chunk(&self, chunk: &[u8]) {
+            let mut decoder = self.body_decoder.borrow_mut();
+            let Some(decoder) = decoder.as_mut() else {
+                return;
+            };
+            let capacity = decoder
+                .max_utf8_buffer_length(chunk.len())
+                .unwrap_or(chunk.len() * 3);
+            let mut text = String::with_capacity(capacity);
+            let _ = decoder.decode_to_string(chunk, &mut text, false);
+
+            let buffer = self.response_body.buffer();
+            buffer.insert(&mut buffer.end_iter(), &text);
+            buffer.place_cursor(&buffer.end_iter());
+            self.response_body
+                .scroll_mark_onscreen(&buffer.get_insert());
+        }
+
+        /// Switches the body tab into the viewer that suits the current response.
+        pub(super) fn end_stream(&self) {
+            self.body_decoder.replace(None);
+            let viewer = match &*self.current_response.borrow() {
+                Some(response) => ResponseViewer::detect(response),
+                None => ResponseViewer::Text,
+            };
+            self.viewer_dropdown.set_sensitive(true);
+            self.encoding_dropdown.set_sensitive(true);
+            // Won't notify if the viewer is the same as in the previous response.
+            self.viewer_dropdown.set_selected(viewer.position());
+            self.show_viewer();
+        }
+
+        pub(super) fn append_event(&self, event: &SseEvent) {
+            let time = glib::DateTime::now_local()
+                .and_then(|now| now.format("%H:%M:%S"))
+                .map(|t| t.to_string())
+                .unwrap_or_default();
+            let header = match &event.id {
+                Some(id) => format!("{time} {} #{id}", event.event),
+                None => format!("{time} {}", event.event),
+            };
+            let label = Label::builder()
+                .label(format!("{header}\n{}", event.data))
+                .xalign(0.0)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .selectable(true)
+                .margin_start(6)
+                .margin_end(6)
+                .margin_top(3)
+                .margin_bottom(3)
+                .build();
+            self.events.append(&label);
+
+            let max_rows = self.max_event_rows();
+            let mut rows = self.event_rows.get() + 1;
+            while rows > max_rows {
+                match self.events.row_at_index(0) {
+                    Some(first) => self.events.remove(&first),
+                    None => break,
+                }
+                rows -= 1;
+            }
+            self.event_rows.set(rows);
+
+            glib::idle_add_local_once(glib::clone!(@weak self as panel => move || {
+                let adjustment = panel.events_window.vadjustment();
+                adjustment.set_value(adjustment.upper());
+            }));
+        }
+
+        /// How many streamed events are kept on screen before dropping the older ones.
+        fn max_event_rows(&self) -> u32 {
+            let app = CarteroApplication::get();
+            let value = app.settings().string("stream-max-events");
+            value.parse::<u32>().unwrap_or(500).max(1)
+        }
+    }
+
+    impl ResponsePanel {
+        fn init_diff_tags(&self) {
+            let table = self.diff_view.buffer().tag_table();
+            let tags = [
+                gtk::TextTag::builder()
+                    .name("added")
+                    .paragraph_background("rgba(46, 194, 126, 0.2)")
+                    .build(),
+                gtk::TextTag::builder()
+                    .name("removed")
+                    .paragraph_background("rgba(224, 27, 36, 0.2)")
+                    .build(),
+                gtk::TextTag::builder()
+                    .name("hunk")
+                    .foreground("rgba(128, 128, 128, 1.0)")
+                    .build(),
+            ];
+            for tag in tags {
+                table.add(&tag);
+            }
+        }
+
+        fn init_encodings(&self) {
+            let encodings = display_encodings();
+            let names: Vec<&str> = encodings.iter().map(|encoding| encoding.name()).collect();
+            self.encoding_dropdown
+                .set_model(Some(&gtk::StringList::new(&names)));
+            self.encodings.replace(encodings.to_vec());
+        }
+
+        /// The position of an encoding in the dropdown, adding it if it is not listed.
+        fn encoding_position(&self, encoding: &'static Encoding) -> u32 {
+            let mut encodings = self.encodings.borrow_mut();
+            if let Some(position) = encodings.iter().position(|e| *e == encoding) {
+                return position as u32;
+            }
+            encodings.push(encoding);
+            if let Some(model) = self
+                .encoding_dropdown
+                .model()
+                .and_downcast::<gtk::StringList>()
+            {
+                model.append(encoding.name());
+            }
+            (encodings.len() - 1) as u32
+        }
+
+        fn selected_encoding(&self) -> &'static Encoding {
+            let position = self.encoding_dropdown.selected() as usize;
+            self.encodings
+                .borrow()
+                .get(position)
+                .copied()
+                .unwrap_or(encoding_rs::UTF_8)
+        }
+
+        /// The encoding of bodies that are not valid UTF-8 and do not announce one.
+        fn fallback_encoding(&self) -> &'static Encoding {
+            let app = CarteroApplication::get();
+            let label = app.settings().string("response-encoding");
+            Encoding::for_label(label.as_bytes()).unwrap_or(encoding_rs::WINDOWS_1252)
+        }
+
+        /// Decodes the body of the current response into the text viewer.
+        ///
+        /// The raw bytes are kept in the response, so picking another encoding
+        /// decodes them again without sending the request again.
+        pub(super) fn render_text(&self) {
+            let response = self.current_response.borrow();
+            let body = response
+                .as_ref()
+                .map(|response| response.pretty_body_str_as(self.selected_encoding()))
+                .unwrap_or_default();
+            self.response_body.buffer().set_text(&body);
+            drop(response);
+            self.update_extract();
+        }
+
+        fn init_extract_tags(&self) {
+            self.response_body.buffer().create_tag(
+                Some("jsonpath-match"),
+                &[("background", &"rgba(246, 211, 45, 0.35)")],
+            );
+        }
+
+        /// Evaluates the JSONPath expression over the current response, highlighting
+        /// the matches in the text viewer and listing them below the expression.
+        fn update_extract(&self) {
+            let buffer = self.response_body.buffer();
+            let (start, end) = buffer.bounds();
+            buffer.remove_tag_by_name("jsonpath-match", &start, &end);
+            self.extracted.replace(None);
+            self.extract_error.set_visible(false);
+            self.extract_results.set_label("");
+
+            let expression = self.extract_entry.text();
+            if !self.extract_toggle.is_active() || expression.trim().is_empty() {
+                self.sync_set_variable();
+                return;
+            }
+            let document = self
+                .current_response
+                .borrow()
+                .as_ref()
+                .and_then(|response| {
+                    let body = response.body_str_as(self.selected_encoding());
+                    serde_json::from_str::<serde_json::Value>(&body).ok()
+                });
+            let Some(document) = document else {
+                self.extract_error
+                    .set_label(&gettext("The response is not a valid JSON document"));
+                self.extract_error.set_visible(true);
+                self.sync_set_variable();
+                return;
+            };
+
+            match select(&document, expression.trim()) {
+                Ok(matches) => {
+                    // The matches can only be found in the body if it is shown indented.
+                    let text = buffer.text(&start, &end, false);
+                    let indented = serde_json::to_string_pretty(&document)
+                        .is_ok_and(|pretty| pretty == text.as_str());
+                    if indented {
+                        for m in &matches {
+                            let from = buffer.iter_at_offset(m.span.start as i32);
+                            let to = buffer.iter_at_offset(m.span.end as i32);
+                            buffer.apply_tag_by_name("jsonpath-match", &from, &to);
+                        }
+                        if let Some(first) = matches.first() {
+                            let mut iter = buffer.iter_at_offset(first.span.start as i32);
+                            self.response_body
+                                .scroll_to_iter(&mut iter, 0.1, false, 0.0, 0.0);
+                        }
+                    }
+
+                    let results = if matches.is_empty() {
+                        gettext("No matches")
+                    } else {
+                        matches
+                            .iter()
+                            .enumerate()
+                            .map(|(i, m)| format!("{}. {}", i + 1, m.value))
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    };
+                    self.extract_results.set_label(&results);
+                    self.extracted.replace(extracted_text(&matches));
+                }
+                Err(e) => {
+                    self.extract_error.set_label(&e);
+                    self.extract_error.set_visible(true);
+                }
+            }
+            self.sync_set_variable();
+        }
+
+        /// Shows the parts of the token found in the response, or hides the
+        /// decoder if there is none.
+        pub(super) fn show_jwt(&self, jwt: Option<Jwt>) {
+            self.jwt_toggle.set_visible(jwt.is_some());
+            let Some(jwt) = jwt else {
+                self.jwt_toggle.set_active(false);
+                self.jwt.replace(None);
+                return;
+            };
+
+            let pretty = |value: &serde_json::Value| {
+                serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+            };
+            self.jwt_header.set_label(&pretty(&jwt.header));
+            self.jwt_payload.set_label(&pretty(&jwt.payload));
+            self.jwt_signature.set_label(&jwt.signature);
+
+            let now = glib::DateTime::now_utc()
+                .map(|now| now.to_unix())
+                .unwrap_or_default();
+            let expired = jwt.is_expired_at(now);
+            let expiry = jwt.expires_at().and_then(|exp| {
+                let date = glib::DateTime::from_unix_local(exp)
+                    .and_then(|date| date.format("%c"))
+                    .ok()?;
+                Some(if expired {
+                    gettext("Expired on {date}").replace("{date}", &date)
+                } else {
+                    gettext("Expires on {date}").replace("{date}", &date)
+                })
+            });
+            match expiry {
+                Some(expiry) => {
+                    self.jwt_expiry.set_label(&expiry);
+                    self.jwt_expiry.set_visible(true);
+                }
+                None => self.jwt_expiry.set_visible(false),
+            }
+            if expired {
+                self.jwt_expiry.add_css_class("error");
+            } else {
+                self.jwt_expiry.remove_css_class("error");
+            }
+            self.jwt.replace(Some(jwt));
+        }
+
+        fn sync_set_variable(&self) {
+            let has_name = !self.variable_name.text().trim().is_empty();
+            let has_value = self.extracted.borrow().is_some();
+            self.set_variable_button
+                .set_sensitive(has_name && has_value);
+        }
+
+        /// Stores a new response, keeping the one that was visible before for diffing.
+        pub(super) fn push_response(&self, response: &ResponseData) {
+            self.rendered_viewers.borrow_mut().clear();
+            let encoding = response.detect_encoding(self.fallback_encoding());
+            let position = self.encoding_position(encoding);
+            self.selecting_encoding.set(true);
+            self.encoding_dropdown.set_selected(position);
+            self.selecting_encoding.set(false);
+            let previous = self.current_response.replace(Some(response.clone()));
+            let has_previous = previous.is_some();
+            self.previous_response.replace(previous);
+            self.diff_toggle.set_sensitive(has_previous);
+            if self.diff_toggle.is_active() {
+                self.update_diff();
+            }
+        }
+
+        /// Shows the body using the viewer picked in the dropdown.
+        ///
+        /// Each viewer renders the cached response the first time it is shown,
+        /// so switching between viewers never sends the request again.
+        fn show_viewer(&self) {
+            if self.diff_toggle.is_active() || !self.viewer_dropdown.is_sensitive() {
+                return;
+            }
+            let viewer =
+                ResponseViewer::from_position(self.viewer_dropdown.selected()).unwrap_or_default();
+            let response = self.current_response.borrow();
+            let Some(response) = response.as_ref() else {
+                return;
+            };
+            self.image_size.set_visible(false);
+            match viewer {
+                ResponseViewer::Text => self.body_stack.set_visible_child_name("body"),
+                ResponseViewer::Tree => self.show_tree(response),
+                ResponseViewer::Image => self.show_image(response),
+                ResponseViewer::Html => self.show_html(response),
+                ResponseViewer::Hex => self.show_hex(response),
+            }
+        }
+
+        /// Whether a viewer still has to render the current response.
+        fn needs_render(&self, viewer: ResponseViewer) -> bool {
+            let mut rendered = self.rendered_viewers.borrow_mut();
+            if rendered.contains(&viewer) {
+                false
+            } else {
+                rendered.push(viewer);
+                true
+            }
+        }
+
+        fn show_image(&self, response: &ResponseData) {
+            if self.needs_render(ResponseViewer::Image) {
+                let bytes = glib::Bytes::from(&response.body);
+                let texture = gtk::gdk::Texture::from_bytes(&bytes).ok();
+                self.response_image.set_paintable(texture.as_ref());
+            }
+            match self.response_image.paintable() {
+                Some(image) => {
+                    let size = format!(
+                        "• {} × {}",
+                        image.intrinsic_width(),
+                        image.intrinsic_height()
+                    );
+                    self.image_size.set_text(&size);
+                    self.image_size.set_visible(true);
+                    self.body_stack.set_visible_child_name("image");
+                }
+                // Not an image that can be decoded, so show the bytes instead.
+                None => self.show_hex(response),
+            }
+        }
+
+        /// Opens the body of the current response in the default image viewer of
+        /// the system, through a temporary file.
+        fn open_image(&self) -> Result<(), CarteroError> {
+            let response = self.current_response.borrow();
+            let Some(response) = response.as_ref() else {
+                return Ok(());
+            };
+            let name = format!(
+                "cartero-{}.{}",
+                glib::monotonic_time(),
+                body_extension(&response.headers)
+            );
+            let path = glib::tmp_dir().join(name);
+            std::fs::write(&path, &response.body)?;
+
+            let file = gio::File::for_path(&path);
+            let context = self.obj().display().app_launch_context();
+            gio::AppInfo::launch_default_for_uri(&file.uri(), Some(&context))
+                .map_err(|e| CarteroError::LaunchError(e.to_string()))
+        }
+
+        #[cfg(feature = "webkit")]
+        fn show_html(&self, response: &ResponseData) {
+            use webkit6::prelude::*;
+
+            let view = self.html_view.get_or_init(|| {
+                let view = webkit6::WebView::new();
+                if let Some(settings) = WebViewExt::settings(&view) {
+                    settings.set_enable_javascript(false);
+                }
+                self.body_stack.add_named(&view, Some("html"));
+                view
+            });
+            if self.needs_render(ResponseViewer::Html) {
+                let bytes = glib::Bytes::from(&response.body);
+                let charset = self.selected_encoding().name();
+                view.load_bytes(&bytes, Some("text/html"), Some(charset), None);
+            }
+            self.body_stack.set_visible_child_name("html");
+        }
+
+        /// Without WebKit, HTML documents are shown as highlighted source.
+        #[cfg(not(feature = "webkit"))]
+        fn show_html(&self, _response: &ResponseData) {
+            self.body_stack.set_visible_child_name("body");
+        }
+
+        fn show_tree(&self, response: &ResponseData) {
+            if self.needs_render(ResponseViewer::Tree) {
+                let body = response.body_str_as(self.selected_encoding());
+                let tree = JsonTree::parse(&body).ok().map(Rc::new);
+                self.json_tree.replace(tree);
+                self.build_tree_model();
+            }
+            if self.json_tree.borrow().is_some() {
+                self.body_stack.set_visible_child_name("tree");
+            } else {
+                // Not a JSON document, so show the text instead.
+                self.body_stack.set_visible_child_name("body");
+            }
+        }
+
+        /// Fills the tree viewer with the nodes of the document that pass the filter.
+        ///
+        /// The children of a node are only listed once it is expanded. While
+        /// filtering, the nodes that lead to a match are expanded, up to a limit.
+        fn build_tree_model(&self) {
+            let tree = self.json_tree.borrow().clone();
+            let Some(tree) = tree else {
+                self.tree_view.set_model(None::<&gtk::SelectionModel>);
+                return;
+            };
+            let query = self.tree_search.text();
+            let search = match query.trim() {
+                "" => None,
+                query => Some(Rc::new(JsonSearch::new(&tree, query))),
+            };
+
+            let roots = node_store(tree.roots(search.as_deref()));
+            let model = gtk::TreeListModel::new(
+                roots,
+                false,
+                false,
+                glib::clone!(@strong tree, @strong search => move |item| {
+                    let node = item.downcast_ref::<glib::BoxedAnyObject>()?;
+                    let children = tree.children(&node.borrow::<JsonNode>(), search.as_deref());
+                    if children.is_empty() {
+                        None
+                    } else {
+                        Some(node_store(children).upcast())
+                    }
+                }),
+            );
+            if let Some(search) = search {
+                let mut position = 0;
+                while let Some(row) = model.row(position) {
+                    if model.n_items() >= MAX_EXPANDED_ROWS {
+                        break;
+                    }
+                    if tree_node(&row).is_some_and(|node| search.leads_to_match(&node)) {
+                        row.set_expanded(true);
+                    }
+                    position += 1;
+                }
+            }
+            let selection = gtk::SingleSelection::new(Some(model));
+            self.tree_view.set_model(Some(&selection));
+        }
+
+        fn init_tree_columns(&self) {
+            let factory = gtk::SignalListItemFactory::new();
+            factory.connect_setup(|_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let icon = gtk::Image::new();
+                let label = gtk::Label::builder().xalign(0.0).build();
+                let content = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+                content.append(&icon);
+                content.append(&label);
+                let expander = gtk::TreeExpander::new();
+                expander.set_child(Some(&content));
+                item.set_child(Some(&expander));
+            });
+            factory.connect_bind(glib::clone!(@weak self as panel => move |_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let Some(expander) = item.child().and_downcast::<gtk::TreeExpander>() else {
+                    return;
+                };
+                let row = item.item().and_downcast::<gtk::TreeListRow>();
+                expander.set_list_row(row.as_ref());
+                let Some(content) = expander.child() else {
+                    return;
+                };
+                let (Some(icon), Some(label)) = (
+                    content.first_child().and_downcast::<gtk::Image>(),
+                    content.last_child().and_downcast::<gtk::Label>(),
+                ) else {
+                    return;
+                };
+                let Some(node) = row.as_ref().and_then(tree_node) else {
+                    return;
+                };
+                let tree = panel.json_tree.borrow();
+                let kind = tree
+                    .as_ref()
+                    .and_then(|tree| tree.value(node.path()))
+                    .map(JsonKind::of);
+                icon.set_icon_name(Some(kind_icon(&node, kind)));
+                label.set_text(&node_name(&node));
+            }));
+            self.tree_name_column.set_factory(Some(&factory));
+
+            let factory = gtk::SignalListItemFactory::new();
+            factory.connect_setup(|_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let label = gtk::Label::builder()
+                    .xalign(0.0)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .selectable(false)
+                    .build();
+                label.add_css_class("monospace");
+                item.set_child(Some(&label));
+            });
+            factory.connect_bind(glib::clone!(@weak self as panel => move |_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let Some(label) = item.child().and_downcast::<gtk::Label>() else {
+                    return;
+                };
+                let node = item
+                    .item()
+                    .and_downcast::<gtk::TreeListRow>()
+                    .and_then(|row| tree_node(&row));
+                let tree = panel.json_tree.borrow();
+                let value = match (&node, tree.as_ref()) {
+                    (Some(JsonNode::Value { path }), Some(tree)) => tree.value(path),
+                    _ => None,
+                };
+                let text = value.map(value_text).unwrap_or_default();
+                label.set_text(&text);
+                // Containers only show how many children they have.
+                if value.is_some_and(|value| scalar_text(value).is_none()) {
+                    label.add_css_class("dim-label");
+                } else {
+                    label.remove_css_class("dim-label");
+                }
+            }));
+            self.tree_value_column.set_factory(Some(&factory));
+        }
+
+        fn show_hex(&self, response: &ResponseData) {
+            if self.needs_render(ResponseViewer::Hex) {
+                self.hex_view.buffer().set_text(&hex_dump(&response.body));
+            }
+            self.body_stack.set_visible_child_name("hex");
+        }
+
+        /// Computes the diff in a separate thread, since bodies can be large.
+        fn update_diff(&self) {
+            let generation = self.diff_generation.get().wrapping_add(1);
+            self.diff_generation.set(generation);
+
+            let previous = self.previous_response.borrow().clone();
+            let current = self.current_response.borrow().clone();
+            let (Some(previous), Some(current)) = (previous, current) else {
+                return;
+            };
+
+            glib::spawn_future_local(glib::clone!(@weak self as panel => async move {
+                let diff = gio::spawn_blocking(move || ResponseDiff::new(&previous, &current)).await;
+                if panel.diff_generation.get() != generation {
+                    return;
+                }
+                if let Ok(diff) = diff {
+                    panel.render_diff(&diff);
+                }
+            }));
+        }
+
+        fn render_diff(&self, diff: &ResponseDiff) {
+            let mut summary = Vec::new();
+            if let Some((old, new)) = diff.status {
+                summary.push(format!("HTTP {old} → {new}"));
+            }
+            for change in &diff.headers {
+                let line = match change {
+                    HeaderChange::Added { name, value } => format!("+ {name}: {value}"),
+                    HeaderChange::Removed { name, value } => format!("- {name}: {value}"),
+                    HeaderChange::Changed { name, old, new } => {
+                        format!("~ {name}: {old} → {new}")
+                    }
+                };
+                summary.push(line);
+            }
+            if diff.is_empty() {
+                summary.push(gettext("Both responses are identical"));
+            } else if diff.status.is_none() && diff.headers.is_empty() {
+                summary.push(gettext("Status and headers did not change"));
+            }
+            self.diff_summary.set_text(&summary.join("\n"));
+
+            let buffer = self.diff_view.buffer();
+            buffer.set_text("");
+            for line in &diff.body {
+                let (prefix, tag) = match line.kind {
+                    DiffLineKind::Hunk => ("", Some("hunk")),
+                    DiffLineKind::Context => (" ", None),
+                    DiffLineKind::Added => ("+", Some("added")),
+                    DiffLineKind::Removed => ("-", Some("removed")),
+                };
+                let text = format!("{prefix}{}\n", line.text);
+                let mut end = buffer.end_iter();
+                match tag {
+                    Some(tag) => buffer.insert_with_tags_by_name(&mut end, &text, &[tag]),
+                    None => buffer.insert(&mut end, &text),
+                }
+            }
+        }
+
+        fn init_actions(&self) {
+            let ag = gio::SimpleActionGroup::new();
+            self.obj().insert_action_group("response", Some(&ag));
+
+            let save = gio::SimpleAction::new("save", Some(glib::VariantTy::STRING));
+            save.connect_activate(glib::clone!(@weak self as panel => move |_, target| {
+                let format = target
+                    .and_then(|t| t.str())
+                    .and_then(|t| ResponseExportFormat::try_from(t).ok());
+                if let Some(format) = format {
+                    glib::spawn_future_local(glib::clone!(@weak panel => async move {
+                        panel.save_response(format).await;
+                    }));
+                }
+            }));
+            ag.add_action(&save);
+        }
+
+        async fn save_response(&self, format: ResponseExportFormat) {
+            let Some(response) = self.current_response.borrow().clone() else {
+                return;
+            };
+            let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() else {
+                return;
+            };
+            let name = format.file_name(&response.headers);
+            let outcome = match save_response_file(&window, &name).await {
+                Ok(file) => match file.path() {
+                    Some(path) => save_response(&path, format, &response),
+                    None => Err(CarteroError::FileDialogError),
+                },
+                Err(e) => Err(e),
+            };
+            match outcome {
+                Ok(_) | Err(CarteroError::NoFilePicked) => {}
+                Err(e) => window.toast_error(e),
+            }
+        }
+
         fn init_settings(&self) {
             let app = CarteroApplication::get();
             let settings = app.settings();
@@ -167,19 +1164,82 @@ mod imp {
         fn spinning(&self) -> bool {
             self.metadata_stack
                 .visible_child()
-                .is_some_and(|w| w.is::<Spinner>())
+                .is_some_and(|w| &w == self.progress.upcast_ref::<gtk::Widget>())
         }
 
         fn set_spinning(&self, spinning: bool) {
             self.stack.set_visible_child_name("response");
+            self.attempt_status.set_visible(false);
+            self.download_status.set_visible(false);
+            self.download_bar.set_visible(false);
+            self.spinner.set_visible(true);
+            self.stop.set_visible(false);
             let widget: &gtk::Widget = if spinning {
-                self.spinner.upcast_ref()
+                self.progress.upcast_ref()
             } else {
                 self.response_meta.upcast_ref()
             };
             self.metadata_stack.set_visible_child(widget);
         }
     }
+
+    /// How many rows the tree viewer may have before it stops expanding the matches of a filter.
+    const MAX_EXPANDED_ROWS: u32 = 1000;
+
+    fn node_store(nodes: Vec<JsonNode>) -> gio::ListStore {
+        let store = gio::ListStore::new::<glib::BoxedAnyObject>();
+        let objects: Vec<glib::BoxedAnyObject> =
+            nodes.into_iter().map(glib::BoxedAnyObject::new).collect();
+        store.extend_from_slice(&objects);
+        store
+    }
+
+    fn tree_node(row: &gtk::TreeListRow) -> Option<JsonNode> {
+        let object = row.item().and_downcast::<glib::BoxedAnyObject>()?;
+        let node = object.borrow::<JsonNode>().clone();
+        Some(node)
+    }
+
+    /// The text shown in the name column of the tree viewer.
+    fn node_name(node: &JsonNode) -> String {
+        match node {
+            JsonNode::Chunk { start, end, .. } => gettext("Items {start}–{end}")
+                .replace("{start}", &start.to_string())
+                .replace("{end}", &(end - 1).to_string()),
+            JsonNode::Value { path } => match path.last() {
+                Some(PathSegment::Key(key)) => key.clone(),
+                Some(PathSegment::Index(index)) => format!("[{index}]"),
+                None => String::from("$"),
+            },
+        }
+    }
+
+    /// The text shown in the value column of the tree viewer.
+    fn value_text(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::Object(map) => {
+                ngettext("{count} key", "{count} keys", map.len() as u32)
+                    .replace("{count}", &map.len().to_string())
+            }
+            serde_json::Value::Array(items) => {
+                ngettext("{count} item", "{count} items", items.len() as u32)
+                    .replace("{count}", &items.len().to_string())
+            }
+            value => scalar_text(value).unwrap_or_default(),
+        }
+    }
+
+    fn kind_icon(node: &JsonNode, kind: Option<JsonKind>) -> &'static str {
+        match (node, kind) {
+            (JsonNode::Chunk { .. }, _) => "view-more-horizontal-symbolic",
+            (_, Some(JsonKind::Object)) => "folder-symbolic",
+            (_, Some(JsonKind::Array)) => "view-list-symbolic",
+            (_, Some(JsonKind::String)) => "text-x-generic-symbolic",
+            (_, Some(JsonKind::Number)) => "input-dialpad-symbolic",
+            (_, Some(JsonKind::Boolean)) => "object-select-symbolic",
+            (_, Some(JsonKind::Null) | None) => "action-unavailable-symbolic",
+        }
+    }
 }
 
 glib::wrapper! {
@@ -195,23 +1255,6 @@ impl Default for ResponsePanel {
 }
 
 // TODO: Whether to use SI units or base 2 units?
-fn format_bytes(count: usize) -> String {
-    let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
-    let mut total = count as f64;
-    let mut unit = 0;
-
-    while total > 1024.0 {
-        total /= 1024.0;
-        unit += 1;
-    }
-
-    if unit > 0 {
-        format!("{:.3} {}", total, units[unit])
-    } else {
-        format!("{} {}", total, units[unit])
-    }
-}
-
 impl ResponsePanel {
     pub fn new() -> Self {
         Object::builder().build()
@@ -220,12 +1263,176 @@ impl ResponsePanel {
     pub fn start_request(&self) {
         let imp = self.imp();
 
-        imp.metadata_stack.set_visible_child(&*imp.spinner);
+        imp.metadata_stack.set_visible_child(&*imp.progress);
+        imp.method_banner.set_revealed(false);
     }
 
-    pub fn assign_from_response(&self, resp: &ResponseData) {
+    /// Explains the parts of the response that depend on the method of the request:
+    /// responses to HEAD have no body, and responses to OPTIONS list the allowed methods.
+    pub fn show_method_notes(&self, method: &RequestMethod) {
+        let imp = self.imp();
+        let allowed = match &*imp.current_response.borrow() {
+            Some(response) => response.allowed_methods(),
+            None => return,
+        };
+        let note = match method {
+            RequestMethod::Head => {
+                // There is nothing to see in the body, so the headers are shown instead.
+                imp.notebook.set_current_page(Some(1));
+                Some(gettext(
+                    "Responses to HEAD requests have no body by design, only headers",
+                ))
+            }
+            RequestMethod::Options if allowed.is_empty() => {
+                Some(gettext("The server did not list the allowed methods"))
+            }
+            RequestMethod::Options => Some(
+                gettext("Allowed methods: {methods}").replace("{methods}", &allowed.join(", ")),
+            ),
+            _ => None,
+        };
+        match note {
+            Some(note) => {
+                imp.method_banner.set_title(&note);
+                imp.method_banner.set_revealed(true);
+            }
+            None => imp.method_banner.set_revealed(false),
+        }
+    }
+
+    /// Shows why a request failed in place of the response, with a button to send it again.
+    pub fn show_error(&self, error: &CarteroError) {
+        let imp = self.imp();
+        let icon = if error.is_network_error() {
+            "network-offline-symbolic"
+        } else {
+            "dialog-warning-symbolic"
+        };
+        imp.error_page.set_icon_name(Some(icon));
+        imp.error_page.set_title(&error.summary());
+        imp.error_page.set_description(Some(&error.to_string()));
+        imp.stack.set_visible_child_name("error");
+    }
+
+    /// Shows that the request was cancelled in place of the response.
+    pub fn show_cancelled(&self) {
+        let imp = self.imp();
+        imp.error_page.set_icon_name(Some("process-stop-symbolic"));
+        imp.error_page.set_title(&gettext("Request cancelled"));
+        imp.error_page
+            .set_description(Some(&gettext("The response was not received")));
+        imp.stack.set_visible_child_name("error");
+    }
+
+    /// Shows which attempt is in progress while a request is being retried.
+    pub fn set_attempt(&self, attempt: u32, total: u32) {
+        let imp = self.imp();
+        let text = gettext("Attempt {attempt} of {total}…")
+            .replace("{attempt}", &attempt.to_string())
+            .replace("{total}", &total.to_string());
+        imp.attempt_status.set_text(&text);
+        imp.attempt_status.set_visible(true);
+    }
+
+    /// Shows the progress of a download while it runs, with a button to stop it.
+    pub fn start_download(&self) {
+        let imp = self.imp();
+        imp.attempt_status.set_visible(false);
+        imp.download_status.set_text("");
+        imp.download_status.set_visible(true);
+        imp.stop
+            .set_tooltip_text(Some(&gettext("Stop the download")));
+        imp.stop.set_visible(true);
+    }
+
+    /// Updates the progress of a download. The spinner stays if the size is unknown.
+    pub fn set_download_progress(&self, progress: &DownloadProgress) {
+        let imp = self.imp();
+        imp.download_status.set_text(&progress.summary());
+        match progress.fraction() {
+            Some(fraction) => {
+                imp.download_bar.set_fraction(fraction);
+                imp.download_bar.set_visible(true);
+                imp.spinner.set_visible(false);
+            }
+            None => {
+                imp.download_bar.set_visible(false);
+                imp.spinner.set_visible(true);
+            }
+        }
+    }
+
+    /// Shows how many attempts were needed to get the current response.
+    pub fn set_attempts(&self, attempts: u32) {
+        let imp = self.imp();
+        let text = ngettext("• {count} attempt", "• {count} attempts", attempts)
+            .replace("{count}", &attempts.to_string());
+        imp.attempts.set_text(&text);
+        imp.attempts.set_visible(attempts > 1);
+    }
+
+    /// Describes the connection of the current response in the tooltip of its duration.
+    pub fn set_connection(&self, connection: &ConnectionInfo) {
         let imp = self.imp();
+        imp.duration.set_tooltip_text(Some(&connection.summary()));
+    }
 
+    /// Connects to the requests to store a value extracted from the response in a variable.
+    pub fn connect_extract_variable<F: Fn(&Self, &str, &str) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "extract-variable",
+            true,
+            glib::closure_local!(|ref panel, name: String, value: String| {
+                f(panel, &name, &value);
+            }),
+        )
+    }
+
+    /// Connects to the requests to send the token found in the response as a Bearer token.
+    pub fn connect_set_bearer_token<F: Fn(&Self, &str) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "set-bearer-token",
+            true,
+            glib::closure_local!(|ref panel, token: String| {
+                f(panel, &token);
+            }),
+        )
+    }
+
+    /// Shows the status and headers of an event stream and starts listing its events.
+    pub fn start_stream(&self, head: &ResponseData) {
+        let imp = self.imp();
+        self.assign_headers(head);
+        imp.start_stream();
+    }
+
+    /// Shows the status and headers of a response whose body is still arriving.
+    pub fn start_body_stream(&self, head: &ResponseData) {
+        let imp = self.imp();
+        self.assign_headers(head);
+        imp.start_body_stream(head.charset().unwrap_or(encoding_rs::UTF_8));
+    }
+
+    /// Adds a piece of the body being streamed at the end of the text viewer.
+    pub fn append_body_chunk(&self, chunk: &[u8]) {
+        let imp = self.imp();
+        imp.append_body_chunk(chunk);
+    }
+
+    /// Adds an event received from the stream, dropping the oldest ones if needed.
+    pub fn append_event(&self, event: &SseEvent) {
+        let imp = self.imp();
+        imp.append_event(event);
+    }
+
+    fn assign_headers(&self, resp: &ResponseData) {
+        let imp = self.imp();
         let mut headers = resp.headers.clone();
         headers.sort();
         let headers: Vec<KeyValueItem> = headers
@@ -249,14 +1456,63 @@ impl ResponsePanel {
         };
         imp.status_code.add_css_class(&status_color);
 
-        let duration = format!("{} s", resp.seconds());
+        match resp.version {
+            Some(version) => {
+                imp.http_version.set_text(version.label());
+                imp.http_version.set_visible(true);
+            }
+            None => imp.http_version.set_visible(false),
+        }
+    }
+
+    /// The response being shown, if any.
+    pub fn current_response(&self) -> Option<ResponseData> {
+        let imp = self.imp();
+        imp.current_response.borrow().clone()
+    }
+
+    pub fn assign_from_response(&self, resp: &ResponseData) {
+        let imp = self.imp();
+        imp.push_response(resp);
+        self.assign_headers(resp);
+        if resp.is_event_stream() {
+            imp.diff_toggle.set_sensitive(false);
+        } else {
+            imp.end_stream();
+        }
+
+        // Extracting values is only possible from JSON responses.
+        if !resp.is_json() {
+            imp.extract_toggle.set_active(false);
+        }
+        imp.extract_toggle.set_visible(resp.is_json());
+        imp.show_jwt(find_jwt(resp));
+
+        let duration = format_duration(resp.duration);
         imp.duration.set_text(&duration);
+        imp.duration.set_tooltip_text(None);
         imp.duration.set_visible(true);
 
-        let size = format_bytes(resp.size);
+        let size = if resp.transfer_size != resp.size {
+            gettext("{transfer} ({size} decompressed)")
+                .replace("{transfer}", &format_bytes(resp.transfer_size))
+                .replace("{size}", &format_bytes(resp.size))
+        } else {
+            format_bytes(resp.size)
+        };
         imp.response_size.set_text(&size);
         imp.response_size.set_visible(true);
 
+        match &resp.decode_warning {
+            Some(warning) => {
+                let tooltip =
+                    gettext("The body is shown as received: {error}").replace("{error}", warning);
+                imp.decode_warning.set_tooltip_text(Some(&tooltip));
+                imp.decode_warning.set_visible(true);
+            }
+            None => imp.decode_warning.set_visible(false),
+        }
+
         imp.metadata_stack.set_visible_child(&*imp.response_meta);
 
         let buffer = imp
@@ -265,15 +1521,7 @@ impl ResponsePanel {
             .downcast::<sourceview5::Buffer>()
             .unwrap();
 
-        buffer.set_text(&resp.body_str());
-
-        if resp.is_json() {
-            let json = serde_json::from_str(&resp.body_str())
-                .and_then(|text: Value| serde_json::to_string_pretty(&text));
-            if let Ok(json) = json {
-                buffer.set_text(&json);
-            }
-        }
+        imp.render_text();
 
         let language = if resp.is_json() {
             LanguageManager::default().language("json")
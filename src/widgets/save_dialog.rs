@@ -15,6 +15,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use adw::prelude::*;
+use gettextrs::gettext;
 use glib::Object;
 
 mod imp {
@@ -61,3 +63,16 @@ impl Default for SaveDialog {
         Object::builder().build()
     }
 }
+
+impl SaveDialog {
+    /// Builds the dialog for closing several tabs at once, listing the tabs with changes.
+    pub fn for_tabs(titles: &[String]) -> Self {
+        let dialog = Self::default();
+        let list: Vec<String> = titles.iter().map(|title| format!("• {title}")).collect();
+        let body = gettext("The following tabs have changes that have not been saved yet:");
+        dialog.set_body(&format!("{body}\n\n{}", list.join("\n")));
+        dialog.set_response_label("discard", &gettext("_Discard All"));
+        dialog.set_response_label("save", &gettext("_Save All"));
+        dialog
+    }
+}
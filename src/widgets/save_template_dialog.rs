@@ -0,0 +1,95 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::glib;
+use gtk::prelude::EditableExt;
+
+mod imp {
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::templates::{is_valid_template_name, TemplateStore};
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/save_template_dialog.ui")]
+    pub struct SaveTemplateDialog {
+        #[template_child]
+        pub name: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub exists: TemplateChild<gtk::Label>,
+
+        store: TemplateStore,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SaveTemplateDialog {
+        const NAME: &'static str = "CarteroSaveTemplateDialog";
+        type Type = super::SaveTemplateDialog;
+        type ParentType = adw::AlertDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SaveTemplateDialog {}
+
+    impl WidgetImpl for SaveTemplateDialog {}
+
+    impl AdwDialogImpl for SaveTemplateDialog {}
+
+    impl AdwAlertDialogImpl for SaveTemplateDialog {}
+
+    #[gtk::template_callbacks]
+    impl SaveTemplateDialog {
+        #[template_callback]
+        fn on_name_changed(&self) {
+            let name = self.name.text();
+            let valid = is_valid_template_name(&name);
+            self.obj().set_response_enabled("save", valid);
+            self.exists.set_visible(valid && self.store.exists(&name));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct SaveTemplateDialog(ObjectSubclass<imp::SaveTemplateDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::AlertDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for SaveTemplateDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl SaveTemplateDialog {
+    pub fn template_name(&self) -> String {
+        self.imp().name.text().trim().to_string()
+    }
+}
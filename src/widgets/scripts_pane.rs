@@ -0,0 +1,155 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::scripts::ScriptLog;
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use adw::subclass::bin::BinImpl;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{CompositeTemplate, TemplateChild};
+    use sourceview5::Buffer;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/es/danirod/Cartero/scripts_pane.ui")]
+    pub struct ScriptsPane {
+        #[template_child]
+        pub pre_request_buffer: TemplateChild<Buffer>,
+        #[template_child]
+        pub post_response_buffer: TemplateChild<Buffer>,
+        #[template_child]
+        pub log_view: TemplateChild<gtk::TextView>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ScriptsPane {
+        const NAME: &'static str = "CarteroScriptsPane";
+        type Type = super::ScriptsPane;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ScriptsPane {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.log_view
+                .buffer()
+                .create_tag(Some("error"), &[("foreground", &"#c01c28")]);
+
+            for buffer in [&*self.pre_request_buffer, &*self.post_response_buffer] {
+                buffer.connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }));
+            }
+        }
+    }
+
+    impl WidgetImpl for ScriptsPane {}
+
+    impl BinImpl for ScriptsPane {}
+
+    #[gtk::template_callbacks]
+    impl ScriptsPane {
+        #[template_callback]
+        fn on_clear_clicked(&self) {
+            self.log_view.buffer().set_text("");
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ScriptsPane(ObjectSubclass<imp::ScriptsPane>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for ScriptsPane {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl ScriptsPane {
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref pane| {
+                f(pane);
+            }),
+        )
+    }
+
+    pub fn pre_request_script(&self) -> String {
+        let buffer = &self.imp().pre_request_buffer;
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, true).to_string()
+    }
+
+    pub fn set_pre_request_script(&self, script: &str) {
+        self.imp().pre_request_buffer.set_text(script);
+    }
+
+    pub fn post_response_script(&self) -> String {
+        let buffer = &self.imp().post_response_buffer;
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, true).to_string()
+    }
+
+    pub fn set_post_response_script(&self, script: &str) {
+        self.imp().post_response_buffer.set_text(script);
+    }
+
+    /// Adds what a script printed to the output, followed by its error if it failed.
+    pub fn append_log(&self, log: &ScriptLog) {
+        let buffer = self.imp().log_view.buffer();
+        for line in &log.lines {
+            buffer.insert(&mut buffer.end_iter(), &format!("{line}\n"));
+        }
+        if let Some(error) = &log.error {
+            let text = gettext("Error: {error}").replace("{error}", error);
+            buffer.insert_with_tags_by_name(
+                &mut buffer.end_iter(),
+                &format!("{text}\n"),
+                &["error"],
+            );
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::pgettext;
+use glib::Object;
+use gtk::glib;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use gettextrs::gettext;
+    use glib::subclass::InitializingObject;
+    use gtk::{gdk, CompositeTemplate};
+
+    use crate::app::{CarteroApplication, DEFAULT_SHORTCUTS};
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/shortcuts_dialog.ui")]
+    pub struct ShortcutsDialog {
+        #[template_child]
+        pub group: TemplateChild<adw::PreferencesGroup>,
+
+        /// The row and the shortcut label of every action.
+        rows: RefCell<Vec<(&'static str, adw::ActionRow, gtk::ShortcutLabel)>>,
+
+        /// The action whose new shortcut is being waited for.
+        editing: RefCell<Option<&'static str>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ShortcutsDialog {
+        const NAME: &'static str = "CarteroShortcutsDialog";
+        type Type = super::ShortcutsDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ShortcutsDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            for &(action, _) in DEFAULT_SHORTCUTS {
+                let label = gtk::ShortcutLabel::builder()
+                    .disabled_text(gettext("Disabled"))
+                    .valign(gtk::Align::Center)
+                    .build();
+                let row = adw::ActionRow::builder()
+                    .title(super::shortcut_title(action))
+                    .activatable(true)
+                    .build();
+                row.add_suffix(&label);
+                row.connect_activated(glib::clone!(@weak self as dialog => move |_| {
+                    dialog.start_editing(action);
+                }));
+                self.group.add(&row);
+                self.rows.borrow_mut().push((action, row, label));
+            }
+            self.refresh();
+
+            // Capture the keys before the focused row does, so that keys such
+            // as Tab or Escape can also be part of a shortcut.
+            let controller = gtk::EventControllerKey::new();
+            controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+            controller.connect_key_pressed(glib::clone!(@weak self as dialog => @default-return glib::Propagation::Proceed, move |_, keyval, _, state| {
+                dialog.on_key_pressed(keyval, state)
+            }));
+            self.obj().add_controller(controller);
+        }
+
+        fn dispose(&self) {
+            self.stop_editing();
+        }
+    }
+
+    impl WidgetImpl for ShortcutsDialog {}
+
+    impl AdwDialogImpl for ShortcutsDialog {}
+
+    impl PreferencesDialogImpl for ShortcutsDialog {}
+
+    #[gtk::template_callbacks]
+    impl ShortcutsDialog {
+        #[template_callback]
+        fn on_reset_clicked(&self) {
+            self.stop_editing();
+            CarteroApplication::get().reset_shortcuts();
+            self.refresh();
+        }
+    }
+
+    impl ShortcutsDialog {
+        fn refresh(&self) {
+            let shortcuts = CarteroApplication::get().effective_shortcuts();
+            let editing = *self.editing.borrow();
+            for (action, row, label) in self.rows.borrow().iter() {
+                let accel = shortcuts
+                    .iter()
+                    .find(|(a, _)| a == action)
+                    .map(|(_, accel)| accel.as_str())
+                    .unwrap_or_default();
+                label.set_accelerator(accel);
+                if editing == Some(*action) {
+                    row.set_subtitle(&gettext(
+                        "Press the new shortcut, Escape to cancel or Backspace to disable it",
+                    ));
+                } else {
+                    row.set_subtitle("");
+                }
+            }
+        }
+
+        fn start_editing(&self, action: &'static str) {
+            // While a shortcut is being recorded the accelerators are removed,
+            // otherwise pressing an existing one would trigger its action.
+            let app = CarteroApplication::get();
+            for (other, _) in DEFAULT_SHORTCUTS {
+                app.set_accels_for_action(other, &[]);
+            }
+            self.editing.replace(Some(action));
+            self.refresh();
+        }
+
+        fn stop_editing(&self) {
+            if self.editing.take().is_some() {
+                CarteroApplication::get().apply_shortcuts();
+            }
+        }
+
+        fn on_key_pressed(&self, keyval: gdk::Key, state: gdk::ModifierType) -> glib::Propagation {
+            let Some(action) = *self.editing.borrow() else {
+                return glib::Propagation::Proceed;
+            };
+            let keyval = keyval.to_lower();
+            let modifiers = state & gtk::accelerator_get_default_mod_mask();
+
+            let accel = if modifiers.is_empty() && keyval == gdk::Key::Escape {
+                None
+            } else if modifiers.is_empty() && keyval == gdk::Key::BackSpace {
+                Some(String::new())
+            } else if gtk::accelerator_valid(keyval, modifiers) {
+                Some(gtk::accelerator_name(keyval, modifiers).to_string())
+            } else {
+                // Only a modifier has been pressed so far.
+                return glib::Propagation::Stop;
+            };
+
+            self.stop_editing();
+            if let Some(accel) = accel {
+                CarteroApplication::get().set_custom_shortcut(action, &accel);
+            }
+            self.refresh();
+            glib::Propagation::Stop
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ShortcutsDialog(ObjectSubclass<imp::ShortcutsDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::PreferencesDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for ShortcutsDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+/// The name of an action as shown in the shortcuts window.
+fn shortcut_title(action: &str) -> String {
+    match action {
+        "win.new" => pgettext("shortcuts window", "New request tab"),
+        "win.new-from-url" => pgettext("shortcuts window", "New request from URL"),
+        "win.open" => pgettext("shortcuts window", "Open request"),
+        "win.save" => pgettext("shortcuts window", "Save request"),
+        "win.save-as" => pgettext("shortcuts window", "Save request as"),
+        "win.close" => pgettext("shortcuts window", "Close request tab"),
+        "win.next-tab" => pgettext("shortcuts window", "Next request tab"),
+        "win.previous-tab" => pgettext("shortcuts window", "Previous request tab"),
+        "win.tab-overview" => pgettext("shortcuts window", "Show all tabs"),
+        "win.request" => pgettext("shortcuts window", "Send request"),
+        "win.preview-request" => pgettext("shortcuts window", "Preview request"),
+        "win.format-body" => pgettext("shortcuts window", "Format request body"),
+        "win.undo" => pgettext("shortcuts window", "Undo change"),
+        "win.redo" => pgettext("shortcuts window", "Redo change"),
+        "win.toggle-console" => pgettext("shortcuts window", "Show console"),
+        "win.preferences" => pgettext("shortcuts window", "Preferences"),
+        "app.quit" => pgettext("shortcuts window", "Quit"),
+        "win.show-help-overlay" => pgettext("shortcuts window", "Show Keyboard Shortcuts"),
+        action => action.to_string(),
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::Object;
+use gtk::glib;
+
+mod imp {
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use gettextrs::gettext;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::error::CarteroError;
+    use crate::templates::TemplateStore;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/templates_dialog.ui")]
+    pub struct TemplatesDialog {
+        #[template_child]
+        pub toaster: TemplateChild<adw::ToastOverlay>,
+
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        pub templates: TemplateChild<gtk::ListBox>,
+
+        store: TemplateStore,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TemplatesDialog {
+        const NAME: &'static str = "CarteroTemplatesDialog";
+        type Type = super::TemplatesDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TemplatesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.reload();
+        }
+    }
+
+    impl WidgetImpl for TemplatesDialog {}
+
+    impl AdwDialogImpl for TemplatesDialog {}
+
+    impl TemplatesDialog {
+        fn reload(&self) {
+            self.templates.remove_all();
+            let names = self.store.list();
+            for name in &names {
+                self.templates.append(&self.build_row(name));
+            }
+            let page = if names.is_empty() {
+                "empty"
+            } else {
+                "templates"
+            };
+            self.stack.set_visible_child_name(page);
+        }
+
+        /// A row that renames the template when its name is edited and applied.
+        fn build_row(&self, name: &str) -> adw::EntryRow {
+            let row = adw::EntryRow::builder()
+                .title(gettext("Template name"))
+                .text(name)
+                .show_apply_button(true)
+                .build();
+            let delete = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Delete template"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            row.add_suffix(&delete);
+
+            let name = name.to_string();
+            row.connect_apply(
+                glib::clone!(@weak self as dialog, @strong name => move |row| {
+                    let result = dialog.store.rename(&name, row.text().trim());
+                    dialog.finish(result);
+                }),
+            );
+            delete.connect_clicked(
+                glib::clone!(@weak self as dialog, @strong name => move |_| {
+                    let result = dialog.store.delete(&name);
+                    dialog.finish(result);
+                }),
+            );
+            row
+        }
+
+        fn finish(&self, result: Result<(), CarteroError>) {
+            match result {
+                Ok(()) => self.reload(),
+                Err(e) => self.toaster.add_toast(adw::Toast::new(&e.to_string())),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct TemplatesDialog(ObjectSubclass<imp::TemplatesDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for TemplatesDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
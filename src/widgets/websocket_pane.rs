@@ -0,0 +1,337 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{subclass::types::ObjectSubclassIsExt, Object};
+use gtk::glib;
+
+use crate::entities::WebSocketData;
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use adw::subclass::bin::BinImpl;
+    use gettextrs::gettext;
+    use glib::subclass::InitializingObject;
+    use glib::Properties;
+    use gtk::subclass::prelude::*;
+    use gtk::{prelude::*, CompositeTemplate};
+
+    use crate::client::{
+        format_hex, parse_hex, WebSocketConnection, WebSocketEvent, WebSocketFrame,
+    };
+    use crate::entities::{KeyValue, WebSocketData};
+    use crate::objects::KeyValueItem;
+    use crate::widgets::{ItemPane, KeyValuePane};
+
+    /// Older rows are removed from the log once this many rows are present.
+    const MAX_LOG_ROWS: u32 = 1000;
+
+    #[derive(CompositeTemplate, Properties, Default)]
+    #[template(resource = "/es/danirod/Cartero/websocket_pane.ui")]
+    #[properties(wrapper_type = super::WebSocketPane)]
+    pub struct WebSocketPane {
+        #[template_child]
+        pub url: TemplateChild<gtk::Entry>,
+
+        #[template_child(id = "connect")]
+        pub connect_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub error_revealer: TemplateChild<gtk::Revealer>,
+
+        #[template_child]
+        pub error_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub header_pane: TemplateChild<KeyValuePane>,
+
+        #[template_child]
+        pub log_window: TemplateChild<gtk::ScrolledWindow>,
+
+        #[template_child]
+        pub log: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        pub composer: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub frame_kind: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub message: TemplateChild<gtk::Entry>,
+
+        #[property(get, set, nullable)]
+        pub item_pane: RefCell<Option<ItemPane>>,
+
+        #[property(get)]
+        pub connected: Cell<bool>,
+
+        connection: RefCell<Option<WebSocketConnection>>,
+        log_rows: Cell<u32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for WebSocketPane {
+        const NAME: &'static str = "CarteroWebSocketPane";
+        type Type = super::WebSocketPane;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for WebSocketPane {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.header_pane.assert_always_placeholder();
+            self.header_pane
+                .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+        }
+
+        fn dispose(&self) {
+            // Dropping the connection closes the socket.
+            self.connection.take();
+        }
+    }
+
+    impl WidgetImpl for WebSocketPane {}
+
+    impl BinImpl for WebSocketPane {}
+
+    #[gtk::template_callbacks]
+    impl WebSocketPane {
+        #[template_callback]
+        fn on_url_changed(&self) {
+            let empty = self.url.buffer().text().is_empty();
+            self.connect_button.set_sensitive(!empty);
+            self.mark_dirty();
+        }
+
+        #[template_callback]
+        fn on_connect_clicked(&self) {
+            if let Some(connection) = self.connection.borrow().as_ref() {
+                connection.close();
+                return;
+            }
+
+            let session = self.extract_session();
+            if session.url.is_empty() {
+                return;
+            }
+            self.error_revealer.set_reveal_child(false);
+            self.append_log(
+                "•",
+                &gettext("Connecting to {url}…").replace("{url}", &session.url),
+            );
+
+            let (connection, mut events) =
+                WebSocketConnection::connect(&session.url, &session.headers);
+            self.connection.replace(Some(connection));
+            self.set_connection_ui(true);
+
+            glib::spawn_future_local(glib::clone!(@weak self as pane => async move {
+                while let Some(event) = events.recv().await {
+                    pane.handle_event(event);
+                }
+            }));
+        }
+
+        #[template_callback]
+        fn on_send_clicked(&self) {
+            let text = self.message.text().to_string();
+            let frame = if self.frame_kind.selected() == 1 {
+                match parse_hex(&text) {
+                    Ok(data) => WebSocketFrame::Binary(data),
+                    Err(e) => {
+                        self.show_error(&e.to_string());
+                        return;
+                    }
+                }
+            } else {
+                WebSocketFrame::Text(text)
+            };
+
+            if let Some(connection) = self.connection.borrow().as_ref() {
+                connection.send(frame);
+                self.message.set_text("");
+            }
+        }
+
+        #[template_callback]
+        fn on_error_dismissed(&self) {
+            self.error_revealer.set_reveal_child(false);
+        }
+    }
+
+    impl WebSocketPane {
+        fn mark_dirty(&self) {
+            if let Some(item_pane) = self.obj().item_pane() {
+                item_pane.set_dirty(true);
+            }
+        }
+
+        fn handle_event(&self, event: WebSocketEvent) {
+            match event {
+                WebSocketEvent::Connected => {
+                    self.connected.set(true);
+                    self.obj().notify_connected();
+                    self.composer.set_sensitive(true);
+                    self.append_log("•", &gettext("Connected"));
+                }
+                WebSocketEvent::Sent(frame) => self.append_log("↑", &describe_frame(&frame)),
+                WebSocketEvent::Received(frame) => self.append_log("↓", &describe_frame(&frame)),
+                WebSocketEvent::Error(message) => {
+                    self.append_log("!", &message);
+                    self.show_error(&message);
+                }
+                WebSocketEvent::Closed(reason) => {
+                    let text = match reason {
+                        Some(reason) => {
+                            gettext("Disconnected: {reason}").replace("{reason}", &reason)
+                        }
+                        None => gettext("Disconnected"),
+                    };
+                    self.append_log("•", &text);
+                    self.connection.take();
+                    self.connected.set(false);
+                    self.obj().notify_connected();
+                    self.set_connection_ui(false);
+                }
+            }
+        }
+
+        fn set_connection_ui(&self, active: bool) {
+            self.url.set_sensitive(!active);
+            self.header_pane.set_sensitive(!active);
+            if active {
+                self.connect_button.set_label(&gettext("Disconnect"));
+                self.connect_button.remove_css_class("suggested-action");
+                self.connect_button.add_css_class("destructive-action");
+            } else {
+                self.connect_button.set_label(&gettext("Connect"));
+                self.connect_button.remove_css_class("destructive-action");
+                self.connect_button.add_css_class("suggested-action");
+                self.composer.set_sensitive(false);
+            }
+        }
+
+        fn show_error(&self, message: &str) {
+            self.error_label.set_text(message);
+            self.error_revealer.set_reveal_child(true);
+        }
+
+        fn append_log(&self, direction: &str, text: &str) {
+            let time = glib::DateTime::now_local()
+                .and_then(|now| now.format("%H:%M:%S"))
+                .map(|t| t.to_string())
+                .unwrap_or_default();
+            let label = gtk::Label::builder()
+                .label(format!("{time} {direction} {text}"))
+                .xalign(0.0)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .selectable(true)
+                .margin_start(6)
+                .margin_end(6)
+                .margin_top(3)
+                .margin_bottom(3)
+                .build();
+            self.log.append(&label);
+
+            let rows = self.log_rows.get() + 1;
+            if rows > MAX_LOG_ROWS {
+                if let Some(first) = self.log.row_at_index(0) {
+                    self.log.remove(&first);
+                }
+            } else {
+                self.log_rows.set(rows);
+            }
+
+            glib::idle_add_local_once(glib::clone!(@weak self as pane => move || {
+                let adjustment = pane.log_window.vadjustment();
+                adjustment.set_value(adjustment.upper());
+            }));
+        }
+
+        pub fn assign_session(&self, session: &WebSocketData) {
+            self.url.buffer().set_text(session.url.clone());
+            let headers: Vec<KeyValueItem> = session
+                .headers
+                .iter()
+                .map(|item| KeyValueItem::from(item.clone()))
+                .collect();
+            self.header_pane.set_entries(&headers);
+        }
+
+        pub fn extract_session(&self) -> WebSocketData {
+            let url = String::from(self.url.buffer().text());
+            let headers = self
+                .header_pane
+                .get_entries()
+                .iter()
+                .map(|pair| KeyValue {
+                    name: pair.header_name(),
+                    value: pair.header_value(),
+                    active: pair.active(),
+                    secret: pair.secret(),
+                    comment: pair.comment(),
+                    required: false,
+                    validation_rule: String::new(),
+                })
+                .collect();
+            WebSocketData { url, headers }
+        }
+    }
+
+    fn describe_frame(frame: &WebSocketFrame) -> String {
+        match frame {
+            WebSocketFrame::Text(text) => text.clone(),
+            WebSocketFrame::Binary(data) => format!("[{}] {}", data.len(), format_hex(data)),
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct WebSocketPane(ObjectSubclass<imp::WebSocketPane>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl Default for WebSocketPane {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl WebSocketPane {
+    pub fn assign_session(&self, session: &WebSocketData) {
+        let imp = self.imp();
+        imp.assign_session(session);
+    }
+
+    pub fn extract_session(&self) -> WebSocketData {
+        let imp = self.imp();
+        imp.extract_session()
+    }
+}
@@ -21,14 +21,19 @@ use glib::Object;
 use gtk::{gio, glib, prelude::SettingsExtManual};
 
 mod imp {
-    use adw::prelude::AlertDialogExtManual;
+    use adw::prelude::{AdwDialogExt, AlertDialogExt, AlertDialogExtManual};
     use adw::AboutWindow;
     use adw::{subclass::prelude::*, TabPage};
-    use gettextrs::gettext;
+    use gettextrs::{gettext, ngettext};
     use gtk::gio::{self, ActionEntry};
     use gtk::prelude::*;
 
-    use crate::utils::SingleExpressionWatch;
+    use std::cell::RefCell;
+
+    use crate::client::Tracer;
+    use crate::entities::EndpointData;
+    use crate::templates::TemplateStore;
+    use crate::utils::{format_bytes, SingleExpressionWatch};
     use crate::{app::CarteroApplication, error::CarteroError};
     use crate::{config, widgets::*};
     use glib::subclass::InitializingObject;
@@ -47,12 +52,49 @@ mod imp {
         #[template_child]
         pub tabview: TemplateChild<adw::TabView>,
 
+        #[template_child]
+        overview: TemplateChild<adw::TabOverview>,
+
         #[template_child]
         pub window_title: TemplateChild<adw::WindowTitle>,
 
+        #[template_child]
+        theme_button: TemplateChild<gtk::Button>,
+
         #[template_child]
         stack: TemplateChild<gtk::Stack>,
 
+        #[template_child]
+        console: TemplateChild<ConsolePanel>,
+
+        #[template_child]
+        new_menu: TemplateChild<gio::Menu>,
+
+        #[template_child]
+        status_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        status_spinner: TemplateChild<gtk::Spinner>,
+
+        #[template_child]
+        presentation_banner: TemplateChild<adw::Banner>,
+
+        /// Lists the templates that a new request can start from.
+        templates_menu: gio::Menu,
+
+        /// The tab whose context menu is open, if any.
+        menu_page: RefCell<Option<TabPage>>,
+
+        /// The timer that saves the modified tabs, if auto-save is enabled.
+        auto_save_source: RefCell<Option<glib::SourceId>>,
+
+        /// Whether the tab bar and the console were visible before presentation mode hid them.
+        presentation_restore: RefCell<Option<(bool, bool)>>,
+
+        request_status_binding: SingleExpressionWatch,
+
+        request_sending_binding: SingleExpressionWatch,
+
         window_title_binding: SingleExpressionWatch,
 
         window_subtitle_binding: SingleExpressionWatch,
@@ -71,8 +113,45 @@ mod imp {
         #[template_child]
         pub tabview: TemplateChild<adw::TabView>,
 
+        #[template_child]
+        overview: TemplateChild<adw::TabOverview>,
+
+        #[template_child]
+        theme_button: TemplateChild<gtk::Button>,
+
         #[template_child]
         stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        console: TemplateChild<ConsolePanel>,
+
+        #[template_child]
+        new_menu: TemplateChild<gio::Menu>,
+
+        #[template_child]
+        status_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        status_spinner: TemplateChild<gtk::Spinner>,
+
+        #[template_child]
+        presentation_banner: TemplateChild<adw::Banner>,
+
+        /// Lists the templates that a new request can start from.
+        templates_menu: gio::Menu,
+
+        /// The tab whose context menu is open, if any.
+        menu_page: RefCell<Option<TabPage>>,
+
+        /// The timer that saves the modified tabs, if auto-save is enabled.
+        auto_save_source: RefCell<Option<glib::SourceId>>,
+
+        /// Whether the tab bar and the console were visible before presentation mode hid them.
+        presentation_restore: RefCell<Option<(bool, bool)>>,
+
+        request_status_binding: SingleExpressionWatch,
+
+        request_sending_binding: SingleExpressionWatch,
     }
 
     #[gtk::template_callbacks]
@@ -80,12 +159,69 @@ mod imp {
         fn update_tab_actions(&self) {
             let has_tabs = self.tabview.n_pages() > 0;
             let obj = self.obj();
-            let actions = vec!["save", "save-as", "close"];
+            let actions = vec![
+                "save",
+                "save-as",
+                "save-as-template",
+                "close",
+                "close-other-tabs",
+                "close-tabs-to-right",
+                "close-saved-tabs",
+                "pin-tab",
+                "tab-overview",
+                "preview-request",
+                "send-and-download",
+                "generate-code",
+                "generate-test",
+                "format-body",
+            ];
             for action in actions {
                 if let Some(action) = obj.lookup_action(action) {
                     action.set_property("enabled", has_tabs);
                 }
             }
+
+            self.update_revert_action();
+
+            // Switching tabs with Ctrl+Tab should not steal the key from text widgets.
+            let can_switch = self.tabview.n_pages() > 1 && !self.focus_in_text();
+            for action in ["next-tab", "previous-tab"] {
+                if let Some(action) = obj.lookup_action(action) {
+                    action.set_property("enabled", can_switch);
+                }
+            }
+        }
+
+        /// Reverting only makes sense for a tab with a file and unsaved changes.
+        fn update_revert_action(&self) {
+            let enabled = self
+                .current_pane()
+                .is_some_and(|pane| pane.dirty() && pane.file().is_some());
+            if let Some(action) = self.obj().lookup_action("revert") {
+                action.set_property("enabled", enabled);
+            }
+        }
+
+        fn set_cancel_enabled(&self, enabled: bool) {
+            if let Some(action) = self.obj().lookup_action("cancel-request") {
+                action.set_property("enabled", enabled);
+            }
+        }
+
+        /// Allows to cancel the request only if the one of the current tab is pending,
+        /// since other tabs may have finished their requests in the meantime.
+        fn sync_cancel_action(&self) {
+            let pending = self
+                .current_pane()
+                .and_then(|tab| tab.endpoint())
+                .is_some_and(|pane| pane.is_request_pending());
+            self.set_cancel_enabled(pending);
+        }
+
+        fn focus_in_text(&self) -> bool {
+            let obj = self.obj();
+            GtkWindowExt::focus(&*obj)
+                .is_some_and(|widget| widget.is::<gtk::Text>() || widget.is::<gtk::TextView>())
         }
 
         #[cfg(feature = "csd")]
@@ -117,6 +253,34 @@ mod imp {
         #[cfg(not(feature = "csd"))]
         fn bind_current_tab(&self, _: Option<&ItemPane>) {}
 
+        /// Shows the state of the request of the given tab in the status bar.
+        fn bind_request_state(&self, tab: Option<&ItemPane>) {
+            match tab.and_then(ItemPane::endpoint) {
+                Some(pane) => {
+                    let status_bind = pane.request_status_binding().bind(
+                        &*self.status_label,
+                        "label",
+                        Some(&pane),
+                    );
+                    let sending_bind = pane.request_sending_binding().bind(
+                        &*self.status_spinner,
+                        "visible",
+                        Some(&pane),
+                    );
+                    self.request_status_binding.replace(status_bind);
+                    self.request_sending_binding.replace(sending_bind);
+                    self.set_cancel_enabled(pane.is_request_pending());
+                }
+                None => {
+                    self.request_status_binding.clear();
+                    self.request_sending_binding.clear();
+                    self.status_label.set_label(&RequestState::Idle.summary());
+                    self.status_spinner.set_visible(false);
+                    self.set_cancel_enabled(false);
+                }
+            }
+        }
+
         fn init_settings(&self) {
             let app = CarteroApplication::get();
             let settings = app.settings();
@@ -126,7 +290,9 @@ mod imp {
                 "auto-indent",
                 "body-wrap",
                 "indent-style",
+                "presentation-mode",
                 "show-line-numbers",
+                "stream-max-events",
                 "tab-width",
             ];
             for action in actions {
@@ -155,6 +321,9 @@ mod imp {
 
         fn finish_window_close(&self) -> glib::Propagation {
             self.save_window_state();
+            if let Some(source) = self.auto_save_source.take() {
+                source.remove();
+            }
             glib::Propagation::Proceed
         }
 
@@ -170,7 +339,12 @@ mod imp {
                     .and_then(|f| f.path())
                     .map(|pb| pb.display().to_string());
                 if let Some(path) = file {
-                    let path = format!("endpoint:{path}");
+                    let kind = if child.websocket().is_some() {
+                        "websocket"
+                    } else {
+                        "endpoint"
+                    };
+                    let path = format!("{kind}:{path}");
                     paths.push(path);
                 }
             }
@@ -180,6 +354,46 @@ mod imp {
             settings.set("open-files", paths).unwrap();
         }
 
+        /// Reverts or repeats the last change made to the request in the current tab.
+        ///
+        /// Text fields that keep their own history, such as the body, and the
+        /// tables of rows handle the change themselves while they have the focus.
+        fn undo_or_redo(&self, redo: bool) {
+            let focus = GtkWindowExt::focus(&*self.obj());
+            let own_history = focus.as_ref().is_some_and(|widget| {
+                if let Some(text) = widget.downcast_ref::<gtk::Text>() {
+                    text.enables_undo()
+                } else if let Some(view) = widget.downcast_ref::<gtk::TextView>() {
+                    view.buffer().enables_undo()
+                } else {
+                    false
+                }
+            });
+            if let (true, Some(widget)) = (own_history, &focus) {
+                let action = if redo { "text.redo" } else { "text.undo" };
+                let _ = widget.activate_action(action, None);
+                return;
+            }
+            let rows = focus
+                .and_then(|widget| widget.ancestor(KeyValuePane::static_type()))
+                .and_downcast::<KeyValuePane>();
+            if let Some(rows) = rows {
+                if redo {
+                    rows.redo();
+                } else {
+                    rows.undo();
+                }
+                return;
+            }
+            if let Some(pane) = self.current_pane().and_then(|p| p.endpoint()) {
+                if redo {
+                    pane.redo();
+                } else {
+                    pane.undo();
+                }
+            }
+        }
+
         /// Returns the pane currently visible in the window.
         ///
         /// This method will make more sense in the future once multiple panes can be visible in tabs.
@@ -189,6 +403,385 @@ mod imp {
             Some(page)
         }
 
+        /// Returns the tab whose context menu is open, or the visible one.
+        fn menu_tab(&self) -> Option<TabPage> {
+            let page = self.menu_page.borrow().clone();
+            page.or_else(|| self.tabview.selected_page())
+        }
+
+        /// Returns the pane of the tab whose context menu is open, or the visible one.
+        fn menu_pane(&self) -> Option<ItemPane> {
+            self.menu_tab()?.child().downcast::<ItemPane>().ok()
+        }
+
+        /// Every tab in the window, in the order they are shown.
+        fn tab_pages(&self) -> Vec<TabPage> {
+            let pages = self.tabview.pages();
+            (0..pages.n_items())
+                .filter_map(|i| pages.item(i).and_downcast::<TabPage>())
+                .collect()
+        }
+
+        /// The title of the tab of a pane, without the mark of unsaved changes.
+        fn tab_title(&self, pane: &ItemPane) -> String {
+            let title = self.tabview.page(pane).title();
+            title.trim_start_matches("• ").to_string()
+        }
+
+        /// Reflects in the pin action whether the tab it acts on is pinned.
+        fn update_pin_action(&self) {
+            let pinned = self.menu_tab().is_some_and(|page| page.is_pinned());
+            if let Some(action) = self.obj().lookup_action("pin-tab") {
+                action.change_state(&pinned.to_variant());
+            }
+        }
+
+        /// Closes several tabs at once. Pinned tabs are kept open.
+        ///
+        /// If any of the tabs has unsaved changes, the user is asked a single
+        /// time whether to save or discard the changes of all of them.
+        async fn close_pages(&self, pages: Vec<TabPage>) -> Result<(), CarteroError> {
+            let pages: Vec<TabPage> = pages.into_iter().filter(|p| !p.is_pinned()).collect();
+            let modified: Vec<ItemPane> = pages
+                .iter()
+                .filter_map(|page| page.child().downcast::<ItemPane>().ok())
+                .filter(|pane| pane.dirty())
+                .collect();
+            if !modified.is_empty() {
+                match self.show_save_changes(&modified).await.as_str() {
+                    "save" => {
+                        for pane in &modified {
+                            self.save_pane(pane).await?;
+                        }
+                    }
+                    "discard" => {
+                        // The tabs are closed right away, so there is nothing to ask anymore.
+                        for pane in &modified {
+                            pane.set_dirty(false);
+                        }
+                    }
+                    _ => return Ok(()),
+                }
+            }
+            for page in pages {
+                self.tabview.close_page(&page);
+            }
+            Ok(())
+        }
+
+        /// Closes the tabs chosen by the given filter from the tab the action acts on.
+        fn trigger_close_tabs(&self, filter: impl Fn(&TabPage, &TabPage) -> bool) {
+            let Some(target) = self.menu_tab() else {
+                return;
+            };
+            let pages: Vec<TabPage> = self
+                .tab_pages()
+                .into_iter()
+                .filter(|page| filter(page, &target))
+                .collect();
+            glib::spawn_future_local(glib::clone!(@weak self as window => async move {
+                match window.close_pages(pages).await {
+                    Ok(_) | Err(CarteroError::NoFilePicked) => {}
+                    Err(e) => window.toast_error(e),
+                }
+            }));
+        }
+
+        fn init_templates_menu(&self) {
+            let section = gio::Menu::new();
+            section.append_submenu(Some(&gettext("From template")), &self.templates_menu);
+            self.new_menu.append_section(None, &section);
+            self.reload_templates_menu();
+
+            self.tabview
+                .connect_setup_menu(glib::clone!(@weak self as window => move |_, page| {
+                    window.menu_page.replace(page.cloned());
+                    window.update_pin_action();
+                }));
+        }
+
+        fn init_theme_button(&self) {
+            self.sync_theme_button();
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings.connect_changed(
+                Some("color-scheme"),
+                glib::clone!(@weak self as window => move |_, _| {
+                    window.sync_theme_button();
+                }),
+            );
+        }
+
+        /// Shows the current style in the icon of the theme button.
+        fn sync_theme_button(&self) {
+            let app = CarteroApplication::get();
+            let (icon, tooltip) = match app.settings().string("color-scheme").as_str() {
+                "light" => ("weather-clear-symbolic", gettext("Light style")),
+                "dark" => ("weather-clear-night-symbolic", gettext("Dark style")),
+                _ => (
+                    "display-brightness-symbolic",
+                    gettext("Follow system style"),
+                ),
+            };
+            self.theme_button.set_icon_name(icon);
+            self.theme_button.set_tooltip_text(Some(&tooltip));
+        }
+
+        fn init_presentation_mode(&self) {
+            self.sync_presentation_mode();
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings.connect_changed(
+                Some("presentation-mode"),
+                glib::clone!(@weak self as window => move |_, _| {
+                    window.sync_presentation_mode();
+                }),
+            );
+        }
+
+        /// Hides the tab bar and the console and enlarges the bodies while presenting.
+        /// Leaving presentation mode shows them again only if they were visible before.
+        fn sync_presentation_mode(&self) {
+            let app = CarteroApplication::get();
+            let enabled = app.settings().boolean("presentation-mode");
+            let obj = self.obj();
+            if enabled {
+                if self.presentation_restore.borrow().is_none() {
+                    let visible = (self.tabs.is_visible(), self.console.is_visible());
+                    self.presentation_restore.replace(Some(visible));
+                }
+                self.tabs.set_visible(false);
+                self.console.set_visible(false);
+                obj.add_css_class("presentation");
+                obj.set_title(Some(&gettext("Cartero — Presentation Mode")));
+            } else {
+                if let Some((tabs, console)) = self.presentation_restore.take() {
+                    self.tabs.set_visible(tabs);
+                    self.console.set_visible(console);
+                }
+                obj.remove_css_class("presentation");
+                obj.set_title(Some("Cartero"));
+            }
+            self.presentation_banner.set_revealed(enabled);
+        }
+
+        fn init_auto_save(&self) {
+            self.restart_auto_save();
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings.connect_changed(
+                Some("auto-save-delay-secs"),
+                glib::clone!(@weak self as window => move |_, _| {
+                    window.restart_auto_save();
+                }),
+            );
+        }
+
+        /// Starts again the auto-save timer, so that it follows the current delay.
+        fn restart_auto_save(&self) {
+            if let Some(source) = self.auto_save_source.take() {
+                source.remove();
+            }
+            let app = CarteroApplication::get();
+            let delay = app.settings().int("auto-save-delay-secs");
+            if delay <= 0 {
+                return;
+            }
+            let source = glib::timeout_add_seconds_local(
+                delay as u32,
+                glib::clone!(@weak self as window => @default-return glib::ControlFlow::Break, move || {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        window.auto_save().await;
+                    }));
+                    glib::ControlFlow::Continue
+                }),
+            );
+            self.auto_save_source.replace(Some(source));
+        }
+
+        /// Saves the tabs with unsaved changes that already have a file.
+        ///
+        /// A tab that cannot be written is left out of auto-save until the
+        /// user saves it by hand, so that the error is not shown over and over.
+        async fn auto_save(&self) {
+            let panes: Vec<ItemPane> = self
+                .get_modified_panes()
+                .into_iter()
+                .filter(|pane| pane.file().is_some() && !pane.auto_save_blocked())
+                .collect();
+            let mut saved = 0;
+            for pane in panes {
+                match self.save_pane(&pane).await {
+                    Ok(_) => saved += 1,
+                    Err(e) => {
+                        pane.block_auto_save();
+                        self.toast_error(e);
+                    }
+                }
+            }
+            if saved > 0 {
+                let title = ngettext("Auto-saved {count} file", "Auto-saved {count} files", saved)
+                    .replace("{count}", &saved.to_string());
+                let toast = adw::Toast::builder().title(title).timeout(2).build();
+                self.toaster.add_toast(toast);
+            }
+        }
+
+        /// Lets the user give a title to the endpoint in a tab by double-clicking it.
+        fn init_tab_renaming(&self) {
+            let gesture = gtk::GestureClick::new();
+            gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+            gesture.connect_pressed(
+                glib::clone!(@weak self as window => move |gesture, n_press, x, y| {
+                    if n_press != 2 {
+                        return;
+                    }
+                    // The tabs of an AdwTabBar are private widgets, but their type is registered.
+                    let Some(tab_type) = glib::Type::from_name("AdwTab") else {
+                        return;
+                    };
+                    let on_label = window
+                        .tabs
+                        .pick(x, y, gtk::PickFlags::DEFAULT)
+                        .is_some_and(|widget| {
+                            widget.ancestor(tab_type).is_some()
+                                && widget.ancestor(gtk::Button::static_type()).is_none()
+                        });
+                    if on_label {
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                        window.rename_current_tab(x, y);
+                    }
+                }),
+            );
+            self.tabs.add_controller(gesture);
+        }
+
+        /// Opens a blank tab when the tab bar is middle-clicked outside of the tabs,
+        /// since middle-clicking a tab already closes it.
+        fn init_tab_bar_new_tab(&self) {
+            let gesture = gtk::GestureClick::builder().button(2).build();
+            gesture.connect_pressed(glib::clone!(@weak self as window => move |_, _, x, y| {
+                let Some(tab_type) = glib::Type::from_name("AdwTab") else {
+                    return;
+                };
+                let on_tab = window
+                    .tabs
+                    .pick(x, y, gtk::PickFlags::DEFAULT)
+                    .is_some_and(|widget| widget.ancestor(tab_type).is_some());
+                if !on_tab {
+                    let _ = WidgetExt::activate_action(&*window.obj(), "win.new", None);
+                }
+            }));
+            self.tabs.add_controller(gesture);
+        }
+
+        /// Shows an entry over the tab bar to change the title of the current endpoint.
+        ///
+        /// Pressing Enter gives the new title to the endpoint. Pressing Escape or
+        /// clicking elsewhere closes the entry and keeps the previous title.
+        fn rename_current_tab(&self, x: f64, y: f64) {
+            let Some(page) = self.tabview.selected_page() else {
+                return;
+            };
+            let Ok(item_pane) = page.child().downcast::<ItemPane>() else {
+                return;
+            };
+            let Some(endpoint) = item_pane.endpoint() else {
+                return;
+            };
+            let title = match item_pane.custom_title() {
+                title if title.is_empty() => self.tab_title(&item_pane),
+                title => title,
+            };
+
+            let entry = gtk::Entry::builder().text(&title).width_chars(24).build();
+            let popover = gtk::Popover::builder()
+                .child(&entry)
+                .pointing_to(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1))
+                .build();
+            popover.set_parent(&*self.tabs);
+            entry.connect_activate(glib::clone!(@weak endpoint, @weak popover => move |entry| {
+                let name = entry.text();
+                let name = name.trim();
+                if name != title {
+                    endpoint.set_request_name(name);
+                }
+                popover.popdown();
+            }));
+            popover.connect_closed(|popover| {
+                // Unparenting while the popover is still being closed confuses GTK.
+                glib::idle_add_local_once(glib::clone!(@weak popover => move || {
+                    popover.unparent();
+                }));
+            });
+            popover.popup();
+            entry.grab_focus();
+        }
+
+        fn reload_templates_menu(&self) {
+            let templates = gio::Menu::new();
+            for name in TemplateStore::default().list() {
+                // Underscores would be taken as mnemonics otherwise.
+                let label = name.replace('_', "__");
+                let item = gio::MenuItem::new(Some(&label), None);
+                item.set_action_and_target_value(
+                    Some("win.new-from-template"),
+                    Some(&name.to_variant()),
+                );
+                templates.append_item(&item);
+            }
+            self.templates_menu.remove_all();
+            self.templates_menu.append_section(None, &templates);
+            self.templates_menu.append(
+                Some(&gettext("Manage templates...")),
+                Some("win.manage-templates"),
+            );
+        }
+
+        async fn trigger_save_as_template(&self) -> Result<(), CarteroError> {
+            let Some(pane) = self.menu_pane().and_then(|pane| pane.endpoint()) else {
+                return Ok(());
+            };
+            let mut endpoint = pane.extract_endpoint()?;
+            // The URLs sent from this tab are not useful to the requests made out of the template.
+            endpoint.url_history.clear();
+
+            let dialog = SaveTemplateDialog::default();
+            if dialog.clone().choose_future(&*self.obj()).await.as_str() != "save" {
+                return Ok(());
+            }
+            TemplateStore::default().save(&dialog.template_name(), &endpoint)?;
+            self.reload_templates_menu();
+            Ok(())
+        }
+
+        /// Opens a new tab with a copy of the endpoint stored in the template.
+        async fn trigger_new_from_template(&self, name: &str) -> Result<(), CarteroError> {
+            let endpoint = TemplateStore::default().load(name)?;
+            let pane = ItemPane::new_for_endpoint(None).await?;
+            if let Some(child) = pane.endpoint() {
+                child.assign_endpoint(&endpoint);
+            }
+            self.add_pane(Ok(pane));
+            Ok(())
+        }
+
+        /// Builds the tracer for a request sent by the given tab, which logs into
+        /// the console. It does nothing while the console is hidden.
+        fn console_tracer(&self, pane: &ItemPane) -> Tracer {
+            if !self.console.is_visible() {
+                return Tracer::disabled();
+            }
+            let source = self.tabview.page(pane).title().to_string();
+            let console = self.console.get();
+            Tracer::new(glib::clone!(@weak console, @weak pane => move |event| {
+                console.append(&pane, &source, event);
+            }))
+        }
+
         fn find_pane_by_path(&self, file: &gio::File) -> Option<TabPage> {
             self.tabview
                 .pages()
@@ -220,7 +813,20 @@ mod imp {
                 }
             }
 
-            match ItemPane::new_for_endpoint(file).await {
+            let pane = match file {
+                Some(file) => ItemPane::open(file).await,
+                None => ItemPane::new_for_endpoint(None).await,
+            };
+            self.add_pane(pane);
+        }
+
+        pub async fn add_websocket(&self) {
+            let pane = ItemPane::new_for_websocket(None).await;
+            self.add_pane(pane);
+        }
+
+        fn add_pane(&self, pane: Result<ItemPane, CarteroError>) {
+            match pane {
                 Ok(pane) => {
                     self.stack.set_visible_child_name("tabview");
                     let page = self.tabview.add_page(&pane, None);
@@ -228,6 +834,12 @@ mod imp {
                         .bind(&page, "title", Some(&pane));
                     pane.window_subtitle_binding()
                         .bind(&page, "tooltip", Some(&pane));
+                    pane.connect_dirty_notify(glib::clone!(@weak self as window => move |_| {
+                        window.update_revert_action();
+                    }));
+                    pane.connect_file_notify(glib::clone!(@weak self as window => move |_| {
+                        window.update_revert_action();
+                    }));
                     self.tabview.set_selected_page(&page);
                     self.save_visible_tabs();
                 }
@@ -237,6 +849,100 @@ mod imp {
             };
         }
 
+        /// Sends the request of the current tab, saving the body of the response
+        /// into a file picked by the user instead of showing it.
+        async fn trigger_send_and_download(&self) -> Result<(), CarteroError> {
+            let Some(item_pane) = self.current_pane() else {
+                return Ok(());
+            };
+            let Some(pane) = item_pane
+                .endpoint()
+                .filter(|pane| !pane.is_request_pending())
+            else {
+                return Ok(());
+            };
+            let file = save_response_file(&self.obj(), &pane.download_file_name()).await?;
+            let path = file.path().ok_or(CarteroError::FileDialogError)?;
+
+            let tracer = self.console_tracer(&item_pane);
+            self.set_cancel_enabled(true);
+            let outcome = pane.download_request(&tracer, &path).await;
+            self.sync_cancel_action();
+
+            // Errors are shown by the response panel of the pane.
+            let Ok(size) = outcome else {
+                return Ok(());
+            };
+            let title = gettext("Saved to {path} ({size})")
+                .replace("{path}", &path.display().to_string())
+                .replace("{size}", &format_bytes(size));
+            let toast = adw::Toast::builder().title(title).timeout(3).build();
+            self.toaster.add_toast(toast);
+            Ok(())
+        }
+
+        async fn trigger_new_from_url(&self) -> Result<(), CarteroError> {
+            let window = self.obj();
+            let dialog = NewFromUrlDialog::default();
+            if dialog.clone().choose_future(&*window).await.as_str() != "open" {
+                return Ok(());
+            }
+
+            let url = dialog.url();
+            let endpoint = EndpointData::from_url(&url)?;
+            NewFromUrlDialog::remember_url(url.trim());
+
+            let pane = ItemPane::new_for_endpoint(None).await?;
+            if let Some(child) = pane.endpoint() {
+                child.assign_endpoint(&endpoint);
+            }
+            self.add_pane(Ok(pane));
+            Ok(())
+        }
+
+        async fn trigger_import_from_curl(&self) -> Result<(), CarteroError> {
+            let window = self.obj();
+            let dialog = ImportCurlDialog::default();
+            if dialog.clone().choose_future(&*window).await.as_str() != "import" {
+                return Ok(());
+            }
+
+            let endpoint = dialog.endpoint()?;
+            let pane = ItemPane::new_for_endpoint(None).await?;
+            if let Some(child) = pane.endpoint() {
+                child.assign_endpoint(&endpoint);
+            }
+            self.add_pane(Ok(pane));
+            Ok(())
+        }
+
+        async fn trigger_import_from_insomnia(&self) -> Result<(), CarteroError> {
+            let window = self.obj();
+            let file = open_insomnia_file(&window).await?;
+            let contents = crate::file::read_file(&file).await?;
+            let import = crate::interop::insomnia::import_insomnia_v4(&contents)?;
+
+            for endpoint in &import.endpoints {
+                let pane = ItemPane::new_for_endpoint(None).await?;
+                if let Some(child) = pane.endpoint() {
+                    child.assign_endpoint(endpoint);
+                }
+                self.add_pane(Ok(pane));
+            }
+            if import.endpoints.is_empty() {
+                self.toast_message(&gettext("The collection has no requests to import"));
+            } else if import.skipped > 0 {
+                let message = ngettext(
+                    "{count} item of the collection is not supported and was skipped",
+                    "{count} items of the collection are not supported and were skipped",
+                    import.skipped as u32,
+                )
+                .replace("{count}", &import.skipped.to_string());
+                self.toast_message(&message);
+            }
+            Ok(())
+        }
+
         async fn trigger_open(&self) -> Result<(), CarteroError> {
             // In order to place the modal, we need a reference to the public type.
             let obj = self.obj();
@@ -249,7 +955,7 @@ mod imp {
         }
 
         async fn save_pane(&self, pane: &ItemPane) -> Result<(), CarteroError> {
-            let Some(endpoint) = pane.endpoint() else {
+            let Some(serialized_payload) = pane.serialize()? else {
                 return Ok(());
             };
 
@@ -261,28 +967,24 @@ mod imp {
                 }
             };
 
-            let endpoint = endpoint.extract_endpoint()?;
-            let serialized_payload = crate::file::store_toml(&endpoint)?;
             crate::file::write_file(&file, &serialized_payload).await?;
             pane.set_file(Some(file.clone()));
-            pane.set_dirty(false);
+            pane.mark_saved(serialized_payload);
 
             Ok(())
         }
 
         async fn save_pane_as(&self, pane: &ItemPane) -> Result<(), CarteroError> {
-            let Some(endpoint) = pane.endpoint() else {
+            let Some(serialized_payload) = pane.serialize()? else {
                 return Ok(());
             };
 
             let obj = self.obj();
             let file = crate::widgets::save_file(&obj).await?;
 
-            let endpoint = endpoint.extract_endpoint()?;
-            let serialized_payload = crate::file::store_toml(&endpoint)?;
             crate::file::write_file(&file, &serialized_payload).await?;
             pane.set_file(Some(file.clone()));
-            pane.set_dirty(false);
+            pane.mark_saved(serialized_payload);
 
             Ok(())
         }
@@ -299,6 +1001,38 @@ mod imp {
             res
         }
 
+        async fn trigger_revert(&self) -> Result<(), CarteroError> {
+            let Some(pane) = self.current_pane() else {
+                return Ok(());
+            };
+            let Some(file) = pane.file() else {
+                return Ok(());
+            };
+
+            let dialog = adw::AlertDialog::builder()
+                .heading(gettext("Revert to saved version?"))
+                .body(gettext("All unsaved changes will be lost."))
+                .close_response("cancel")
+                .default_response("cancel")
+                .build();
+            dialog.add_responses(&[
+                ("cancel", &gettext("_Cancel")),
+                ("revert", &gettext("_Revert")),
+            ]);
+            dialog.set_response_appearance("revert", adw::ResponseAppearance::Destructive);
+            if dialog.choose_future(&*self.obj()).await != "revert" {
+                return Ok(());
+            }
+
+            if !file.query_exists(gio::Cancellable::NONE) {
+                // There is nothing to go back to, so the tab goes away without asking.
+                pane.set_dirty(false);
+                self.tabview.close_page(&self.tabview.page(&pane));
+                return Err(CarteroError::FileNotFound);
+            }
+            pane.revert().await
+        }
+
         async fn trigger_save_as(&self) -> Result<(), CarteroError> {
             let Some(pane) = self.current_pane() else {
                 return Ok(());
@@ -312,7 +1046,48 @@ mod imp {
         }
 
         pub(super) fn toast_error(&self, error: CarteroError) {
-            let toast = adw::Toast::new(&error.to_string());
+            let message = error.to_string();
+            let details = error.details();
+            let toast = adw::Toast::builder()
+                .title(message.as_str())
+                .button_label(gettext("Details"))
+                .build();
+            toast.connect_button_clicked(glib::clone!(@weak self as window => move |_| {
+                window.show_error_details(&message, &details);
+            }));
+            self.toaster.add_toast(toast);
+        }
+
+        /// Shows the whole chain of causes of an error, which can be copied to report it.
+        fn show_error_details(&self, message: &str, details: &str) {
+            let label = gtk::Label::builder()
+                .label(details)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .selectable(true)
+                .xalign(0.0)
+                .css_classes(["monospace"])
+                .build();
+            let dialog = adw::AlertDialog::builder()
+                .heading(gettext("Error details"))
+                .body(message)
+                .extra_child(&label)
+                .close_response("close")
+                .default_response("close")
+                .build();
+            dialog.add_responses(&[("copy", &gettext("_Copy")), ("close", &gettext("_Close"))]);
+            let details = details.to_string();
+            dialog.connect_response(
+                Some("copy"),
+                glib::clone!(@weak self as window => move |_, _| {
+                    window.obj().clipboard().set_text(&details);
+                }),
+            );
+            dialog.present(&*self.obj());
+        }
+
+        pub(super) fn toast_message(&self, message: &str) {
+            let toast = adw::Toast::new(message);
             self.toaster.add_toast(toast);
         }
 
@@ -332,9 +1107,10 @@ mod imp {
             panes
         }
 
-        async fn show_save_changes(&self) -> String {
+        async fn show_save_changes(&self, panes: &[ItemPane]) -> String {
             let window = self.obj();
-            let dialog = SaveDialog::default();
+            let titles: Vec<String> = panes.iter().map(|pane| self.tab_title(pane)).collect();
+            let dialog = SaveDialog::for_tabs(&titles);
             dialog.choose_future(&*window).await.as_str().to_string()
         }
 
@@ -381,13 +1157,24 @@ mod imp {
             }
 
             self.init_settings();
+            self.init_templates_menu();
+            self.init_tab_renaming();
+            self.init_tab_bar_new_tab();
+            self.init_auto_save();
+            self.init_theme_button();
+            self.init_presentation_mode();
 
             self.tabview.connect_selected_page_notify(
                 glib::clone!(@weak self as window => move |tabview| {
                     if let Some(page) = tabview.selected_page() {
                         let item_pane = page.child().downcast::<ItemPane>().unwrap();
                         window.bind_current_tab(Some(&item_pane));
+                        window.bind_request_state(Some(&item_pane));
+                        window.console.set_current(Some(&item_pane));
                         window.update_tab_actions();
+                        window.update_pin_action();
+                    } else {
+                        window.bind_request_state(None);
                     }
                 }),
             );
@@ -431,6 +1218,10 @@ mod imp {
                 true
             }));
 
+            obj.connect_focus_widget_notify(glib::clone!(@weak self as window => move |_| {
+                window.update_tab_actions();
+            }));
+
             self.tabview.connect_page_reordered(
                 glib::clone!(@weak self as window => move |_, _, _| {
                         window.save_visible_tabs();
@@ -446,19 +1237,183 @@ mod imp {
                 }))
                 .build();
 
+            let action_new_websocket = ActionEntry::builder("new-websocket")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(async move {
+                        window.add_websocket().await;
+                    });
+                }))
+                .build();
+
+            let action_new_from_url = ActionEntry::builder("new-from-url")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_new_from_url().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_import_from_curl = ActionEntry::builder("import-from-curl")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_import_from_curl().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_import_from_insomnia = ActionEntry::builder("import-from-insomnia")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_import_from_insomnia().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_new_from_template = ActionEntry::builder("new-from-template")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(glib::clone!(@weak self as window => move |_, _, param| {
+                    let Some(name) = param.and_then(|p| p.get::<String>()) else {
+                        return;
+                    };
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_new_from_template(&name).await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+            let action_open_endpoint = ActionEntry::builder("open-endpoint")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(glib::clone!(@weak self as window => move |_, _, param| {
+                    let Some(path) = param.and_then(|p| p.get::<String>()) else {
+                        return;
+                    };
+                    let file = gio::File::for_commandline_arg(path);
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        window.obj().open_endpoint(&file).await;
+                    }));
+                }))
+                .build();
+            let action_save_as_template = ActionEntry::builder("save-as-template")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_save_as_template().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+            let action_manage_templates = ActionEntry::builder("manage-templates")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = TemplatesDialog::default();
+                    dialog.connect_closed(glib::clone!(@weak window => move |_| {
+                        window.reload_templates_menu();
+                    }));
+                    dialog.present(&*window.obj());
+                }))
+                .build();
             let action_request = ActionEntry::builder("request")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     glib::spawn_future_local(glib::clone!(@weak window => async move {
-                        if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
-                            pane.set_sensitive(false);
-                            if let Err(e) = pane.perform_request().await {
-                                window.toast_error(e);
+                        let Some(item_pane) = window.current_pane() else {
+                            return;
+                        };
+                        if let Some(pane) = item_pane.endpoint() {
+                            if pane.is_request_pending() {
+                                return;
                             }
-                            pane.set_sensitive(true);
+                            let tracer = window.console_tracer(&item_pane);
+                            window.set_cancel_enabled(true);
+                            // Errors are shown by the response panel of the pane.
+                            let _ = pane.perform_request(&tracer).await;
+                            window.sync_cancel_action();
+                        }
+                    }));
+                }))
+                .build();
+            let action_send_and_download = ActionEntry::builder("send-and-download")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        match window.trigger_send_and_download().await {
+                            Ok(_) | Err(CarteroError::NoFilePicked) => {}
+                            Err(e) => window.toast_error(e),
                         }
                     }));
                 }))
                 .build();
+            let action_cancel_request = ActionEntry::builder("cancel-request")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
+                        pane.cancel_request();
+                    }
+                }))
+                .build();
+            let action_preview_request = ActionEntry::builder("preview-request")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
+                        match pane.export_request() {
+                            Ok(request) => {
+                                let dialog = RequestPreviewDialog::new(&request);
+                                dialog.present(&*window.obj());
+                            }
+                            Err(e) => window.toast_error(e),
+                        }
+                    }
+                }))
+                .build();
+            let action_format_body = ActionEntry::builder("format-body")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
+                        pane.format_body();
+                    }
+                }))
+                .build();
+            let action_generate_code = ActionEntry::builder("generate-code")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
+                        match pane.export_request() {
+                            Ok(request) => {
+                                let dialog = GenerateCodeDialog::new(&request);
+                                dialog.present(&*window.obj());
+                            }
+                            Err(e) => window.toast_error(e),
+                        }
+                    }
+                }))
+                .build();
+            let action_generate_test = ActionEntry::builder("generate-test")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) else {
+                        return;
+                    };
+                    let Some(response) = pane.last_response() else {
+                        return;
+                    };
+                    match pane.export_request() {
+                        Ok(request) => {
+                            let dialog = GenerateTestDialog::new(&request, &response);
+                            dialog.present(&*window.obj());
+                        }
+                        Err(e) => window.toast_error(e),
+                    }
+                }))
+                .build();
+            let action_undo = ActionEntry::builder("undo")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.undo_or_redo(false);
+                }))
+                .build();
+            let action_redo = ActionEntry::builder("redo")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.undo_or_redo(true);
+                }))
+                .build();
             let action_open = ActionEntry::builder("open")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     glib::spawn_future_local(glib::clone!(@weak window => async move {
@@ -489,6 +1444,15 @@ mod imp {
                     }));
                 }))
                 .build();
+            let action_revert = ActionEntry::builder("revert")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_revert().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
             let action_close = ActionEntry::builder("close")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     if let Some(page) = window.tabview.selected_page() {
@@ -496,6 +1460,110 @@ mod imp {
                     }
                 }))
                 .build();
+            let action_close_other_tabs = ActionEntry::builder("close-other-tabs")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.trigger_close_tabs(|page, target| page != target);
+                }))
+                .build();
+            let action_close_tabs_to_right = ActionEntry::builder("close-tabs-to-right")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let tabview = window.tabview.clone();
+                    window.trigger_close_tabs(move |page, target| {
+                        tabview.page_position(page) > tabview.page_position(target)
+                    });
+                }))
+                .build();
+            let action_close_saved_tabs = ActionEntry::builder("close-saved-tabs")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.trigger_close_tabs(|page, _| {
+                        page.child()
+                            .downcast::<ItemPane>()
+                            .is_ok_and(|pane| !pane.dirty())
+                    });
+                }))
+                .build();
+            let action_pin_tab = ActionEntry::builder("pin-tab")
+                .state(false.to_variant())
+                .activate(glib::clone!(@weak self as window => move |_, action, _| {
+                    if let Some(page) = window.menu_tab() {
+                        let pinned = !page.is_pinned();
+                        window.tabview.set_page_pinned(&page, pinned);
+                        action.set_state(&pinned.to_variant());
+                    }
+                }))
+                .build();
+            let action_tab_overview = ActionEntry::builder("tab-overview")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.overview.set_open(!window.overview.is_open());
+                }))
+                .build();
+            let action_next_tab = ActionEntry::builder("next-tab")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.tabview.select_next_page();
+                }))
+                .build();
+            let action_previous_tab = ActionEntry::builder("previous-tab")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.tabview.select_previous_page();
+                }))
+                .build();
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            let side_by_side = settings.string("split-view-mode") != "vertical";
+            let action_toggle_split_view = ActionEntry::builder("toggle-split-view")
+                .state(side_by_side.to_variant())
+                .activate(|_, _, _| {
+                    let app = CarteroApplication::get();
+                    let settings = app.settings();
+                    let mode = match settings.string("split-view-mode").as_str() {
+                        "vertical" => "horizontal",
+                        _ => "vertical",
+                    };
+                    let _ = settings.set_string("split-view-mode", mode);
+                })
+                .build();
+
+            let action_toggle_theme = ActionEntry::builder("toggle-theme")
+                .activate(|_, _, _| {
+                    let app = CarteroApplication::get();
+                    let settings = app.settings();
+                    let scheme = match settings.string("color-scheme").as_str() {
+                        "default" => "light",
+                        "light" => "dark",
+                        _ => "default",
+                    };
+                    let _ = settings.set_string("color-scheme", scheme);
+                })
+                .build();
+
+            let action_preferences = ActionEntry::builder("preferences")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = PreferencesDialog::default();
+                    dialog.present(&*window.obj());
+                }))
+                .build();
+
+            let action_customize_shortcuts = ActionEntry::builder("customize-shortcuts")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = ShortcutsDialog::default();
+                    dialog.present(&*window.obj());
+                }))
+                .build();
+
+            let action_base64_tool = ActionEntry::builder("base64-tool")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = EncodingToolDialog::new(EncodingTool::Base64);
+                    dialog.present(&*window.obj());
+                }))
+                .build();
+
+            let action_url_encoding_tool = ActionEntry::builder("url-encoding-tool")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = EncodingToolDialog::new(EncodingTool::Url);
+                    dialog.present(&*window.obj());
+                }))
+                .build();
 
             let action_about = ActionEntry::builder("about")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
@@ -519,14 +1587,60 @@ mod imp {
             let obj = self.obj();
             obj.add_action_entries([
                 action_new,
+                action_new_websocket,
+                action_new_from_url,
+                action_import_from_curl,
+                action_import_from_insomnia,
+                action_new_from_template,
+                action_open_endpoint,
+                action_save_as_template,
+                action_manage_templates,
                 action_request,
+                action_cancel_request,
+                action_preview_request,
+                action_format_body,
+                action_send_and_download,
+                action_generate_code,
+                action_generate_test,
+                action_undo,
+                action_redo,
                 action_open,
                 action_save,
                 action_save_as,
+                action_revert,
                 action_close,
+                action_close_other_tabs,
+                action_close_tabs_to_right,
+                action_close_saved_tabs,
+                action_pin_tab,
+                action_tab_overview,
+                action_next_tab,
+                action_previous_tab,
+                action_toggle_split_view,
+                action_toggle_theme,
+                action_preferences,
+                action_customize_shortcuts,
+                action_base64_tool,
+                action_url_encoding_tool,
                 action_about,
             ]);
+            let action_toggle_console =
+                gio::PropertyAction::new("toggle-console", &*self.console, "visible");
+            obj.add_action(&action_toggle_console);
+
+            // Every window follows the layout, even if it was toggled from another one.
+            settings.connect_changed(
+                Some("split-view-mode"),
+                glib::clone!(@weak obj => move |settings, key| {
+                    let side_by_side = settings.string(key) != "vertical";
+                    if let Some(action) = obj.lookup_action("toggle-split-view") {
+                        action.change_state(&side_by_side.to_variant());
+                    }
+                }),
+            );
+
             self.update_tab_actions();
+            self.set_cancel_enabled(false);
         }
     }
 
@@ -538,7 +1652,8 @@ mod imp {
             if panes.is_empty() {
                 self.finish_window_close()
             } else {
-                let response = glib::MainContext::default().block_on(self.show_save_changes());
+                let response =
+                    glib::MainContext::default().block_on(self.show_save_changes(&panes));
                 match response.as_str() {
                     "discard" => self.finish_window_close(),
                     "save" => {
@@ -587,11 +1702,21 @@ impl CarteroWindow {
         imp.add_endpoint(ep).await
     }
 
+    /// Opens the given file in a tab, or focuses its tab if it is already open.
+    pub async fn open_endpoint(&self, file: &gio::File) {
+        self.add_endpoint(Some(file)).await
+    }
+
     pub fn toast_error(&self, e: CarteroError) {
         let imp = self.imp();
         imp.toast_error(e);
     }
 
+    pub fn toast_message(&self, message: &str) {
+        let imp = self.imp();
+        imp.toast_message(message);
+    }
+
     pub fn sync_open_files(&self) {
         let imp = self.imp();
         imp.save_visible_tabs();